@@ -0,0 +1,86 @@
+use chesslib::{board::Board, prelude::*};
+use criterion::{Criterion, black_box, criterion_group, criterion_main};
+
+const KIWIPETE: &str = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+const POSITIONS: [(&str, &str); 3] = [
+    ("startpos", DEFAULT_FEN),
+    ("kiwipete", KIWIPETE),
+    ("endgame", "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1"),
+];
+
+fn bench_legal_moves(c: &mut Criterion) {
+    let mut group = c.benchmark_group("legal_moves");
+    for (name, fen) in POSITIONS {
+        let gs = GameState::try_from_fen(fen).unwrap();
+        group.bench_function(name, |b| b.iter(|| black_box(&gs).legal_moves()));
+    }
+    group.finish();
+}
+
+fn bench_make_move(c: &mut Criterion) {
+    let mut group = c.benchmark_group("make_move");
+    for (name, fen) in POSITIONS {
+        let gs = GameState::try_from_fen(fen).unwrap();
+        let moves = gs.legal_moves();
+        // there is no unmake, so every move is made on a fresh copy
+        group.bench_function(name, |b| {
+            b.iter(|| {
+                for mv in &moves {
+                    let mut child = *black_box(&gs);
+                    let _ = match mv.promotion() {
+                        Some(figure) => child.make_promotion(mv.from, mv.to, Piece {
+                            color: gs.get_turn(),
+                            figure,
+                        }),
+                        None => child.make_move(mv.from, mv.to),
+                    };
+                }
+            })
+        });
+    }
+    group.finish();
+}
+
+fn bench_copy_state(c: &mut Criterion) {
+    let gs = GameState::try_from_fen(KIWIPETE).unwrap();
+    c.bench_function("copy_state", |b| b.iter(|| *black_box(&gs)));
+}
+
+fn bench_is_square_attacked(c: &mut Criterion) {
+    let board = Board::try_from_fen(KIWIPETE).unwrap();
+    c.bench_function("is_square_attacked", |b| {
+        b.iter(|| {
+            let mut attacked = 0;
+            for col in 0..8u8 {
+                for row in 0..8u8 {
+                    let square =
+                        Square::from_coords(col.try_into().unwrap(), row.try_into().unwrap());
+                    if black_box(&board).is_square_attacked(square, Color::White) {
+                        attacked += 1;
+                    }
+                }
+            }
+            attacked
+        })
+    });
+}
+
+fn bench_perft(c: &mut Criterion) {
+    let mut group = c.benchmark_group("perft_4");
+    group.sample_size(10);
+    for (name, fen) in POSITIONS {
+        let gs = GameState::try_from_fen(fen).unwrap();
+        group.bench_function(name, |b| b.iter(|| black_box(&gs).perft(4)));
+    }
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    bench_legal_moves,
+    bench_make_move,
+    bench_copy_state,
+    bench_is_square_attacked,
+    bench_perft
+);
+criterion_main!(benches);
@@ -0,0 +1,22 @@
+pub mod motifs;
+pub mod review;
+
+use crate::{
+    board::{Board, Square},
+    pieces::Color,
+};
+
+// pieces of each side bearing on every square, indexed by color then square; on an occupied
+// square its own side's count is the defenders. pins are ignored and sliders don't see
+// through each other
+pub fn influence_map(board: &Board) -> [[u8; 64]; 2] {
+    let occupied = board.occupied();
+    let mut map = [[0; 64]; 2];
+    for color in [Color::White, Color::Black] {
+        for square in (0..64).filter_map(Square::new) {
+            map[color as usize][square] =
+                board.attackers_to(square, color, occupied).count_squares();
+        }
+    }
+    map
+}
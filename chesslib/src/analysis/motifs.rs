@@ -0,0 +1,211 @@
+use crate::{
+    GameState,
+    board::{Board, Row, Square, bitboard::BitBoard},
+    pieces::{Color, Figure, Piece},
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Motif {
+    Fork {
+        knight: Square,
+        targets: Vec<Square>,
+    },
+    Pin {
+        pinner: Square,
+        pinned: Square,
+        target: Square,
+        absolute: bool,
+    },
+    Skewer {
+        attacker: Square,
+        front: Square,
+        behind: Square,
+    },
+    Hanging {
+        square: Square,
+    },
+    // the king can't step off its back rank, and a rook or queen of ours reaches the rank
+    // with nothing in between; `entries` are the squares it would land on
+    BackRank {
+        king: Square,
+        entries: Vec<Square>,
+    },
+    // the king is walled in by its own pieces and a knight of ours checks it from `square`,
+    // now or after one move
+    Smothered {
+        king: Square,
+        knight: Square,
+        square: Square,
+    },
+}
+
+impl Motif {
+    // the lichess puzzle theme for the motif
+    pub fn theme(&self) -> &'static str {
+        match self {
+            Motif::Fork { .. } => "fork",
+            Motif::Pin { .. } => "pin",
+            Motif::Skewer { .. } => "skewer",
+            Motif::Hanging { .. } => "hangingPiece",
+            Motif::BackRank { .. } => "backRankMate",
+            Motif::Smothered { .. } => "smotheredMate",
+        }
+    }
+}
+
+// motifs that `color` can exploit against the other side
+pub fn find_motifs(gs: &GameState, color: Color) -> Vec<Motif> {
+    let board = &gs.board;
+    let mut motifs = find_forks(board, color);
+    motifs.extend(find_pins_and_skewers(board, color));
+    motifs.extend(find_hanging(board, color));
+    motifs.extend(find_back_rank(board, color));
+    motifs.extend(find_smothered(board, color));
+    motifs
+}
+
+fn find_forks(board: &Board, color: Color) -> Vec<Motif> {
+    let mut forks = Vec::new();
+    for knight in board.pieces(Figure::Knight, color).iter() {
+        let targets: Vec<Square> = BitBoard::knight_moves(knight)
+            .iter()
+            .filter(|&sq| {
+                board.get_sq(sq).is_some_and(|piece| {
+                    piece.color == !color
+                        && (piece.figure == Figure::King
+                            || piece.figure.value() > Figure::Knight.value())
+                })
+            })
+            .collect();
+        if targets.len() >= 2 {
+            forks.push(Motif::Fork { knight, targets });
+        }
+    }
+    forks
+}
+
+fn find_pins_and_skewers(board: &Board, color: Color) -> Vec<Motif> {
+    let occupied = board.occupied_color(color) | board.occupied_color(!color);
+    let enemies = board.occupied_color(!color);
+    let mut motifs = Vec::new();
+    for figure in [Figure::Bishop, Figure::Rook, Figure::Queen] {
+        let piece = Piece { color, figure };
+        for attacker in board.pieces(figure, color).iter() {
+            let attacks = BitBoard::attacks(piece, attacker, occupied);
+            for front in (attacks & enemies).iter() {
+                // removing the front piece only opens the ray running through it
+                let xray = BitBoard::attacks(piece, attacker, occupied & !BitBoard::from(front))
+                    & !attacks;
+                let Some(behind) = (xray & enemies).iter().next() else {
+                    continue;
+                };
+                let (Some(front_piece), Some(behind_piece)) =
+                    (board.get_sq(front), board.get_sq(behind))
+                else {
+                    continue;
+                };
+                if behind_piece.figure == Figure::King {
+                    motifs.push(Motif::Pin {
+                        pinner: attacker,
+                        pinned: front,
+                        target: behind,
+                        absolute: true,
+                    });
+                } else if front_piece.figure == Figure::King
+                    || front_piece.figure.value() > behind_piece.figure.value()
+                {
+                    motifs.push(Motif::Skewer {
+                        attacker,
+                        front,
+                        behind,
+                    });
+                } else if behind_piece.figure.value() > front_piece.figure.value() {
+                    motifs.push(Motif::Pin {
+                        pinner: attacker,
+                        pinned: front,
+                        target: behind,
+                        absolute: false,
+                    });
+                }
+            }
+        }
+    }
+    motifs
+}
+
+fn find_hanging(board: &Board, color: Color) -> Vec<Motif> {
+    board
+        .occupied_color(!color)
+        .iter()
+        .filter(|&square| {
+            board
+                .get_sq(square)
+                .is_some_and(|piece| piece.figure != Figure::King)
+                && board.is_square_attacked(square, !color)
+                && !board.is_square_attacked(square, color)
+        })
+        .map(|square| Motif::Hanging { square })
+        .collect()
+}
+
+fn find_back_rank(board: &Board, color: Color) -> Vec<Motif> {
+    let Some(king) = board.pieces(Figure::King, !color).iter().next() else {
+        return Vec::new();
+    };
+    let back_rank = match !color {
+        Color::White => Row::One,
+        Color::Black => Row::Eight,
+    };
+    if king.row() != back_rank {
+        return Vec::new();
+    }
+    let own = board.occupied_color(!color);
+    let boxed_in = (BitBoard::king_moves(king) & !BitBoard::from(back_rank))
+        .iter()
+        .all(|square| own.contains(square) || board.is_square_attacked(square, !color));
+    if !boxed_in {
+        return Vec::new();
+    }
+    let occupied = board.occupied();
+    let heavies = board.pieces(Figure::Rook, color) | board.pieces(Figure::Queen, color);
+    let entries: Vec<Square> = heavies
+        .iter()
+        .fold(BitBoard::default(), |acc, square| {
+            acc | BitBoard::rook_attacks(square, occupied)
+        })
+        .iter()
+        .filter(|&square| {
+            square.row() == back_rank
+                && square != king
+                && !board.occupied_color(color).contains(square)
+                && (BitBoard::straight_ray(square, king) & occupied).is_subset(square.into())
+        })
+        .collect();
+    if entries.is_empty() {
+        return Vec::new();
+    }
+    vec![Motif::BackRank { king, entries }]
+}
+
+fn find_smothered(board: &Board, color: Color) -> Vec<Motif> {
+    let Some(king) = board.pieces(Figure::King, !color).iter().next() else {
+        return Vec::new();
+    };
+    let own = board.occupied_color(!color);
+    if !BitBoard::king_moves(king).is_subset(own) {
+        return Vec::new();
+    }
+    let checks = BitBoard::knight_moves(king) & !board.occupied_color(color);
+    board
+        .pieces(Figure::Knight, color)
+        .iter()
+        .flat_map(|knight| {
+            let reach = BitBoard::knight_moves(knight) | knight.into();
+            (reach & checks).iter().map(move |square| Motif::Smothered {
+                king,
+                knight,
+                square,
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,142 @@
+use crate::{pieces::Color, uci::Score};
+use std::fmt::Display;
+
+// drops in the mover's winning chances, in percentage points, that make a move an
+// inaccuracy, a mistake or a blunder, as on lichess
+const INACCURACY_DROP: f32 = 5.0;
+const MISTAKE_DROP: f32 = 10.0;
+const BLUNDER_DROP: f32 = 15.0;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Judgement {
+    Inaccuracy,
+    Mistake,
+    Blunder,
+}
+
+impl Judgement {
+    pub const ALL: [Judgement; 3] = [
+        Judgement::Inaccuracy,
+        Judgement::Mistake,
+        Judgement::Blunder,
+    ];
+
+    // the PGN annotation glyph
+    pub fn glyph(self) -> &'static str {
+        match self {
+            Judgement::Inaccuracy => "?!",
+            Judgement::Mistake => "?",
+            Judgement::Blunder => "??",
+        }
+    }
+}
+
+impl Display for Judgement {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            Judgement::Inaccuracy => "inaccuracy",
+            Judgement::Mistake => "mistake",
+            Judgement::Blunder => "blunder",
+        };
+        write!(f, "{s}")
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct MoveReview {
+    pub judgement: Option<Judgement>,
+    // 0 to 100, how much of the mover's chances the move kept
+    pub accuracy: f32,
+}
+
+// White's chances from 0 to 100 for a score from White's point of view, on lichess's curve
+pub fn win_percent(score: Score) -> f32 {
+    match score {
+        Score::Mate(n) if n > 0 => 100.0,
+        Score::Mate(n) if n < 0 => 0.0,
+        Score::Mate(_) => 50.0,
+        Score::Centipawns(cp) => 100.0 / (1.0 + (-0.00368208 * cp as f32).exp()),
+    }
+}
+
+// a move by `mover` that took the game from `before` to `after`, both from White's
+// point of view
+pub fn review_move(before: Score, after: Score, mover: Color) -> MoveReview {
+    let (before, after) = match mover {
+        Color::White => (win_percent(before), win_percent(after)),
+        Color::Black => (100.0 - win_percent(before), 100.0 - win_percent(after)),
+    };
+    let drop = (before - after).max(0.0);
+    let judgement = match drop {
+        d if d >= BLUNDER_DROP => Some(Judgement::Blunder),
+        d if d >= MISTAKE_DROP => Some(Judgement::Mistake),
+        d if d >= INACCURACY_DROP => Some(Judgement::Inaccuracy),
+        _ => None,
+    };
+    let accuracy = (103.1668 * (-0.04354 * drop).exp() - 3.1669).clamp(0.0, 100.0);
+    MoveReview {
+        judgement,
+        accuracy,
+    }
+}
+
+// an engine's pass over a whole game: a score for every position, from White's point
+// of view, and a verdict on every move between them
+#[derive(Debug, Clone, PartialEq)]
+pub struct GameReview {
+    pub scores: Vec<Score>,
+    pub moves: Vec<MoveReview>,
+    first_mover: Color,
+}
+
+impl GameReview {
+    pub fn new(scores: Vec<Score>, first_mover: Color) -> Self {
+        let moves = scores
+            .windows(2)
+            .enumerate()
+            .map(|(ply, pair)| review_move(pair[0], pair[1], Self::mover_at(first_mover, ply)))
+            .collect();
+        Self {
+            scores,
+            moves,
+            first_mover,
+        }
+    }
+
+    fn mover_at(first_mover: Color, ply: usize) -> Color {
+        if ply.is_multiple_of(2) {
+            first_mover
+        } else {
+            !first_mover
+        }
+    }
+
+    // who played the move at `ply`, counting from 0
+    pub fn mover(&self, ply: usize) -> Color {
+        Self::mover_at(self.first_mover, ply)
+    }
+
+    fn moves_by(&self, color: Color) -> impl Iterator<Item = &MoveReview> {
+        self.moves
+            .iter()
+            .enumerate()
+            .filter(move |&(ply, _)| self.mover(ply) == color)
+            .map(|(_, review)| review)
+    }
+
+    // the mean of `color`'s move accuracies, if they moved at all
+    pub fn accuracy(&self, color: Color) -> Option<f32> {
+        let (count, total) = self
+            .moves_by(color)
+            .fold((0, 0.0), |(count, total), review| {
+                (count + 1, total + review.accuracy)
+            });
+        (count > 0).then(|| total / count as f32)
+    }
+
+    pub fn count(&self, color: Color, judgement: Judgement) -> usize {
+        self.moves_by(color)
+            .filter(|review| review.judgement == Some(judgement))
+            .count()
+    }
+}
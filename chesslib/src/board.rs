@@ -3,19 +3,135 @@ mod mailbox;
 use std::{fmt::Display, iter::repeat, str::FromStr};
 
 use crate::{
-    errors::{InvalidCharError, InvalidValueError, ParseFenError},
-    pieces::{Color, Figure, Piece, constants::*},
+    errors::{FenField, InvalidCharError, InvalidValueError, ParseFenError, ParseSquareError},
+    geometry::{BoardGeometry, SquareSet, Standard},
+    pieces::{Color, Figure, Officer, Piece},
 };
 use bitboard::BitBoard;
 use mailbox::MailBox;
 use std::ops::{Index, IndexMut};
 
-#[derive(Debug, PartialEq, Eq)]
-pub struct Board {
-    white_pieces: PieceSet,
-    black_pieces: PieceSet,
-    occupied: BitBoard,
-    mailbox: MailBox,
+// one square's contents going from one board to another
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct SquareChange {
+    pub square: Square,
+    pub before: Option<Piece>,
+    pub after: Option<Piece>,
+}
+
+// the pieces on a board of shape `G`. The 8x8 `Board` everything else plays on has the
+// attack tables and move generation; any shape can hold and place pieces
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Board<G: BoardGeometry = Standard> {
+    figures: [G::BitBoard; 6],
+    colors: [G::BitBoard; 2],
+    mailbox: MailBox<G>,
+}
+
+impl<G: BoardGeometry> Board<G> {
+    pub fn empty() -> Self {
+        Self {
+            figures: [G::BitBoard::default(); 6],
+            colors: [G::BitBoard::default(); 2],
+            mailbox: MailBox::default(),
+        }
+    }
+
+    // squares by index, a1 first along the ranks; None past the last one
+    pub fn piece_at(&self, index: u8) -> Option<Piece> {
+        self.mailbox.get_index(index)
+    }
+
+    pub fn put_piece(&mut self, index: u8, piece: Option<Piece>) -> Option<Piece> {
+        if index >= G::GEOMETRY.squares() {
+            return None;
+        }
+        let before = self.mailbox.set_index(index, piece);
+        let bit = G::BitBoard::from_index(index);
+        if let Some(before) = before {
+            self.figures[before.figure] &= !bit;
+            self.colors[before.color] &= !bit;
+        }
+        if let Some(piece) = piece {
+            self.figures[piece.figure] |= bit;
+            self.colors[piece.color] |= bit;
+        }
+        before
+    }
+
+    pub fn piece_squares(&self, piece: Piece) -> G::BitBoard {
+        self.figures[piece.figure] & self.colors[piece.color]
+    }
+
+    pub fn color_squares(&self, color: Color) -> G::BitBoard {
+        self.colors[color]
+    }
+
+    // a FEN placement for the shape, top rank first, where a run of empty squares may take
+    // two digits
+    pub fn from_placement(placement: &str) -> Result<Self, ParseFenError> {
+        let geometry = G::GEOMETRY;
+        let invalid = || ParseFenError::InvalidField {
+            field: FenField::Placement,
+            value: placement.to_owned(),
+        };
+        let rows: Vec<&str> = placement.split('/').collect();
+        if rows.len() != geometry.ranks() as usize {
+            return Err(invalid());
+        }
+        let mut board = Self::empty();
+        for (row, rank) in rows.iter().zip((0..geometry.ranks()).rev()) {
+            let mut file = 0u8;
+            let mut empty = 0u8;
+            for (index, c) in row.chars().enumerate() {
+                if let Some(digit) = c.to_digit(10) {
+                    empty = (empty.checked_mul(10))
+                        .and_then(|empty| empty.checked_add(digit as u8))
+                        .filter(|&empty| empty > 0 && file + empty <= geometry.files())
+                        .ok_or_else(invalid)?;
+                    continue;
+                }
+                file += std::mem::take(&mut empty);
+                let piece = Piece::try_from(c).map_err(|source| ParseFenError::InvalidPiece {
+                    rank: rank + 1,
+                    index: index + 1,
+                    source,
+                })?;
+                let index = geometry.index(file, rank).ok_or_else(invalid)?;
+                board.put_piece(index, Some(piece));
+                file += 1;
+            }
+            if file + empty != geometry.files() {
+                return Err(invalid());
+            }
+        }
+        Ok(board)
+    }
+
+    pub fn to_placement(&self) -> String {
+        let geometry = G::GEOMETRY;
+        let rows = (0..geometry.ranks()).rev().map(|rank| {
+            let mut row = String::new();
+            let mut empty = 0;
+            for file in 0..geometry.files() {
+                let piece = geometry.index(file, rank).and_then(|i| self.piece_at(i));
+                match piece {
+                    Some(piece) => {
+                        if empty > 0 {
+                            row.push_str(&std::mem::take(&mut empty).to_string());
+                        }
+                        row.push(piece.into());
+                    }
+                    None => empty += 1,
+                }
+            }
+            if empty > 0 {
+                row.push_str(&empty.to_string());
+            }
+            row
+        });
+        rows.collect::<Vec<_>>().join("/")
+    }
 }
 
 impl Default for Board {
@@ -27,9 +143,8 @@ impl Default for Board {
 impl Board {
     pub fn new() -> Self {
         Self {
-            white_pieces: PieceSet::new(Color::White),
-            black_pieces: PieceSet::new(Color::Black),
-            occupied: BitBoard::default(),
+            figures: [BitBoard::default(); 6],
+            colors: [BitBoard::default(); 2],
             mailbox: MailBox::default(),
         }
     }
@@ -96,7 +211,7 @@ impl Board {
             return true;
         }
 
-        let enemy_pawn_mask = self.pawn_moves(square, turn);
+        let enemy_pawn_mask = BitBoard::pawn_attacks(square, turn);
         let enemy_pawn_location = self.get_piece_board(Piece {
             color: !turn,
             figure: Figure::Pawn,
@@ -109,7 +224,7 @@ impl Board {
             color: !turn,
             figure: Figure::Rook,
         }) {
-            if self.is_pseudo::<{ Figure::Rook }>(rook_sq, square, !turn) {
+            if self.is_pseudo(Officer::Rook, rook_sq, square, !turn) {
                 return true;
             }
         }
@@ -118,7 +233,7 @@ impl Board {
             color: !turn,
             figure: Figure::Bishop,
         }) {
-            if self.is_pseudo::<{ Figure::Bishop }>(bishop_sq, square, !turn) {
+            if self.is_pseudo(Officer::Bishop, bishop_sq, square, !turn) {
                 return true;
             }
         }
@@ -127,15 +242,109 @@ impl Board {
             color: !turn,
             figure: Figure::Queen,
         }) {
-            if self.is_pseudo::<{ Figure::Queen }>(queen_sq, square, !turn) {
+            if self.is_pseudo(Officer::Queen, queen_sq, square, !turn) {
                 return true;
             }
         }
         false
     }
 
+    // the pieces of `color` hitting `square`, sliders seeing through anything left out of `occupied`
+    pub fn attackers_to(&self, square: Square, color: Color, occupied: BitBoard) -> BitBoard {
+        let board = |figure| self.get_piece_board(Piece { color, figure });
+        let queens = board(Figure::Queen);
+        (BitBoard::king_moves(square) & board(Figure::King))
+            | (BitBoard::knight_moves(square) & board(Figure::Knight))
+            | (BitBoard::pawn_attacks(square, !color) & board(Figure::Pawn))
+            | (BitBoard::rook_attacks(square, occupied) & (board(Figure::Rook) | queens))
+            | (BitBoard::bishop_attacks(square, occupied) & (board(Figure::Bishop) | queens))
+    }
+
+    // every square the king walks over while castling, both ends included, is out of reach of
+    // the enemy with the king already lifted off its start square
+    pub(crate) fn path_is_safe(&self, king_from: Square, king_to: Square, turn: Color) -> bool {
+        let path = BitBoard::straight_ray(king_from, king_to) | king_to.into();
+        let occupied = self.occupied() - king_from.into();
+        path.iter()
+            .all(|square| self.attackers_to(square, !turn, occupied).empty())
+    }
+
+    // every square `color` attacks or defends, pins ignored
+    pub fn attack_map(&self, color: Color) -> BitBoard {
+        let occupied = self.occupied();
+        self.iter_color(color)
+            .fold(BitBoard::default(), |acc, (square, piece)| {
+                acc | BitBoard::attacks(piece, square, occupied)
+            })
+    }
+
+    // `color`'s pieces other than the king that are attacked and not defended
+    pub fn hanging_pieces(&self, color: Color) -> BitBoard {
+        self.occupied_color(color)
+            & !self.pieces(Figure::King, color)
+            & self.attack_map(!color)
+            & !self.attack_map(color)
+    }
+
+    pub fn has_opposite_colored_bishops(&self) -> bool {
+        let white = self.pieces(Figure::Bishop, Color::White);
+        let black = self.pieces(Figure::Bishop, Color::Black);
+        white.count_squares() == 1
+            && black.count_squares() == 1
+            && ((white & BitBoard::DARK).empty() != (black & BitBoard::DARK).empty())
+    }
+
+    pub fn passed_pawns(&self, color: Color) -> BitBoard {
+        let enemy_front = self.pawns(!color).front_span(!color);
+        let blocked = enemy_front | enemy_front.shift::<-1, 0>() | enemy_front.shift::<1, 0>();
+        self.pawns(color) & !blocked
+    }
+
+    pub fn isolated_pawns(&self, color: Color) -> BitBoard {
+        let pawns = self.pawns(color);
+        let files = pawns.file_fill();
+        pawns & !(files.shift::<-1, 0>() | files.shift::<1, 0>())
+    }
+
+    pub fn doubled_pawns(&self, color: Color) -> BitBoard {
+        let pawns = self.pawns(color);
+        pawns & (pawns.front_span(color) | pawns.rear_span(color))
+    }
+
+    pub fn backward_pawns(&self, color: Color) -> BitBoard {
+        let pawns = self.pawns(color);
+        let enemy_attacks = self.pawns(!color).pawn_attack_set(!color);
+        let support = pawns.attack_span(color);
+        match color {
+            Color::White => (pawns.shift::<0, 1>() & enemy_attacks & !support).shift::<0, -1>(),
+            Color::Black => (pawns.shift::<0, -1>() & enemy_attacks & !support).shift::<0, 1>(),
+        }
+    }
+
+    // squares guarded by an own pawn that no enemy pawn can ever attack
+    pub fn outposts(&self, color: Color) -> BitBoard {
+        self.pawns(color).pawn_attack_set(color) & !self.pawns(!color).attack_span(!color)
+    }
+
+    // holes: squares on the third and fourth ranks that own pawns can no longer guard
+    pub fn weak_squares(&self, color: Color) -> BitBoard {
+        let ranks = match color {
+            Color::White => BitBoard::from(Row::Three) | Row::Four.into(),
+            Color::Black => BitBoard::from(Row::Six) | Row::Five.into(),
+        };
+        ranks & !self.pawns(color).attack_span(color)
+    }
+
     pub fn iter(&self) -> impl Iterator<Item = (Square, Piece)> {
-        self.white_pieces.iter().chain(self.black_pieces.iter())
+        self.iter_color(Color::White)
+            .chain(self.iter_color(Color::Black))
+    }
+
+    pub fn iter_color(&self, color: Color) -> impl Iterator<Item = (Square, Piece)> {
+        Figure::iter().flat_map(move |&figure| {
+            let piece = Piece { color, figure };
+            self.iter_piece(piece).zip(repeat(piece))
+        })
     }
 
     pub fn iter_piece(&self, piece: Piece) -> impl Iterator<Item = Square> {
@@ -143,126 +352,149 @@ impl Board {
     }
 
     pub fn try_from_fen(fen: &str) -> Result<Self, ParseFenError> {
-        let piece_data = fen.split(' ').next().ok_or(ParseFenError::EmptyFen)?;
+        let piece_data = fen.split(' ').next().unwrap_or_default();
         let row_data = piece_data.split('/');
-        if row_data.clone().count() != 8 {
-            return Err(ParseFenError::WrongRowCount);
+        let row_count = row_data.clone().count();
+        if row_count != 8 {
+            return Err(ParseFenError::WrongRowCount(row_count));
         }
         let mut board = Self::new();
-        for (row_idx, row) in (0..8).rev().zip(row_data) {
+        for (row_idx, row) in (0..8u8).rev().zip(row_data) {
+            let rank = row_idx + 1;
             let mut col_idx = 0;
-            for c in row.chars() {
-                if c.is_ascii_digit() {
-                    col_idx += c.to_digit(10).unwrap() as u8;
+            for (index, c) in row.chars().enumerate() {
+                if let Some(empty) = c.to_digit(10)
+                    && (1..=8).contains(&empty)
+                {
+                    if col_idx + empty as u8 > 8 {
+                        return Err(ParseFenError::WrongRowLength { rank });
+                    }
+                    col_idx += empty as u8;
                 } else {
-                    let piece = Piece::try_from(c)?;
-                    let square = Square::from_coords(col_idx.try_into()?, row_idx.try_into()?);
+                    let piece =
+                        Piece::try_from(c).map_err(|source| ParseFenError::InvalidPiece {
+                            rank,
+                            index: index + 1,
+                            source,
+                        })?;
+                    if col_idx >= 8 {
+                        return Err(ParseFenError::WrongRowLength { rank });
+                    }
+                    // Safety: col_idx and row_idx are < 8
+                    let square = unsafe { Square::from_u8_unchecked(8 * row_idx + col_idx) };
                     board.set_sq(square, piece);
                     col_idx += 1;
                 }
             }
+            if col_idx != 8 {
+                return Err(ParseFenError::WrongRowLength { rank });
+            }
         }
         Ok(board)
     }
 
-    pub fn is_pseudo<const FIGURE: Figure>(&self, from: Square, to: Square, turn: Color) -> bool {
-        use Figure::*;
-        match FIGURE {
+    pub fn is_pseudo(&self, officer: Officer, from: Square, to: Square, turn: Color) -> bool {
+        use Officer::*;
+        match officer {
             Knight => (BitBoard::knight_moves(from) & !self.occupied_color(turn)).contains(to),
             Rook => {
-                let is_cleared = BitBoard::straight_ray(from, to) & self.occupied == from.into();
+                let is_cleared = BitBoard::straight_ray(from, to) & self.occupied() == from.into();
                 is_cleared && !self.occupied_color(turn).contains(to)
             }
             Bishop => {
-                let is_cleared = BitBoard::diag_ray(from, to) & self.occupied == from.into();
+                let is_cleared = BitBoard::diag_ray(from, to) & self.occupied() == from.into();
                 is_cleared && !self.occupied_color(turn).contains(to)
             }
             Queen => {
                 let is_cleared = (BitBoard::straight_ray(from, to) | BitBoard::diag_ray(from, to))
-                    & self.occupied
+                    & self.occupied()
                     == from.into();
                 is_cleared && !self.occupied_color(turn).contains(to)
             }
-            _ => todo!(),
         }
     }
 
     pub fn pawn_moves(&self, from: Square, turn: Color) -> BitBoard {
         let attacks = BitBoard::pawn_attacks(from, turn) & self.occupied_color(!turn);
-        let moves = match turn {
-            Color::White => {
-                let mut moves = BitBoard::from(from).shift::<0, 1>() & !self.occupied;
-                moves |= moves.shift::<0, 1>() & Row::Four.into() & !self.occupied;
-                moves
-            }
-            Color::Black => {
-                let mut moves = BitBoard::from(from).shift::<0, -1>() & !self.occupied;
-                moves |= moves.shift::<0, -1>() & Row::Five.into() & !self.occupied;
-                moves
-            }
-        };
-        attacks | moves
+        attacks | BitBoard::pawn_pushes(from, turn, self.occupied())
     }
 
     fn clear_piece_board(&mut self, piece: Piece, mask: BitBoard) {
         let should_keep = !mask;
-        *self.get_piece_board_mut(piece) &= should_keep;
-        *self.occupied_color_mut(piece.color) &= should_keep;
-        self.occupied &= should_keep;
+        self.figures[piece.figure] &= should_keep;
+        self.colors[piece.color] &= should_keep;
     }
 
     fn set_piece_board(&mut self, piece: Piece, mask: BitBoard) {
-        *self.get_piece_board_mut(piece) |= mask;
-        *self.occupied_color_mut(piece.color) |= mask;
-        self.occupied |= mask;
+        self.figures[piece.figure] |= mask;
+        self.colors[piece.color] |= mask;
     }
 
-    fn get_piece_board(&self, piece: Piece) -> BitBoard {
-        match piece {
-            WHITE_PAWN => self.white_pieces.pawns,
-            WHITE_ROOK => self.white_pieces.rooks,
-            WHITE_KNIGHT => self.white_pieces.knights,
-            WHITE_BISHOP => self.white_pieces.bishops,
-            WHITE_QUEEN => self.white_pieces.queens,
-            WHITE_KING => self.white_pieces.kings,
-            BLACK_PAWN => self.black_pieces.pawns,
-            BLACK_ROOK => self.black_pieces.rooks,
-            BLACK_KNIGHT => self.black_pieces.knights,
-            BLACK_BISHOP => self.black_pieces.bishops,
-            BLACK_QUEEN => self.black_pieces.queens,
-            BLACK_KING => self.black_pieces.kings,
-        }
+    pub fn pieces(&self, figure: Figure, color: Color) -> BitBoard {
+        self.figures[figure] & self.colors[color]
     }
 
-    fn get_piece_board_mut(&mut self, piece: Piece) -> &mut BitBoard {
-        match piece {
-            WHITE_PAWN => &mut self.white_pieces.pawns,
-            WHITE_ROOK => &mut self.white_pieces.rooks,
-            WHITE_KNIGHT => &mut self.white_pieces.knights,
-            WHITE_BISHOP => &mut self.white_pieces.bishops,
-            WHITE_QUEEN => &mut self.white_pieces.queens,
-            WHITE_KING => &mut self.white_pieces.kings,
-            BLACK_PAWN => &mut self.black_pieces.pawns,
-            BLACK_ROOK => &mut self.black_pieces.rooks,
-            BLACK_KNIGHT => &mut self.black_pieces.knights,
-            BLACK_BISHOP => &mut self.black_pieces.bishops,
-            BLACK_QUEEN => &mut self.black_pieces.queens,
-            BLACK_KING => &mut self.black_pieces.kings,
-        }
+    fn pawns(&self, color: Color) -> BitBoard {
+        self.pieces(Figure::Pawn, color)
+    }
+
+    fn get_piece_board(&self, piece: Piece) -> BitBoard {
+        self.figures[piece.figure] & self.colors[piece.color]
     }
 
     pub fn occupied_color(&self, color: Color) -> BitBoard {
-        match color {
-            Color::White => self.white_pieces.occupied,
-            Color::Black => self.black_pieces.occupied,
-        }
+        self.colors[color]
     }
 
-    fn occupied_color_mut(&mut self, color: Color) -> &mut BitBoard {
-        match color {
-            Color::White => &mut self.white_pieces.occupied,
-            Color::Black => &mut self.black_pieces.occupied,
+    pub fn occupied(&self) -> BitBoard {
+        self.colors[Color::White] | self.colors[Color::Black]
+    }
+}
+
+impl Board {
+    pub fn to_fen(&self) -> String {
+        let mut squares: [Option<Piece>; 64] = [None; 64];
+        for (square, piece) in self.iter() {
+            squares[square] = Some(piece);
         }
+        let mut fen = String::new();
+        for row in squares.chunks(8).rev() {
+            let mut empty = 0;
+            for square in row {
+                match square {
+                    Some(piece) => {
+                        if empty > 0 {
+                            fen.push_str(&empty.to_string());
+                            empty = 0;
+                        }
+                        fen.push((*piece).into());
+                    }
+                    None => empty += 1,
+                }
+            }
+            if empty > 0 {
+                fen.push_str(&empty.to_string());
+            }
+            fen.push('/');
+        }
+        fen.pop();
+        fen
+    }
+
+    // every square that reads differently on `other`, a1 first
+    pub fn diff(&self, other: &Board) -> Vec<SquareChange> {
+        let changed = (0..6).fold(BitBoard::default(), |acc, idx| {
+            acc | (self.figures[idx] ^ other.figures[idx])
+        }) | (self.colors[0] ^ other.colors[0])
+            | (self.colors[1] ^ other.colors[1]);
+        changed
+            .iter()
+            .map(|square| SquareChange {
+                square,
+                before: self.get_sq(square),
+                after: other.get_sq(square),
+            })
+            .collect()
     }
 }
 
@@ -326,7 +558,10 @@ impl TryFrom<char> for Column {
     type Error = InvalidCharError;
 
     fn try_from(val: char) -> Result<Self, Self::Error> {
-        let int_repr = u8::try_from(u32::from(val) - 97).map_err(|_| InvalidCharError(val))?;
+        let int_repr = u32::from(val)
+            .checked_sub(u32::from('a'))
+            .and_then(|v| u8::try_from(v).ok())
+            .ok_or(InvalidCharError(val))?;
         Self::try_from(int_repr).map_err(|_| InvalidCharError(val))
     }
 }
@@ -387,7 +622,10 @@ impl TryFrom<char> for Row {
     type Error = InvalidCharError;
 
     fn try_from(val: char) -> Result<Self, Self::Error> {
-        let int_repr = u8::try_from(u32::from(val) - 49).map_err(|_| InvalidCharError(val))?;
+        let int_repr = u32::from(val)
+            .checked_sub(u32::from('1'))
+            .and_then(|v| u8::try_from(v).ok())
+            .ok_or(InvalidCharError(val))?;
         Self::try_from(int_repr).map_err(|_| InvalidCharError(val))
     }
 }
@@ -421,6 +659,20 @@ pub enum Square {
 }
 
 impl Square {
+    // the 0..63 index, a1 = 0 and h8 = 63, for tables kept per square
+    pub const fn index(self) -> usize {
+        self as usize
+    }
+
+    pub const fn new(index: usize) -> Option<Self> {
+        if index < 64 {
+            // Safety: index < 64
+            unsafe { Some(Self::from_u8_unchecked(index as u8)) }
+        } else {
+            None
+        }
+    }
+
     pub const fn from_coords(col: Column, row: Row) -> Self {
         // Safety: 8 * row + col < 64
         unsafe { Self::from_u8_unchecked(8 * row as u8 + col as u8) }
@@ -436,6 +688,10 @@ impl Square {
         unsafe { Row::from_u8_unchecked(self as u8 >> 3) }
     }
 
+    pub const fn is_dark(self) -> bool {
+        (self.col() as u8 + self.row() as u8) & 1 == 0
+    }
+
     pub const fn shift<const COLS: i8, const ROWS: i8>(self) -> Option<Self> {
         let (new_col, new_row) = (self.col() as i8 + COLS, self.row() as i8 + ROWS);
         if 0 <= new_col && new_col < 8 && 0 <= new_row && new_row < 8 {
@@ -445,30 +701,53 @@ impl Square {
         }
     }
 
-    const unsafe fn from_u8_unchecked(val: u8) -> Self {
+    pub(crate) const unsafe fn from_u8_unchecked(val: u8) -> Self {
         // Safety: val must be < 64
         unsafe { std::mem::transmute::<u8, Self>(val) }
     }
 }
 
+impl TryFrom<u8> for Square {
+    type Error = InvalidValueError;
+
+    fn try_from(val: u8) -> Result<Self, Self::Error> {
+        Self::new(val as usize).ok_or(InvalidValueError(val))
+    }
+}
+
+impl From<Square> for usize {
+    fn from(square: Square) -> Self {
+        square.index()
+    }
+}
+
 impl FromStr for Square {
-    type Err = ParseFenError;
+    type Err = ParseSquareError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let chars: Vec<char> = s.chars().collect();
         if chars.len() != 2 {
-            return Err(ParseFenError::InvalidString(s.into()));
+            return Err(ParseSquareError(s.into()));
         }
         let col = chars[0]
             .try_into()
-            .map_err(|_| ParseFenError::InvalidString(s.into()))?;
+            .map_err(|_| ParseSquareError(s.into()))?;
         let row = chars[1]
             .try_into()
-            .map_err(|_| ParseFenError::InvalidString(s.into()))?;
+            .map_err(|_| ParseSquareError(s.into()))?;
         Ok(Self::from_coords(col, row))
     }
 }
 
+impl Display for Square {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let col = (b'a' + self.col() as u8) as char;
+        let row = (b'1' + self.row() as u8) as char;
+        write!(f, "{col}{row}")
+    }
+}
+
+// arrays pick this up through std's `[T; N]: Index<I> where [T]: Index<I>`
 impl<T> Index<Square> for [T] {
     type Output = T;
 
@@ -483,62 +762,5 @@ impl<T> IndexMut<Square> for [T] {
     }
 }
 
-#[derive(Debug, PartialEq, Eq)]
-struct PieceSet {
-    color: Color,
-    pawns: BitBoard,
-    rooks: BitBoard,
-    knights: BitBoard,
-    bishops: BitBoard,
-    queens: BitBoard,
-    kings: BitBoard,
-    occupied: BitBoard,
-}
-
-impl PieceSet {
-    fn new(color: Color) -> Self {
-        Self {
-            color,
-            pawns: BitBoard::default(),
-            rooks: BitBoard::default(),
-            knights: BitBoard::default(),
-            bishops: BitBoard::default(),
-            queens: BitBoard::default(),
-            kings: BitBoard::default(),
-            occupied: BitBoard::default(),
-        }
-    }
-
-    fn iter(&self) -> impl Iterator<Item = (Square, Piece)> {
-        let color = self.color;
-        self.pawns
-            .iter()
-            .zip(repeat(Piece {
-                color,
-                figure: Figure::Pawn,
-            }))
-            .chain(self.rooks.iter().zip(repeat(Piece {
-                color,
-                figure: Figure::Rook,
-            })))
-            .chain(self.knights.iter().zip(repeat(Piece {
-                color,
-                figure: Figure::Knight,
-            })))
-            .chain(self.bishops.iter().zip(repeat(Piece {
-                color,
-                figure: Figure::Bishop,
-            })))
-            .chain(self.queens.iter().zip(repeat(Piece {
-                color,
-                figure: Figure::Queen,
-            })))
-            .chain(self.kings.iter().zip(repeat(Piece {
-                color,
-                figure: Figure::King,
-            })))
-    }
-}
-
 #[cfg(test)]
 mod tests;
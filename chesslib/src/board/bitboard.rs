@@ -1,6 +1,14 @@
+#[cfg(all(feature = "pext", target_arch = "x86_64"))]
+mod pext;
+
 use super::{Column, Row, Square};
-use crate::pieces::Color;
-use std::ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Not};
+use crate::{
+    geometry::SquareSet,
+    pieces::{Color, Figure, Piece},
+};
+use std::ops::{
+    BitAnd, BitAndAssign, BitOr, BitOrAssign, BitXor, BitXorAssign, Not, Sub, SubAssign,
+};
 
 macro_rules! gen_table {
     ($mask_fn:expr $(,$arg0:expr)*) => {{
@@ -20,6 +28,20 @@ static KING_MOVES: [BitBoard; 64] = gen_table!(BitBoard::king_move_mask);
 static KNIGHT_MOVES: [BitBoard; 64] = gen_table!(BitBoard::knight_move_mask);
 static WHITE_PAWN_ATTACKS: [BitBoard; 64] = gen_table!(BitBoard::pawn_attack_mask, Color::White);
 static BLACK_PAWN_ATTACKS: [BitBoard; 64] = gen_table!(BitBoard::pawn_attack_mask, Color::Black);
+static WHITE_PAWN_PUSHES: [BitBoard; 64] = gen_table!(BitBoard::pawn_push_mask, Color::White);
+static BLACK_PAWN_PUSHES: [BitBoard; 64] = gen_table!(BitBoard::pawn_push_mask, Color::Black);
+static WHITE_PAWN_DOUBLE_PUSHES: [BitBoard; 64] =
+    gen_table!(BitBoard::pawn_double_push_mask, Color::White);
+static BLACK_PAWN_DOUBLE_PUSHES: [BitBoard; 64] =
+    gen_table!(BitBoard::pawn_double_push_mask, Color::Black);
+static NORTH_RAYS: [BitBoard; 64] = gen_table!(BitBoard::ray_mask::<0, 1>);
+static EAST_RAYS: [BitBoard; 64] = gen_table!(BitBoard::ray_mask::<1, 0>);
+static SOUTH_RAYS: [BitBoard; 64] = gen_table!(BitBoard::ray_mask::<0, -1>);
+static WEST_RAYS: [BitBoard; 64] = gen_table!(BitBoard::ray_mask::<-1, 0>);
+static NORTH_EAST_RAYS: [BitBoard; 64] = gen_table!(BitBoard::ray_mask::<1, 1>);
+static SOUTH_EAST_RAYS: [BitBoard; 64] = gen_table!(BitBoard::ray_mask::<1, -1>);
+static SOUTH_WEST_RAYS: [BitBoard; 64] = gen_table!(BitBoard::ray_mask::<-1, -1>);
+static NORTH_WEST_RAYS: [BitBoard; 64] = gen_table!(BitBoard::ray_mask::<-1, 1>);
 static STRAIGHT_MOVES: [[BitBoard; 64]; 64] = gen_straight_moves();
 static DIAG_MOVES: [[BitBoard; 64]; 64] = gen_diag_moves();
 
@@ -27,6 +49,9 @@ static DIAG_MOVES: [[BitBoard; 64]; 64] = gen_diag_moves();
 pub struct BitBoard(u64);
 
 impl BitBoard {
+    pub const DARK: Self = Self(0xaa55_aa55_aa55_aa55);
+    pub const LIGHT: Self = Self(!Self::DARK.0);
+
     pub fn king_moves(square: Square) -> Self {
         KING_MOVES[square]
     }
@@ -50,6 +75,64 @@ impl BitBoard {
         }
     }
 
+    pub fn pawn_pushes(square: Square, color: Color, occupied: BitBoard) -> Self {
+        let (single, double) = match color {
+            Color::White => (WHITE_PAWN_PUSHES[square], WHITE_PAWN_DOUBLE_PUSHES[square]),
+            Color::Black => (BLACK_PAWN_PUSHES[square], BLACK_PAWN_DOUBLE_PUSHES[square]),
+        };
+        let single = single & !occupied;
+        if single.empty() {
+            return single;
+        }
+        single | (double & !occupied)
+    }
+
+    // slider attacks from `square` given whatever blocks them, own pieces included
+    pub fn rook_attacks(square: Square, occupied: BitBoard) -> Self {
+        #[cfg(all(feature = "pext", target_arch = "x86_64"))]
+        if let Some(attacks) = pext::rook_attacks(square, occupied) {
+            return attacks;
+        }
+        Self::ray_rook_attacks(square, occupied)
+    }
+
+    pub fn bishop_attacks(square: Square, occupied: BitBoard) -> Self {
+        #[cfg(all(feature = "pext", target_arch = "x86_64"))]
+        if let Some(attacks) = pext::bishop_attacks(square, occupied) {
+            return attacks;
+        }
+        Self::ray_bishop_attacks(square, occupied)
+    }
+
+    pub fn queen_attacks(square: Square, occupied: BitBoard) -> Self {
+        Self::rook_attacks(square, occupied) | Self::bishop_attacks(square, occupied)
+    }
+
+    pub fn attacks(piece: Piece, square: Square, occupied: BitBoard) -> Self {
+        match piece.figure {
+            Figure::Pawn => Self::pawn_attacks(square, piece.color),
+            Figure::Knight => Self::knight_moves(square),
+            Figure::Bishop => Self::bishop_attacks(square, occupied),
+            Figure::Rook => Self::rook_attacks(square, occupied),
+            Figure::Queen => Self::queen_attacks(square, occupied),
+            Figure::King => Self::king_moves(square),
+        }
+    }
+
+    fn ray_rook_attacks(square: Square, occupied: BitBoard) -> Self {
+        Self::positive_ray_attacks(&NORTH_RAYS, square, occupied)
+            | Self::positive_ray_attacks(&EAST_RAYS, square, occupied)
+            | Self::negative_ray_attacks(&SOUTH_RAYS, square, occupied)
+            | Self::negative_ray_attacks(&WEST_RAYS, square, occupied)
+    }
+
+    fn ray_bishop_attacks(square: Square, occupied: BitBoard) -> Self {
+        Self::positive_ray_attacks(&NORTH_EAST_RAYS, square, occupied)
+            | Self::positive_ray_attacks(&NORTH_WEST_RAYS, square, occupied)
+            | Self::negative_ray_attacks(&SOUTH_EAST_RAYS, square, occupied)
+            | Self::negative_ray_attacks(&SOUTH_WEST_RAYS, square, occupied)
+    }
+
     pub fn count_squares(&self) -> u8 {
         self.0.count_ones() as u8
     }
@@ -63,10 +146,38 @@ impl BitBoard {
         (*self & sq) == sq
     }
 
-    pub fn iter(self) -> impl Iterator<Item = Square> {
+    pub const fn more_than_one(&self) -> bool {
+        self.0 & self.0.wrapping_sub(1) != 0
+    }
+
+    pub const fn is_subset(&self, other: Self) -> bool {
+        self.0 & !other.0 == 0
+    }
+
+    // the squares of self that aren't in rhs
+    pub const fn difference(self, rhs: Self) -> Self {
+        Self(self.0 & !rhs.0)
+    }
+
+    // lowest square first, `.rev()` for highest first
+    pub fn iter(self) -> impl DoubleEndedIterator<Item = Square> {
         BitBoardIter { rem_board: self }
     }
 
+    pub const fn pop_lsb(&mut self) -> Option<Square> {
+        let lsb = self.bitscan_forward();
+        self.0 &= self.0.wrapping_sub(1);
+        lsb
+    }
+
+    pub const fn pop_msb(&mut self) -> Option<Square> {
+        let msb = self.bitscan_reverse();
+        if let Some(square) = msb {
+            self.0 ^= 1 << square as u8;
+        }
+        msb
+    }
+
     const fn from_col(c: Column) -> Self {
         Self(0x0101010101010101 << c as u8)
     }
@@ -79,6 +190,52 @@ impl BitBoard {
         Self(1 << s as u8)
     }
 
+    pub const fn north_fill(self) -> Self {
+        let mut fill = self.0;
+        fill |= fill << 8;
+        fill |= fill << 16;
+        fill |= fill << 32;
+        Self(fill)
+    }
+
+    pub const fn south_fill(self) -> Self {
+        let mut fill = self.0;
+        fill |= fill >> 8;
+        fill |= fill >> 16;
+        fill |= fill >> 32;
+        Self(fill)
+    }
+
+    pub const fn file_fill(self) -> Self {
+        self.north_fill().or(self.south_fill())
+    }
+
+    pub const fn front_span(self, color: Color) -> Self {
+        match color {
+            Color::White => self.shift::<0, 1>().north_fill(),
+            Color::Black => self.shift::<0, -1>().south_fill(),
+        }
+    }
+
+    pub const fn rear_span(self, color: Color) -> Self {
+        match color {
+            Color::White => self.shift::<0, -1>().south_fill(),
+            Color::Black => self.shift::<0, 1>().north_fill(),
+        }
+    }
+
+    pub const fn attack_span(self, color: Color) -> Self {
+        let span = self.front_span(color);
+        span.shift::<-1, 0>().or(span.shift::<1, 0>())
+    }
+
+    pub const fn pawn_attack_set(self, color: Color) -> Self {
+        match color {
+            Color::White => self.shift::<-1, 1>().or(self.shift::<1, 1>()),
+            Color::Black => self.shift::<-1, -1>().or(self.shift::<1, -1>()),
+        }
+    }
+
     pub const fn shift<const COLS: i8, const ROWS: i8>(self) -> Self {
         if ROWS.abs() >= 8 || COLS.abs() >= 8 {
             return Self(0);
@@ -128,7 +285,54 @@ impl BitBoard {
         left.or(right)
     }
 
-    const fn bitscan_forward(&self) -> Option<Square> {
+    const fn ray_mask<const COLS: i8, const ROWS: i8>(square: Square) -> Self {
+        let mut ray = Self(0);
+        let mut next = square.shift::<COLS, ROWS>();
+        while let Some(sq) = next {
+            ray.or_assign(Self::from_square(sq));
+            next = sq.shift::<COLS, ROWS>();
+        }
+        ray
+    }
+
+    // rays pointing towards higher square indices are cut at their first blocker
+    fn positive_ray_attacks(rays: &[BitBoard; 64], square: Square, occupied: BitBoard) -> Self {
+        let ray = rays[square];
+        match (ray & occupied).bitscan_forward() {
+            Some(blocker) => ray ^ rays[blocker],
+            None => ray,
+        }
+    }
+
+    fn negative_ray_attacks(rays: &[BitBoard; 64], square: Square, occupied: BitBoard) -> Self {
+        let ray = rays[square];
+        match (ray & occupied).bitscan_reverse() {
+            Some(blocker) => ray ^ rays[blocker],
+            None => ray,
+        }
+    }
+
+    const fn pawn_push_mask(square: Square, color: Color) -> Self {
+        let square = Self::from_square(square);
+        match color {
+            Color::White => square.shift::<0, 1>(),
+            Color::Black => square.shift::<0, -1>(),
+        }
+    }
+
+    const fn pawn_double_push_mask(square: Square, color: Color) -> Self {
+        let (start_row, shifted) = match color {
+            Color::White => (Row::Two, Self::from_square(square).shift::<0, 2>()),
+            Color::Black => (Row::Seven, Self::from_square(square).shift::<0, -2>()),
+        };
+        if square.row() as u8 == start_row as u8 {
+            shifted
+        } else {
+            Self(0)
+        }
+    }
+
+    pub const fn bitscan_forward(&self) -> Option<Square> {
         match self.0.trailing_zeros() {
             64 => None,
             // Safety: x < 64
@@ -136,6 +340,14 @@ impl BitBoard {
         }
     }
 
+    pub const fn bitscan_reverse(&self) -> Option<Square> {
+        match self.0.leading_zeros() {
+            64 => None,
+            // Safety: x < 64
+            x => unsafe { Some(Square::from_u8_unchecked(63 - x as u8)) },
+        }
+    }
+
     const fn and(self, rhs: Self) -> Self {
         Self(self.0 & rhs.0)
     }
@@ -165,6 +377,20 @@ impl BitBoard {
     }
 }
 
+impl SquareSet for BitBoard {
+    fn from_index(index: u8) -> Self {
+        Self(1 << index)
+    }
+
+    fn has_index(self, index: u8) -> bool {
+        self.0 >> index & 1 == 1
+    }
+
+    fn indices(self) -> impl Iterator<Item = u8> {
+        self.iter().map(|square| square as u8)
+    }
+}
+
 impl From<Square> for BitBoard {
     fn from(s: Square) -> Self {
         Self::from_square(s)
@@ -211,6 +437,19 @@ impl Not for BitBoard {
     }
 }
 
+impl Sub for BitBoard {
+    type Output = Self;
+    fn sub(self, rhs: Self) -> Self::Output {
+        self.difference(rhs)
+    }
+}
+
+impl SubAssign for BitBoard {
+    fn sub_assign(&mut self, rhs: Self) {
+        *self = self.difference(rhs);
+    }
+}
+
 impl BitAndAssign for BitBoard {
     fn bitand_assign(&mut self, rhs: Self) {
         self.and_assign(rhs);
@@ -236,9 +475,13 @@ struct BitBoardIter {
 impl Iterator for BitBoardIter {
     type Item = Square;
     fn next(&mut self) -> Option<Self::Item> {
-        self.rem_board.bitscan_forward().inspect(|&lsb| {
-            self.rem_board ^= BitBoard::from(lsb);
-        })
+        self.rem_board.pop_lsb()
+    }
+}
+
+impl DoubleEndedIterator for BitBoardIter {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.rem_board.pop_msb()
     }
 }
 
@@ -476,6 +719,106 @@ mod tests {
         assert_eq!(pawn_attacks, expected);
     }
 
+    #[test]
+    fn test_pawn_pushes() {
+        let empty = BitBoard(0);
+        let pushes = BitBoard::pawn_pushes(Square::E2, Color::White, empty);
+        let expected = BitBoard::from(Square::E3) | Square::E4.into();
+        assert_eq!(pushes, expected);
+
+        let pushes = BitBoard::pawn_pushes(Square::E3, Color::White, empty);
+        assert_eq!(pushes, BitBoard::from(Square::E4));
+
+        let pushes = BitBoard::pawn_pushes(Square::E2, Color::White, Square::E4.into());
+        assert_eq!(pushes, BitBoard::from(Square::E3));
+
+        let pushes = BitBoard::pawn_pushes(Square::E2, Color::White, Square::E3.into());
+        assert_eq!(pushes, BitBoard(0));
+
+        let pushes = BitBoard::pawn_pushes(Square::D7, Color::Black, empty);
+        let expected = BitBoard::from(Square::D6) | Square::D5.into();
+        assert_eq!(pushes, expected);
+
+        let pushes = BitBoard::pawn_pushes(Square::D1, Color::Black, empty);
+        assert_eq!(pushes, BitBoard(0));
+    }
+
+    #[test]
+    fn test_slider_attacks() {
+        let occupied = BitBoard::from(Square::D6) | Square::B4.into() | Square::G4.into();
+        let rook_attacks = BitBoard::rook_attacks(Square::D4, occupied);
+        let expected = BitBoard::from(Square::D1)
+            | Square::D2.into()
+            | Square::D3.into()
+            | Square::D5.into()
+            | Square::D6.into()
+            | Square::B4.into()
+            | Square::C4.into()
+            | Square::E4.into()
+            | Square::F4.into()
+            | Square::G4.into();
+        assert_eq!(rook_attacks, expected);
+
+        let occupied = BitBoard::from(Square::B2) | Square::F6.into();
+        let bishop_attacks = BitBoard::bishop_attacks(Square::D4, occupied);
+        let expected = BitBoard::from(Square::B2)
+            | Square::C3.into()
+            | Square::E5.into()
+            | Square::F6.into()
+            | Square::A7.into()
+            | Square::B6.into()
+            | Square::C5.into()
+            | Square::E3.into()
+            | Square::F2.into()
+            | Square::G1.into();
+        assert_eq!(bishop_attacks, expected);
+
+        let rook_attacks = BitBoard::rook_attacks(Square::A1, BitBoard(0));
+        let expected = (BitBoard::from(Row::One) | Column::A.into()) ^ Square::A1.into();
+        assert_eq!(rook_attacks, expected);
+
+        let occupied = BitBoard::from(Square::D6) | Square::F6.into() | Square::B2.into();
+        let queen_attacks = BitBoard::queen_attacks(Square::D4, occupied);
+        assert_eq!(
+            queen_attacks,
+            BitBoard::rook_attacks(Square::D4, occupied)
+                | BitBoard::bishop_attacks(Square::D4, occupied)
+        );
+        assert!(queen_attacks.contains(Square::D6) && !queen_attacks.contains(Square::D7));
+        assert!(queen_attacks.contains(Square::F6) && !queen_attacks.contains(Square::G7));
+        assert!(queen_attacks.contains(Square::B2) && !queen_attacks.contains(Square::A1));
+        assert_eq!(
+            BitBoard::queen_attacks(Square::D4, BitBoard(0)).count_squares(),
+            27
+        );
+    }
+
+    #[test]
+    fn test_fills_and_spans() {
+        let pawns = BitBoard::from(Square::C3) | Square::F6.into();
+        let c_file = BitBoard::from(Column::C);
+        let f_file = BitBoard::from(Column::F);
+        assert_eq!(pawns.file_fill(), c_file | f_file);
+
+        let front = pawns.front_span(Color::White);
+        assert!(front.contains(Square::C4) && front.contains(Square::C8));
+        assert!(!front.contains(Square::C3) && !front.contains(Square::F6));
+        assert_eq!(front.count_squares(), 5 + 2);
+        assert_eq!(pawns.rear_span(Color::Black), front);
+
+        let rear = pawns.rear_span(Color::White);
+        assert_eq!(rear.count_squares(), 2 + 5);
+        assert_eq!(front | rear | pawns, pawns.file_fill());
+
+        let span = BitBoard::from(Square::A6).attack_span(Color::White);
+        assert_eq!(span, BitBoard::from(Square::B7) | Square::B8.into());
+
+        let attacks = pawns.pawn_attack_set(Color::Black);
+        let expected =
+            BitBoard::from(Square::B2) | Square::D2.into() | Square::E5.into() | Square::G5.into();
+        assert_eq!(attacks, expected);
+    }
+
     #[test]
     fn test_bitscan_forward() {
         let bitboard = BitBoard::from(Row::One);
@@ -502,7 +845,7 @@ mod tests {
         let lsb = bitboard.bitscan_forward();
         assert_eq!(lsb, Some(Square::H8));
 
-        let bitboard = BitBoard(std::u64::MAX);
+        let bitboard = BitBoard(u64::MAX);
         let lsb = bitboard.bitscan_forward();
         assert_eq!(lsb, Some(Square::A1));
 
@@ -511,6 +854,53 @@ mod tests {
         assert_eq!(lsb, None);
     }
 
+    #[test]
+    fn test_bitscan_reverse() {
+        let bitboard = BitBoard::from(Row::One);
+        assert_eq!(bitboard.bitscan_reverse(), Some(Square::H1));
+
+        let bitboard = BitBoard::from(Column::A);
+        assert_eq!(bitboard.bitscan_reverse(), Some(Square::A8));
+
+        let bitboard = BitBoard(0);
+        assert_eq!(bitboard.bitscan_reverse(), None);
+    }
+
+    #[test]
+    fn test_pop_and_set_ops() {
+        let mut bitboard = BitBoard::from(Square::C3) | Square::A1.into() | Square::H8.into();
+        assert!(bitboard.more_than_one());
+        assert_eq!(bitboard.pop_lsb(), Some(Square::A1));
+        assert_eq!(bitboard.pop_msb(), Some(Square::H8));
+        assert!(!bitboard.more_than_one());
+        assert_eq!(bitboard.pop_lsb(), Some(Square::C3));
+        assert_eq!(bitboard.pop_lsb(), None);
+        assert_eq!(bitboard.pop_msb(), None);
+        assert!(!bitboard.more_than_one());
+
+        let row = BitBoard::from(Row::One);
+        let corners = BitBoard::from(Square::A1) | Square::H1.into();
+        assert!(corners.is_subset(row));
+        assert!(!row.is_subset(corners));
+        assert!(BitBoard(0).is_subset(corners));
+        assert_eq!((row - corners).count_squares(), 6);
+        assert_eq!(row.difference(corners), row & !corners);
+        let mut rest = row;
+        rest -= corners;
+        assert_eq!(rest, row - corners);
+        assert_eq!(corners - row, BitBoard(0));
+
+        let forward: Vec<Square> = row.iter().collect();
+        let mut backward: Vec<Square> = row.iter().rev().collect();
+        backward.reverse();
+        assert_eq!(forward, backward);
+        assert_eq!(row.iter().next_back(), Some(Square::H1));
+        let mut both = corners.iter();
+        assert_eq!(both.next_back(), Some(Square::H1));
+        assert_eq!(both.next(), Some(Square::A1));
+        assert_eq!(both.next(), None);
+    }
+
     #[test]
     fn aaa() {
         gen_straight_moves();
@@ -0,0 +1,122 @@
+use super::*;
+use std::{arch::x86_64::_pext_u64, sync::LazyLock};
+
+static ROOK_TABLE: LazyLock<Option<PextTable>> = LazyLock::new(|| {
+    is_x86_feature_detected!("bmi2")
+        .then(|| PextTable::new(rook_relevant_mask, BitBoard::ray_rook_attacks))
+});
+static BISHOP_TABLE: LazyLock<Option<PextTable>> = LazyLock::new(|| {
+    is_x86_feature_detected!("bmi2")
+        .then(|| PextTable::new(bishop_relevant_mask, BitBoard::ray_bishop_attacks))
+});
+
+pub(super) fn rook_attacks(square: Square, occupied: BitBoard) -> Option<BitBoard> {
+    ROOK_TABLE
+        .as_ref()
+        .map(|table| table.attacks(square, occupied))
+}
+
+pub(super) fn bishop_attacks(square: Square, occupied: BitBoard) -> Option<BitBoard> {
+    BISHOP_TABLE
+        .as_ref()
+        .map(|table| table.attacks(square, occupied))
+}
+
+struct PextTable {
+    masks: [u64; 64],
+    offsets: [usize; 64],
+    attacks: Vec<BitBoard>,
+}
+
+impl PextTable {
+    fn new(
+        relevant_mask: fn(Square) -> BitBoard,
+        slow_attacks: fn(Square, BitBoard) -> BitBoard,
+    ) -> Self {
+        let mut masks = [0; 64];
+        let mut offsets = [0; 64];
+        let mut attacks = Vec::new();
+        for idx in 0..64u8 {
+            // Safety: idx < 64
+            let square = unsafe { Square::from_u8_unchecked(idx) };
+            let mask = relevant_mask(square).0;
+            masks[square] = mask;
+            offsets[square] = attacks.len();
+            attacks.resize(attacks.len() + (1 << mask.count_ones()), BitBoard(0));
+            // enumerate every subset of the mask (carry-rippler)
+            let mut subset = 0u64;
+            loop {
+                // Safety: tables are only built once bmi2 support is detected
+                let index = unsafe { pext(subset, mask) } as usize;
+                attacks[offsets[square] + index] = slow_attacks(square, BitBoard(subset));
+                subset = subset.wrapping_sub(mask) & mask;
+                if subset == 0 {
+                    break;
+                }
+            }
+        }
+        Self {
+            masks,
+            offsets,
+            attacks,
+        }
+    }
+
+    fn attacks(&self, square: Square, occupied: BitBoard) -> BitBoard {
+        // Safety: tables are only built once bmi2 support is detected
+        let index = unsafe { pext(occupied.0, self.masks[square]) } as usize;
+        self.attacks[self.offsets[square] + index]
+    }
+}
+
+#[target_feature(enable = "bmi2")]
+unsafe fn pext(value: u64, mask: u64) -> u64 {
+    _pext_u64(value, mask)
+}
+
+fn rook_relevant_mask(square: Square) -> BitBoard {
+    (NORTH_RAYS[square] & !BitBoard::from(Row::Eight))
+        | (SOUTH_RAYS[square] & !BitBoard::from(Row::One))
+        | (EAST_RAYS[square] & !BitBoard::from(Column::H))
+        | (WEST_RAYS[square] & !BitBoard::from(Column::A))
+}
+
+fn bishop_relevant_mask(square: Square) -> BitBoard {
+    let edges = BitBoard::from(Row::One) | Row::Eight.into() | Column::A.into() | Column::H.into();
+    (NORTH_EAST_RAYS[square]
+        | NORTH_WEST_RAYS[square]
+        | SOUTH_EAST_RAYS[square]
+        | SOUTH_WEST_RAYS[square])
+        & !edges
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pext_matches_rays() {
+        if !is_x86_feature_detected!("bmi2") {
+            return;
+        }
+        let mut occupied = 0x9d2c_5680_2b1e_4f37u64;
+        for _ in 0..256 {
+            for idx in 0..64u8 {
+                let square = unsafe { Square::from_u8_unchecked(idx) };
+                let occ = BitBoard(occupied);
+                assert_eq!(
+                    rook_attacks(square, occ),
+                    Some(BitBoard::ray_rook_attacks(square, occ))
+                );
+                assert_eq!(
+                    bishop_attacks(square, occ),
+                    Some(BitBoard::ray_bishop_attacks(square, occ))
+                );
+            }
+            // xorshift to walk through varied occupancies
+            occupied ^= occupied << 13;
+            occupied ^= occupied >> 7;
+            occupied ^= occupied << 17;
+        }
+    }
+}
@@ -1,25 +1,63 @@
 use super::Square;
-use crate::pieces::Piece;
+use crate::{
+    geometry::{BoardGeometry, Standard},
+    pieces::{Color, Figure, Piece},
+};
 
-#[derive(Debug, PartialEq, Eq)]
-pub struct MailBox(Box<[Option<Piece>; 64]>);
+const EMPTY: u8 = 0;
+const BLACK_BIT: u8 = 0b1000;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct MailBox<G: BoardGeometry = Standard>(G::Squares);
+
+impl<G: BoardGeometry> MailBox<G> {
+    pub fn get_index(&self, index: u8) -> Option<Piece> {
+        decode(*self.0.as_ref().get(index as usize)?)
+    }
+
+    pub fn set_index(&mut self, index: u8, piece: Option<Piece>) -> Option<Piece> {
+        let slot = self.0.as_mut().get_mut(index as usize)?;
+        decode(std::mem::replace(slot, piece.map_or(EMPTY, encode)))
+    }
+}
 
 impl MailBox {
     pub fn get_sq(&self, square: Square) -> Option<Piece> {
-        self.0[square]
+        decode(self.0[square])
     }
 
     pub fn clear_sq(&mut self, square: Square) -> Option<Piece> {
-        self.0[square].take()
+        decode(std::mem::replace(&mut self.0[square], EMPTY))
     }
 
     pub fn set_sq(&mut self, square: Square, piece: Piece) -> Option<Piece> {
-        self.0[square].replace(piece)
+        decode(std::mem::replace(&mut self.0[square], encode(piece)))
     }
 }
 
-impl Default for MailBox {
+impl<G: BoardGeometry> Default for MailBox<G> {
     fn default() -> Self {
-        MailBox(Box::new([None; 64]))
+        MailBox(G::EMPTY)
+    }
+}
+
+// 0 is an empty square, otherwise the low bits hold figure + 1 and bit 3 the color
+fn encode(piece: Piece) -> u8 {
+    let color_bit = match piece.color {
+        Color::White => 0,
+        Color::Black => BLACK_BIT,
+    };
+    color_bit | (piece.figure as u8 + 1)
+}
+
+fn decode(val: u8) -> Option<Piece> {
+    if val == EMPTY {
+        return None;
     }
+    let color = match val & BLACK_BIT {
+        0 => Color::White,
+        _ => Color::Black,
+    };
+    let figure = Figure::try_from((val & !BLACK_BIT) - 1).ok()?;
+    Some(Piece { color, figure })
 }
@@ -1,7 +1,9 @@
-use super::{Board, Row, Square, bitboard::BitBoard};
+use super::{Board, Row, Square, SquareChange, bitboard::BitBoard};
 use crate::{
     constants::DEFAULT_FEN,
-    pieces::{Color, constants::*},
+    errors::InvalidValueError,
+    geometry::{Capablanca, NineByNine, SquareSet, Standard},
+    pieces::{Color, Figure, constants::*},
 };
 
 #[test]
@@ -10,49 +12,31 @@ fn test_default_fen() {
     let board = Board::try_from_fen(fen).unwrap();
 
     let pawn_mask = BitBoard::from(Row::Two) | BitBoard::from(Row::Seven);
-    assert_eq!(
-        board.white_pieces.pawns | board.black_pieces.pawns,
-        pawn_mask
-    );
+    assert_eq!(board.figures[Figure::Pawn], pawn_mask);
 
     let rook_mask = BitBoard::from(Square::A1)
         | BitBoard::from(Square::H1)
         | BitBoard::from(Square::A8)
         | BitBoard::from(Square::H8);
-    assert_eq!(
-        board.white_pieces.rooks | board.black_pieces.rooks,
-        rook_mask
-    );
+    assert_eq!(board.figures[Figure::Rook], rook_mask);
 
     let knight_mask = BitBoard::from(Square::B1)
         | BitBoard::from(Square::G1)
         | BitBoard::from(Square::B8)
         | BitBoard::from(Square::G8);
-    assert_eq!(
-        board.white_pieces.knights | board.black_pieces.knights,
-        knight_mask
-    );
+    assert_eq!(board.figures[Figure::Knight], knight_mask);
 
     let bishop_mask = BitBoard::from(Square::C1)
         | BitBoard::from(Square::F1)
         | BitBoard::from(Square::C8)
         | BitBoard::from(Square::F8);
-    assert_eq!(
-        board.white_pieces.bishops | board.black_pieces.bishops,
-        bishop_mask
-    );
+    assert_eq!(board.figures[Figure::Bishop], bishop_mask);
 
     let queen_mask = BitBoard::from(Square::D1) | BitBoard::from(Square::D8);
-    assert_eq!(
-        board.white_pieces.queens | board.black_pieces.queens,
-        queen_mask
-    );
+    assert_eq!(board.figures[Figure::Queen], queen_mask);
 
     let king_mask = BitBoard::from(Square::E1) | BitBoard::from(Square::E8);
-    assert_eq!(
-        board.white_pieces.kings | board.black_pieces.kings,
-        king_mask
-    );
+    assert_eq!(board.figures[Figure::King], king_mask);
 
     let white_mask = BitBoard::from(Row::One) | BitBoard::from(Row::Two);
     assert_eq!(board.occupied_color(Color::White), white_mask);
@@ -61,7 +45,7 @@ fn test_default_fen() {
     assert_eq!(board.occupied_color(Color::Black), black_mask);
 
     let occ_mask = white_mask | black_mask;
-    assert_eq!(board.occupied, occ_mask);
+    assert_eq!(board.occupied(), occ_mask);
 
     //let to_fen = board.to_fen();
     //assert_eq!(to_fen, "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR");
@@ -74,25 +58,16 @@ fn test_clear_and_set_sq() {
     board.clear_sq(Square::A8);
 
     let rook_mask = BitBoard::from(Square::A1) | Square::H1.into() | Square::H8.into();
-    assert_eq!(
-        board.white_pieces.rooks | board.black_pieces.rooks,
-        rook_mask
-    );
+    assert_eq!(board.figures[Figure::Rook], rook_mask);
 
     let piece = board.set_sq(Square::H1, BLACK_QUEEN);
     assert_eq!(piece, Some(WHITE_ROOK));
 
     let rook_mask = BitBoard::from(Square::A1) | Square::H8.into();
-    assert_eq!(
-        board.white_pieces.rooks | board.black_pieces.rooks,
-        rook_mask
-    );
+    assert_eq!(board.figures[Figure::Rook], rook_mask);
 
     let queen_mask = BitBoard::from(Square::D1) | Square::H1.into() | Square::D8.into();
-    assert_eq!(
-        board.white_pieces.queens | board.black_pieces.queens,
-        queen_mask
-    );
+    assert_eq!(board.figures[Figure::Queen], queen_mask);
 
     let _white_mask =
         BitBoard::from(Row::One) | BitBoard::from(Row::Two) ^ BitBoard::from(Square::H1);
@@ -104,6 +79,149 @@ fn test_clear_and_set_sq() {
     //assert_eq!(board.black_occupied, black_mask);
 }
 
+#[test]
+fn test_accessors() {
+    let board = Board::try_from_fen("4k3/8/8/8/8/8/3PP3/RN2K3 w - - 0 1").unwrap();
+    let white: Vec<_> = board.iter_color(Color::White).collect();
+    assert_eq!(white.len(), 5);
+    assert!(white.contains(&(Square::B1, WHITE_KNIGHT)));
+    assert_eq!(board.iter_color(Color::Black).collect::<Vec<_>>(), [(
+        Square::E8,
+        BLACK_KING
+    )]);
+    let pawns = BitBoard::from(Square::D2) | Square::E2.into();
+    assert_eq!(board.pieces(Figure::Pawn, Color::White), pawns);
+    assert_eq!(
+        board.pieces(Figure::Pawn, Color::Black),
+        BitBoard::default()
+    );
+    assert_eq!(board.occupied().count_squares(), 6);
+}
+
+#[test]
+fn test_pawn_structure() {
+    let board = Board::try_from_fen("4k3/1p6/8/3P4/4P3/8/P3P1PP/4K3 w - - 0 1").unwrap();
+    let passed = BitBoard::from(Square::D5)
+        | Square::E4.into()
+        | Square::E2.into()
+        | Square::G2.into()
+        | Square::H2.into();
+    assert_eq!(board.passed_pawns(Color::White), passed);
+    assert_eq!(board.passed_pawns(Color::Black), BitBoard::default());
+    assert_eq!(board.isolated_pawns(Color::White), Square::A2.into());
+    assert_eq!(board.isolated_pawns(Color::Black), Square::B7.into());
+    let doubled = BitBoard::from(Square::E2) | Square::E4.into();
+    assert_eq!(board.doubled_pawns(Color::White), doubled);
+    assert_eq!(board.doubled_pawns(Color::Black), BitBoard::default());
+
+    let board = Board::try_from_fen("4k3/8/8/2p5/4P3/3P4/8/4K3 w - - 0 1").unwrap();
+    assert_eq!(board.backward_pawns(Color::White), Square::D3.into());
+    assert_eq!(board.backward_pawns(Color::Black), Square::C5.into());
+}
+
+#[test]
+fn test_outposts_and_weak_squares() {
+    let board = Board::try_from_fen(DEFAULT_FEN).unwrap();
+    assert_eq!(board.outposts(Color::White), BitBoard::default());
+    assert_eq!(board.weak_squares(Color::White), BitBoard::default());
+    assert_eq!(board.weak_squares(Color::Black), BitBoard::default());
+
+    let board = Board::try_from_fen("4k3/pp4pp/3p4/2pP4/2P5/8/PP3PPP/4K3 w - - 0 1").unwrap();
+    // e6 is guarded by d5, and black has no f-pawn left to chase a piece away
+    assert!(board.outposts(Color::White).contains(Square::E6));
+    assert!(!board.outposts(Color::White).contains(Square::C6));
+    assert!(!board.outposts(Color::Black).contains(Square::B4));
+    assert!(board.outposts(Color::Black).contains(Square::D4));
+    // white's d-pawn is gone from the third and fourth ranks
+    assert!(board.weak_squares(Color::White).contains(Square::D4));
+    assert!(board.weak_squares(Color::White).contains(Square::D3));
+    assert!(!board.weak_squares(Color::White).contains(Square::E3));
+    assert!(board.weak_squares(Color::Black).contains(Square::E6));
+    assert!(!board.weak_squares(Color::Black).contains(Square::C6));
+}
+
+#[test]
+fn test_attack_map() {
+    let board = Board::try_from_fen(DEFAULT_FEN).unwrap();
+    let white = board.attack_map(Color::White);
+    assert_eq!(white.count_squares(), 22);
+    assert!(white.contains(Square::F3) && white.contains(Square::D2));
+    assert!(!white.contains(Square::E4));
+    assert_eq!(board.hanging_pieces(Color::White), BitBoard::default());
+
+    // the knight on c6 is attacked by the bishop and nothing covers it
+    let board = Board::try_from_fen("4k3/8/2n5/8/4B3/8/8/4K3 w - - 0 1").unwrap();
+    assert!(board.attack_map(Color::White).contains(Square::C6));
+    assert!(board.attack_map(Color::White).contains(Square::H7));
+    assert_eq!(board.hanging_pieces(Color::Black), Square::C6.into());
+    assert_eq!(board.hanging_pieces(Color::White), BitBoard::default());
+}
+
+#[test]
+fn test_attackers_to() {
+    let board = Board::try_from_fen("4k3/8/8/8/8/2n5/8/r3K3 w - - 0 1").unwrap();
+    let occupied = board.occupied();
+    let attackers = board.attackers_to(Square::D1, Color::Black, occupied);
+    assert_eq!(attackers, BitBoard::from(Square::A1) | Square::C3.into());
+    // the king shields f1 until it steps off e1
+    assert!(
+        board
+            .attackers_to(Square::F1, Color::Black, occupied)
+            .empty()
+    );
+    let lifted = occupied - Square::E1.into();
+    assert_eq!(
+        board.attackers_to(Square::F1, Color::Black, lifted),
+        Square::A1.into()
+    );
+    assert_eq!(
+        board.attackers_to(Square::D2, Color::White, occupied),
+        Square::E1.into()
+    );
+}
+
+#[test]
+fn test_square_colors() {
+    assert!(Square::A1.is_dark());
+    assert!(!Square::H1.is_dark());
+    assert!(Square::H8.is_dark());
+    assert!(!Square::E4.is_dark() && Square::D4.is_dark());
+    assert!(BitBoard::DARK.contains(Square::A1));
+    assert!(BitBoard::LIGHT.contains(Square::B1));
+    assert_eq!(BitBoard::DARK | BitBoard::LIGHT, !BitBoard::default());
+    assert_eq!(BitBoard::DARK.count_squares(), 32);
+    for square in BitBoard::DARK.iter() {
+        assert!(square.is_dark());
+    }
+
+    let board = Board::try_from_fen("4kb2/8/8/8/8/8/8/2B1K3 w - - 0 1").unwrap();
+    assert!(!board.has_opposite_colored_bishops());
+    let board = Board::try_from_fen("4k1b1/8/8/8/8/8/8/2B1K3 w - - 0 1").unwrap();
+    assert!(board.has_opposite_colored_bishops());
+    let board = Board::try_from_fen("4k1b1/8/8/8/8/8/8/2BBK3 w - - 0 1").unwrap();
+    assert!(!board.has_opposite_colored_bishops());
+}
+
+#[test]
+fn test_square_index() {
+    for index in 0..64 {
+        let square = Square::new(index).unwrap();
+        assert_eq!(square.index(), index);
+        assert_eq!(usize::from(square), index);
+        assert_eq!(Square::try_from(index as u8), Ok(square));
+    }
+    assert_eq!(Square::A1.index(), 0);
+    assert_eq!(Square::H1.index(), 7);
+    assert_eq!(Square::H8.index(), 63);
+    assert_eq!(Square::new(64), None);
+    assert_eq!(Square::try_from(64), Err(InvalidValueError(64)));
+
+    let mut table = [0u32; 64];
+    table[Square::E4] += 5;
+    assert_eq!(table[Square::E4], 5);
+    assert_eq!(table[Square::E4.index()], 5);
+}
+
 #[test]
 fn test_shift() {
     let s = Square::A1;
@@ -124,3 +242,77 @@ fn test_shift() {
     let s = s.shift::<0, 1>().unwrap();
     println!("{s:?}");
 }
+
+#[test]
+fn test_board_diff() {
+    let start = Board::default();
+    assert!(start.diff(&start).is_empty());
+
+    // 1. e4 e5 2. Nf3 Nc6 3. Bb5 a6 4. Bxc6
+    let after = Board::try_from_fen("r1bqkbnr/1ppp1ppp/p1B5/4p3/4P3/5N2/PPPP1PPP/RNBQK2R").unwrap();
+    let changes = start.diff(&after);
+    let squares: Vec<Square> = changes.iter().map(|change| change.square).collect();
+    assert_eq!(squares, [
+        Square::F1,
+        Square::G1,
+        Square::E2,
+        Square::F3,
+        Square::E4,
+        Square::E5,
+        Square::A6,
+        Square::C6,
+        Square::A7,
+        Square::E7,
+        Square::B8,
+    ]);
+    assert_eq!(changes[7], SquareChange {
+        square: Square::C6,
+        before: None,
+        after: Some(WHITE_BISHOP),
+    });
+    assert_eq!(changes[2].after, None);
+
+    // a capture in place changes only the color
+    let before = Board::try_from_fen("4k3/8/8/3r4/8/8/8/4K3").unwrap();
+    let after = Board::try_from_fen("4k3/8/8/3R4/8/8/8/4K3").unwrap();
+    assert_eq!(before.diff(&after), [SquareChange {
+        square: Square::D5,
+        before: Some(BLACK_ROOK),
+        after: Some(WHITE_ROOK),
+    }]);
+}
+
+#[test]
+fn test_board_geometries() {
+    // 8x8 stays on the u64 bitboards, and reads placements the same either way
+    assert_eq!(std::mem::size_of::<Board>(), 128);
+    let standard = Board::<Standard>::from_placement(DEFAULT_FEN.split(' ').next().unwrap());
+    assert_eq!(standard.unwrap(), Board::default());
+
+    let placement = "r1nbqkbn1r/pppppppppp/10/10/4P5/10/PPPP1PPPPP/R1NBQKBN1R";
+    let board = Board::<Capablanca>::from_placement(placement).unwrap();
+    assert_eq!(board.to_placement(), placement);
+    assert_eq!(board.piece_squares(WHITE_PAWN).indices().count(), 10);
+    // j1, the last square of the first rank, and e4 past the tenth square of a rank
+    assert_eq!(board.piece_at(9), Some(WHITE_ROOK));
+    assert_eq!(board.piece_at(34), Some(WHITE_PAWN));
+    assert_eq!(board.piece_at(80), None);
+
+    let placement = "rnbqkbnr1/ppppppppp/9/9/9/9/9/PPPPPPPPP/1RNBQKBNR";
+    let mut board = Board::<NineByNine>::from_placement(placement).unwrap();
+    assert_eq!(board.to_placement(), placement);
+    assert_eq!(board.put_piece(80, Some(WHITE_QUEEN)), None);
+    assert_eq!(board.put_piece(76, None), Some(BLACK_KING));
+    assert!(board.color_squares(Color::White).has_index(80));
+    assert!(!board.piece_squares(BLACK_KING).has_index(76));
+    assert_eq!(board.put_piece(81, Some(WHITE_QUEEN)), None);
+
+    for placement in [
+        "r1nbqkbn1r/pppppppppp/11/10/10/10/PPPPPPPPPP/R1NBQKBN1R",
+        "r1nbqkbn1r/pppppppppp/9/10/10/10/PPPPPPPPPP/R1NBQKBN1R",
+        "r1nbqkbn1r/pppppppppp/10/10/10/PPPPPPPPPP/R1NBQKBN1R",
+        "r1nbqkbn1r/pppppppppp/0/10/10/10/PPPPPPPPPP/R1NBQKBN1R",
+    ] {
+        assert!(Board::<Capablanca>::from_placement(placement).is_err());
+    }
+}
@@ -1,4 +1,7 @@
-use crate::{errors::ParseFenError, pieces::Color};
+use crate::{
+    errors::{FenField, ParseFenError},
+    pieces::Color,
+};
 use std::{
     fmt::Display,
     ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign},
@@ -146,7 +149,10 @@ impl FromStr for Castle {
             "Kkq" => Ok(Castle::Kkq),
             "Qkq" => Ok(Castle::Qkq),
             "KQkq" => Ok(Castle::KQkq),
-            _ => Err(ParseFenError::InvalidString(s.into())),
+            _ => Err(ParseFenError::InvalidField {
+                field: FenField::Castling,
+                value: s.into(),
+            }),
         }
     }
 }
@@ -0,0 +1,55 @@
+use crate::{
+    GameState,
+    board::{Square, bitboard::BitBoard},
+    movegen::castle_rook_squares,
+    moves::{CheckKind, Move, MoveKind},
+    pieces::{Figure, Piece},
+};
+
+impl GameState {
+    pub fn gives_check(&self, mv: Move) -> CheckKind {
+        let Some(king_sq) = self.board.pieces(Figure::King, !self.turn).iter().next() else {
+            return CheckKind::None;
+        };
+        let Some(moved) = self.board.get_sq(mv.from) else {
+            return CheckKind::None;
+        };
+        let mut vacated = BitBoard::from(mv.from);
+        let mut occupied =
+            self.board.occupied_color(self.turn) | self.board.occupied_color(!self.turn);
+        let checker = match mv.kind {
+            MoveKind::KingCastle | MoveKind::QueenCastle => {
+                let (rook_from, rook_to) = castle_rook_squares(mv.kind, mv.from.row());
+                vacated |= rook_from.into();
+                occupied |= rook_to.into();
+                (rook_to, Figure::Rook)
+            }
+            MoveKind::EnPassant => {
+                vacated |= Square::from_coords(mv.to.col(), mv.from.row()).into();
+                (mv.to, moved.figure)
+            }
+            _ => (mv.to, mv.promotion().unwrap_or(moved.figure)),
+        };
+        occupied = (occupied & !vacated) | mv.to.into();
+
+        let (checker_sq, figure) = checker;
+        let piece = Piece {
+            color: self.turn,
+            figure,
+        };
+        let is_direct = BitBoard::attacks(piece, checker_sq, occupied).contains(king_sq);
+
+        let queens = self.board.pieces(Figure::Queen, self.turn);
+        let straight = (self.board.pieces(Figure::Rook, self.turn) | queens) & !vacated;
+        let diag = (self.board.pieces(Figure::Bishop, self.turn) | queens) & !vacated;
+        let is_discovered = !(straight & BitBoard::rook_attacks(king_sq, occupied)).empty()
+            || !(diag & BitBoard::bishop_attacks(king_sq, occupied)).empty();
+
+        match (is_direct, is_discovered) {
+            (true, true) => CheckKind::Double,
+            (true, false) => CheckKind::Direct,
+            (false, true) => CheckKind::Discovered,
+            (false, false) => CheckKind::None,
+        }
+    }
+}
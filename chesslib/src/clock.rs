@@ -0,0 +1,181 @@
+use crate::{errors::ParseTimeControlError, pieces::Color};
+use std::{
+    fmt::Display,
+    str::FromStr,
+    sync::OnceLock,
+    time::{Duration, Instant},
+};
+
+// where clocks read the time from, `Instant` unless a source was set. wasm32-unknown-unknown
+// has no `Instant`, so a web build hands in its own, e.g. macroquad's `get_time`
+static TIME_SOURCE: OnceLock<fn() -> Duration> = OnceLock::new();
+static START: OnceLock<Instant> = OnceLock::new();
+
+// the time since some fixed start, for every clock from now on; only the first call counts
+pub fn set_time_source(now: fn() -> Duration) {
+    let _ = TIME_SOURCE.set(now);
+}
+
+fn now() -> Duration {
+    match TIME_SOURCE.get() {
+        Some(now) => now(),
+        None => START.get_or_init(Instant::now).elapsed(),
+    }
+}
+
+// cutechess-style "moves/base+inc" in seconds, e.g. "40/60+0.6" or "300+2"
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct TimeControl {
+    pub moves_per_period: Option<u32>,
+    pub base: Duration,
+    pub increment: Duration,
+}
+
+impl FromStr for TimeControl {
+    type Err = ParseTimeControlError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || ParseTimeControlError(s.to_owned());
+        let seconds = |v: &str| {
+            v.parse::<f64>()
+                .ok()
+                .and_then(|v| Duration::try_from_secs_f64(v).ok())
+                .ok_or_else(invalid)
+        };
+        let (moves_per_period, rest) = match s.split_once('/') {
+            // a period of no moves would never end
+            Some((moves, rest)) => match moves.parse() {
+                Ok(0) | Err(_) => return Err(invalid()),
+                Ok(moves) => (Some(moves), rest),
+            },
+            None => (None, s),
+        };
+        let (base, increment) = match rest.split_once('+') {
+            Some((base, inc)) => (seconds(base)?, seconds(inc)?),
+            None => (seconds(rest)?, Duration::ZERO),
+        };
+        Ok(TimeControl {
+            moves_per_period,
+            base,
+            increment,
+        })
+    }
+}
+
+impl Display for TimeControl {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(moves) = self.moves_per_period {
+            write!(f, "{moves}/")?;
+        }
+        write!(f, "{}", self.base.as_secs_f64())?;
+        if !self.increment.is_zero() {
+            write!(f, "+{}", self.increment.as_secs_f64())?;
+        }
+        Ok(())
+    }
+}
+
+impl TimeControl {
+    // Black's side of an Armageddon game, which takes a draw as a win: four fifths of the
+    // base time, like four minutes to five, and the same increment
+    pub fn armageddon_black(self) -> Self {
+        Self {
+            base: self.base * 4 / 5,
+            ..self
+        }
+    }
+}
+
+// runs down both sides' time, each on a time control of its own for time odds and Armageddon
+#[derive(Debug, Clone)]
+pub struct Clock {
+    time_control: [TimeControl; 2],
+    remaining: [Duration; 2],
+    moves_made: [u32; 2],
+    running: Option<(Color, Duration)>,
+}
+
+impl Clock {
+    pub fn new(time_control: TimeControl) -> Self {
+        Self::with_time_controls([time_control; 2])
+    }
+
+    pub fn with_time_controls(time_controls: [TimeControl; 2]) -> Self {
+        Self {
+            time_control: time_controls,
+            remaining: time_controls.map(|time_control| time_control.base),
+            moves_made: [0; 2],
+            running: None,
+        }
+    }
+
+    // a stopped clock picked up where an earlier one left off, e.g. from a saved game
+    pub fn restore(
+        time_controls: [TimeControl; 2],
+        remaining: [Duration; 2],
+        moves_made: [u32; 2],
+    ) -> Self {
+        Self {
+            time_control: time_controls,
+            remaining,
+            moves_made,
+            running: None,
+        }
+    }
+
+    // White's, which is Black's too unless they were given their own
+    pub fn time_control(&self) -> TimeControl {
+        self.time_control[0]
+    }
+
+    pub fn time_controls(&self) -> [TimeControl; 2] {
+        self.time_control
+    }
+
+    pub fn remaining(&self, color: Color) -> Duration {
+        match self.running {
+            Some((running, started)) if running == color => {
+                self.remaining[color].saturating_sub(now().saturating_sub(started))
+            }
+            _ => self.remaining[color],
+        }
+    }
+
+    pub fn moves_to_go(&self, color: Color) -> Option<u32> {
+        self.time_control[color]
+            .moves_per_period
+            .map(|period| period - self.moves_made[color] % period)
+    }
+
+    // how long the side to move has been thinking, if the clock's running
+    pub fn elapsed(&self) -> Option<Duration> {
+        self.running
+            .map(|(_, started)| now().saturating_sub(started))
+    }
+
+    pub fn start(&mut self, color: Color) {
+        self.running = Some((color, now()));
+    }
+
+    // stops the running side's clock; false if it ran out of time
+    pub fn stop(&mut self) -> bool {
+        let Some((color, started)) = self.running.take() else {
+            return true;
+        };
+        let elapsed = now().saturating_sub(started);
+        if elapsed > self.remaining[color] {
+            self.remaining[color] = Duration::ZERO;
+            return false;
+        }
+        self.remaining[color] -= elapsed;
+        let time_control = self.time_control[color];
+        self.remaining[color] = self.remaining[color].saturating_add(time_control.increment);
+        self.moves_made[color] = self.moves_made[color].saturating_add(1);
+        if let Some(period) = time_control.moves_per_period
+            && self.moves_made[color].is_multiple_of(period)
+        {
+            self.remaining[color] = self.remaining[color].saturating_add(time_control.base);
+        }
+        true
+    }
+}
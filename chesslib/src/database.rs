@@ -0,0 +1,321 @@
+use crate::{
+    GameState,
+    errors::DatabaseError,
+    moves::{Move, Move16},
+    pgn::{GameResult, PgnGame, parse_pgn, parse_pgn_with_offsets},
+};
+use std::{
+    collections::HashMap,
+    fs,
+    io::{Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    time::UNIX_EPOCH,
+};
+
+const INDEX_MAGIC: &[u8; 4] = b"CPIX";
+const INDEX_VERSION: u32 = 3;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+struct Hit {
+    game: u32,
+    ply: u16,
+    // the move the game went on with, null where it ended
+    next: Move16,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GameIndex {
+    source_len: u64,
+    // nanoseconds since the epoch the source was last written at, 0 if not known
+    source_modified: u64,
+    offsets: Vec<u64>,
+    // per game: 0 when it has none, then White's win, Black's win and a draw
+    results: Vec<u8>,
+    positions: HashMap<u64, Vec<Hit>>,
+}
+
+// a move played from a position, and how the games that went on with it ended
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Continuation {
+    pub mv: Move,
+    pub games: u32,
+    pub white_wins: u32,
+    pub draws: u32,
+    pub black_wins: u32,
+}
+
+impl GameIndex {
+    pub fn build(pgn: &str) -> Result<Self, DatabaseError> {
+        let mut index = GameIndex {
+            source_len: pgn.len() as u64,
+            ..Default::default()
+        };
+        for (game_idx, (offset, game)) in parse_pgn_with_offsets(pgn)?.into_iter().enumerate() {
+            index.offsets.push(offset as u64);
+            index.results.push(match game.result {
+                None => 0,
+                Some(GameResult::WhiteWins) => 1,
+                Some(GameResult::BlackWins) => 2,
+                Some(GameResult::Draw) => 3,
+            });
+            let mut gs = game.start_position()?;
+            for (ply, san) in game.moves.iter().enumerate() {
+                let mv = gs.parse_san(san)?;
+                index.insert(gs.zobrist(), game_idx, ply, mv.into());
+                gs.play(mv);
+            }
+            index.insert(gs.zobrist(), game_idx, game.moves.len(), Move16::NULL);
+        }
+        Ok(index)
+    }
+
+    fn insert(&mut self, hash: u64, game: usize, ply: usize, next: Move16) {
+        let hits = self.positions.entry(hash).or_default();
+        // only the first time a game reaches the position
+        if hits.last().is_none_or(|hit| hit.game != game as u32) {
+            hits.push(Hit {
+                game: game as u32,
+                ply: ply as u16,
+                next,
+            });
+        }
+    }
+
+    // the moves played from `gs`, most played first, without reading any of the games
+    pub fn explore(&self, gs: &GameState) -> Vec<Continuation> {
+        let mut continuations: Vec<Continuation> = Vec::new();
+        let hits = self
+            .positions
+            .get(&gs.zobrist())
+            .map_or(&[][..], Vec::as_slice);
+        for hit in hits.iter().filter(|hit| !hit.next.is_null()) {
+            let Ok(mv) = Move::try_from(hit.next) else {
+                continue;
+            };
+            let idx = match continuations.iter().position(|c| c.mv == mv) {
+                Some(idx) => idx,
+                None => {
+                    continuations.push(Continuation {
+                        mv,
+                        games: 0,
+                        white_wins: 0,
+                        draws: 0,
+                        black_wins: 0,
+                    });
+                    continuations.len() - 1
+                }
+            };
+            let continuation = &mut continuations[idx];
+            continuation.games += 1;
+            match self.results.get(hit.game as usize) {
+                Some(1) => continuation.white_wins += 1,
+                Some(2) => continuation.black_wins += 1,
+                Some(3) => continuation.draws += 1,
+                _ => {}
+            }
+        }
+        continuations.sort_by_key(|c| std::cmp::Reverse(c.games));
+        continuations
+    }
+
+    pub fn game_count(&self) -> usize {
+        self.offsets.len()
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), DatabaseError> {
+        let mut buf = Vec::new();
+        buf.extend_from_slice(INDEX_MAGIC);
+        buf.extend_from_slice(&INDEX_VERSION.to_le_bytes());
+        buf.extend_from_slice(&self.source_len.to_le_bytes());
+        buf.extend_from_slice(&self.source_modified.to_le_bytes());
+        buf.extend_from_slice(&(self.offsets.len() as u64).to_le_bytes());
+        for offset in &self.offsets {
+            buf.extend_from_slice(&offset.to_le_bytes());
+        }
+        buf.extend_from_slice(&self.results);
+        buf.extend_from_slice(&(self.positions.len() as u64).to_le_bytes());
+        for (hash, hits) in &self.positions {
+            buf.extend_from_slice(&hash.to_le_bytes());
+            buf.extend_from_slice(&(hits.len() as u32).to_le_bytes());
+            for hit in hits {
+                buf.extend_from_slice(&hit.game.to_le_bytes());
+                buf.extend_from_slice(&hit.ply.to_le_bytes());
+                buf.extend_from_slice(&u16::from(hit.next).to_le_bytes());
+            }
+        }
+        fs::File::create(path)?.write_all(&buf)?;
+        Ok(())
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, DatabaseError> {
+        let mut buf = Vec::new();
+        fs::File::open(path)?.read_to_end(&mut buf)?;
+        let mut reader = ByteReader(&buf);
+        if reader.take::<4>()? != *INDEX_MAGIC
+            || u32::from_le_bytes(reader.take()?) != INDEX_VERSION
+        {
+            return Err(DatabaseError::InvalidIndex);
+        }
+        let source_len = u64::from_le_bytes(reader.take()?);
+        let source_modified = u64::from_le_bytes(reader.take()?);
+        let game_count = u64::from_le_bytes(reader.take()?);
+        // counts are checked against the bytes left before anything is allocated for them
+        if game_count > reader.remaining() as u64 / 8 {
+            return Err(DatabaseError::InvalidIndex);
+        }
+        let offsets: Vec<u64> = (0..game_count)
+            .map(|_| Ok(u64::from_le_bytes(reader.take()?)))
+            .collect::<Result<_, DatabaseError>>()?;
+        let results = (0..game_count)
+            .map(|_| Ok(reader.take::<1>()?[0]))
+            .collect::<Result<_, DatabaseError>>()?;
+        let position_count = u64::from_le_bytes(reader.take()?);
+        let mut positions = HashMap::new();
+        for _ in 0..position_count {
+            let hash = u64::from_le_bytes(reader.take()?);
+            let hit_count = u32::from_le_bytes(reader.take()?);
+            if hit_count as usize > reader.remaining() / 8 {
+                return Err(DatabaseError::InvalidIndex);
+            }
+            let hits: Vec<Hit> = (0..hit_count)
+                .map(|_| {
+                    Ok(Hit {
+                        game: u32::from_le_bytes(reader.take()?),
+                        ply: u16::from_le_bytes(reader.take()?),
+                        next: u16::from_le_bytes(reader.take()?).into(),
+                    })
+                })
+                .collect::<Result<_, DatabaseError>>()?;
+            if hits.iter().any(|hit| hit.game as u64 >= game_count) {
+                return Err(DatabaseError::InvalidIndex);
+            }
+            positions.insert(hash, hits);
+        }
+        // each game starts after the one before it and inside the source
+        let in_order = offsets.windows(2).all(|pair| pair[0] < pair[1]);
+        if !in_order || offsets.last().is_some_and(|&last| last > source_len) {
+            return Err(DatabaseError::InvalidIndex);
+        }
+        Ok(Self {
+            source_len,
+            source_modified,
+            offsets,
+            results,
+            positions,
+        })
+    }
+}
+
+struct ByteReader<'a>(&'a [u8]);
+
+impl ByteReader<'_> {
+    fn remaining(&self) -> usize {
+        self.0.len()
+    }
+
+    fn take<const N: usize>(&mut self) -> Result<[u8; N], DatabaseError> {
+        let (head, tail) = self
+            .0
+            .split_first_chunk::<N>()
+            .ok_or(DatabaseError::InvalidIndex)?;
+        self.0 = tail;
+        Ok(*head)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GameMatch {
+    pub game: PgnGame,
+    pub ply: usize,
+    pub next_move: Option<Move>,
+}
+
+pub struct GameDatabase {
+    pgn_path: PathBuf,
+    index: GameIndex,
+}
+
+impl GameDatabase {
+    // reuses `<pgn>.idx` when it was built from the file as it is now, going by its length
+    // and when it was last written, otherwise rebuilds it
+    pub fn open(pgn_path: impl AsRef<Path>) -> Result<Self, DatabaseError> {
+        let pgn_path = pgn_path.as_ref().to_path_buf();
+        let index_path = Self::index_path(&pgn_path);
+        let metadata = fs::metadata(&pgn_path)?;
+        let source_len = metadata.len();
+        let source_modified = metadata
+            .modified()
+            .ok()
+            .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+            .map_or(0, |since| since.as_nanos() as u64);
+        let index = match GameIndex::load(&index_path) {
+            Ok(index)
+                if index.source_len == source_len && index.source_modified == source_modified =>
+            {
+                index
+            }
+            _ => {
+                let mut index = GameIndex::build(&fs::read_to_string(&pgn_path)?)?;
+                index.source_modified = source_modified;
+                index.save(&index_path)?;
+                index
+            }
+        };
+        Ok(Self { pgn_path, index })
+    }
+
+    pub fn index_path(pgn_path: &Path) -> PathBuf {
+        let mut path = pgn_path.as_os_str().to_owned();
+        path.push(".idx");
+        path.into()
+    }
+
+    pub fn game_count(&self) -> usize {
+        self.index.game_count()
+    }
+
+    pub fn explore(&self, gs: &GameState) -> Vec<Continuation> {
+        self.index.explore(gs)
+    }
+
+    pub fn search(&self, gs: &GameState) -> Result<Vec<GameMatch>, DatabaseError> {
+        let Some(hits) = self.index.positions.get(&gs.zobrist()) else {
+            return Ok(Vec::new());
+        };
+        let mut file = fs::File::open(&self.pgn_path)?;
+        let mut matches = Vec::with_capacity(hits.len());
+        for hit in hits {
+            let game = self.read_game(&mut file, hit.game as usize)?;
+            let ply = hit.ply as usize;
+            let next_move = if ply < game.moves.len() {
+                Some(game.replay()?[ply].1)
+            } else {
+                None
+            };
+            matches.push(GameMatch {
+                game,
+                ply,
+                next_move,
+            });
+        }
+        Ok(matches)
+    }
+
+    fn read_game(&self, file: &mut fs::File, game: usize) -> Result<PgnGame, DatabaseError> {
+        let start = self.index.offsets[game];
+        let end = self
+            .index
+            .offsets
+            .get(game + 1)
+            .copied()
+            .unwrap_or(self.index.source_len);
+        let mut buf = vec![0; (end - start) as usize];
+        file.seek(SeekFrom::Start(start))?;
+        file.read_exact(&mut buf)?;
+        let text = String::from_utf8_lossy(&buf);
+        parse_pgn(&text)?
+            .into_iter()
+            .next()
+            .ok_or(DatabaseError::InvalidIndex)
+    }
+}
@@ -0,0 +1,167 @@
+use crate::{GameState, errors::ParsePgnError, moves::Move, pgn::expand_variations, pieces::Color};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Display,
+};
+
+// days until a position comes back, by how many times in a row it was answered right
+const INTERVAL_DAYS: [u64; 6] = [0, 1, 3, 7, 16, 35];
+const DAY_SECS: u64 = 86_400;
+
+#[derive(Debug, Clone, Default)]
+struct Node {
+    turn: Option<Color>,
+    // each move out of the position with the position it leads to
+    moves: Vec<(Move, u64)>,
+}
+
+// one side of a repertoire as a tree of positions: the moves prepared for `color`, and the
+// replies it expects from the other side. Transpositions meet in the same position.
+#[derive(Debug, Clone)]
+pub struct Repertoire {
+    pub color: Color,
+    pub start: GameState,
+    nodes: HashMap<u64, Node>,
+}
+
+impl Repertoire {
+    // every line of every game, variations included; the first game's start is where
+    // the drills begin
+    pub fn from_pgn(text: &str, color: Color) -> Result<Self, ParsePgnError> {
+        let lines = expand_variations(text)?;
+        let start = match lines.first() {
+            Some(line) => line.start_position()?,
+            None => GameState::default(),
+        };
+        let mut nodes: HashMap<u64, Node> = HashMap::new();
+        for line in &lines {
+            for (gs, mv) in line.replay()? {
+                let mut next = gs;
+                next.play(mv);
+                let node = nodes.entry(gs.zobrist()).or_default();
+                node.turn = Some(gs.get_turn());
+                if !node.moves.iter().any(|&(known, _)| known == mv) {
+                    node.moves.push((mv, next.zobrist()));
+                }
+            }
+        }
+        Ok(Self {
+            color,
+            start,
+            nodes,
+        })
+    }
+
+    // the repertoire's moves in `gs`, the trainee's or the opponent's
+    pub fn moves(&self, gs: &GameState) -> Vec<Move> {
+        self.nodes
+            .get(&gs.zobrist())
+            .map(|node| node.moves.iter().map(|&(mv, _)| mv).collect())
+            .unwrap_or_default()
+    }
+
+    // the positions the trainee has a prepared move in
+    pub fn positions(&self) -> impl Iterator<Item = u64> + '_ {
+        self.nodes
+            .iter()
+            .filter(|(_, node)| node.turn == Some(self.color) && !node.moves.is_empty())
+            .map(|(&hash, _)| hash)
+    }
+
+    // the opponent's reply with the most positions due for review behind it, the
+    // repertoire's first in a tie
+    pub fn pick_reply(&self, gs: &GameState, schedule: &Schedule, now: u64) -> Option<Move> {
+        let node = self.nodes.get(&gs.zobrist())?;
+        let mut best: Option<(Move, usize)> = None;
+        for &(mv, hash) in &node.moves {
+            let due = self.due_below(hash, schedule, now, &mut HashSet::new());
+            if best.is_none_or(|(_, most)| due > most) {
+                best = Some((mv, due));
+            }
+        }
+        best.map(|(mv, _)| mv)
+    }
+
+    fn due_below(
+        &self,
+        hash: u64,
+        schedule: &Schedule,
+        now: u64,
+        seen: &mut HashSet<u64>,
+    ) -> usize {
+        if !seen.insert(hash) {
+            return 0;
+        }
+        let Some(node) = self.nodes.get(&hash) else {
+            return 0;
+        };
+        let own = node.turn == Some(self.color) && !node.moves.is_empty();
+        let here = usize::from(own && schedule.is_due(hash, now));
+        let below: usize = node
+            .moves
+            .iter()
+            .map(|&(_, next)| self.due_below(next, schedule, now, seen))
+            .sum();
+        here + below
+    }
+}
+
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+struct Card {
+    streak: u32,
+    due: u64,
+}
+
+// when each position comes up for review again, in seconds since the epoch; kept as
+// `hash streak due` lines. A position never seen is due straight away.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Schedule {
+    cards: HashMap<u64, Card>,
+}
+
+impl Schedule {
+    // lines that don't read are skipped, and their positions start over
+    pub fn parse(text: &str) -> Self {
+        let cards = text
+            .lines()
+            .filter_map(|line| {
+                let mut fields = line.split_whitespace();
+                let hash = u64::from_str_radix(fields.next()?, 16).ok()?;
+                let streak = fields.next()?.parse().ok()?;
+                let due = fields.next()?.parse().ok()?;
+                Some((hash, Card { streak, due }))
+            })
+            .collect();
+        Self { cards }
+    }
+
+    pub fn is_due(&self, hash: u64, now: u64) -> bool {
+        self.cards.get(&hash).is_none_or(|card| card.due <= now)
+    }
+
+    // a right answer pushes the position further out, a wrong one brings it back now
+    pub fn review(&mut self, hash: u64, correct: bool, now: u64) {
+        let card = self.cards.entry(hash).or_default();
+        card.streak = if correct { card.streak + 1 } else { 0 };
+        let days = INTERVAL_DAYS[(card.streak as usize).min(INTERVAL_DAYS.len() - 1)];
+        card.due = now + days * DAY_SECS;
+    }
+
+    pub fn due_count(&self, positions: impl IntoIterator<Item = u64>, now: u64) -> usize {
+        positions
+            .into_iter()
+            .filter(|&hash| self.is_due(hash, now))
+            .count()
+    }
+}
+
+impl Display for Schedule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut cards: Vec<_> = self.cards.iter().collect();
+        cards.sort_by_key(|&(&hash, _)| hash);
+        for (hash, card) in cards {
+            writeln!(f, "{hash:016x} {} {}", card.streak, card.due)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,245 @@
+use crate::{GameState, errors::ParseEcoError};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EcoOpening {
+    pub eco: String,
+    pub name: String,
+}
+
+// the better-known lines; a fuller book can be read from the lichess chess-openings
+// TSV files with `EcoTable::parse_tsv`
+pub(crate) const BUILTIN: &[(&str, &str, &str)] = &[
+    ("A00", "Polish Opening", "b4"),
+    ("A01", "Nimzo-Larsen Attack", "b3"),
+    ("A02", "Bird Opening", "f4"),
+    ("A04", "Zukertort Opening", "Nf3"),
+    ("A07", "King's Indian Attack", "Nf3 d5 g3"),
+    ("A09", "Réti Opening", "Nf3 d5 c4"),
+    ("A10", "English Opening", "c4"),
+    ("A20", "English Opening: King's English Variation", "c4 e5"),
+    ("A30", "English Opening: Symmetrical Variation", "c4 c5"),
+    ("A40", "Queen's Pawn Game", "d4"),
+    ("A43", "Benoni Defense: Old Benoni", "d4 c5"),
+    ("A45", "Indian Defense", "d4 Nf6"),
+    ("A46", "Indian Defense: Knights Variation", "d4 Nf6 Nf3"),
+    ("A51", "Budapest Defense", "d4 Nf6 c4 e5"),
+    ("A56", "Benoni Defense", "d4 Nf6 c4 c5"),
+    ("A57", "Benko Gambit", "d4 Nf6 c4 c5 d5 b5"),
+    (
+        "A60",
+        "Benoni Defense: Modern Variation",
+        "d4 Nf6 c4 c5 d5 e6",
+    ),
+    ("A80", "Dutch Defense", "d4 f5"),
+    ("B00", "King's Pawn Game", "e4"),
+    ("B01", "Scandinavian Defense", "e4 d5"),
+    ("B02", "Alekhine Defense", "e4 Nf6"),
+    ("B06", "Modern Defense", "e4 g6"),
+    ("B07", "Pirc Defense", "e4 d6 d4 Nf6 Nc3 g6"),
+    ("B10", "Caro-Kann Defense", "e4 c6"),
+    (
+        "B12",
+        "Caro-Kann Defense: Advance Variation",
+        "e4 c6 d4 d5 e5",
+    ),
+    (
+        "B13",
+        "Caro-Kann Defense: Exchange Variation",
+        "e4 c6 d4 d5 exd5 cxd5",
+    ),
+    ("B20", "Sicilian Defense", "e4 c5"),
+    ("B22", "Sicilian Defense: Alapin Variation", "e4 c5 c3"),
+    ("B23", "Sicilian Defense: Closed", "e4 c5 Nc3"),
+    ("B30", "Sicilian Defense: Old Sicilian", "e4 c5 Nf3 Nc6"),
+    (
+        "B33",
+        "Sicilian Defense: Sveshnikov Variation",
+        "e4 c5 Nf3 Nc6 d4 cxd4 Nxd4 Nf6 Nc3 e5",
+    ),
+    ("B40", "Sicilian Defense: French Variation", "e4 c5 Nf3 e6"),
+    ("B50", "Sicilian Defense: Modern Variations", "e4 c5 Nf3 d6"),
+    (
+        "B70",
+        "Sicilian Defense: Dragon Variation",
+        "e4 c5 Nf3 d6 d4 cxd4 Nxd4 Nf6 Nc3 g6",
+    ),
+    (
+        "B80",
+        "Sicilian Defense: Scheveningen Variation",
+        "e4 c5 Nf3 d6 d4 cxd4 Nxd4 Nf6 Nc3 e6",
+    ),
+    (
+        "B90",
+        "Sicilian Defense: Najdorf Variation",
+        "e4 c5 Nf3 d6 d4 cxd4 Nxd4 Nf6 Nc3 a6",
+    ),
+    ("C00", "French Defense", "e4 e6"),
+    (
+        "C01",
+        "French Defense: Exchange Variation",
+        "e4 e6 d4 d5 exd5",
+    ),
+    ("C02", "French Defense: Advance Variation", "e4 e6 d4 d5 e5"),
+    (
+        "C03",
+        "French Defense: Tarrasch Variation",
+        "e4 e6 d4 d5 Nd2",
+    ),
+    (
+        "C10",
+        "French Defense: Paulsen Variation",
+        "e4 e6 d4 d5 Nc3",
+    ),
+    (
+        "C15",
+        "French Defense: Winawer Variation",
+        "e4 e6 d4 d5 Nc3 Bb4",
+    ),
+    ("C20", "King's Pawn Game", "e4 e5"),
+    ("C23", "Bishop's Opening", "e4 e5 Bc4"),
+    ("C25", "Vienna Game", "e4 e5 Nc3"),
+    ("C30", "King's Gambit", "e4 e5 f4"),
+    ("C33", "King's Gambit Accepted", "e4 e5 f4 exf4"),
+    ("C40", "King's Knight Opening", "e4 e5 Nf3"),
+    ("C41", "Philidor Defense", "e4 e5 Nf3 d6"),
+    ("C42", "Petrov's Defense", "e4 e5 Nf3 Nf6"),
+    (
+        "C44",
+        "King's Knight Opening: Normal Variation",
+        "e4 e5 Nf3 Nc6",
+    ),
+    ("C44", "Scotch Game", "e4 e5 Nf3 Nc6 d4"),
+    ("C46", "Three Knights Opening", "e4 e5 Nf3 Nc6 Nc3"),
+    ("C47", "Four Knights Game", "e4 e5 Nf3 Nc6 Nc3 Nf6"),
+    ("C50", "Italian Game", "e4 e5 Nf3 Nc6 Bc4"),
+    ("C50", "Italian Game: Giuoco Piano", "e4 e5 Nf3 Nc6 Bc4 Bc5"),
+    (
+        "C51",
+        "Italian Game: Evans Gambit",
+        "e4 e5 Nf3 Nc6 Bc4 Bc5 b4",
+    ),
+    (
+        "C55",
+        "Italian Game: Two Knights Defense",
+        "e4 e5 Nf3 Nc6 Bc4 Nf6",
+    ),
+    ("C60", "Ruy Lopez", "e4 e5 Nf3 Nc6 Bb5"),
+    ("C65", "Ruy Lopez: Berlin Defense", "e4 e5 Nf3 Nc6 Bb5 Nf6"),
+    (
+        "C68",
+        "Ruy Lopez: Exchange Variation",
+        "e4 e5 Nf3 Nc6 Bb5 a6 Bxc6",
+    ),
+    ("C70", "Ruy Lopez: Morphy Defense", "e4 e5 Nf3 Nc6 Bb5 a6"),
+    (
+        "C84",
+        "Ruy Lopez: Closed",
+        "e4 e5 Nf3 Nc6 Bb5 a6 Ba4 Nf6 O-O Be7",
+    ),
+    ("D00", "Queen's Pawn Game", "d4 d5"),
+    (
+        "D00",
+        "Queen's Pawn Game: Accelerated London System",
+        "d4 d5 Bf4",
+    ),
+    (
+        "D02",
+        "Queen's Pawn Game: London System",
+        "d4 d5 Nf3 Nf6 Bf4",
+    ),
+    ("D06", "Queen's Gambit", "d4 d5 c4"),
+    ("D10", "Slav Defense", "d4 d5 c4 c6"),
+    ("D20", "Queen's Gambit Accepted", "d4 d5 c4 dxc4"),
+    ("D30", "Queen's Gambit Declined", "d4 d5 c4 e6"),
+    ("D43", "Semi-Slav Defense", "d4 d5 c4 c6 Nf3 Nf6 Nc3 e6"),
+    ("D80", "Grünfeld Defense", "d4 Nf6 c4 g6 Nc3 d5"),
+    ("E00", "Catalan Opening", "d4 Nf6 c4 e6 g3"),
+    ("E12", "Queen's Indian Defense", "d4 Nf6 c4 e6 Nf3 b6"),
+    ("E20", "Nimzo-Indian Defense", "d4 Nf6 c4 e6 Nc3 Bb4"),
+    ("E60", "King's Indian Defense", "d4 Nf6 c4 g6"),
+    ("E61", "King's Indian Defense", "d4 Nf6 c4 g6 Nc3 Bg7"),
+];
+
+// opening names by position, so transpositions are recognised
+#[derive(Debug, Clone, Default)]
+pub struct EcoTable {
+    positions: HashMap<u64, EcoOpening>,
+}
+
+impl EcoTable {
+    pub fn builtin() -> Self {
+        let mut table = Self::default();
+        for &(eco, name, moves) in BUILTIN {
+            // every line is checked by the tests
+            let _ = table.add(eco, name, moves);
+        }
+        table
+    }
+
+    // lichess chess-openings rows: `eco<TAB>name<TAB>pgn`, after a header line
+    pub fn parse_tsv(text: &str) -> Result<Self, ParseEcoError> {
+        let mut table = Self::default();
+        let rows = text
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty() && !line.starts_with("eco\t"));
+        for (idx, line) in rows {
+            let mut fields = line.split('\t');
+            let mut field = |name| {
+                fields
+                    .next()
+                    .filter(|f| !f.trim().is_empty())
+                    .ok_or(ParseEcoError::MissingField(idx + 1, name))
+            };
+            let (eco, name, moves) = (field("eco")?, field("name")?, field("pgn")?);
+            table
+                .add(eco, name, moves)
+                .map_err(|san| ParseEcoError::IllegalMove(idx + 1, san))?;
+        }
+        Ok(table)
+    }
+
+    // the first line to reach a position names it
+    pub(crate) fn add(&mut self, eco: &str, name: &str, moves: &str) -> Result<(), String> {
+        let mut gs = GameState::default();
+        // move numbers like `1.` or `2...` are skipped
+        let sans = moves
+            .split_whitespace()
+            .filter(|token| !token.ends_with('.'));
+        for san in sans {
+            let mv = gs.parse_san(san).map_err(|_| san.to_owned())?;
+            gs.play(mv);
+        }
+        self.positions
+            .entry(gs.zobrist())
+            .or_insert_with(|| EcoOpening {
+                eco: eco.to_owned(),
+                name: name.to_owned(),
+            });
+        Ok(())
+    }
+
+    pub fn lookup(&self, gs: &GameState) -> Option<&EcoOpening> {
+        self.positions.get(&gs.zobrist())
+    }
+
+    // the last named position the game went through, which stays once it leaves book
+    pub fn classify<'a>(
+        &self,
+        positions: impl IntoIterator<Item = &'a GameState>,
+    ) -> Option<&EcoOpening> {
+        positions
+            .into_iter()
+            .filter_map(|gs| self.lookup(gs))
+            .last()
+    }
+
+    pub fn len(&self) -> usize {
+        self.positions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.positions.is_empty()
+    }
+}
@@ -0,0 +1,228 @@
+use crate::{
+    GameState,
+    board::{Board, Column, Row, Square},
+    castle::Castle,
+    moves::Move,
+    pieces::{Color, Figure, Piece},
+    random::{RandomSetup, Rng},
+};
+use std::fmt::Display;
+
+const LUCENA: &str = "1K1k4/1P6/8/8/8/8/r7/2R5 w - - 0 1";
+const PHILIDOR: &str = "4k3/7R/r7/3KP3/8/8/8/8 b - - 0 1";
+const MATE: i32 = 100_000;
+
+// the theoretical endgames the trainer sets up; White is always the side with the extra material
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Endgame {
+    QueenVsKing,
+    RookVsKing,
+    PawnVsKing,
+    Lucena,
+    Philidor,
+}
+
+// what the trainee has to get out of the position
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Goal {
+    // mate before the fifty-move rule runs out
+    Win,
+    // a draw of any kind, without being mated first
+    Draw,
+}
+
+impl Endgame {
+    pub const ALL: [Endgame; 5] = [
+        Endgame::QueenVsKing,
+        Endgame::RookVsKing,
+        Endgame::PawnVsKing,
+        Endgame::Lucena,
+        Endgame::Philidor,
+    ];
+
+    pub fn goal(self) -> Goal {
+        match self {
+            Endgame::Philidor => Goal::Draw,
+            _ => Goal::Win,
+        }
+    }
+
+    // the side the trainee plays; the other one defends
+    pub fn trainee(self) -> Color {
+        match self.goal() {
+            Goal::Win => Color::White,
+            Goal::Draw => Color::Black,
+        }
+    }
+
+    // a fresh instance: the bare-king endings placed at random, the pawn with its king on a
+    // key square, the book positions on either wing
+    pub fn position(self, rng: &mut Rng) -> GameState {
+        let gs = match self {
+            Endgame::QueenVsKing => bare_king(Figure::Queen, rng),
+            Endgame::RookVsKing => bare_king(Figure::Rook, rng),
+            Endgame::PawnVsKing => key_square(rng),
+            Endgame::Lucena => GameState::try_from_fen(LUCENA).unwrap(),
+            Endgame::Philidor => GameState::try_from_fen(PHILIDOR).unwrap(),
+        };
+        if rng.below(2) == 0 { mirrored(&gs) } else { gs }
+    }
+}
+
+impl Display for Endgame {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            Endgame::QueenVsKing => "Queen vs king",
+            Endgame::RookVsKing => "Rook vs king",
+            Endgame::PawnVsKing => "King and pawn vs king",
+            Endgame::Lucena => "Lucena position",
+            Endgame::Philidor => "Philidor position",
+        };
+        write!(f, "{s}")
+    }
+}
+
+fn bare_king(figure: Figure, rng: &mut Rng) -> GameState {
+    let setup = RandomSetup {
+        turn: Some(Color::White),
+        ..RandomSetup::new(&[figure], &[])
+    };
+    // a queen or rook always fits next to two kings
+    setup.generate(rng).unwrap()
+}
+
+// pawn on b-g, ranks 2-5, its king two ranks in front, which wins whoever is to move; the
+// defending king can't take the pawn on its first move
+fn key_square(rng: &mut Rng) -> GameState {
+    loop {
+        let pawn = Square::from_coords(
+            Column::try_from(1 + rng.below(6) as u8).unwrap(),
+            Row::try_from(1 + rng.below(4) as u8).unwrap(),
+        );
+        let king = Square::from_coords(
+            Column::try_from((pawn.col() as usize + rng.below(3) - 1) as u8).unwrap(),
+            Row::try_from(pawn.row() as u8 + 2).unwrap(),
+        );
+        let defender = Square::new(rng.below(64)).unwrap();
+        if distance(defender, pawn) < 2 || distance(defender, king) < 2 {
+            continue;
+        }
+        let mut board = Board::new();
+        board.set_sq(pawn, Piece {
+            color: Color::White,
+            figure: Figure::Pawn,
+        });
+        board.set_sq(king, Piece {
+            color: Color::White,
+            figure: Figure::King,
+        });
+        board.set_sq(defender, Piece {
+            color: Color::Black,
+            figure: Figure::King,
+        });
+        let gs = GameState {
+            board,
+            turn: Color::White,
+            castle: Castle::Null,
+            ep_square: None,
+            half_move: 0,
+            full_move: 1,
+        };
+        if !gs.board.is_in_check(Color::Black) && !gs.status().is_over() {
+            return gs;
+        }
+    }
+}
+
+// the same position reflected across the d/e line
+fn mirrored(gs: &GameState) -> GameState {
+    let mut board = Board::new();
+    for (square, piece) in gs.board.iter() {
+        let col = Column::try_from(7 - square.col() as u8).unwrap();
+        board.set_sq(Square::from_coords(col, square.row()), piece);
+    }
+    GameState { board, ..*gs }
+}
+
+// king moves between two squares
+fn distance(a: Square, b: Square) -> i32 {
+    let cols = (a.col() as i32 - b.col() as i32).abs();
+    let rows = (a.row() as i32 - b.row() as i32).abs();
+    cols.max(rows)
+}
+
+// the defending side's reply when no engine is at hand: a shallow search that sees mates
+// coming and keeps its king central and away from the attacker's
+pub fn defend(gs: &GameState) -> Option<Move> {
+    gs.legal_moves().into_iter().max_by_key(|&mv| {
+        let mut child = *gs;
+        child.play(mv);
+        -negamax(&child, 1)
+    })
+}
+
+fn negamax(gs: &GameState, depth: u32) -> i32 {
+    let moves = gs.legal_moves();
+    if moves.is_empty() {
+        return if gs.board.is_in_check(gs.turn) {
+            -MATE
+        } else {
+            0
+        };
+    }
+    if gs.half_move >= 100 || gs.is_insufficient_material() {
+        return 0;
+    }
+    if depth == 0 {
+        return evaluate(gs);
+    }
+    moves
+        .into_iter()
+        .map(|mv| {
+            let mut child = *gs;
+            child.play(mv);
+            -negamax(&child, depth - 1)
+        })
+        .max()
+        .unwrap_or_default()
+}
+
+// material and pawn progress for the side to move, plus the stronger side's pull towards
+// the other king once it's driven to the edge
+fn evaluate(gs: &GameState) -> i32 {
+    let score = |color: Color| -> i32 {
+        gs.board
+            .iter_color(color)
+            .map(|(square, piece)| {
+                let advance = match (piece.figure, color) {
+                    (Figure::Pawn, Color::White) => square.row() as i32 - 1,
+                    (Figure::Pawn, Color::Black) => 6 - square.row() as i32,
+                    _ => 0,
+                };
+                piece.figure.value() + 20 * advance
+            })
+            .sum()
+    };
+    let (own, theirs) = (score(gs.turn), score(!gs.turn));
+    let king = |color: Color| {
+        gs.board
+            .iter_piece(Piece {
+                color,
+                figure: Figure::King,
+            })
+            .next()
+    };
+    let (Some(own_king), Some(their_king)) = (king(gs.turn), king(!gs.turn)) else {
+        return own - theirs;
+    };
+    let mop_up = |strong: Square, weak: Square| {
+        let edge = |n: i32| (2 * n - 7).abs() / 2;
+        let corner = edge(weak.col() as i32) + edge(weak.row() as i32);
+        10 * corner + 4 * (7 - distance(strong, weak))
+    };
+    match own.cmp(&theirs) {
+        std::cmp::Ordering::Greater => own - theirs + mop_up(own_king, their_king),
+        std::cmp::Ordering::Less => own - theirs - mop_up(their_king, own_king),
+        std::cmp::Ordering::Equal => 0,
+    }
+}
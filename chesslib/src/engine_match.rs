@@ -0,0 +1,412 @@
+use crate::{
+    GameState,
+    clock::{Clock, TimeControl},
+    errors::{MatchError, ParsePgnError, UciError},
+    moves::Move,
+    pgn::{GameResult, PgnGame, parse_pgn},
+    pieces::Color,
+    ratings,
+    uci::{GoLimits, Score, SearchResult, UciEngine},
+};
+use std::{collections::HashMap, fmt::Display, time::Duration};
+
+// extra time an engine gets past its clock before it is considered hung
+const TIMEOUT_MARGIN: Duration = Duration::from_secs(5);
+const MATE_SCORE: i32 = 100_000;
+// the most an engine can think it's ahead by and still take a draw
+const DRAW_ACCEPT_SCORE: i32 = 25;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Opening {
+    pub fen: Option<String>,
+    pub moves: Vec<Move>,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum OpeningFormat {
+    Epd,
+    Pgn,
+}
+
+pub fn load_openings(text: &str, format: OpeningFormat) -> Result<Vec<Opening>, ParsePgnError> {
+    match format {
+        OpeningFormat::Epd => text
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(|line| {
+                // epd lines carry no move counters, just opcodes after the fourth field
+                let fields: Vec<&str> = line.split_whitespace().take(4).collect();
+                let fen = format!("{} 0 1", fields.join(" "));
+                GameState::try_from_fen(&fen)?;
+                Ok(Opening {
+                    fen: Some(fen),
+                    moves: Vec::new(),
+                })
+            })
+            .collect(),
+        OpeningFormat::Pgn => parse_pgn(text)?
+            .iter()
+            .map(|game| {
+                Ok(Opening {
+                    fen: game.tag("FEN").map(str::to_owned),
+                    moves: game.replay()?.into_iter().map(|(_, mv)| mv).collect(),
+                })
+            })
+            .collect(),
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct DrawAdjudication {
+    pub move_number: u32,
+    pub move_count: u32,
+    pub score: i32,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ResignAdjudication {
+    pub move_count: u32,
+    pub score: i32,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MatchConfig {
+    pub event: String,
+    pub time_control: TimeControl,
+    pub draw: Option<DrawAdjudication>,
+    pub resign: Option<ResignAdjudication>,
+    pub max_moves: Option<u32>,
+    // Black's own time control, when it isn't White's
+    pub black_time_control: Option<TimeControl>,
+    // draws go to Black, as in an Armageddon tie-break
+    pub armageddon: bool,
+}
+
+// an Armageddon game's result: Black wins it by drawing
+pub fn armageddon_result(result: GameResult) -> GameResult {
+    match result {
+        GameResult::Draw => GameResult::BlackWins,
+        result => result,
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Termination {
+    Checkmate,
+    Stalemate,
+    Repetition,
+    FiftyMoves,
+    InsufficientMaterial,
+    TimeForfeit,
+    IllegalMove,
+    Adjudication,
+    EngineError,
+    Resignation,
+    Agreement,
+}
+
+impl Display for Termination {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            Termination::Checkmate => "checkmate",
+            Termination::Stalemate => "stalemate",
+            Termination::Repetition => "threefold repetition",
+            Termination::FiftyMoves => "fifty moves",
+            Termination::InsufficientMaterial => "insufficient material",
+            Termination::TimeForfeit => "time forfeit",
+            Termination::IllegalMove => "illegal move",
+            Termination::Adjudication => "adjudication",
+            Termination::EngineError => "engine error",
+            Termination::Resignation => "resignation",
+            Termination::Agreement => "agreement",
+        };
+        write!(f, "{s}")
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GameRecord {
+    pub game: PgnGame,
+    pub result: GameResult,
+    pub termination: Termination,
+}
+
+// what the watcher of a game hears as it goes
+pub enum GameEvent<'a> {
+    // the running search of the side to move in the position
+    Searching(&'a GameState, &'a SearchResult),
+    // a move just played, with the search that found it; the clocks stay stopped
+    // until the watcher returns
+    Moved(Move, &'a SearchResult, &'a Clock),
+}
+
+// white engine first
+pub fn play_game(
+    engines: [&mut UciEngine; 2],
+    opening: &Opening,
+    round: usize,
+    config: &MatchConfig,
+) -> Result<GameRecord, MatchError> {
+    play_game_watched(engines, opening, round, config, |_| true)
+}
+
+// like `play_game`, telling `watch` about every search and move; the game is abandoned
+// as soon as it returns false
+pub fn play_game_watched(
+    engines: [&mut UciEngine; 2],
+    opening: &Opening,
+    round: usize,
+    config: &MatchConfig,
+    mut watch: impl FnMut(GameEvent) -> bool,
+) -> Result<GameRecord, MatchError> {
+    let mut tags = vec![
+        ("Event".to_owned(), config.event.clone()),
+        ("Site".to_owned(), "?".to_owned()),
+        ("Date".to_owned(), "????.??.??".to_owned()),
+        ("Round".to_owned(), round.to_string()),
+        ("White".to_owned(), engines[0].name().to_owned()),
+        ("Black".to_owned(), engines[1].name().to_owned()),
+        ("TimeControl".to_owned(), config.time_control.to_string()),
+    ];
+    let black_time_control = config.black_time_control.unwrap_or(config.time_control);
+    if black_time_control != config.time_control {
+        tags.push((
+            "BlackTimeControl".to_owned(),
+            black_time_control.to_string(),
+        ));
+    }
+    if config.armageddon {
+        tags.push(("Armageddon".to_owned(), "1".to_owned()));
+    }
+    if let Some(fen) = &opening.fen {
+        tags.push(("SetUp".to_owned(), "1".to_owned()));
+        tags.push(("FEN".to_owned(), fen.clone()));
+    }
+    let mut gs = match &opening.fen {
+        Some(fen) => GameState::try_from_fen(fen).map_err(ParsePgnError::from)?,
+        None => GameState::default(),
+    };
+    let mut sans = Vec::new();
+    for &mv in &opening.moves {
+        sans.push(gs.to_san(mv));
+        gs.play(mv);
+    }
+
+    let mut engines = engines;
+    let mut moves = opening.moves.clone();
+    let mut clock = Clock::with_time_controls([config.time_control, black_time_control]);
+    let mut seen: HashMap<u64, u32> = HashMap::from([(gs.zobrist(), 1)]);
+    let mut adjudicator = Adjudicator::default();
+    for engine in engines.iter_mut() {
+        engine.new_game()?;
+    }
+
+    let (result, termination) = loop {
+        if let Some(end) = game_over(&gs, &seen) {
+            break end;
+        }
+        if let Some(max) = config.max_moves
+            && u32::from(gs.full_move) > max
+        {
+            break (GameResult::Draw, Termination::Adjudication);
+        }
+        let turn = gs.turn;
+        let loss = match turn {
+            Color::White => GameResult::BlackWins,
+            Color::Black => GameResult::WhiteWins,
+        };
+        let engine = &mut engines[turn as usize];
+        let limits = GoLimits {
+            wtime: Some(clock.remaining(Color::White)),
+            btime: Some(clock.remaining(Color::Black)),
+            winc: Some(config.time_control.increment),
+            binc: Some(black_time_control.increment),
+            moves_to_go: clock.moves_to_go(turn),
+            ..Default::default()
+        };
+        let timeout = clock.remaining(turn) + TIMEOUT_MARGIN;
+        clock.start(turn);
+        let mut abandoned = false;
+        let search = engine
+            .set_position(opening.fen.as_deref(), &moves)
+            .and_then(|_| {
+                engine.go_with_info(&limits, timeout, |search| {
+                    abandoned |= !watch(GameEvent::Searching(&gs, search));
+                    !abandoned
+                })
+            });
+        let in_time = clock.stop();
+        if abandoned {
+            return Err(MatchError::Abandoned);
+        }
+        let search = match search {
+            Ok(search) => search,
+            Err(UciError::Timeout) => break (loss, Termination::TimeForfeit),
+            Err(_) => break (loss, Termination::EngineError),
+        };
+        if !in_time {
+            break (loss, Termination::TimeForfeit);
+        }
+        let Some(mv) = gs.parse_uci_move(&search.best_move) else {
+            break (loss, Termination::IllegalMove);
+        };
+        if let Some(end) = adjudicator.update(config, &gs, search.score) {
+            break end;
+        }
+        sans.push(gs.to_san(mv));
+        gs.play(mv);
+        moves.push(mv);
+        *seen.entry(gs.zobrist()).or_default() += 1;
+        if !watch(GameEvent::Moved(mv, &search, &clock)) {
+            return Err(MatchError::Abandoned);
+        }
+    };
+
+    let result = match config.armageddon {
+        true => armageddon_result(result),
+        false => result,
+    };
+    tags.insert(6, ("Result".to_owned(), result.to_string()));
+    tags.push(("Termination".to_owned(), termination.to_string()));
+    Ok(GameRecord {
+        game: PgnGame {
+            tags,
+            moves: sans,
+            annotations: Vec::new(),
+            result: Some(result),
+        },
+        result,
+        termination,
+    })
+}
+
+fn game_over(gs: &GameState, seen: &HashMap<u64, u32>) -> Option<(GameResult, Termination)> {
+    if gs.legal_move_count() == 0 {
+        if gs.board.is_in_check(gs.turn) {
+            let result = match gs.turn {
+                Color::White => GameResult::BlackWins,
+                Color::Black => GameResult::WhiteWins,
+            };
+            return Some((result, Termination::Checkmate));
+        }
+        return Some((GameResult::Draw, Termination::Stalemate));
+    }
+    if seen.get(&gs.zobrist()).is_some_and(|&count| count >= 3) {
+        return Some((GameResult::Draw, Termination::Repetition));
+    }
+    if gs.half_move >= 100 {
+        return Some((GameResult::Draw, Termination::FiftyMoves));
+    }
+    if gs.is_insufficient_material() {
+        return Some((GameResult::Draw, Termination::InsufficientMaterial));
+    }
+    None
+}
+
+// whether an engine takes a draw offer, going by its last score from its own side
+pub fn accepts_draw(score: Option<Score>) -> bool {
+    match score {
+        Some(Score::Centipawns(cp)) => cp <= DRAW_ACCEPT_SCORE,
+        Some(Score::Mate(n)) => n < 0,
+        None => false,
+    }
+}
+
+#[derive(Debug, Default)]
+struct Adjudicator {
+    draw_plies: u32,
+    resign_moves: [u32; 2],
+}
+
+impl Adjudicator {
+    // scores are from the point of view of the side to move
+    fn update(
+        &mut self,
+        config: &MatchConfig,
+        gs: &GameState,
+        score: Option<Score>,
+    ) -> Option<(GameResult, Termination)> {
+        let score = match score {
+            Some(Score::Centipawns(cp)) => cp,
+            Some(Score::Mate(n)) if n > 0 => MATE_SCORE,
+            Some(Score::Mate(_)) => -MATE_SCORE,
+            None => {
+                *self = Self::default();
+                return None;
+            }
+        };
+        if let Some(draw) = config.draw {
+            if u32::from(gs.full_move) >= draw.move_number && score.abs() <= draw.score {
+                self.draw_plies += 1;
+            } else {
+                self.draw_plies = 0;
+            }
+            if self.draw_plies >= 2 * draw.move_count {
+                return Some((GameResult::Draw, Termination::Adjudication));
+            }
+        }
+        if let Some(resign) = config.resign {
+            let side = &mut self.resign_moves[gs.turn as usize];
+            if score <= -resign.score {
+                *side += 1;
+            } else {
+                *side = 0;
+            }
+            if *side >= resign.move_count {
+                let result = match gs.turn {
+                    Color::White => GameResult::BlackWins,
+                    Color::Black => GameResult::WhiteWins,
+                };
+                return Some((result, Termination::Adjudication));
+            }
+        }
+        None
+    }
+}
+
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct MatchScore {
+    pub wins: u32,
+    pub losses: u32,
+    pub draws: u32,
+}
+
+impl MatchScore {
+    // records a game from the point of view of the first engine
+    pub fn record(&mut self, result: GameResult, first_engine_color: Color) {
+        match (result, first_engine_color) {
+            (GameResult::Draw, _) => self.draws += 1,
+            (GameResult::WhiteWins, Color::White) | (GameResult::BlackWins, Color::Black) => {
+                self.wins += 1
+            }
+            _ => self.losses += 1,
+        }
+    }
+
+    pub fn games(&self) -> u32 {
+        self.wins + self.losses + self.draws
+    }
+
+    pub fn score_fraction(&self) -> Option<f64> {
+        let games = self.games();
+        (games > 0).then(|| (self.wins as f64 + self.draws as f64 / 2.0) / games as f64)
+    }
+
+    pub fn elo_difference(&self) -> Option<f64> {
+        self.score_fraction().and_then(ratings::elo_difference)
+    }
+}
+
+impl Display for MatchScore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} - {} - {}", self.wins, self.losses, self.draws)?;
+        if let Some(fraction) = self.score_fraction() {
+            write!(f, " [{fraction:.3}]")?;
+        }
+        if let Some(elo) = self.elo_difference() {
+            write!(f, " Elo diff {elo:+.1}")?;
+        }
+        Ok(())
+    }
+}
@@ -1,4 +1,5 @@
-use std::num::ParseIntError;
+use crate::moves::DrawClaim;
+use std::{fmt::Display, num::ParseIntError};
 use thiserror::Error;
 
 #[derive(Error, Debug, Copy, Clone)]
@@ -15,36 +16,192 @@ pub enum MoveError {
     Promoting,
     #[error("Failed EP")]
     FailedEp,
+    #[error("Game over")]
+    GameOver,
 }
 
-#[derive(Error, Debug, Clone)]
+#[derive(Error, Debug, Copy, Clone, PartialEq, Eq)]
+#[error("Can't claim a draw by {0}")]
+pub struct ClaimError(pub DrawClaim);
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum FenField {
+    Placement,
+    Turn,
+    Castling,
+    EnPassant,
+    HalfMove,
+    FullMove,
+}
+
+impl Display for FenField {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            FenField::Placement => "piece placement",
+            FenField::Turn => "side to move",
+            FenField::Castling => "castling",
+            FenField::EnPassant => "en passant",
+            FenField::HalfMove => "halfmove clock",
+            FenField::FullMove => "fullmove number",
+        };
+        write!(f, "{name}")
+    }
+}
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
 pub enum ParseFenError {
-    #[error("Empty FEN entry")]
-    EmptyFen,
-    #[error("Wrong number of rows")]
-    WrongRowCount,
-    #[error("Illegal state")]
+    #[error("Missing {0} field")]
+    MissingField(FenField),
+    #[error("Expected 8 ranks, found {0}")]
+    WrongRowCount(usize),
+    #[error("Rank {rank} does not describe 8 squares")]
+    WrongRowLength { rank: u8 },
+    #[error("Invalid piece on rank {rank} at character {index}")]
+    InvalidPiece {
+        rank: u8,
+        index: usize,
+        #[source]
+        source: InvalidCharError,
+    },
+    #[error("Invalid {field} field: {value:#?}")]
+    InvalidField { field: FenField, value: String },
+    #[error("Invalid {field} field: {value:#?}")]
+    InvalidNumber {
+        field: FenField,
+        value: String,
+        #[source]
+        source: ParseIntError,
+    },
+    #[error("Each side needs exactly one king")]
     IllegalState,
-    #[error("Invalid color: {0:#?}")]
-    InvalidColor(String),
+}
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error("Invalid square: {0:#?}")]
+pub struct ParseSquareError(pub String);
+
+#[derive(Error, Debug, Copy, Clone, PartialEq, Eq)]
+#[error("Invalid value: {0}")]
+pub struct InvalidValueError(pub u8);
+
+#[derive(Error, Debug, Copy, Clone, PartialEq, Eq)]
+#[error("Invalid character: {0:?}")]
+pub struct InvalidCharError(pub char);
+
+#[derive(Error, Debug, Clone)]
+pub enum ParseSanError {
+    #[error("Invalid SAN: {0:#?}")]
+    InvalidString(String),
+    #[error("Illegal move: {0}")]
+    IllegalMove(String),
+    #[error("Ambiguous move: {0}")]
+    AmbiguousMove(String),
+}
+
+#[derive(Error, Debug, Clone)]
+pub enum ParsePgnError {
+    #[error("Invalid tag: {0:#?}")]
+    InvalidTag(String),
+    #[error("Invalid result: {0:#?}")]
+    InvalidResult(String),
+    #[error("Unterminated {0}")]
+    Unterminated(char),
     #[error(transparent)]
-    ParseIntError(#[from] ParseIntError),
+    ParseFenError(#[from] ParseFenError),
     #[error(transparent)]
-    ParsePieceError(#[from] ParsePieceError),
+    ParseSanError(#[from] ParseSanError),
+}
+
+#[derive(Error, Debug)]
+pub enum DatabaseError {
+    #[error("Invalid index file")]
+    InvalidIndex,
     #[error(transparent)]
-    InvalidValueError(#[from] InvalidValueError),
-    #[error("Invalid string: {0:#?}")]
-    InvalidString(String),
+    Io(#[from] std::io::Error),
+    #[error(transparent)]
+    ParsePgnError(#[from] ParsePgnError),
 }
 
-#[derive(Error, Debug, Copy, Clone)]
-#[error("Invalid char {0}")]
-pub struct ParsePieceError(pub char);
+impl From<ParseFenError> for DatabaseError {
+    fn from(err: ParseFenError) -> Self {
+        Self::ParsePgnError(err.into())
+    }
+}
 
-#[derive(Error, Debug, Copy, Clone)]
-#[error("Invalid input: {0}")]
-pub struct InvalidValueError(pub u8);
+impl From<ParseSanError> for DatabaseError {
+    fn from(err: ParseSanError) -> Self {
+        Self::ParsePgnError(err.into())
+    }
+}
 
-#[derive(Error, Debug, Copy, Clone)]
-#[error("Invalid input: {0}")]
-pub struct InvalidCharError(pub char);
+#[derive(Error, Debug, Clone)]
+#[error("Invalid time control: {0:#?}")]
+pub struct ParseTimeControlError(pub String);
+
+#[derive(Error, Debug, Clone)]
+#[error("Invalid message: {0:#?}")]
+pub struct ParseNetMessageError(pub String);
+
+#[derive(Error, Debug)]
+pub enum UciError {
+    #[error("Engine timed out")]
+    Timeout,
+    #[error("Engine disconnected")]
+    Disconnected,
+    #[error("Unexpected engine output: {0:#?}")]
+    Protocol(String),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+#[derive(Error, Debug)]
+pub enum MatchError {
+    #[error(transparent)]
+    ParsePgnError(#[from] ParsePgnError),
+    #[error(transparent)]
+    UciError(#[from] UciError),
+    #[error("game abandoned")]
+    Abandoned,
+}
+
+#[derive(Error, Debug, Clone)]
+pub enum ParsePositionError {
+    #[error("Missing startpos or fen")]
+    MissingPosition,
+    #[error("Unexpected token: {0:#?}")]
+    InvalidToken(String),
+    #[error("Illegal move: {0}")]
+    IllegalMove(String),
+    #[error(transparent)]
+    ParseFenError(#[from] ParseFenError),
+}
+
+#[derive(Error, Debug, Clone)]
+pub enum ParsePuzzleError {
+    #[error("Line {0}: missing {1}")]
+    MissingField(usize, &'static str),
+    #[error("Line {0}: invalid rating {1:#?}")]
+    InvalidRating(usize, String),
+    #[error("Line {0}: illegal move {1}")]
+    IllegalMove(usize, String),
+    #[error("Line {0}: {1}")]
+    ParseFenError(usize, #[source] ParseFenError),
+}
+
+#[derive(Error, Debug, Clone)]
+pub enum ParseFairyError {
+    #[error("Unknown movement atom {0:?}")]
+    UnknownAtom(char),
+    #[error("Unknown piece {0:?}")]
+    UnknownPiece(char),
+    #[error("Invalid position: {0:#?}")]
+    InvalidPosition(String),
+}
+
+#[derive(Error, Debug, Clone)]
+pub enum ParseEcoError {
+    #[error("Line {0}: missing {1}")]
+    MissingField(usize, &'static str),
+    #[error("Line {0}: illegal move {1}")]
+    IllegalMove(usize, String),
+}
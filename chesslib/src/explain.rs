@@ -0,0 +1,176 @@
+use crate::{
+    GameState,
+    board::{Column, Row, Square, bitboard::BitBoard},
+    movegen,
+    moves::MoveKind,
+    pieces::{Color, Figure, Piece},
+};
+use std::fmt::Display;
+
+// why a move can't be played, in terms a beginner can act on
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum IllegalReason {
+    EmptySquare,
+    WrongTurn,
+    OwnPiece,
+    // not how the figure moves at all
+    WrongPattern(Figure),
+    PathBlocked,
+    // straight ahead onto a piece
+    PawnBlocked,
+    // diagonally onto an empty square
+    PawnNeedsCapture,
+    KingIntoCheck,
+    Pinned,
+    IgnoresCheck,
+    CastlingRightsLost,
+    CastlingBlocked,
+    CastlingOutOfCheck,
+    CastlingThroughCheck,
+}
+
+impl Display for IllegalReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            IllegalReason::EmptySquare => "there's no piece there",
+            IllegalReason::WrongTurn => "it's the other side's move",
+            IllegalReason::OwnPiece => "you can't take your own piece",
+            IllegalReason::WrongPattern(figure) => {
+                let name = match figure {
+                    Figure::Pawn => "a pawn",
+                    Figure::Knight => "a knight",
+                    Figure::Bishop => "a bishop",
+                    Figure::Rook => "a rook",
+                    Figure::Queen => "a queen",
+                    Figure::King => "the king",
+                };
+                return write!(f, "{name} doesn't move that way");
+            }
+            IllegalReason::PathBlocked => "something is in the way",
+            IllegalReason::PawnBlocked => "pawns can't take straight ahead",
+            IllegalReason::PawnNeedsCapture => "pawns only go diagonally to take",
+            IllegalReason::KingIntoCheck => "the king would walk into check",
+            IllegalReason::Pinned => "that piece is pinned to its king",
+            IllegalReason::IgnoresCheck => "your king is in check and this doesn't help",
+            IllegalReason::CastlingRightsLost => "the king or that rook has already moved",
+            IllegalReason::CastlingBlocked => "pieces stand between the king and the rook",
+            IllegalReason::CastlingOutOfCheck => "you can't castle out of check",
+            IllegalReason::CastlingThroughCheck => {
+                "the king can't castle through or into an attacked square"
+            }
+        };
+        write!(f, "{s}")
+    }
+}
+
+impl GameState {
+    // what's wrong with moving from `from` to `to`, or None when some move does just that
+    pub fn explain_illegal(&self, from: Square, to: Square) -> Option<IllegalReason> {
+        if self
+            .legal_moves()
+            .iter()
+            .any(|mv| mv.from == from && mv.to == to)
+        {
+            return None;
+        }
+        let Some(piece) = self.board.get_sq(from) else {
+            return Some(IllegalReason::EmptySquare);
+        };
+        if piece.color != self.turn {
+            return Some(IllegalReason::WrongTurn);
+        }
+        let reason = match piece.figure {
+            Figure::King if self.is_castle_attempt(from, to) => self.explain_castle(from, to),
+            _ if self.board.occupied_color(self.turn).contains(to) => IllegalReason::OwnPiece,
+            Figure::King if BitBoard::king_moves(from).contains(to) => self.explain_check(piece),
+            Figure::Pawn => self.explain_pawn(from, to),
+            figure => {
+                let occupied = self.board.occupied();
+                if BitBoard::attacks(piece, from, occupied).contains(to) {
+                    self.explain_check(piece)
+                } else if BitBoard::attacks(piece, from, BitBoard::default()).contains(to) {
+                    IllegalReason::PathBlocked
+                } else {
+                    IllegalReason::WrongPattern(figure)
+                }
+            }
+        };
+        Some(reason)
+    }
+
+    // the king two files over along its home rank, from its home square
+    fn is_castle_attempt(&self, from: Square, to: Square) -> bool {
+        let home = match self.turn {
+            Color::White => Square::E1,
+            Color::Black => Square::E8,
+        };
+        from == home && to.row() == from.row() && matches!(to.col(), Column::C | Column::G)
+    }
+
+    fn explain_castle(&self, from: Square, to: Square) -> IllegalReason {
+        let (kind, allowed) = match to.col() {
+            Column::G => (MoveKind::KingCastle, self.castle.can_king_castle(self.turn)),
+            _ => (
+                MoveKind::QueenCastle,
+                self.castle.can_queen_castle(self.turn),
+            ),
+        };
+        let (rook_from, _) = movegen::castle_rook_squares(kind, from.row());
+        if !allowed {
+            IllegalReason::CastlingRightsLost
+        } else if BitBoard::straight_ray(from, rook_from) & self.board.occupied() != from.into() {
+            IllegalReason::CastlingBlocked
+        } else if self.board.is_in_check(self.turn) {
+            IllegalReason::CastlingOutOfCheck
+        } else {
+            IllegalReason::CastlingThroughCheck
+        }
+    }
+
+    fn explain_pawn(&self, from: Square, to: Square) -> IllegalReason {
+        let pawn = Piece {
+            color: self.turn,
+            figure: Figure::Pawn,
+        };
+        if self.board.pawn_moves(from, self.turn).contains(to)
+            || (self.ep_square == Some(to) && BitBoard::pawn_attacks(from, self.turn).contains(to))
+        {
+            return self.explain_check(pawn);
+        }
+        if BitBoard::pawn_attacks(from, self.turn).contains(to) {
+            return IllegalReason::PawnNeedsCapture;
+        }
+        let (start_row, step) = match self.turn {
+            Color::White => (Row::Two, 1),
+            Color::Black => (Row::Seven, -1),
+        };
+        let ahead = |rows: i8| {
+            let row = Row::try_from((from.row() as i8 + step * rows) as u8).ok()?;
+            Some(Square::from_coords(from.col(), row))
+        };
+        let occupied = self.board.occupied();
+        if ahead(1) == Some(to) {
+            IllegalReason::PawnBlocked
+        } else if ahead(2) == Some(to) && from.row() == start_row {
+            // the square in between or the one landed on
+            if ahead(1).is_some_and(|square| occupied.contains(square)) {
+                IllegalReason::PathBlocked
+            } else {
+                IllegalReason::PawnBlocked
+            }
+        } else {
+            IllegalReason::WrongPattern(Figure::Pawn)
+        }
+    }
+
+    // a move the piece could make, but for its own king
+    fn explain_check(&self, piece: Piece) -> IllegalReason {
+        if piece.figure == Figure::King {
+            IllegalReason::KingIntoCheck
+        } else if self.board.is_in_check(self.turn) {
+            IllegalReason::IgnoresCheck
+        } else {
+            IllegalReason::Pinned
+        }
+    }
+}
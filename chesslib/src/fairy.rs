@@ -0,0 +1,483 @@
+use crate::{
+    errors::ParseFairyError,
+    geometry::{Geometry, WideBitBoard},
+    pieces::{Color, Figure},
+};
+use std::{fmt::Display, str::FromStr};
+
+// one way of moving, as a (file, rank) offset: a leap goes straight there, a ride keeps
+// going along the offset until something is in the way
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Step {
+    Leap(i8, i8),
+    Ride(i8, i8),
+}
+
+// how a piece moves, built from leapers and riders. Each is taken in all eight
+// directions, as in Betza's notation, which `from_str` reads: the atoms W F D N A H C Z G,
+// doubled to make a rider, and K R B Q as shorthands, so "BN" is an archbishop
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Movement {
+    steps: Vec<Step>,
+}
+
+impl Movement {
+    pub fn leaper(files: i8, ranks: i8) -> Self {
+        Self::symmetric(files, ranks, Step::Leap)
+    }
+
+    pub fn rider(files: i8, ranks: i8) -> Self {
+        Self::symmetric(files, ranks, Step::Ride)
+    }
+
+    fn symmetric(files: i8, ranks: i8, step: fn(i8, i8) -> Step) -> Self {
+        let mut steps = Vec::new();
+        for (a, b) in [(files, ranks), (ranks, files)] {
+            for (x, y) in [(a, b), (-a, b), (a, -b), (-a, -b)] {
+                if !steps.contains(&step(x, y)) {
+                    steps.push(step(x, y));
+                }
+            }
+        }
+        Self { steps }
+    }
+
+    // both ways of moving, as a compound piece
+    pub fn and(mut self, other: Movement) -> Self {
+        for step in other.steps {
+            if !self.steps.contains(&step) {
+                self.steps.push(step);
+            }
+        }
+        self
+    }
+
+    pub fn steps(&self) -> &[Step] {
+        &self.steps
+    }
+}
+
+impl FromStr for Movement {
+    type Err = ParseFairyError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut movement = Movement::default();
+        let mut chars = s.chars().peekable();
+        while let Some(atom) = chars.next() {
+            let ride = chars.next_if_eq(&atom).is_some();
+            let (files, ranks) = match atom {
+                'W' => (1, 0),
+                'F' => (1, 1),
+                'D' => (2, 0),
+                'N' => (2, 1),
+                'A' => (2, 2),
+                'H' => (3, 0),
+                'C' => (3, 1),
+                'Z' => (3, 2),
+                'G' => (3, 3),
+                'K' => {
+                    movement = movement.and(Self::leaper(1, 0)).and(Self::leaper(1, 1));
+                    continue;
+                }
+                'R' | 'B' | 'Q' => {
+                    if atom != 'B' {
+                        movement = movement.and(Self::rider(1, 0));
+                    }
+                    if atom != 'R' {
+                        movement = movement.and(Self::rider(1, 1));
+                    }
+                    continue;
+                }
+                atom => return Err(ParseFairyError::UnknownAtom(atom)),
+            };
+            movement = match ride {
+                true => movement.and(Self::rider(files, ranks)),
+                false => movement.and(Self::leaper(files, ranks)),
+            };
+        }
+        Ok(movement)
+    }
+}
+
+// what a piece is to the rules beyond how it moves: pawns push, take diagonally and
+// promote, and the royal piece mustn't be left attacked
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Role {
+    Pawn,
+    Royal,
+    Piece,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FairyFigure {
+    pub name: String,
+    // White's letter in a position; Black's is the lowercase one
+    pub letter: char,
+    pub movement: Movement,
+    pub role: Role,
+}
+
+impl FairyFigure {
+    pub fn new(name: &str, letter: char, betza: &str) -> Result<Self, ParseFairyError> {
+        Ok(Self {
+            name: name.to_owned(),
+            letter: letter.to_ascii_uppercase(),
+            movement: betza.parse()?,
+            role: Role::Piece,
+        })
+    }
+}
+
+impl From<Figure> for FairyFigure {
+    fn from(figure: Figure) -> Self {
+        let (name, letter, betza, role) = match figure {
+            Figure::Pawn => ("pawn", 'P', "", Role::Pawn),
+            Figure::Knight => ("knight", 'N', "N", Role::Piece),
+            Figure::Bishop => ("bishop", 'B', "B", Role::Piece),
+            Figure::Rook => ("rook", 'R', "R", Role::Piece),
+            Figure::Queen => ("queen", 'Q', "Q", Role::Piece),
+            Figure::King => ("king", 'K', "K", Role::Royal),
+        };
+        Self {
+            name: name.to_owned(),
+            letter,
+            // the shorthands always read
+            movement: betza.parse().unwrap_or_default(),
+            role,
+        }
+    }
+}
+
+// a board of any `Geometry` and the figures that play on it. Only moves are covered:
+// castling and en passant are left to the variants that need them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Variant {
+    pub name: String,
+    pub geometry: Geometry,
+    pub figures: Vec<FairyFigure>,
+    // the piece placement and side to move, FEN-style
+    pub start: String,
+}
+
+impl Variant {
+    pub fn standard() -> Self {
+        Self {
+            name: "Standard".to_owned(),
+            geometry: Geometry::STANDARD,
+            figures: Figure::iter().map(|&figure| figure.into()).collect(),
+            start: "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w".to_owned(),
+        }
+    }
+
+    // 10x8, with the archbishop (bishop and knight) and chancellor (rook and knight)
+    pub fn capablanca() -> Self {
+        let mut figures: Vec<FairyFigure> = Figure::iter().map(|&figure| figure.into()).collect();
+        figures.extend([
+            FairyFigure::new("archbishop", 'A', "BN").unwrap(),
+            FairyFigure::new("chancellor", 'C', "RN").unwrap(),
+        ]);
+        Self {
+            name: "Capablanca".to_owned(),
+            geometry: Geometry::CAPABLANCA,
+            figures,
+            start: "rnabqkbcnr/pppppppppp/10/10/10/10/PPPPPPPPPP/RNABQKBCNR w".to_owned(),
+        }
+    }
+
+    pub fn start_position(&self) -> Result<FairyPosition<'_>, ParseFairyError> {
+        FairyPosition::parse(self, &self.start)
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct FairyPiece {
+    pub color: Color,
+    // which of the variant's figures
+    pub figure: usize,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct FairySquare {
+    pub file: u8,
+    pub rank: u8,
+}
+
+impl Display for FairySquare {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}{}", (b'a' + self.file) as char, self.rank + 1)
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct FairyMove {
+    pub from: FairySquare,
+    pub to: FairySquare,
+    pub promotion: Option<usize>,
+}
+
+// the squares only: the figure promoted to needs the variant, see `FairyPosition::to_uci`
+impl Display for FairyMove {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}{}", self.from, self.to)
+    }
+}
+
+// a position in a variant, for generating and playing its moves: a mailbox for what
+// stands where, and each side's squares as bitboards for the move generation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FairyPosition<'v> {
+    variant: &'v Variant,
+    squares: Vec<Option<FairyPiece>>,
+    colors: [WideBitBoard; 2],
+    turn: Color,
+}
+
+impl<'v> FairyPosition<'v> {
+    // "placement turn", the placement top rank first with runs of empty squares as numbers
+    pub fn parse(variant: &'v Variant, text: &str) -> Result<Self, ParseFairyError> {
+        let invalid = || ParseFairyError::InvalidPosition(text.to_owned());
+        let geometry = variant.geometry;
+        let mut fields = text.split_whitespace();
+        let placement = fields.next().ok_or_else(invalid)?;
+        let turn = match fields.next() {
+            Some("w") | None => Color::White,
+            Some("b") => Color::Black,
+            Some(_) => return Err(invalid()),
+        };
+        let rows: Vec<&str> = placement.split('/').collect();
+        if rows.len() != geometry.ranks() as usize {
+            return Err(invalid());
+        }
+        let mut gs = Self {
+            variant,
+            squares: vec![None; geometry.squares() as usize],
+            colors: [WideBitBoard::default(); 2],
+            turn,
+        };
+        for (row_idx, row) in rows.iter().enumerate() {
+            let rank = geometry.ranks() - 1 - row_idx as u8;
+            let mut file = 0u32;
+            let mut chars = row.chars().peekable();
+            while let Some(c) = chars.next() {
+                if let Some(digit) = c.to_digit(10) {
+                    let mut empty = digit;
+                    while let Some(digit) = chars.peek().and_then(|c| c.to_digit(10)) {
+                        empty = (empty.checked_mul(10))
+                            .and_then(|empty| empty.checked_add(digit))
+                            .ok_or_else(invalid)?;
+                        chars.next();
+                    }
+                    file = file.checked_add(empty).ok_or_else(invalid)?;
+                    continue;
+                }
+                let figure = (variant.figures.iter())
+                    .position(|figure| figure.letter == c.to_ascii_uppercase())
+                    .ok_or(ParseFairyError::UnknownPiece(c))?;
+                let color = match c.is_ascii_uppercase() {
+                    true => Color::White,
+                    false => Color::Black,
+                };
+                let index = u8::try_from(file)
+                    .ok()
+                    .and_then(|file| geometry.index(file, rank))
+                    .ok_or_else(invalid)?;
+                gs.put(index, Some(FairyPiece { color, figure }));
+                file += 1;
+            }
+            if file != geometry.files() as u32 {
+                return Err(invalid());
+            }
+        }
+        Ok(gs)
+    }
+
+    pub fn turn(&self) -> Color {
+        self.turn
+    }
+
+    pub fn get(&self, square: FairySquare) -> Option<FairyPiece> {
+        let index = self.variant.geometry.index(square.file, square.rank)?;
+        self.squares[index as usize]
+    }
+
+    fn put(&mut self, index: u8, piece: Option<FairyPiece>) -> Option<FairyPiece> {
+        let before = std::mem::replace(&mut self.squares[index as usize], piece);
+        let bit = WideBitBoard::from_index(index);
+        self.colors[Color::White] &= !bit;
+        self.colors[Color::Black] &= !bit;
+        if let Some(piece) = piece {
+            self.colors[piece.color] |= bit;
+        }
+        before
+    }
+
+    fn square(&self, index: u8) -> FairySquare {
+        let geometry = self.variant.geometry;
+        FairySquare {
+            file: geometry.file(index),
+            rank: geometry.rank(index),
+        }
+    }
+
+    fn occupied(&self) -> WideBitBoard {
+        self.colors[0] | self.colors[1]
+    }
+
+    fn pieces(&self, color: Color) -> impl Iterator<Item = (u8, FairyPiece)> + '_ {
+        let pieces = self.colors[color].iter();
+        pieces.filter_map(|index| Some((index, self.squares[index as usize]?)))
+    }
+
+    // the squares the piece on `index` attacks, whoever stands on them
+    fn attacks(&self, index: u8, piece: FairyPiece) -> WideBitBoard {
+        let geometry = self.variant.geometry;
+        let from = WideBitBoard::from_index(index);
+        let figure = &self.variant.figures[piece.figure];
+        if figure.role == Role::Pawn {
+            let forward = forward(piece.color);
+            return from.shift(geometry, -1, forward) | from.shift(geometry, 1, forward);
+        }
+        let occupied = self.occupied();
+        let mut attacks = WideBitBoard::default();
+        for &step in figure.movement.steps() {
+            attacks |= match step {
+                Step::Leap(files, ranks) => from.shift(geometry, files, ranks),
+                Step::Ride(files, ranks) => {
+                    WideBitBoard::ray(geometry, index, files, ranks, occupied)
+                }
+            };
+        }
+        attacks
+    }
+
+    // where the piece on `index` can go, ignoring its own side's royal piece
+    fn targets(&self, index: u8, piece: FairyPiece) -> WideBitBoard {
+        let own = self.colors[piece.color];
+        let figure = &self.variant.figures[piece.figure];
+        if figure.role != Role::Pawn {
+            return self.attacks(index, piece) & !own;
+        }
+        let geometry = self.variant.geometry;
+        let enemy = self.colors[!piece.color];
+        let empty = !self.occupied();
+        let forward = forward(piece.color);
+        let single = WideBitBoard::from_index(index).shift(geometry, 0, forward) & empty;
+        let home = match piece.color {
+            Color::White => 1,
+            Color::Black => geometry.ranks() - 2,
+        };
+        let double = match geometry.rank(index) == home {
+            true => single.shift(geometry, 0, forward) & empty,
+            false => WideBitBoard::default(),
+        };
+        (self.attacks(index, piece) & enemy) | single | double
+    }
+
+    pub fn is_attacked(&self, square: FairySquare, by: Color) -> bool {
+        let Some(index) = self.variant.geometry.index(square.file, square.rank) else {
+            return false;
+        };
+        self.pieces(by)
+            .any(|(from, piece)| self.attacks(from, piece).contains(index))
+    }
+
+    // whether the side to move's royal piece is attacked
+    pub fn in_check(&self) -> bool {
+        self.pieces(self.turn)
+            .filter(|(_, piece)| self.variant.figures[piece.figure].role == Role::Royal)
+            .any(|(index, _)| self.is_attacked(self.square(index), !self.turn))
+    }
+
+    pub fn legal_moves(&self) -> Vec<FairyMove> {
+        let geometry = self.variant.geometry;
+        // what a pawn reaching the last rank can become
+        let promotions: Vec<usize> = (self.variant.figures.iter().enumerate())
+            .filter(|(_, figure)| figure.role == Role::Piece)
+            .map(|(idx, _)| idx)
+            .collect();
+        let last_rank = match self.turn {
+            Color::White => geometry.rank_mask(geometry.ranks() - 1),
+            Color::Black => geometry.rank_mask(0),
+        };
+        let mut moves = Vec::new();
+        for (index, piece) in self.pieces(self.turn) {
+            let pawn = self.variant.figures[piece.figure].role == Role::Pawn;
+            let from = self.square(index);
+            for target in self.targets(index, piece).iter() {
+                let to = self.square(target);
+                match pawn && last_rank.contains(target) {
+                    true => moves.extend(promotions.iter().map(|&figure| FairyMove {
+                        from,
+                        to,
+                        promotion: Some(figure),
+                    })),
+                    false => moves.push(FairyMove {
+                        from,
+                        to,
+                        promotion: None,
+                    }),
+                }
+            }
+        }
+        moves.retain(|&mv| {
+            let mut after = self.clone();
+            after.play(mv);
+            // still the mover's royal piece being looked at
+            after.turn = self.turn;
+            !after.in_check()
+        });
+        moves
+    }
+
+    // "e7e8q" style, promotions by the lowercase letter of the figure
+    pub fn to_uci(&self, mv: FairyMove) -> String {
+        match mv.promotion {
+            Some(figure) => {
+                let letter = self.variant.figures[figure].letter.to_ascii_lowercase();
+                format!("{mv}{letter}")
+            }
+            None => mv.to_string(),
+        }
+    }
+
+    // plays `mv`, which should be one of `legal_moves`, and returns what it took
+    pub fn play(&mut self, mv: FairyMove) -> Option<FairyPiece> {
+        let geometry = self.variant.geometry;
+        let (Some(from), Some(to)) = (
+            geometry.index(mv.from.file, mv.from.rank),
+            geometry.index(mv.to.file, mv.to.rank),
+        ) else {
+            return None;
+        };
+        let mut piece = self.put(from, None);
+        if let (Some(piece), Some(figure)) = (&mut piece, mv.promotion) {
+            piece.figure = figure;
+        }
+        let captured = self.put(to, piece);
+        self.turn = !self.turn;
+        captured
+    }
+
+    pub fn perft(&self, depth: u8) -> u64 {
+        match depth {
+            0 => 1,
+            1 => self.legal_moves().len() as u64,
+            _ => self
+                .legal_moves()
+                .into_iter()
+                .map(|mv| {
+                    let mut child = self.clone();
+                    child.play(mv);
+                    child.perft(depth - 1)
+                })
+                .sum(),
+        }
+    }
+}
+
+fn forward(color: Color) -> i8 {
+    match color {
+        Color::White => 1,
+        Color::Black => -1,
+    }
+}
@@ -0,0 +1,113 @@
+use crate::{
+    GameState,
+    board::Square,
+    errors::{ClaimError, MoveError},
+    moves::{DrawClaim, GameStatus, MoveOutcome},
+    pieces::Piece,
+};
+
+// a game together with the positions it went through, which is what repetitions are judged by
+#[derive(Debug, Clone)]
+pub struct Game {
+    gs: GameState,
+    // a hash of every position so far, the current one last
+    positions: Vec<u64>,
+    claimed: Option<DrawClaim>,
+}
+
+impl Game {
+    pub fn new(gs: GameState) -> Self {
+        Self {
+            positions: vec![gs.zobrist()],
+            gs,
+            claimed: None,
+        }
+    }
+
+    pub fn position(&self) -> &GameState {
+        &self.gs
+    }
+
+    pub fn make_move(&mut self, from: Square, to: Square) -> Result<MoveOutcome, MoveError> {
+        self.play(|gs| gs.make_move(from, to))
+    }
+
+    pub fn make_promotion(
+        &mut self,
+        from: Square,
+        to: Square,
+        piece: Piece,
+    ) -> Result<MoveOutcome, MoveError> {
+        self.play(|gs| gs.make_promotion(from, to, piece))
+    }
+
+    fn play(
+        &mut self,
+        make: impl FnOnce(&mut GameState) -> Result<MoveOutcome, MoveError>,
+    ) -> Result<MoveOutcome, MoveError> {
+        if self.is_over() {
+            return Err(MoveError::GameOver);
+        }
+        let mut outcome = make(&mut self.gs)?;
+        self.positions.push(self.gs.zobrist());
+        if outcome.status == GameStatus::Ongoing {
+            outcome.status = self.status();
+        }
+        Ok(outcome)
+    }
+
+    // how often the current position has come up, this time included; nothing repeats across a
+    // capture or pawn move, so only the positions since the last one are looked at
+    pub fn repetitions(&self) -> usize {
+        let current = self.gs.zobrist();
+        self.positions
+            .iter()
+            .rev()
+            .take(self.gs.half_move as usize + 1)
+            .filter(|&&key| key == current)
+            .count()
+    }
+
+    // the position's status, plus the fivefold repetition only the history can tell
+    pub fn status(&self) -> GameStatus {
+        match self.gs.status() {
+            GameStatus::Ongoing if self.repetitions() >= 5 => GameStatus::FivefoldRepetition,
+            status => status,
+        }
+    }
+
+    pub fn claimed(&self) -> Option<DrawClaim> {
+        self.claimed
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.claimed.is_some() || self.status().is_over()
+    }
+
+    // the draws the side to move may claim now; a client wanting them automatic just claims
+    // whatever shows up here after each move
+    pub fn claimable_draws(&self) -> Vec<DrawClaim> {
+        if self.is_over() {
+            return Vec::new();
+        }
+        let mut claims = self.gs.claimable_draws();
+        if self.repetitions() >= 3 {
+            claims.push(DrawClaim::Repetition);
+        }
+        claims
+    }
+
+    pub fn claim_draw(&mut self, reason: DrawClaim) -> Result<(), ClaimError> {
+        if !self.claimable_draws().contains(&reason) {
+            return Err(ClaimError(reason));
+        }
+        self.claimed = Some(reason);
+        Ok(())
+    }
+}
+
+impl From<GameState> for Game {
+    fn from(gs: GameState) -> Self {
+        Self::new(gs)
+    }
+}
@@ -0,0 +1,246 @@
+use crate::board::bitboard::BitBoard;
+use std::{
+    fmt::Debug,
+    ops::{BitAnd, BitAndAssign, BitOr, BitOrAssign, Not},
+};
+
+// the shape of a board, numbered a1 first along the ranks, up to 128 squares. `Board` takes
+// one at compile time as a `BoardGeometry`, keeping 8x8 on the u64 `BitBoard` that
+// `GameState` and the movegen are built on; pieces beyond the standard six go through
+// `fairy::FairyPosition`, which takes one at run time
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Geometry {
+    files: u8,
+    ranks: u8,
+}
+
+impl Geometry {
+    pub const STANDARD: Self = Self { files: 8, ranks: 8 };
+    pub const CAPABLANCA: Self = Self {
+        files: 10,
+        ranks: 8,
+    };
+    pub const NINE_BY_NINE: Self = Self { files: 9, ranks: 9 };
+
+    // None for a board with more files than letters or more squares than bits
+    pub const fn new(files: u8, ranks: u8) -> Option<Self> {
+        match files > 0 && ranks > 0 && files <= 26 && files as u16 * ranks as u16 <= 128 {
+            true => Some(Self { files, ranks }),
+            false => None,
+        }
+    }
+
+    pub const fn files(self) -> u8 {
+        self.files
+    }
+
+    pub const fn ranks(self) -> u8 {
+        self.ranks
+    }
+
+    pub const fn squares(self) -> u8 {
+        self.files * self.ranks
+    }
+
+    pub fn index(self, file: u8, rank: u8) -> Option<u8> {
+        (file < self.files && rank < self.ranks).then_some(rank * self.files + file)
+    }
+
+    pub const fn file(self, index: u8) -> u8 {
+        index % self.files
+    }
+
+    pub const fn rank(self, index: u8) -> u8 {
+        index / self.files
+    }
+
+    pub const fn all(self) -> WideBitBoard {
+        match self.squares() {
+            128 => WideBitBoard(u128::MAX),
+            squares => WideBitBoard((1 << squares) - 1),
+        }
+    }
+
+    pub fn rank_mask(self, rank: u8) -> WideBitBoard {
+        let rank_bits = (1u128 << self.files) - 1;
+        WideBitBoard(rank_bits << (rank * self.files)) & self.all()
+    }
+
+    // the squares on the files `from..to`
+    fn files_mask(self, from: u8, to: u8) -> WideBitBoard {
+        let rank_bits = ((1u128 << to) - 1) & !((1u128 << from) - 1);
+        (0..self.ranks).fold(WideBitBoard::default(), |mask, rank| {
+            mask | WideBitBoard(rank_bits << (rank * self.files))
+        })
+    }
+}
+
+// the squares of a `Board`, by index, whichever bitboard holds them
+pub trait SquareSet:
+    Copy
+    + Default
+    + Eq
+    + Debug
+    + BitAnd<Output = Self>
+    + BitOr<Output = Self>
+    + Not<Output = Self>
+    + BitAndAssign
+    + BitOrAssign
+{
+    fn from_index(index: u8) -> Self;
+    fn has_index(self, index: u8) -> bool;
+    fn indices(self) -> impl Iterator<Item = u8>;
+}
+
+// a board shape fixed at compile time, with the bitboard and the mailbox it needs
+pub trait BoardGeometry: Copy + Debug + Eq {
+    const GEOMETRY: Geometry;
+    type BitBoard: SquareSet;
+    // a byte per square
+    type Squares: Copy + Debug + Eq + AsRef<[u8]> + AsMut<[u8]>;
+    const EMPTY: Self::Squares;
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Standard;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Capablanca;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct NineByNine;
+
+impl BoardGeometry for Standard {
+    const GEOMETRY: Geometry = Geometry::STANDARD;
+    type BitBoard = BitBoard;
+    type Squares = [u8; 64];
+    const EMPTY: Self::Squares = [0; 64];
+}
+
+impl BoardGeometry for Capablanca {
+    const GEOMETRY: Geometry = Geometry::CAPABLANCA;
+    type BitBoard = WideBitBoard;
+    type Squares = [u8; 80];
+    const EMPTY: Self::Squares = [0; 80];
+}
+
+impl BoardGeometry for NineByNine {
+    const GEOMETRY: Geometry = Geometry::NINE_BY_NINE;
+    type BitBoard = WideBitBoard;
+    type Squares = [u8; 81];
+    const EMPTY: Self::Squares = [0; 81];
+}
+
+// a set of squares on a board of some `Geometry`, which is passed in wherever the edges
+// matter
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+pub struct WideBitBoard(u128);
+
+impl WideBitBoard {
+    pub const fn from_index(index: u8) -> Self {
+        Self(1 << index)
+    }
+
+    pub const fn contains(self, index: u8) -> bool {
+        self.0 >> index & 1 == 1
+    }
+
+    pub const fn count_squares(self) -> u32 {
+        self.0.count_ones()
+    }
+
+    pub const fn empty(self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn iter(self) -> impl Iterator<Item = u8> {
+        let mut bits = self.0;
+        std::iter::from_fn(move || {
+            let index = (bits != 0).then(|| bits.trailing_zeros() as u8)?;
+            bits &= bits - 1;
+            Some(index)
+        })
+    }
+
+    // every square moved `files` across and `ranks` up, those going off the board dropped
+    pub fn shift(self, geometry: Geometry, files: i8, ranks: i8) -> Self {
+        let width = geometry.files as i8;
+        if files.abs() >= width || ranks.abs() >= geometry.ranks as i8 {
+            return Self::default();
+        }
+        let kept = match files.is_negative() {
+            true => geometry.files_mask(-files as u8, geometry.files),
+            false => geometry.files_mask(0, geometry.files - files as u8),
+        };
+        let bits = (self & kept).0;
+        let shift = ranks as i32 * width as i32 + files as i32;
+        let moved = match shift.is_negative() {
+            true => bits >> -shift,
+            false => bits << shift,
+        };
+        Self(moved) & geometry.all()
+    }
+
+    // the squares a slider from `index` reaches along (`files`, `ranks`), up to and
+    // including the first one in `occupied`
+    pub fn ray(geometry: Geometry, index: u8, files: i8, ranks: i8, occupied: Self) -> Self {
+        let mut ray = Self::default();
+        let mut at = Self::from_index(index).shift(geometry, files, ranks);
+        while !at.empty() {
+            ray |= at;
+            if !(at & occupied).empty() {
+                break;
+            }
+            at = at.shift(geometry, files, ranks);
+        }
+        ray
+    }
+}
+
+impl SquareSet for WideBitBoard {
+    fn from_index(index: u8) -> Self {
+        Self::from_index(index)
+    }
+
+    fn has_index(self, index: u8) -> bool {
+        self.contains(index)
+    }
+
+    fn indices(self) -> impl Iterator<Item = u8> {
+        self.iter()
+    }
+}
+
+impl BitAnd for WideBitBoard {
+    type Output = Self;
+    fn bitand(self, rhs: Self) -> Self::Output {
+        Self(self.0 & rhs.0)
+    }
+}
+
+impl BitOr for WideBitBoard {
+    type Output = Self;
+    fn bitor(self, rhs: Self) -> Self::Output {
+        Self(self.0 | rhs.0)
+    }
+}
+
+// squares past the board's last one come in too, so mask with `Geometry::all` if they matter
+impl Not for WideBitBoard {
+    type Output = Self;
+    fn not(self) -> Self::Output {
+        Self(!self.0)
+    }
+}
+
+impl BitAndAssign for WideBitBoard {
+    fn bitand_assign(&mut self, rhs: Self) {
+        self.0 &= rhs.0;
+    }
+}
+
+impl BitOrAssign for WideBitBoard {
+    fn bitor_assign(&mut self, rhs: Self) {
+        self.0 |= rhs.0;
+    }
+}
@@ -1,11 +1,37 @@
 #![feature(let_chains)]
-#![feature(adt_const_params)]
+pub mod analysis;
 pub mod board;
 mod castle;
+mod check;
+pub mod clock;
+pub mod database;
+pub mod drill;
+pub mod eco;
+pub mod endgames;
+pub mod engine_match;
 pub mod errors;
+pub mod explain;
+pub mod fairy;
+pub mod game;
+pub mod geometry;
+pub mod movegen;
+pub mod moves;
+pub mod netplay;
+pub mod odds;
+mod perft;
+pub mod pgn;
 pub mod pieces;
+pub mod puzzles;
+pub mod random;
+pub mod ratings;
+pub mod repertoire;
+mod san;
+pub mod snapshot;
+pub mod training;
+pub mod uci;
+pub mod variant_state;
+mod zobrist;
 
-use core::panic;
 use std::fmt::Display;
 
 use board::Column;
@@ -13,15 +39,16 @@ use board::Row;
 use board::Square;
 use board::{Board, bitboard::BitBoard};
 use castle::Castle;
-use errors::{MoveError, ParseFenError};
+use errors::{FenField, MoveError, ParseFenError};
+use moves::{DrawClaim, GameStatus, MoveKind, MoveOutcome};
 use pieces::{
-    Color, Figure, Piece,
+    Color, Figure, Officer, Piece,
     constants::{BLACK_KING, WHITE_KING},
 };
 
 type MoveResult = Result<Option<Piece>, MoveError>;
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub struct GameState {
     board: Board,
     turn: Color,
@@ -40,37 +67,61 @@ impl Default for GameState {
 impl GameState {
     pub fn try_from_fen(fen: &str) -> Result<Self, ParseFenError> {
         let mut fen_iter = fen.split(' ');
-        let position_fen = fen_iter.next().ok_or(ParseFenError::EmptyFen)?;
-        let board = Board::try_from_fen(position_fen)?;
-        let turn = match fen_iter.next().ok_or(ParseFenError::EmptyFen)? {
+        let mut field = |field| fen_iter.next().ok_or(ParseFenError::MissingField(field));
+        let board = Board::try_from_fen(field(FenField::Placement)?)?;
+        let turn = match field(FenField::Turn)? {
             "w" => Color::White,
             "b" => Color::Black,
-            s => return Err(ParseFenError::InvalidColor(s.to_owned())),
+            s => {
+                return Err(ParseFenError::InvalidField {
+                    field: FenField::Turn,
+                    value: s.to_owned(),
+                });
+            }
         };
-        let castle = fen_iter.next().ok_or(ParseFenError::EmptyFen)?.parse()?;
-        let ep_square = match fen_iter.next().ok_or(ParseFenError::EmptyFen)? {
-            "-" => Result::<_, ParseFenError>::Ok(None),
-            ep => Ok(Some(ep.parse()?)),
-        }?;
-        let half_move = fen_iter.next().ok_or(ParseFenError::EmptyFen)?.parse()?;
-        let full_move = fen_iter.next().ok_or(ParseFenError::EmptyFen)?.parse()?;
+        let castle = field(FenField::Castling)?.parse()?;
+        let ep_square = match field(FenField::EnPassant)? {
+            "-" => None,
+            ep => Some(ep.parse().map_err(|_| ParseFenError::InvalidField {
+                field: FenField::EnPassant,
+                value: ep.to_owned(),
+            })?),
+        };
+        let half_move = parse_counter(field(FenField::HalfMove)?, FenField::HalfMove)?;
+        let full_move = parse_counter(field(FenField::FullMove)?, FenField::FullMove)?;
         if board.count_pieces(WHITE_KING) != 1 || board.count_pieces(BLACK_KING) != 1 {
             return Err(ParseFenError::IllegalState);
         }
-        Ok(GameState {
+        let mut gs = GameState {
             board,
             turn,
             castle,
             ep_square,
             half_move,
             full_move,
-        })
+        };
+        // rights the position can't back up, like a king or rook off its home square
+        gs.fix_castle_rights();
+        Ok(gs)
     }
 
     pub fn get_turn(&self) -> Color {
         self.turn
     }
 
+    pub fn get_full_move(&self) -> u16 {
+        self.full_move
+    }
+
+    // plies since the last capture or pawn move
+    pub fn get_half_move(&self) -> u16 {
+        self.half_move
+    }
+
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
     pub fn get_sq(&self, square: Square) -> Option<Piece> {
         self.board.get_sq(square)
     }
@@ -80,7 +131,7 @@ impl GameState {
         from: Square,
         to: Square,
         promotion_piece: Piece,
-    ) -> MoveResult {
+    ) -> Result<MoveOutcome, MoveError> {
         let Some(Piece {
             color,
             figure: Figure::Pawn,
@@ -91,6 +142,17 @@ impl GameState {
         if color != self.turn {
             return Err(MoveError::WrongTurn);
         }
+        // only onto the last row, and only into one of the mover's own officers
+        let last_row = match self.turn {
+            Color::White => Row::Eight,
+            Color::Black => Row::One,
+        };
+        if to.row() != last_row
+            || promotion_piece.color != self.turn
+            || promotion_piece.figure.officer().is_none()
+        {
+            return Err(MoveError::IllegalMove);
+        }
         let moves = self.board.pawn_moves(from, self.turn);
         let captured = if moves.contains(to) {
             self.test_move_for_check(from, to)
@@ -103,10 +165,10 @@ impl GameState {
         // ep square
         self.ep_square = None;
         self.end_move(to);
-        Ok(captured)
+        Ok(self.outcome(captured))
     }
 
-    pub fn make_move(&mut self, from: Square, to: Square) -> MoveResult {
+    pub fn make_move(&mut self, from: Square, to: Square) -> Result<MoveOutcome, MoveError> {
         let Some(Piece { color, figure }) = self.board.get_sq(from) else {
             return Err(MoveError::EmptySquare);
         };
@@ -116,13 +178,48 @@ impl GameState {
         let captured = match figure {
             Figure::Pawn => self.make_pawn_move(from, to)?,
             Figure::King => self.make_king_move(from, to)?,
-            Figure::Knight => self.make_generic_move::<{ Figure::Knight }>(from, to)?,
-            Figure::Rook => self.make_generic_move::<{ Figure::Rook }>(from, to)?,
-            Figure::Bishop => self.make_generic_move::<{ Figure::Bishop }>(from, to)?,
-            Figure::Queen => self.make_generic_move::<{ Figure::Queen }>(from, to)?,
+            Figure::Knight => self.make_officer_move(Officer::Knight, from, to)?,
+            Figure::Rook => self.make_officer_move(Officer::Rook, from, to)?,
+            Figure::Bishop => self.make_officer_move(Officer::Bishop, from, to)?,
+            Figure::Queen => self.make_officer_move(Officer::Queen, from, to)?,
         };
         self.end_move(to);
-        Ok(captured)
+        Ok(self.outcome(captured))
+    }
+
+    // mate and stalemate before the material count, which only matters while play goes on
+    pub fn status(&self) -> GameStatus {
+        if self.legal_move_count() == 0 {
+            return if self.board.is_in_check(self.turn) {
+                GameStatus::Checkmate
+            } else {
+                GameStatus::Stalemate
+            };
+        }
+        if self.is_insufficient_material() {
+            return GameStatus::InsufficientMaterial;
+        }
+        if self.half_move >= 150 {
+            return GameStatus::SeventyFiveMoves;
+        }
+        GameStatus::Ongoing
+    }
+
+    // what the side to move could claim from the position alone; repetitions are up to `Game`
+    pub fn claimable_draws(&self) -> Vec<DrawClaim> {
+        if self.half_move >= 100 && !self.status().is_over() {
+            vec![DrawClaim::FiftyMoves]
+        } else {
+            Vec::new()
+        }
+    }
+
+    fn outcome(&self, captured: Option<Piece>) -> MoveOutcome {
+        MoveOutcome {
+            captured,
+            check: self.board.is_in_check(self.turn),
+            status: self.status(),
+        }
     }
 
     fn end_move(&mut self, to_square: Square) {
@@ -138,7 +235,7 @@ impl GameState {
         }
         // full move
         if self.turn == Color::Black {
-            self.full_move += 1;
+            self.full_move = self.full_move.saturating_add(1);
         }
         // turn
         self.turn = !self.turn;
@@ -199,41 +296,35 @@ impl GameState {
                 Color::White => Row::One,
                 Color::Black => Row::Eight,
             };
-            match to.col() {
-                Column::C if to.row() == castle_row => {
-                    if self.castle.can_queen_castle(self.turn) {
-                        let rook_from = Square::from_coords(Column::A, castle_row);
-                        let rook_to = Square::from_coords(Column::D, castle_row);
-                        for square in BitBoard::straight_ray(from, rook_from).iter() {
-                            if self.board.is_square_attacked(square, self.turn) {
-                                return Err(MoveError::KingInCheck);
-                            }
-                        }
-                        self.board.move_piece(from, to);
-                        self.board.move_piece(rook_from, rook_to);
-                        Ok(None)
-                    } else {
-                        Err(MoveError::IllegalMove)
-                    }
+            let kind = match to.col() {
+                Column::C if to.row() == castle_row && self.castle.can_queen_castle(self.turn) => {
+                    MoveKind::QueenCastle
                 }
-                Column::G if to.row() == castle_row => {
-                    if self.castle.can_king_castle(self.turn) {
-                        let rook_from = Square::from_coords(Column::H, castle_row);
-                        let rook_to = Square::from_coords(Column::F, castle_row);
-                        for square in BitBoard::straight_ray(from, rook_from).iter() {
-                            if self.board.is_square_attacked(square, self.turn) {
-                                return Err(MoveError::KingInCheck);
-                            }
-                        }
-                        self.board.move_piece(from, to);
-                        self.board.move_piece(rook_from, rook_to);
-                        Ok(None)
-                    } else {
-                        Err(MoveError::IllegalMove)
-                    }
+                Column::G if to.row() == castle_row && self.castle.can_king_castle(self.turn) => {
+                    MoveKind::KingCastle
                 }
-                _ => Err(MoveError::IllegalMove),
+                _ => return Err(MoveError::IllegalMove),
+            };
+            let (rook_from, rook_to) = movegen::castle_rook_squares(kind, castle_row);
+            let own_rook = Piece {
+                color: self.turn,
+                figure: Figure::Rook,
+            };
+            if self.board.get_sq(rook_from) != Some(own_rook) {
+                return Err(MoveError::IllegalMove);
+            }
+            let is_cleared =
+                BitBoard::straight_ray(from, rook_from) & self.board.occupied() == from.into();
+            if !is_cleared {
+                return Err(MoveError::IllegalMove);
+            }
+            // only the king's own path has to be safe, the rook may pass through attacks
+            if !self.board.path_is_safe(from, to, self.turn) {
+                return Err(MoveError::KingInCheck);
             }
+            self.board.move_piece(from, to);
+            self.board.move_piece(rook_from, rook_to);
+            Ok(None)
         }?;
         // own castle
         self.castle.remove_castle(self.turn);
@@ -242,47 +333,63 @@ impl GameState {
         // half move
         match captured {
             Some(_piece) => self.half_move = 0,
-            None => self.half_move += 1,
+            None => self.half_move = self.half_move.saturating_add(1),
         }
         Ok(captured)
     }
 
-    fn make_generic_move<const FIGURE: Figure>(&mut self, from: Square, to: Square) -> MoveResult {
-        use Figure::*;
-        let is_pseudo = match FIGURE {
-            Knight => self.board.is_pseudo::<{ Knight }>(from, to, self.turn),
-            Rook => self.board.is_pseudo::<{ Rook }>(from, to, self.turn),
-            Bishop => self.board.is_pseudo::<{ Bishop }>(from, to, self.turn),
-            Queen => self.board.is_pseudo::<{ Queen }>(from, to, self.turn),
-            _ => panic!("Cannot make a generic move with a King or Pawn"),
-        };
-        let captured = if is_pseudo {
+    fn make_officer_move(&mut self, officer: Officer, from: Square, to: Square) -> MoveResult {
+        let captured = if self.board.is_pseudo(officer, from, to, self.turn) {
             self.test_move_for_check(from, to)
         } else {
             Err(MoveError::IllegalMove)
         }?;
         // own castle
-        if FIGURE == Rook {
-            let (q_rook_sq, k_rook_sq) = match self.turn {
-                Color::White => (Square::A1, Square::H1),
-                Color::Black => (Square::A8, Square::H8),
-            };
-            match from {
-                sq if sq == k_rook_sq => self.castle.remove_king_castle(self.turn),
-                sq if sq == q_rook_sq => self.castle.remove_queen_castle(self.turn),
-                _ => {}
-            }
+        if officer == Officer::Rook {
+            self.remove_rook_castle(from);
         }
         // ep
         self.ep_square = None;
         // half move
         match captured {
             Some(_piece) => self.half_move = 0,
-            None => self.half_move += 1,
+            None => self.half_move = self.half_move.saturating_add(1),
         }
         Ok(captured)
     }
 
+    // drops castling rights whose king or rook has left its home square
+    pub(crate) fn fix_castle_rights(&mut self) {
+        for (color, row) in [(Color::White, Row::One), (Color::Black, Row::Eight)] {
+            let has = |col, figure| {
+                self.board.get_sq(Square::from_coords(col, row)) == Some(Piece { color, figure })
+            };
+            let (king_home, q_rook, k_rook) = (
+                has(Column::E, Figure::King),
+                has(Column::A, Figure::Rook),
+                has(Column::H, Figure::Rook),
+            );
+            if !king_home || !k_rook {
+                self.castle.remove_king_castle(color);
+            }
+            if !king_home || !q_rook {
+                self.castle.remove_queen_castle(color);
+            }
+        }
+    }
+
+    fn remove_rook_castle(&mut self, rook_from: Square) {
+        let (q_rook_sq, k_rook_sq) = match self.turn {
+            Color::White => (Square::A1, Square::H1),
+            Color::Black => (Square::A8, Square::H8),
+        };
+        match rook_from {
+            sq if sq == k_rook_sq => self.castle.remove_king_castle(self.turn),
+            sq if sq == q_rook_sq => self.castle.remove_queen_castle(self.turn),
+            _ => {}
+        }
+    }
+
     fn test_move_for_check(&mut self, from: Square, to: Square) -> MoveResult {
         let captured = self.board.move_piece(from, to);
         if self.board.is_in_check(self.turn) {
@@ -295,6 +402,97 @@ impl GameState {
     pub fn iter(&self) -> impl Iterator<Item = (Square, Piece)> {
         self.board.iter()
     }
+
+    pub fn is_insufficient_material(&self) -> bool {
+        let mut minors = Vec::new();
+        for (square, piece) in self.board.iter() {
+            match piece.figure {
+                Figure::King => {}
+                Figure::Knight | Figure::Bishop => minors.push((square, piece.figure)),
+                _ => return false,
+            }
+        }
+        // bishops that all live on the same square color can't mate either
+        match minors.as_slice() {
+            [] | [_] => true,
+            [(first, _), ..] => minors.iter().all(|&(square, figure)| {
+                figure == Figure::Bishop && square.is_dark() == first.is_dark()
+            }),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct DisplayOptions {
+    pub fen: bool,
+    pub coordinates: bool,
+    pub flipped: bool,
+}
+
+impl Default for DisplayOptions {
+    fn default() -> Self {
+        Self {
+            fen: true,
+            coordinates: true,
+            flipped: false,
+        }
+    }
+}
+
+impl GameState {
+    pub fn to_fen(&self) -> String {
+        let ep = self.ep_square.map_or("-".to_owned(), |sq| sq.to_string());
+        let turn = match self.turn {
+            Color::White => 'w',
+            Color::Black => 'b',
+        };
+        format!(
+            "{} {turn} {} {ep} {} {}",
+            self.board.to_fen(),
+            self.castle,
+            self.half_move,
+            self.full_move
+        )
+    }
+
+    pub fn to_string_pretty(&self, opts: DisplayOptions) -> String {
+        let mut rows: Vec<u8> = (0..8).rev().collect();
+        let mut cols: Vec<u8> = (0..8).collect();
+        if opts.flipped {
+            rows.reverse();
+            cols.reverse();
+        }
+        let mut out = String::new();
+        for &row in &rows {
+            if opts.coordinates {
+                out.push_str(&format!("{} ", row + 1));
+            }
+            let line: Vec<String> = cols
+                .iter()
+                .map(|&col| {
+                    // Safety: row and col are < 8
+                    let square = unsafe { Square::from_u8_unchecked(8 * row + col) };
+                    self.board
+                        .get_sq(square)
+                        .map_or('.', char::from)
+                        .to_string()
+                })
+                .collect();
+            out.push_str(&line.join(" "));
+            out.push('\n');
+        }
+        if opts.coordinates {
+            let files: Vec<String> = cols
+                .iter()
+                .map(|&col| char::from(b'a' + col).to_string())
+                .collect();
+            out.push_str(&format!("  {}\n", files.join(" ")));
+        }
+        if opts.fen {
+            out.push_str(&format!("FEN: {}\n", self.to_fen()));
+        }
+        out
+    }
 }
 
 impl Display for GameState {
@@ -308,11 +506,23 @@ impl Display for GameState {
     }
 }
 
+fn parse_counter(value: &str, field: FenField) -> Result<u16, ParseFenError> {
+    value
+        .parse()
+        .map_err(|source| ParseFenError::InvalidNumber {
+            field,
+            value: value.to_owned(),
+            source,
+        })
+}
+
 pub mod prelude {
     pub use crate::{
-        GameState,
+        DisplayOptions, GameState,
         board::{Column, Row, Square},
         constants::*,
+        game::Game,
+        moves::{CheckKind, DrawClaim, GameStatus, Move, MoveKind, MoveOutcome},
         pieces::{Color, Figure, Piece, constants::*},
     };
 }
@@ -0,0 +1,451 @@
+use crate::{
+    GameState,
+    board::{Board, Column, Row, Square, bitboard::BitBoard},
+    moves::{Move, MoveKind},
+    pieces::{Color, Figure, Piece},
+};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum GenKind {
+    All,
+    Tactical,
+    Quiet,
+}
+
+const PROMOTION_FIGURES: [Figure; 4] =
+    [Figure::Queen, Figure::Rook, Figure::Bishop, Figure::Knight];
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Stage {
+    HashMove,
+    GenCaptures,
+    GoodCaptures,
+    Killers(usize),
+    GenQuiets,
+    Quiets,
+    BadCaptures,
+    Done,
+}
+
+pub struct MoveGen<'a> {
+    gs: &'a GameState,
+    scratch: Board,
+    stage: Stage,
+    hash_move: Option<Move>,
+    killers: [Option<Move>; 2],
+    // the killers the killer stage handed out, which the later stages skip
+    tried_killers: [Option<Move>; 2],
+    moves: Vec<Move>,
+    bad_captures: Vec<Move>,
+}
+
+impl<'a> MoveGen<'a> {
+    pub fn new(gs: &'a GameState, hash_move: Option<Move>, killers: [Option<Move>; 2]) -> Self {
+        Self {
+            gs,
+            scratch: gs.board,
+            stage: Stage::HashMove,
+            hash_move,
+            killers,
+            tried_killers: [None; 2],
+            moves: Vec::with_capacity(64),
+            bad_captures: Vec::new(),
+        }
+    }
+
+    fn is_legal(&mut self, mv: Move) -> bool {
+        is_legal(&mut self.scratch, mv, self.gs.turn)
+    }
+
+    fn already_tried(&self, mv: Move) -> bool {
+        self.hash_move == Some(mv) || self.tried_killers.contains(&Some(mv))
+    }
+
+    // MVV-LVA score, and whether the victim is worth at least as much as the attacker
+    fn capture_score(&self, mv: Move) -> (i32, bool) {
+        let victim = match mv.kind {
+            MoveKind::EnPassant => Figure::Pawn.value(),
+            _ => self.gs.board.get_sq(mv.to).map_or(0, |p| p.figure.value()),
+        };
+        let attacker = self
+            .gs
+            .board
+            .get_sq(mv.from)
+            .map_or(0, |p| p.figure.value());
+        match mv.promotion() {
+            Some(Figure::Queen) => (10 * victim + Figure::Queen.value(), true),
+            Some(_) => (victim - Figure::Queen.value(), false),
+            None => (10 * victim - attacker, victim >= attacker),
+        }
+    }
+}
+
+impl Iterator for MoveGen<'_> {
+    type Item = Move;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.stage {
+                Stage::HashMove => {
+                    self.stage = Stage::GenCaptures;
+                    if let Some(mv) = self.hash_move
+                        && self.gs.is_pseudo_legal(mv)
+                        && self.is_legal(mv)
+                    {
+                        return Some(mv);
+                    }
+                }
+                Stage::GenCaptures => {
+                    let mut captures = Vec::with_capacity(32);
+                    self.gs
+                        .gen_pseudo_moves(GenKind::Tactical, !BitBoard::default(), |mv| {
+                            captures.push(mv)
+                        });
+                    let (good, bad): (Vec<_>, Vec<_>) = captures
+                        .into_iter()
+                        .map(|mv| (self.capture_score(mv), mv))
+                        .partition(|&((_, is_good), _)| is_good);
+                    self.moves = sorted_by_score(good);
+                    self.bad_captures = sorted_by_score(bad);
+                    self.stage = Stage::GoodCaptures;
+                }
+                Stage::GoodCaptures => match self.moves.pop() {
+                    Some(mv) if !self.already_tried(mv) && self.is_legal(mv) => return Some(mv),
+                    Some(_) => {}
+                    None => self.stage = Stage::Killers(0),
+                },
+                Stage::Killers(idx) => {
+                    self.stage = match idx + 1 {
+                        next if next < self.killers.len() => Stage::Killers(next),
+                        _ => Stage::GenQuiets,
+                    };
+                    if let Some(mv) = self.killers[idx]
+                        && Some(mv) != self.hash_move
+                        && !self.killers[..idx].contains(&Some(mv))
+                        && !mv.is_capture()
+                        && mv.promotion().is_none()
+                        && self.gs.is_pseudo_legal(mv)
+                        && self.is_legal(mv)
+                    {
+                        self.tried_killers[idx] = Some(mv);
+                        return Some(mv);
+                    }
+                }
+                Stage::GenQuiets => {
+                    let mut quiets = std::mem::take(&mut self.moves);
+                    self.gs
+                        .gen_pseudo_moves(GenKind::Quiet, !BitBoard::default(), |mv| {
+                            quiets.push(mv)
+                        });
+                    // popped from the back, so reverse to keep generation order
+                    quiets.reverse();
+                    self.moves = quiets;
+                    self.stage = Stage::Quiets;
+                }
+                Stage::Quiets => match self.moves.pop() {
+                    Some(mv) if !self.already_tried(mv) && self.is_legal(mv) => return Some(mv),
+                    Some(_) => {}
+                    None => self.stage = Stage::BadCaptures,
+                },
+                Stage::BadCaptures => match self.bad_captures.pop() {
+                    Some(mv) if !self.already_tried(mv) && self.is_legal(mv) => return Some(mv),
+                    Some(_) => {}
+                    None => self.stage = Stage::Done,
+                },
+                Stage::Done => return None,
+            }
+        }
+    }
+}
+
+// ascending by score, so the best move is popped first
+fn sorted_by_score(mut scored: Vec<((i32, bool), Move)>) -> Vec<Move> {
+    scored.sort_by_key(|&((score, _), _)| score);
+    scored.into_iter().map(|(_, mv)| mv).collect()
+}
+
+impl GameState {
+    pub fn legal_moves(&self) -> Vec<Move> {
+        let mut moves = Vec::with_capacity(64);
+        self.gen_legal_moves(|mv| moves.push(mv));
+        moves
+    }
+
+    // the ep capture, if any, that is legal this move
+    pub fn legal_ep_capture(&self) -> Option<(Square, Square)> {
+        let mut scratch = self.board;
+        let mut capture = None;
+        self.gen_ep_moves(!BitBoard::default(), &mut |mv| {
+            if capture.is_none() && is_legal(&mut scratch, mv, self.turn) {
+                capture = Some((mv.from, mv.to));
+            }
+        });
+        capture
+    }
+
+    pub fn legal_move_count(&self) -> usize {
+        let mut count = 0;
+        self.gen_legal_moves(|_| count += 1);
+        count
+    }
+
+    // legal moves per origin square, indexable by Square; zero for empty or stuck pieces
+    pub fn mobility(&self) -> [u8; 64] {
+        let mut counts = [0; 64];
+        self.gen_legal_moves(|mv| counts[mv.from] += 1);
+        counts
+    }
+
+    pub(crate) fn play(&mut self, mv: Move) -> Option<Piece> {
+        let Move { from, to, kind } = mv;
+        let moved = self.board.get_sq(from);
+        let captured = match kind {
+            MoveKind::EnPassant => {
+                self.board.move_piece(from, to);
+                self.board
+                    .clear_sq(Square::from_coords(to.col(), from.row()))
+            }
+            MoveKind::KingCastle | MoveKind::QueenCastle => {
+                let (rook_from, rook_to) = castle_rook_squares(kind, from.row());
+                self.board.move_piece(from, to);
+                self.board.move_piece(rook_from, rook_to);
+                None
+            }
+            MoveKind::Promotion(figure) | MoveKind::PromotionCapture(figure) => {
+                self.board.clear_sq(from);
+                self.board.set_sq(to, Piece {
+                    color: self.turn,
+                    figure,
+                })
+            }
+            MoveKind::Quiet | MoveKind::DoublePush | MoveKind::Capture => {
+                self.board.move_piece(from, to)
+            }
+        };
+        // own castle
+        match moved.map(|p| p.figure) {
+            Some(Figure::King) => self.castle.remove_castle(self.turn),
+            Some(Figure::Rook) => self.remove_rook_castle(from),
+            _ => {}
+        }
+        // ep
+        self.ep_square = match kind {
+            MoveKind::DoublePush => {
+                let ep_row = match self.turn {
+                    Color::White => Row::Three,
+                    Color::Black => Row::Six,
+                };
+                Some(Square::from_coords(from.col(), ep_row))
+            }
+            _ => None,
+        };
+        // half move
+        if captured.is_some() || moved.is_some_and(|p| p.figure == Figure::Pawn) {
+            self.half_move = 0;
+        } else {
+            self.half_move = self.half_move.saturating_add(1);
+        }
+        self.end_move(to);
+        captured
+    }
+
+    pub(crate) fn is_pseudo_legal(&self, mv: Move) -> bool {
+        let mut found = false;
+        self.gen_pseudo_moves(GenKind::All, mv.from.into(), |m| found |= m == mv);
+        found
+    }
+
+    fn gen_legal_moves(&self, mut push: impl FnMut(Move)) {
+        let mut scratch = self.board;
+        self.gen_pseudo_moves(GenKind::All, !BitBoard::default(), |mv| {
+            if is_legal(&mut scratch, mv, self.turn) {
+                push(mv)
+            }
+        });
+    }
+
+    fn gen_pseudo_moves(&self, kind: GenKind, from_mask: BitBoard, mut push: impl FnMut(Move)) {
+        let own = self.board.occupied_color(self.turn);
+        let enemy = self.board.occupied_color(!self.turn);
+        let occupied = own | enemy;
+        let target_mask = match kind {
+            GenKind::All => !own,
+            GenKind::Tactical => enemy,
+            GenKind::Quiet => !occupied,
+        };
+        for (from, piece) in self.board.iter() {
+            if piece.color != self.turn || !from_mask.contains(from) {
+                continue;
+            }
+            let targets = match piece.figure {
+                Figure::Pawn => {
+                    self.gen_pawn_moves(from, kind, enemy, occupied, &mut push);
+                    continue;
+                }
+                Figure::Knight => BitBoard::knight_moves(from),
+                Figure::Bishop => BitBoard::bishop_attacks(from, occupied),
+                Figure::Rook => BitBoard::rook_attacks(from, occupied),
+                Figure::Queen => BitBoard::queen_attacks(from, occupied),
+                Figure::King => BitBoard::king_moves(from),
+            } & target_mask;
+            for to in targets.iter() {
+                let kind = if enemy.contains(to) {
+                    MoveKind::Capture
+                } else {
+                    MoveKind::Quiet
+                };
+                push(Move { from, to, kind });
+            }
+        }
+        if kind != GenKind::Quiet {
+            self.gen_ep_moves(from_mask, &mut push);
+        }
+        if kind != GenKind::Tactical {
+            self.gen_castle_moves(from_mask, occupied, &mut push);
+        }
+    }
+
+    // promotions count as tactical moves, all other pushes as quiet ones
+    fn gen_pawn_moves(
+        &self,
+        from: Square,
+        kind: GenKind,
+        enemy: BitBoard,
+        occupied: BitBoard,
+        push: &mut impl FnMut(Move),
+    ) {
+        let last_row = match self.turn {
+            Color::White => Row::Eight,
+            Color::Black => Row::One,
+        };
+        let pushes = BitBoard::pawn_pushes(from, self.turn, occupied);
+        let captures = BitBoard::pawn_attacks(from, self.turn) & enemy;
+        let promotions = (pushes | captures) & last_row.into();
+        let targets = match kind {
+            GenKind::All => pushes | captures,
+            GenKind::Tactical => captures | promotions,
+            GenKind::Quiet => pushes & !promotions,
+        };
+        for to in targets.iter() {
+            let is_capture = enemy.contains(to);
+            if to.row() == last_row {
+                for figure in PROMOTION_FIGURES {
+                    let kind = if is_capture {
+                        MoveKind::PromotionCapture(figure)
+                    } else {
+                        MoveKind::Promotion(figure)
+                    };
+                    push(Move { from, to, kind });
+                }
+                continue;
+            }
+            let kind = if is_capture {
+                MoveKind::Capture
+            } else if (from.row() as u8).abs_diff(to.row() as u8) == 2 {
+                MoveKind::DoublePush
+            } else {
+                MoveKind::Quiet
+            };
+            push(Move { from, to, kind });
+        }
+    }
+
+    fn gen_ep_moves(&self, from_mask: BitBoard, push: &mut impl FnMut(Move)) {
+        let Some(ep) = self.ep_square else {
+            return;
+        };
+        let attackers = BitBoard::pawn_attacks(ep, !self.turn) & from_mask;
+        let pawn = Piece {
+            color: self.turn,
+            figure: Figure::Pawn,
+        };
+        for from in self.board.iter_piece(pawn) {
+            if attackers.contains(from) {
+                push(Move {
+                    from,
+                    to: ep,
+                    kind: MoveKind::EnPassant,
+                });
+            }
+        }
+    }
+
+    fn gen_castle_moves(
+        &self,
+        from_mask: BitBoard,
+        occupied: BitBoard,
+        push: &mut impl FnMut(Move),
+    ) {
+        let castle_row = match self.turn {
+            Color::White => Row::One,
+            Color::Black => Row::Eight,
+        };
+        let king = Piece {
+            color: self.turn,
+            figure: Figure::King,
+        };
+        let king_from = Square::from_coords(Column::E, castle_row);
+        if !from_mask.contains(king_from)
+            || self.board.get_sq(king_from) != Some(king)
+            || self.board.is_square_attacked(king_from, self.turn)
+        {
+            return;
+        }
+        let mut try_castle = |kind: MoveKind, king_to: Column| {
+            let (rook_from, _) = castle_rook_squares(kind, castle_row);
+            let rook = Piece {
+                color: self.turn,
+                figure: Figure::Rook,
+            };
+            let is_cleared =
+                BitBoard::straight_ray(king_from, rook_from) & occupied == king_from.into();
+            let king_to = Square::from_coords(king_to, castle_row);
+            if self.board.get_sq(rook_from) == Some(rook)
+                && is_cleared
+                && self.board.path_is_safe(king_from, king_to, self.turn)
+            {
+                push(Move {
+                    from: king_from,
+                    to: king_to,
+                    kind,
+                });
+            }
+        };
+        if self.castle.can_king_castle(self.turn) {
+            try_castle(MoveKind::KingCastle, Column::G);
+        }
+        if self.castle.can_queen_castle(self.turn) {
+            try_castle(MoveKind::QueenCastle, Column::C);
+        }
+    }
+}
+
+pub(crate) fn castle_rook_squares(kind: MoveKind, castle_row: Row) -> (Square, Square) {
+    match kind {
+        MoveKind::KingCastle => (
+            Square::from_coords(Column::H, castle_row),
+            Square::from_coords(Column::F, castle_row),
+        ),
+        _ => (
+            Square::from_coords(Column::A, castle_row),
+            Square::from_coords(Column::D, castle_row),
+        ),
+    }
+}
+
+fn is_legal(board: &mut Board, mv: Move, turn: Color) -> bool {
+    let captured = board.move_piece(mv.from, mv.to);
+    let ep_capture = match mv.kind {
+        MoveKind::EnPassant => {
+            let capture_sq = Square::from_coords(mv.to.col(), mv.from.row());
+            board.clear_sq(capture_sq).map(|p| (capture_sq, p))
+        }
+        _ => None,
+    };
+    let is_legal = !board.is_in_check(turn);
+    board.unmove_piece(mv.from, mv.to, captured);
+    if let Some((capture_sq, pawn)) = ep_capture {
+        board.set_sq(capture_sq, pawn);
+    }
+    is_legal
+}
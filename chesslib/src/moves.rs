@@ -0,0 +1,213 @@
+use crate::{
+    board::Square,
+    errors::InvalidValueError,
+    pieces::{Color, Figure, Piece},
+};
+use std::fmt::Display;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Move {
+    pub from: Square,
+    pub to: Square,
+    pub kind: MoveKind,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum MoveKind {
+    Quiet,
+    DoublePush,
+    KingCastle,
+    QueenCastle,
+    Capture,
+    EnPassant,
+    Promotion(Figure),
+    PromotionCapture(Figure),
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CheckKind {
+    None,
+    Direct,
+    Discovered,
+    Double,
+}
+
+// where the game stands once a move is on the board
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum GameStatus {
+    Ongoing,
+    Checkmate,
+    Stalemate,
+    // the rest are the automatic draws, the ones that only get claimed are `DrawClaim`s
+    InsufficientMaterial,
+    SeventyFiveMoves,
+    // needs the game's history, so only `Game` reports it
+    FivefoldRepetition,
+}
+
+impl GameStatus {
+    pub fn is_over(self) -> bool {
+        self != GameStatus::Ongoing
+    }
+
+    pub fn is_draw(self) -> bool {
+        matches!(
+            self,
+            GameStatus::Stalemate
+                | GameStatus::InsufficientMaterial
+                | GameStatus::SeventyFiveMoves
+                | GameStatus::FivefoldRepetition
+        )
+    }
+}
+
+// draws a player may ask for but that don't end the game by themselves
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum DrawClaim {
+    FiftyMoves,
+    Repetition,
+}
+
+impl Display for DrawClaim {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            DrawClaim::FiftyMoves => "the fifty-move rule",
+            DrawClaim::Repetition => "threefold repetition",
+        };
+        write!(f, "{s}")
+    }
+}
+
+// what a move did, so callers needn't work it out again from the new position
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct MoveOutcome {
+    pub captured: Option<Piece>,
+    // the side now to move is in check
+    pub check: bool,
+    pub status: GameStatus,
+}
+
+impl Move {
+    pub fn is_capture(&self) -> bool {
+        matches!(
+            self.kind,
+            MoveKind::Capture | MoveKind::EnPassant | MoveKind::PromotionCapture(_)
+        )
+    }
+
+    pub fn is_castle(&self) -> bool {
+        matches!(self.kind, MoveKind::KingCastle | MoveKind::QueenCastle)
+    }
+
+    pub fn promotion(&self) -> Option<Figure> {
+        match self.kind {
+            MoveKind::Promotion(figure) | MoveKind::PromotionCapture(figure) => Some(figure),
+            _ => None,
+        }
+    }
+}
+
+impl Display for Move {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}{}", self.from, self.to)?;
+        if let Some(figure) = self.promotion() {
+            let piece = Piece {
+                color: Color::Black,
+                figure,
+            };
+            write!(f, "{}", char::from(piece))?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+pub struct Move16(u16);
+
+impl Move16 {
+    pub const NULL: Self = Self(0);
+
+    pub const fn from_square(self) -> Square {
+        // Safety: masked to 6 bits, so < 64
+        unsafe { Square::from_u8_unchecked((self.0 & 0x3f) as u8) }
+    }
+
+    pub const fn to_square(self) -> Square {
+        // Safety: masked to 6 bits, so < 64
+        unsafe { Square::from_u8_unchecked((self.0 >> 6 & 0x3f) as u8) }
+    }
+
+    pub const fn flags(self) -> u8 {
+        (self.0 >> 12) as u8
+    }
+
+    pub const fn is_null(self) -> bool {
+        self.0 == 0
+    }
+}
+
+impl From<Move> for Move16 {
+    fn from(mv: Move) -> Self {
+        let flags: u16 = match mv.kind {
+            MoveKind::Quiet => 0,
+            MoveKind::DoublePush => 1,
+            MoveKind::KingCastle => 2,
+            MoveKind::QueenCastle => 3,
+            MoveKind::Capture => 4,
+            MoveKind::EnPassant => 5,
+            MoveKind::Promotion(figure) => 8 | promotion_bits(figure),
+            MoveKind::PromotionCapture(figure) => 12 | promotion_bits(figure),
+        };
+        Self(mv.from as u16 | (mv.to as u16) << 6 | flags << 12)
+    }
+}
+
+impl TryFrom<Move16> for Move {
+    type Error = InvalidValueError;
+
+    fn try_from(mv: Move16) -> Result<Self, Self::Error> {
+        let promotion_figure = match mv.flags() & 3 {
+            0 => Figure::Knight,
+            1 => Figure::Bishop,
+            2 => Figure::Rook,
+            _ => Figure::Queen,
+        };
+        let kind = match mv.flags() {
+            0 => MoveKind::Quiet,
+            1 => MoveKind::DoublePush,
+            2 => MoveKind::KingCastle,
+            3 => MoveKind::QueenCastle,
+            4 => MoveKind::Capture,
+            5 => MoveKind::EnPassant,
+            8..=11 => MoveKind::Promotion(promotion_figure),
+            12..=15 => MoveKind::PromotionCapture(promotion_figure),
+            v => return Err(InvalidValueError(v)),
+        };
+        Ok(Move {
+            from: mv.from_square(),
+            to: mv.to_square(),
+            kind,
+        })
+    }
+}
+
+impl From<u16> for Move16 {
+    fn from(bits: u16) -> Self {
+        Self(bits)
+    }
+}
+
+impl From<Move16> for u16 {
+    fn from(mv: Move16) -> Self {
+        mv.0
+    }
+}
+
+fn promotion_bits(figure: Figure) -> u16 {
+    match figure {
+        Figure::Bishop => 1,
+        Figure::Rook => 2,
+        Figure::Queen => 3,
+        _ => 0,
+    }
+}
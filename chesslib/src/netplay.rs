@@ -0,0 +1,71 @@
+use crate::{GameState, errors::ParseNetMessageError, moves::Move, pieces::Color};
+use std::{fmt::Display, str::FromStr};
+
+// one line of the two-player protocol. `seq` counts the moves since the last sync, so a
+// side that missed one can ask for the position again.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NetMessage {
+    Move { seq: usize, uci: String },
+    Sync { seq: usize, fen: String },
+    Resync,
+    Chat(String),
+    // an offer, or the answer to one
+    Draw,
+    Resign,
+    // after the game, an offer of another with colors swapped, or the answer to one
+    Rematch,
+}
+
+impl Display for NetMessage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NetMessage::Move { seq, uci } => write!(f, "move {seq} {uci}"),
+            NetMessage::Sync { seq, fen } => write!(f, "sync {seq} {fen}"),
+            NetMessage::Resync => write!(f, "resync"),
+            // one message per line
+            NetMessage::Chat(text) => write!(f, "chat {}", text.replace(['\r', '\n'], " ")),
+            NetMessage::Draw => write!(f, "draw"),
+            NetMessage::Resign => write!(f, "resign"),
+            NetMessage::Rematch => write!(f, "rematch"),
+        }
+    }
+}
+
+impl FromStr for NetMessage {
+    type Err = ParseNetMessageError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || ParseNetMessageError(s.to_owned());
+        let (command, rest) = s.trim_end().split_once(' ').unwrap_or((s.trim_end(), ""));
+        let seq_and = |rest: &str| -> Result<(usize, String), Self::Err> {
+            let (seq, arg) = rest.split_once(' ').ok_or_else(invalid)?;
+            let seq = seq.parse().map_err(|_| invalid())?;
+            Ok((seq, arg.trim().to_owned()))
+        };
+        match command {
+            "move" => seq_and(rest).map(|(seq, uci)| NetMessage::Move { seq, uci }),
+            "sync" => seq_and(rest).map(|(seq, fen)| NetMessage::Sync { seq, fen }),
+            "resync" => Ok(NetMessage::Resync),
+            "chat" => Ok(NetMessage::Chat(rest.to_owned())),
+            "draw" => Ok(NetMessage::Draw),
+            "resign" => Ok(NetMessage::Resign),
+            "rematch" => Ok(NetMessage::Rematch),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+// the peer's move, if it's the one expected next and theirs to play; `local` is the
+// color played on this side
+pub fn peer_move(
+    gs: &GameState,
+    local: Color,
+    seq: usize,
+    expected: usize,
+    uci: &str,
+) -> Option<Move> {
+    if seq != expected || gs.get_turn() == local {
+        return None;
+    }
+    gs.parse_uci_move(uci)
+}
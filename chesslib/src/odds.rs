@@ -0,0 +1,82 @@
+use crate::{
+    GameState,
+    board::{Column, Row, Square},
+    pieces::Color,
+};
+use std::fmt::Display;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Odds {
+    PawnAndMove,
+    Knight,
+    Rook,
+    RookAndKnight,
+    Queen,
+}
+
+impl Odds {
+    pub const ALL: [Odds; 5] = [
+        Odds::PawnAndMove,
+        Odds::Knight,
+        Odds::Rook,
+        Odds::RookAndKnight,
+        Odds::Queen,
+    ];
+
+    // squares emptied on the giver's side, counted from White's point of view
+    fn removed(self) -> &'static [(Column, Row)] {
+        match self {
+            Odds::PawnAndMove => &[(Column::F, Row::Two)],
+            Odds::Knight => &[(Column::B, Row::One)],
+            Odds::Rook => &[(Column::A, Row::One)],
+            Odds::RookAndKnight => &[(Column::A, Row::One), (Column::B, Row::One)],
+            Odds::Queen => &[(Column::D, Row::One)],
+        }
+    }
+}
+
+impl Display for Odds {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            Odds::PawnAndMove => "Pawn and move",
+            Odds::Knight => "Knight odds",
+            Odds::Rook => "Rook odds",
+            Odds::RookAndKnight => "Rook and knight odds",
+            Odds::Queen => "Queen odds",
+        };
+        write!(f, "{s}")
+    }
+}
+
+impl GameState {
+    // start position with `giver` playing without the odds material;
+    // with pawn and move the receiving side also moves first
+    pub fn with_odds(odds: Odds, giver: Color) -> Self {
+        let mut gs = GameState::default();
+        for &(col, row) in odds.removed() {
+            let row = match giver {
+                Color::White => row,
+                Color::Black => mirror(row),
+            };
+            gs.board.clear_sq(Square::from_coords(col, row));
+        }
+        if odds == Odds::PawnAndMove {
+            gs.turn = !giver;
+        }
+        gs.fix_castle_rights();
+        gs
+    }
+}
+
+fn mirror(row: Row) -> Row {
+    match row {
+        Row::One => Row::Eight,
+        Row::Two => Row::Seven,
+        Row::Three => Row::Six,
+        Row::Four => Row::Five,
+        Row::Five => Row::Four,
+        Row::Six => Row::Three,
+        Row::Seven => Row::Two,
+        Row::Eight => Row::One,
+    }
+}
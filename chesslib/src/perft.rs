@@ -0,0 +1,29 @@
+use crate::{GameState, moves::Move};
+
+impl GameState {
+    pub fn perft(&self, depth: u8) -> u64 {
+        match depth {
+            0 => 1,
+            // bulk counting: leaf moves are counted, never made
+            1 => self.legal_move_count() as u64,
+            _ => self
+                .legal_moves()
+                .into_iter()
+                .map(|mv| self.child(mv).perft(depth - 1))
+                .sum(),
+        }
+    }
+
+    pub fn perft_divide(&self, depth: u8) -> Vec<(Move, u64)> {
+        self.legal_moves()
+            .into_iter()
+            .map(|mv| (mv, self.child(mv).perft(depth.saturating_sub(1))))
+            .collect()
+    }
+
+    fn child(&self, mv: Move) -> Self {
+        let mut child = *self;
+        child.play(mv);
+        child
+    }
+}
@@ -0,0 +1,786 @@
+use crate::{
+    GameState,
+    board::Square,
+    errors::{DatabaseError, ParsePgnError},
+    moves::Move,
+    pieces::Color,
+};
+use std::{
+    collections::VecDeque,
+    fmt::Display,
+    io::{BufRead, Lines},
+    str::FromStr,
+    time::Duration,
+};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum GameResult {
+    WhiteWins,
+    BlackWins,
+    Draw,
+}
+
+impl FromStr for GameResult {
+    type Err = ParsePgnError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "1-0" => Ok(GameResult::WhiteWins),
+            "0-1" => Ok(GameResult::BlackWins),
+            "1/2-1/2" => Ok(GameResult::Draw),
+            s => Err(ParsePgnError::InvalidResult(s.to_owned())),
+        }
+    }
+}
+
+impl Display for GameResult {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            GameResult::WhiteWins => "1-0",
+            GameResult::BlackWins => "0-1",
+            GameResult::Draw => "1/2-1/2",
+        };
+        write!(f, "{s}")
+    }
+}
+
+// the move suffixes that stand for the first six NAGs
+const GLYPHS: [&str; 6] = ["!", "?", "!!", "??", "!?", "?!"];
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PgnGame {
+    pub tags: Vec<(String, String)>,
+    pub moves: Vec<String>,
+    // what's said about each move, by index; may run shorter than `moves`
+    pub annotations: Vec<Annotation>,
+    pub result: Option<GameResult>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Annotation {
+    pub nags: Vec<u8>,
+    pub comment: Option<String>,
+    // lines played instead of the move, from the position before it
+    pub variations: Vec<Variation>,
+    // the mover's time left after the move, `[%clk]` in the comment
+    pub clock: Option<Duration>,
+    // how long the move took, `[%emt]`
+    pub elapsed: Option<Duration>,
+    // `[%cal]` and `[%csl]`, drawn over the position the move left
+    pub arrows: Vec<Arrow>,
+    pub marks: Vec<SquareMark>,
+}
+
+// the colors study tools draw in, a letter each in the commands
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum MarkColor {
+    Green,
+    Red,
+    Yellow,
+    Blue,
+}
+
+impl MarkColor {
+    fn from_letter(letter: char) -> Option<Self> {
+        match letter {
+            'G' => Some(MarkColor::Green),
+            'R' => Some(MarkColor::Red),
+            'Y' => Some(MarkColor::Yellow),
+            'B' => Some(MarkColor::Blue),
+            _ => None,
+        }
+    }
+
+    fn letter(self) -> char {
+        match self {
+            MarkColor::Green => 'G',
+            MarkColor::Red => 'R',
+            MarkColor::Yellow => 'Y',
+            MarkColor::Blue => 'B',
+        }
+    }
+}
+
+// "Ge2e4"
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Arrow {
+    pub color: MarkColor,
+    pub from: Square,
+    pub to: Square,
+}
+
+impl FromStr for Arrow {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let color = MarkColor::from_letter(s.chars().next().ok_or(())?).ok_or(())?;
+        match (s.get(1..3), s.get(3..)) {
+            (Some(from), Some(to)) => Ok(Arrow {
+                color,
+                from: from.parse().map_err(|_| ())?,
+                to: to.parse().map_err(|_| ())?,
+            }),
+            _ => Err(()),
+        }
+    }
+}
+
+impl Display for Arrow {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}{}{}", self.color.letter(), self.from, self.to)
+    }
+}
+
+// "Rd4"
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct SquareMark {
+    pub color: MarkColor,
+    pub square: Square,
+}
+
+impl FromStr for SquareMark {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let color = MarkColor::from_letter(s.chars().next().ok_or(())?).ok_or(())?;
+        Ok(SquareMark {
+            color,
+            square: s.get(1..).ok_or(())?.parse().map_err(|_| ())?,
+        })
+    }
+}
+
+impl Display for SquareMark {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}{}", self.color.letter(), self.square)
+    }
+}
+
+impl Annotation {
+    pub fn is_empty(&self) -> bool {
+        self.nags.is_empty()
+            && self.comment.is_none()
+            && self.variations.is_empty()
+            && self.clock.is_none()
+            && self.elapsed.is_none()
+            && self.arrows.is_empty()
+            && self.marks.is_empty()
+    }
+
+    // a second comment on the same move runs on from the first
+    fn add_comment(&mut self, text: &str) {
+        let text = self.take_commands(text);
+        let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+        if text.is_empty() {
+            return;
+        }
+        match &mut self.comment {
+            Some(comment) => {
+                comment.push(' ');
+                comment.push_str(&text);
+            }
+            None => self.comment = Some(text),
+        }
+    }
+
+    // the `[%name value]` commands it knows come out of the comment, anything else stays
+    fn take_commands(&mut self, text: &str) -> String {
+        let mut rest = String::new();
+        let mut text = text;
+        while let Some(start) = text.find("[%")
+            && let Some(len) = text[start..].find(']')
+        {
+            rest.push_str(&text[..start]);
+            if !self.read_command(&text[start + 2..start + len]) {
+                rest.push_str(&text[start..=start + len]);
+            }
+            text = &text[start + len + 1..];
+        }
+        rest.push_str(text);
+        rest
+    }
+
+    fn read_command(&mut self, command: &str) -> bool {
+        let (name, value) = command
+            .split_once(char::is_whitespace)
+            .unwrap_or((command, ""));
+        let value = value.trim();
+        // a list with anything unreadable in it is left to the comment whole
+        match name {
+            "clk" => match parse_time(value) {
+                Some(time) => self.clock = Some(time),
+                None => return false,
+            },
+            "emt" => match parse_time(value) {
+                Some(time) => self.elapsed = Some(time),
+                None => return false,
+            },
+            "cal" => match parse_list::<Arrow>(value) {
+                Some(arrows) => self.arrows.extend(arrows),
+                None => return false,
+            },
+            "csl" => match parse_list::<SquareMark>(value) {
+                Some(marks) => self.marks.extend(marks),
+                None => return false,
+            },
+            _ => return false,
+        }
+        true
+    }
+
+    // the commands first, then whatever was said
+    fn comment_text(&self) -> Option<String> {
+        let mut parts = Vec::new();
+        if let Some(clock) = self.clock {
+            parts.push(format!("[%clk {}]", format_time(clock)));
+        }
+        if let Some(elapsed) = self.elapsed {
+            parts.push(format!("[%emt {}]", format_time(elapsed)));
+        }
+        if !self.marks.is_empty() {
+            let marks: Vec<String> = self.marks.iter().map(|m| m.to_string()).collect();
+            parts.push(format!("[%csl {}]", marks.join(",")));
+        }
+        if !self.arrows.is_empty() {
+            let arrows: Vec<String> = self.arrows.iter().map(|a| a.to_string()).collect();
+            parts.push(format!("[%cal {}]", arrows.join(",")));
+        }
+        parts.extend(self.comment.clone());
+        (!parts.is_empty()).then(|| parts.join(" "))
+    }
+}
+
+// h:mm:ss, with a tenth of a second when there is one
+fn format_time(time: Duration) -> String {
+    let secs = time.as_secs();
+    let hms = format!("{}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60);
+    match time.subsec_millis() / 100 {
+        0 => hms,
+        tenths => format!("{hms}.{tenths}"),
+    }
+}
+
+// "Ge2e4,Rd7d5"
+fn parse_list<T: FromStr>(text: &str) -> Option<Vec<T>> {
+    text.split(',')
+        .map(|item| item.trim().parse().ok())
+        .collect()
+}
+
+// "1:02:03", "2:03" or "3.5"
+fn parse_time(text: &str) -> Option<Duration> {
+    let mut secs = 0.0;
+    for part in text.split(':') {
+        secs = secs * 60.0 + part.parse::<f64>().ok()?;
+    }
+    Duration::try_from_secs_f64(secs).ok()
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Variation {
+    pub moves: Vec<String>,
+    pub annotations: Vec<Annotation>,
+}
+
+// "!" and the like for the NAGs that have one, `$n` for the rest
+pub fn nag_glyph(nag: u8) -> String {
+    match GLYPHS.get(usize::from(nag).wrapping_sub(1)) {
+        Some(glyph) => (*glyph).to_owned(),
+        None => format!("${nag}"),
+    }
+}
+
+impl PgnGame {
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn start_position(&self) -> Result<GameState, ParsePgnError> {
+        match self.tag("FEN") {
+            Some(fen) => Ok(GameState::try_from_fen(fen)?),
+            None => Ok(GameState::default()),
+        }
+    }
+
+    // every position before a move, paired with the move played from it
+    pub fn replay(&self) -> Result<Vec<(GameState, Move)>, ParsePgnError> {
+        let mut gs = self.start_position()?;
+        let mut played = Vec::with_capacity(self.moves.len());
+        for san in &self.moves {
+            // older files use long algebraic
+            let mv = gs
+                .parse_san(san)
+                .or_else(|err| gs.parse_lan(san).map_err(|_| err))?;
+            played.push((gs, mv));
+            gs.play(mv);
+        }
+        Ok(played)
+    }
+
+    pub fn annotation(&self, idx: usize) -> Option<&Annotation> {
+        self.annotations.get(idx)
+    }
+
+    // grows the annotations out to `idx` as needed
+    pub fn annotation_mut(&mut self, idx: usize) -> &mut Annotation {
+        if self.annotations.len() <= idx {
+            self.annotations.resize_with(idx + 1, Annotation::default);
+        }
+        &mut self.annotations[idx]
+    }
+}
+
+impl Display for PgnGame {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (name, value) in &self.tags {
+            writeln!(f, "[{name} \"{}\"]", value.replace('"', "\\\""))?;
+        }
+        writeln!(f)?;
+        let (full_move, turn) = self
+            .start_position()
+            .map_or((1, Color::White), |gs| (gs.full_move, gs.turn));
+        let mut tokens = Vec::with_capacity(self.moves.len() * 3 / 2 + 1);
+        movetext(&mut tokens, &self.moves, &self.annotations, full_move, turn);
+        tokens.push(self.result.map_or("*".to_owned(), |r| r.to_string()));
+        // keep movetext lines under 80 columns
+        let mut line_len = 0;
+        for token in tokens {
+            if line_len > 0 && line_len + token.len() + 1 > 80 {
+                writeln!(f)?;
+                line_len = 0;
+            } else if line_len > 0 {
+                write!(f, " ")?;
+                line_len += 1;
+            }
+            write!(f, "{token}")?;
+            line_len += token.len();
+        }
+        writeln!(f)
+    }
+}
+
+// the games of a file holding many, in file order
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PgnCollection {
+    games: Vec<PgnGame>,
+}
+
+impl PgnCollection {
+    pub fn len(&self) -> usize {
+        self.games.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.games.is_empty()
+    }
+
+    pub fn get(&self, idx: usize) -> Option<&PgnGame> {
+        self.games.get(idx)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &PgnGame> {
+        self.games.iter()
+    }
+
+    // the games whose tag `name` is `value`, by their index in the file
+    pub fn with_tag<'a>(
+        &'a self,
+        name: &'a str,
+        value: &'a str,
+    ) -> impl Iterator<Item = (usize, &'a PgnGame)> {
+        self.games
+            .iter()
+            .enumerate()
+            .filter(move |(_, game)| game.tag(name) == Some(value))
+    }
+
+    pub fn push(&mut self, game: PgnGame) {
+        self.games.push(game);
+    }
+}
+
+impl FromStr for PgnCollection {
+    type Err = ParsePgnError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Self {
+            games: parse_pgn(s)?,
+        })
+    }
+}
+
+// a blank line between games, as most tools write them
+impl Display for PgnCollection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (idx, game) in self.games.iter().enumerate() {
+            if idx > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{game}")?;
+        }
+        Ok(())
+    }
+}
+
+impl From<Vec<PgnGame>> for PgnCollection {
+    fn from(games: Vec<PgnGame>) -> Self {
+        Self { games }
+    }
+}
+
+impl FromIterator<PgnGame> for PgnCollection {
+    fn from_iter<I: IntoIterator<Item = PgnGame>>(iter: I) -> Self {
+        Self {
+            games: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl IntoIterator for PgnCollection {
+    type Item = PgnGame;
+    type IntoIter = std::vec::IntoIter<PgnGame>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.games.into_iter()
+    }
+}
+
+// the games of a PGN file too big to read in whole, a game at a time: lines are gathered
+// until tags come after movetext, outside any comment, and then parsed like `parse_pgn`.
+// A game that doesn't parse is an error of its own, and reading goes on after it.
+pub struct PgnReader<R> {
+    lines: Lines<R>,
+    text: String,
+    has_moves: bool,
+    in_comment: bool,
+    parsed: VecDeque<PgnGame>,
+}
+
+impl<R: BufRead> PgnReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            lines: reader.lines(),
+            text: String::new(),
+            has_moves: false,
+            in_comment: false,
+            parsed: VecDeque::new(),
+        }
+    }
+
+    // the text of the next game, or of what's left at the end
+    fn next_text(&mut self) -> Option<Result<String, DatabaseError>> {
+        for line in self.lines.by_ref() {
+            let line = match line {
+                Ok(line) => line,
+                Err(err) => return Some(Err(err.into())),
+            };
+            let starts_tag = !self.in_comment && line.trim_start().starts_with('[');
+            let done = starts_tag && self.has_moves;
+            let text = done.then(|| std::mem::take(&mut self.text));
+            if done {
+                self.has_moves = false;
+            }
+            if !starts_tag && !line.trim().is_empty() {
+                self.has_moves = true;
+            }
+            for c in line.chars() {
+                match c {
+                    '{' => self.in_comment = true,
+                    '}' => self.in_comment = false,
+                    _ => {}
+                }
+            }
+            self.text.push_str(&line);
+            self.text.push('\n');
+            if let Some(text) = text {
+                return Some(Ok(text));
+            }
+        }
+        match self.text.trim().is_empty() {
+            true => None,
+            false => Some(Ok(std::mem::take(&mut self.text))),
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for PgnReader<R> {
+    type Item = Result<PgnGame, DatabaseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.parsed.is_empty() {
+            match self.next_text()? {
+                Ok(text) => match parse_pgn(&text) {
+                    Ok(games) => self.parsed.extend(games),
+                    Err(err) => return Some(Err(err.into())),
+                },
+                Err(err) => return Some(Err(err)),
+            }
+        }
+        self.parsed.pop_front().map(Ok)
+    }
+}
+
+// numbered moves with their glyphs, comments and variations; a black move gets its
+// number again after anything that comes between it and White's
+fn movetext(
+    tokens: &mut Vec<String>,
+    moves: &[String],
+    annotations: &[Annotation],
+    mut full_move: u16,
+    mut turn: Color,
+) {
+    let mut interrupted = true;
+    for (idx, san) in moves.iter().enumerate() {
+        match turn {
+            Color::White => tokens.push(format!("{full_move}.")),
+            Color::Black if interrupted => tokens.push(format!("{full_move}...")),
+            Color::Black => {}
+        }
+        let annotation = annotations.get(idx);
+        // one glyph can go on the move itself, any more follow as `$n`
+        let mut token = san.clone();
+        let mut rest = Vec::new();
+        for &nag in annotation.map_or(&[][..], |a| &a.nags) {
+            match nag_glyph(nag) {
+                glyph if token.len() == san.len() && !glyph.starts_with('$') => {
+                    token.push_str(&glyph)
+                }
+                _ => rest.push(format!("${nag}")),
+            }
+        }
+        tokens.push(token);
+        tokens.extend(rest);
+        interrupted = false;
+        if let Some(comment) = annotation.and_then(Annotation::comment_text) {
+            // a word at a time so long comments wrap with the moves
+            let words: Vec<&str> = comment.split_whitespace().collect();
+            let last = words.len().saturating_sub(1);
+            for (word_idx, word) in words.iter().enumerate() {
+                let open = if word_idx == 0 { "{" } else { "" };
+                let close = if word_idx == last { "}" } else { "" };
+                tokens.push(format!("{open}{word}{close}"));
+            }
+            interrupted = true;
+        }
+        for variation in annotation.map_or(&[][..], |a| &a.variations) {
+            let mut inner = Vec::new();
+            movetext(
+                &mut inner,
+                &variation.moves,
+                &variation.annotations,
+                full_move,
+                turn,
+            );
+            if let Some(first) = inner.first_mut() {
+                first.insert(0, '(');
+            }
+            if let Some(last) = inner.last_mut() {
+                last.push(')');
+                tokens.extend(inner);
+                interrupted = true;
+            }
+        }
+        if turn == Color::Black {
+            full_move = full_move.saturating_add(1);
+        }
+        turn = !turn;
+    }
+}
+
+pub fn parse_pgn(text: &str) -> Result<Vec<PgnGame>, ParsePgnError> {
+    Ok(parse_pgn_with_offsets(text)?
+        .into_iter()
+        .map(|(_, game)| game)
+        .collect())
+}
+
+// each game paired with the byte offset it starts at
+pub(crate) fn parse_pgn_with_offsets(text: &str) -> Result<Vec<(usize, PgnGame)>, ParsePgnError> {
+    let mut games = Vec::new();
+    let mut game = PgnGame::default();
+    // variations still open, innermost last
+    let mut open: Vec<Variation> = Vec::new();
+    let mut start = None;
+    let mut chars = text.char_indices().peekable();
+    while let Some((idx, c)) = chars.next() {
+        match c {
+            '[' => {
+                if !open.is_empty() {
+                    return Err(ParsePgnError::Unterminated('('));
+                }
+                // tags after movetext start the next game
+                if !game.moves.is_empty() {
+                    games.push((start.take().unwrap_or(idx), std::mem::take(&mut game)));
+                }
+                start.get_or_insert(idx);
+                let tag: String = chars
+                    .by_ref()
+                    .map(|(_, c)| c)
+                    .take_while(|&c| c != ']')
+                    .collect();
+                game.tags.push(parse_tag(&tag)?);
+            }
+            '{' => {
+                let mut comment = String::new();
+                let mut closed = false;
+                for (_, c) in chars.by_ref() {
+                    if c == '}' {
+                        closed = true;
+                        break;
+                    }
+                    comment.push(c);
+                }
+                if !closed {
+                    return Err(ParsePgnError::Unterminated('{'));
+                }
+                // a comment before the first move has nowhere to go
+                if let Some(annotation) = last_annotation(&mut game, &mut open) {
+                    annotation.add_comment(&comment);
+                }
+            }
+            ';' => {
+                let comment: String = chars
+                    .by_ref()
+                    .map(|(_, c)| c)
+                    .take_while(|&c| c != '\n')
+                    .collect();
+                if let Some(annotation) = last_annotation(&mut game, &mut open) {
+                    annotation.add_comment(&comment);
+                }
+            }
+            '(' => open.push(Variation::default()),
+            ')' => {
+                // a variation stands in for the move before it
+                if let Some(variation) = open.pop()
+                    && !variation.moves.is_empty()
+                    && let Some(annotation) = last_annotation(&mut game, &mut open)
+                {
+                    annotation.variations.push(variation);
+                }
+            }
+            c if c.is_whitespace() => {}
+            c => {
+                start.get_or_insert(idx);
+                let mut token = String::from(c);
+                while let Some(&(_, next)) = chars.peek()
+                    && !next.is_whitespace()
+                    && !"{}()[];".contains(next)
+                {
+                    token.push(next);
+                    chars.next();
+                }
+                match token.as_str() {
+                    // results inside a variation only end the variation's line
+                    "*" | "1-0" | "0-1" | "1/2-1/2" if !open.is_empty() => {}
+                    "*" => games.push((start.take().unwrap_or(idx), std::mem::take(&mut game))),
+                    "1-0" | "0-1" | "1/2-1/2" => {
+                        game.result = Some(token.parse()?);
+                        games.push((start.take().unwrap_or(idx), std::mem::take(&mut game)));
+                    }
+                    t if t.starts_with('$') => {
+                        if let Ok(nag) = t[1..].parse()
+                            && let Some(annotation) = last_annotation(&mut game, &mut open)
+                        {
+                            annotation.nags.push(nag);
+                        }
+                    }
+                    t => {
+                        // strip move numbers such as "12." or "12..."
+                        let san = t.rsplit('.').next().unwrap_or_default();
+                        if san.is_empty() || san.chars().all(|c| c.is_ascii_digit()) {
+                            continue;
+                        }
+                        let bare = san.trim_end_matches(['!', '?']);
+                        let glyph = GLYPHS.iter().position(|&g| g == &san[bare.len()..]);
+                        let (moves, annotations) = match open.last_mut() {
+                            Some(variation) => (&mut variation.moves, &mut variation.annotations),
+                            None => (&mut game.moves, &mut game.annotations),
+                        };
+                        moves.push(bare.to_owned());
+                        if let Some(glyph) = glyph {
+                            annotations.resize_with(moves.len(), Annotation::default);
+                            annotations[moves.len() - 1].nags.push(glyph as u8 + 1);
+                        }
+                    }
+                }
+            }
+        }
+    }
+    if !open.is_empty() {
+        return Err(ParsePgnError::Unterminated('('));
+    }
+    if !game.moves.is_empty() || !game.tags.is_empty() {
+        games.push((start.unwrap_or_default(), game));
+    }
+    Ok(games)
+}
+
+// the annotation of the last move in the innermost line being read
+fn last_annotation<'a>(
+    game: &'a mut PgnGame,
+    open: &'a mut [Variation],
+) -> Option<&'a mut Annotation> {
+    let (moves, annotations) = match open.last_mut() {
+        Some(variation) => (&variation.moves, &mut variation.annotations),
+        None => (&game.moves, &mut game.annotations),
+    };
+    let idx = moves.len().checked_sub(1)?;
+    annotations.resize_with(moves.len(), Annotation::default);
+    annotations.get_mut(idx)
+}
+
+// every line a PGN holds, variations included: each game's main line, then each of its
+// variations played out from the game's start, with the game's tags
+pub fn expand_variations(text: &str) -> Result<Vec<PgnGame>, ParsePgnError> {
+    let mut lines = Vec::new();
+    for game in parse_pgn(text)? {
+        let mut branches = Vec::new();
+        collect_branches(&[], &game.moves, &game.annotations, &mut branches);
+        lines.push(PgnGame {
+            annotations: Vec::new(),
+            ..game.clone()
+        });
+        for moves in branches {
+            lines.push(PgnGame {
+                tags: game.tags.clone(),
+                moves,
+                annotations: Vec::new(),
+                result: game.result,
+            });
+        }
+    }
+    Ok(lines)
+}
+
+// variations nested in a variation come before it, the order they close in
+fn collect_branches(
+    prefix: &[String],
+    moves: &[String],
+    annotations: &[Annotation],
+    branches: &mut Vec<Vec<String>>,
+) {
+    for (idx, annotation) in annotations.iter().enumerate() {
+        let before = [prefix, &moves[..idx.min(moves.len())]].concat();
+        for variation in &annotation.variations {
+            collect_branches(&before, &variation.moves, &variation.annotations, branches);
+            branches.push([&before[..], &variation.moves].concat());
+        }
+    }
+}
+
+fn parse_tag(tag: &str) -> Result<(String, String), ParsePgnError> {
+    let invalid = || ParsePgnError::InvalidTag(tag.to_owned());
+    let (name, value) = tag
+        .trim()
+        .split_once(char::is_whitespace)
+        .ok_or_else(invalid)?;
+    let value = value
+        .trim()
+        .strip_prefix('"')
+        .and_then(|v| v.strip_suffix('"'))
+        .ok_or_else(invalid)?;
+    Ok((name.to_owned(), value.replace("\\\"", "\"")))
+}
@@ -1,6 +1,6 @@
-use crate::errors::ParsePieceError;
+use crate::errors::{InvalidCharError, InvalidValueError};
 use constants::*;
-use std::{marker::ConstParamTy, ops::Not};
+use std::ops::{Index, IndexMut, Not};
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub struct Piece {
@@ -20,13 +20,13 @@ impl From<Piece> for char {
         };
         match piece.color {
             Color::White => c,
-            Color::Black => c.to_lowercase().next().unwrap(),
+            Color::Black => c.to_ascii_lowercase(),
         }
     }
 }
 
 impl TryFrom<char> for Piece {
-    type Error = ParsePieceError;
+    type Error = InvalidCharError;
     fn try_from(value: char) -> Result<Self, Self::Error> {
         match value {
             'P' => Ok(WHITE_PAWN),
@@ -41,7 +41,7 @@ impl TryFrom<char> for Piece {
             'b' => Ok(BLACK_BISHOP),
             'q' => Ok(BLACK_QUEEN),
             'k' => Ok(BLACK_KING),
-            v => Err(ParsePieceError(v)),
+            v => Err(InvalidCharError(v)),
         }
     }
 }
@@ -53,6 +53,20 @@ pub enum Color {
     Black,
 }
 
+impl<T> Index<Color> for [T] {
+    type Output = T;
+
+    fn index(&self, index: Color) -> &Self::Output {
+        &self[index as usize]
+    }
+}
+
+impl<T> IndexMut<Color> for [T] {
+    fn index_mut(&mut self, index: Color) -> &mut Self::Output {
+        &mut self[index as usize]
+    }
+}
+
 impl Not for Color {
     type Output = Self;
     fn not(self) -> Self::Output {
@@ -63,7 +77,7 @@ impl Not for Color {
     }
 }
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq, ConstParamTy)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 #[repr(u8)]
 pub enum Figure {
     Pawn,
@@ -75,11 +89,84 @@ pub enum Figure {
 }
 
 impl Figure {
+    pub const fn value(self) -> i32 {
+        match self {
+            Figure::Pawn => 100,
+            Figure::Knight => 300,
+            Figure::Bishop => 300,
+            Figure::Rook => 500,
+            Figure::Queen => 900,
+            Figure::King => 0,
+        }
+    }
+
     pub fn iter() -> impl Iterator<Item = &'static Self> {
         use Figure::*;
         static FIGURES: [Figure; 6] = [Pawn, Rook, Knight, Bishop, Queen, King];
         FIGURES.iter()
     }
+
+    // None for the pawn and king, whose moves have rules of their own
+    pub const fn officer(self) -> Option<Officer> {
+        match self {
+            Figure::Knight => Some(Officer::Knight),
+            Figure::Bishop => Some(Officer::Bishop),
+            Figure::Rook => Some(Officer::Rook),
+            Figure::Queen => Some(Officer::Queen),
+            Figure::Pawn | Figure::King => None,
+        }
+    }
+}
+
+// the figures that move the same way whatever the position: to any square they attack
+// that isn't their own side's
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Officer {
+    Knight,
+    Bishop,
+    Rook,
+    Queen,
+}
+
+impl From<Officer> for Figure {
+    fn from(officer: Officer) -> Self {
+        match officer {
+            Officer::Knight => Figure::Knight,
+            Officer::Bishop => Figure::Bishop,
+            Officer::Rook => Figure::Rook,
+            Officer::Queen => Figure::Queen,
+        }
+    }
+}
+
+impl TryFrom<u8> for Figure {
+    type Error = InvalidValueError;
+
+    fn try_from(val: u8) -> Result<Self, Self::Error> {
+        match val {
+            0 => Ok(Figure::Pawn),
+            1 => Ok(Figure::Rook),
+            2 => Ok(Figure::Knight),
+            3 => Ok(Figure::Bishop),
+            4 => Ok(Figure::Queen),
+            5 => Ok(Figure::King),
+            v => Err(InvalidValueError(v)),
+        }
+    }
+}
+
+impl<T> Index<Figure> for [T] {
+    type Output = T;
+
+    fn index(&self, index: Figure) -> &Self::Output {
+        &self[index as usize]
+    }
+}
+
+impl<T> IndexMut<Figure> for [T] {
+    fn index_mut(&mut self, index: Figure) -> &mut Self::Output {
+        &mut self[index as usize]
+    }
 }
 
 pub mod constants {
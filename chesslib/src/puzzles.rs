@@ -0,0 +1,100 @@
+use crate::{
+    GameState,
+    analysis::motifs::{Motif, find_motifs},
+    errors::{ParseFenError, ParsePuzzleError},
+    moves::Move,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Puzzle {
+    pub id: String,
+    pub fen: String,
+    // the first move is the opponent's, the solver answers on every other ply
+    pub moves: Vec<Move>,
+    pub rating: u32,
+    pub themes: Vec<String>,
+}
+
+impl Puzzle {
+    // always fine for a parsed puzzle, but the fields are anyone's to fill in
+    pub fn start_position(&self) -> Result<GameState, ParseFenError> {
+        GameState::try_from_fen(&self.fen)
+    }
+
+    // `gs` is the position before ply `ply`; any mate on the last move is as good as the
+    // one in the file
+    pub fn accepts(&self, gs: &GameState, ply: usize, mv: Move) -> bool {
+        if self.moves.get(ply) == Some(&mv) {
+            return true;
+        }
+        if ply + 1 != self.moves.len() || !gs.legal_moves().contains(&mv) {
+            return false;
+        }
+        let mut child = *gs;
+        child.play(mv);
+        child.board.is_in_check(child.turn) && child.legal_move_count() == 0
+    }
+
+    // what the motif finder sees for the solver once the opponent's first move is on the
+    // board, for tagging puzzles that came without themes
+    pub fn detected_themes(&self) -> Vec<&'static str> {
+        let Ok(mut gs) = self.start_position() else {
+            return Vec::new();
+        };
+        if let Some(&mv) = self.moves.first() {
+            gs.play(mv);
+        }
+        let mut themes: Vec<_> = find_motifs(&gs, gs.turn).iter().map(Motif::theme).collect();
+        themes.sort_unstable();
+        themes.dedup();
+        themes
+    }
+}
+
+// lichess puzzle database rows:
+// PuzzleId,FEN,Moves,Rating,RatingDeviation,Popularity,NbPlays,Themes,GameUrl,OpeningTags
+pub fn parse_lichess_csv(text: &str) -> Result<Vec<Puzzle>, ParsePuzzleError> {
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty() && !line.starts_with("PuzzleId"))
+        .map(|(idx, line)| parse_row(idx + 1, line))
+        .collect()
+}
+
+fn parse_row(line_no: usize, line: &str) -> Result<Puzzle, ParsePuzzleError> {
+    let fields: Vec<&str> = line.trim().split(',').collect();
+    let field = |idx, name| {
+        fields
+            .get(idx)
+            .copied()
+            .filter(|f: &&str| !f.is_empty())
+            .ok_or(ParsePuzzleError::MissingField(line_no, name))
+    };
+    let id = field(0, "PuzzleId")?.to_owned();
+    let fen = field(1, "FEN")?.to_owned();
+    let mut gs = GameState::try_from_fen(&fen)
+        .map_err(|err| ParsePuzzleError::ParseFenError(line_no, err))?;
+    let mut moves = Vec::new();
+    for uci in field(2, "Moves")?.split_whitespace() {
+        let mv = gs
+            .parse_uci_move(uci)
+            .ok_or_else(|| ParsePuzzleError::IllegalMove(line_no, uci.to_owned()))?;
+        gs.play(mv);
+        moves.push(mv);
+    }
+    let rating = field(3, "Rating")?;
+    let rating = rating
+        .parse()
+        .map_err(|_| ParsePuzzleError::InvalidRating(line_no, rating.to_owned()))?;
+    let themes = fields
+        .get(7)
+        .map(|themes| themes.split_whitespace().map(str::to_owned).collect())
+        .unwrap_or_default();
+    Ok(Puzzle {
+        id,
+        fen,
+        moves,
+        rating,
+        themes,
+    })
+}
@@ -0,0 +1,112 @@
+use crate::{
+    GameState,
+    board::{Board, Row, Square},
+    castle::Castle,
+    pieces::{Color, Figure, Piece},
+};
+
+// placements tried before a setup is given up on
+const MAX_TRIES: usize = 10_000;
+
+// splitmix64, seeded so a run can be repeated exactly
+#[derive(Debug, Clone)]
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    // 0..n, n > 0
+    pub fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+}
+
+// what a random position holds besides the two kings, and what it has to satisfy
+#[derive(Debug, Clone)]
+pub struct RandomSetup {
+    pub white: Vec<Figure>,
+    pub black: Vec<Figure>,
+    // either side when none
+    pub turn: Option<Color>,
+    // the side to move may start in check
+    pub allow_check: bool,
+    // not over before it starts: no mate, stalemate or bare-minors draw
+    pub ongoing: bool,
+}
+
+impl RandomSetup {
+    pub fn new(white: &[Figure], black: &[Figure]) -> Self {
+        Self {
+            white: white.to_vec(),
+            black: black.to_vec(),
+            turn: None,
+            allow_check: false,
+            ongoing: true,
+        }
+    }
+
+    // a legal position with the pieces placed at random: pawns off the back ranks, the side
+    // not to move out of check; None when no placement fits the constraints
+    pub fn generate(&self, rng: &mut Rng) -> Option<GameState> {
+        // each side gets its king whatever the lists say
+        let pieces: Vec<Piece> = [(Color::White, &self.white), (Color::Black, &self.black)]
+            .into_iter()
+            .flat_map(|(color, figures)| {
+                let others = figures.iter().filter(|&&figure| figure != Figure::King);
+                [Figure::King]
+                    .iter()
+                    .chain(others)
+                    .map(move |&figure| Piece { color, figure })
+            })
+            .collect();
+        let pawns = pieces.iter().filter(|p| p.figure == Figure::Pawn).count();
+        if pieces.len() > 64 || pawns > 48 {
+            return None;
+        }
+        (0..MAX_TRIES).find_map(|_| self.try_place(&pieces, rng))
+    }
+
+    fn try_place(&self, pieces: &[Piece], rng: &mut Rng) -> Option<GameState> {
+        let mut board = Board::new();
+        for &piece in pieces {
+            let free: Vec<Square> = (0..64)
+                .filter_map(Square::new)
+                .filter(|&square| board.get_sq(square).is_none())
+                .filter(|square| {
+                    piece.figure != Figure::Pawn || !matches!(square.row(), Row::One | Row::Eight)
+                })
+                .collect();
+            if free.is_empty() {
+                return None;
+            }
+            board.set_sq(free[rng.below(free.len())], piece);
+        }
+        let turn = self.turn.unwrap_or(if rng.below(2) == 0 {
+            Color::White
+        } else {
+            Color::Black
+        });
+        let gs = GameState {
+            board,
+            turn,
+            castle: Castle::Null,
+            ep_square: None,
+            half_move: 0,
+            full_move: 1,
+        };
+        let legal = !gs.board.is_in_check(!turn)
+            && (self.allow_check || !gs.board.is_in_check(turn))
+            && (!self.ongoing || !gs.status().is_over());
+        legal.then_some(gs)
+    }
+}
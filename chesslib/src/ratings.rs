@@ -0,0 +1,55 @@
+use crate::{pgn::GameResult, pieces::Color};
+
+pub const DEFAULT_K: f64 = 20.0;
+
+pub fn expected_score(rating: f64, opponent: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf((opponent - rating) / 400.0))
+}
+
+pub fn elo_update(rating: f64, opponent: f64, score: f64, k: f64) -> f64 {
+    rating + k * (score - expected_score(rating, opponent))
+}
+
+// rating difference implied by a score fraction, undefined for 0% and 100%
+pub fn elo_difference(score_fraction: f64) -> Option<f64> {
+    (score_fraction > 0.0 && score_fraction < 1.0)
+        .then(|| -400.0 * (1.0 / score_fraction - 1.0).log10())
+}
+
+pub fn score(result: GameResult, color: Color) -> f64 {
+    match (result, color) {
+        (GameResult::Draw, _) => 0.5,
+        (GameResult::WhiteWins, Color::White) | (GameResult::BlackWins, Color::Black) => 1.0,
+        _ => 0.0,
+    }
+}
+
+// rating at which the expected total matches the actual total, given (opponent, score) pairs.
+// perfect and zero scores are capped 800 points beyond the strongest/weakest opponent.
+pub fn performance_rating(results: &[(f64, f64)]) -> Option<f64> {
+    if results.is_empty() {
+        return None;
+    }
+    let total: f64 = results.iter().map(|&(_, score)| score).sum();
+    let expected = |rating: f64| -> f64 {
+        results
+            .iter()
+            .map(|&(opponent, _)| expected_score(rating, opponent))
+            .sum()
+    };
+    let (min, max) = results
+        .iter()
+        .fold((f64::MAX, f64::MIN), |(min, max), &(opponent, _)| {
+            (min.min(opponent), max.max(opponent))
+        });
+    let (mut low, mut high) = (min - 800.0, max + 800.0);
+    for _ in 0..64 {
+        let mid = (low + high) / 2.0;
+        if expected(mid) < total {
+            low = mid;
+        } else {
+            high = mid;
+        }
+    }
+    Some((low + high) / 2.0)
+}
@@ -0,0 +1,130 @@
+use crate::{
+    GameState,
+    errors::ParsePgnError,
+    moves::Move,
+    pgn::{GameResult, PgnGame, parse_pgn},
+};
+use std::{
+    cmp::Reverse,
+    collections::{HashMap, HashSet},
+};
+
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct ResultStats {
+    pub games: u32,
+    pub white_wins: u32,
+    pub black_wins: u32,
+    pub draws: u32,
+}
+
+impl ResultStats {
+    fn record(&mut self, result: Option<GameResult>) {
+        self.games += 1;
+        match result {
+            Some(GameResult::WhiteWins) => self.white_wins += 1,
+            Some(GameResult::BlackWins) => self.black_wins += 1,
+            Some(GameResult::Draw) => self.draws += 1,
+            None => {}
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct MoveStats {
+    pub mv: Move,
+    pub stats: ResultStats,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RepertoireNode {
+    pub stats: ResultStats,
+    // most played first
+    pub moves: Vec<MoveStats>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct RepertoireTree {
+    nodes: HashMap<u64, RepertoireNode>,
+}
+
+impl RepertoireTree {
+    pub fn get(&self, gs: &GameState) -> Option<&RepertoireNode> {
+        self.nodes.get(&gs.zobrist())
+    }
+
+    pub fn get_hash(&self, hash: u64) -> Option<&RepertoireNode> {
+        self.nodes.get(&hash)
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RepertoireBuilder {
+    max_ply: usize,
+    tree: RepertoireTree,
+}
+
+impl Default for RepertoireBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RepertoireBuilder {
+    pub fn new() -> Self {
+        Self {
+            max_ply: usize::MAX,
+            tree: RepertoireTree::default(),
+        }
+    }
+
+    pub fn max_ply(mut self, max_ply: usize) -> Self {
+        self.max_ply = max_ply;
+        self
+    }
+
+    pub fn add_game(&mut self, game: &PgnGame) -> Result<(), ParsePgnError> {
+        let played = game.replay()?;
+        // count a position once per game even if it repeats
+        let mut seen = HashSet::new();
+        for (gs, mv) in played.into_iter().take(self.max_ply) {
+            let hash = gs.zobrist();
+            if !seen.insert(hash) {
+                continue;
+            }
+            let node = self.tree.nodes.entry(hash).or_default();
+            node.stats.record(game.result);
+            match node.moves.iter_mut().find(|m| m.mv == mv) {
+                Some(m) => m.stats.record(game.result),
+                None => {
+                    let mut stats = ResultStats::default();
+                    stats.record(game.result);
+                    node.moves.push(MoveStats { mv, stats });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn add_pgn(&mut self, text: &str) -> Result<usize, ParsePgnError> {
+        let games = parse_pgn(text)?;
+        for game in &games {
+            self.add_game(game)?;
+        }
+        Ok(games.len())
+    }
+
+    pub fn build(mut self) -> RepertoireTree {
+        for node in self.tree.nodes.values_mut() {
+            node.moves.sort_by_key(|m| Reverse(m.stats.games));
+        }
+        self.tree
+    }
+}
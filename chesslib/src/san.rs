@@ -0,0 +1,294 @@
+use crate::{
+    GameState,
+    board::{Column, Row, Square},
+    errors::ParseSanError,
+    moves::{Move, MoveKind},
+    pieces::{Color, Figure, Piece},
+};
+
+impl GameState {
+    pub fn parse_san(&self, san: &str) -> Result<Move, ParseSanError> {
+        let trimmed = san.trim_end_matches(['+', '#', '!', '?']);
+        let castle = match trimmed {
+            "O-O" | "0-0" => Some(MoveKind::KingCastle),
+            "O-O-O" | "0-0-0" => Some(MoveKind::QueenCastle),
+            _ => None,
+        };
+        if let Some(kind) = castle {
+            return self
+                .legal_moves()
+                .into_iter()
+                .find(|mv| mv.kind == kind)
+                .ok_or_else(|| ParseSanError::IllegalMove(san.to_owned()));
+        }
+
+        let invalid = || ParseSanError::InvalidString(san.to_owned());
+        let mut chars: Vec<char> = trimmed.chars().filter(|&c| c != 'x').collect();
+        let figure = match chars.first() {
+            Some('N') => Figure::Knight,
+            Some('B') => Figure::Bishop,
+            Some('R') => Figure::Rook,
+            Some('Q') => Figure::Queen,
+            Some('K') => Figure::King,
+            _ => Figure::Pawn,
+        };
+        if figure != Figure::Pawn {
+            chars.remove(0);
+        }
+        let promotion = match chars.last() {
+            Some(&c) if c.is_ascii_uppercase() => {
+                chars.pop();
+                if chars.last() == Some(&'=') {
+                    chars.pop();
+                }
+                Some(piece_from_letter(c).ok_or_else(invalid)?)
+            }
+            _ => None,
+        };
+        if chars.len() < 2 {
+            return Err(invalid());
+        }
+        let to: String = chars.split_off(chars.len() - 2).into_iter().collect();
+        let to: Square = to.parse().map_err(|_| invalid())?;
+        let mut from_col = None;
+        let mut from_row = None;
+        for c in chars {
+            match c {
+                'a'..='h' => from_col = Column::try_from(c).ok(),
+                '1'..='8' => from_row = Row::try_from(c).ok(),
+                _ => return Err(invalid()),
+            }
+        }
+
+        let mut candidates = self.legal_moves().into_iter().filter(|mv| {
+            mv.to == to
+                && !mv.is_castle()
+                && self.board.get_sq(mv.from).map(|p| p.figure) == Some(figure)
+                && mv.promotion() == promotion
+                && from_col.is_none_or(|col| mv.from.col() == col)
+                && from_row.is_none_or(|row| mv.from.row() == row)
+        });
+        match (candidates.next(), candidates.next()) {
+            (Some(mv), None) => Ok(mv),
+            (Some(_), Some(_)) => Err(ParseSanError::AmbiguousMove(san.to_owned())),
+            (None, _) => Err(ParseSanError::IllegalMove(san.to_owned())),
+        }
+    }
+
+    pub fn to_san(&self, mv: Move) -> String {
+        let mut san = match mv.kind {
+            MoveKind::KingCastle => "O-O".to_owned(),
+            MoveKind::QueenCastle => "O-O-O".to_owned(),
+            _ => self.piece_san(mv),
+        };
+        san.extend(self.check_suffix(mv));
+        san
+    }
+
+    // an engine's principal variation in SAN, up to the first move that doesn't fit
+    pub fn pv_to_san(&self, pv: &[String]) -> Vec<String> {
+        let mut gs = *self;
+        let mut sans = Vec::new();
+        for uci in pv {
+            let Some(mv) = gs.parse_uci_move(uci) else {
+                break;
+            };
+            sans.push(gs.to_san(mv));
+            gs.play(mv);
+        }
+        sans
+    }
+
+    // the position the principal variation leads to, as far as `pv_to_san` gets
+    pub fn pv_end(&self, pv: &[String]) -> GameState {
+        let mut gs = *self;
+        for uci in pv {
+            let Some(mv) = gs.parse_uci_move(uci) else {
+                break;
+            };
+            gs.play(mv);
+        }
+        gs
+    }
+
+    // the move in plain words for reading aloud, e.g. "Knight from g1 to f3, check"
+    pub fn describe_move(&self, mv: Move) -> String {
+        let figure = self.board.get_sq(mv.from).map(|piece| piece.figure);
+        let name = |figure: Figure| format!("{figure:?}").to_lowercase();
+        let mut words = match mv.kind {
+            MoveKind::KingCastle => "Castles kingside".to_owned(),
+            MoveKind::QueenCastle => "Castles queenside".to_owned(),
+            _ => {
+                let mut words = match figure {
+                    Some(figure) => format!("{figure:?} from {}", mv.from),
+                    None => format!("From {}", mv.from),
+                };
+                match self.board.get_sq(mv.to) {
+                    Some(captured) => {
+                        words.push_str(&format!(" takes {} on {}", name(captured.figure), mv.to));
+                    }
+                    None if mv.kind == MoveKind::EnPassant => {
+                        words.push_str(&format!(" takes pawn on {} en passant", mv.to));
+                    }
+                    None => words.push_str(&format!(" to {}", mv.to)),
+                }
+                if let Some(promotion) = mv.promotion() {
+                    words.push_str(&format!(", promotes to {}", name(promotion)));
+                }
+                words
+            }
+        };
+        match self.check_suffix(mv) {
+            Some('#') => words.push_str(", checkmate"),
+            Some(_) => words.push_str(", check"),
+            None => {}
+        }
+        words
+    }
+
+    // long algebraic, e.g. "Ng1-f3" or "e7xd8=Q+"
+    pub fn to_lan(&self, mv: Move) -> String {
+        let mut lan = match mv.kind {
+            MoveKind::KingCastle => "O-O".to_owned(),
+            MoveKind::QueenCastle => "O-O-O".to_owned(),
+            _ => {
+                let mut lan = String::new();
+                if let Some(Piece { figure, .. }) = self.board.get_sq(mv.from)
+                    && figure != Figure::Pawn
+                {
+                    lan.push(char::from(Piece {
+                        color: Color::White,
+                        figure,
+                    }));
+                }
+                let sep = if mv.is_capture() { 'x' } else { '-' };
+                lan.push_str(&format!("{}{sep}{}", mv.from, mv.to));
+                if let Some(figure) = mv.promotion() {
+                    lan.push('=');
+                    lan.push(char::from(Piece {
+                        color: Color::White,
+                        figure,
+                    }));
+                }
+                lan
+            }
+        };
+        lan.extend(self.check_suffix(mv));
+        lan
+    }
+
+    // accepts "Ng1-f3", "Ng1f3", "e7xd8=Q" and plain coordinates like "e2e4"
+    pub fn parse_lan(&self, lan: &str) -> Result<Move, ParseSanError> {
+        let trimmed = lan.trim_end_matches(['+', '#', '!', '?']);
+        if matches!(trimmed, "O-O" | "0-0" | "O-O-O" | "0-0-0") {
+            return self.parse_san(trimmed);
+        }
+        let invalid = || ParseSanError::InvalidString(lan.to_owned());
+        let mut chars: Vec<char> = trimmed
+            .chars()
+            .filter(|c| !matches!(c, '-' | 'x' | ':' | '='))
+            .collect();
+        // without a piece letter this is plain coordinate notation
+        let figure = match chars.first() {
+            Some(&'K') => Some(Figure::King),
+            Some(&c) if c.is_ascii_uppercase() => Some(piece_from_letter(c).ok_or_else(invalid)?),
+            _ => None,
+        };
+        if figure.is_some() {
+            chars.remove(0);
+        }
+        let promotion = match chars.len() {
+            4 => None,
+            5 => Some(piece_from_letter(chars[4].to_ascii_uppercase()).ok_or_else(invalid)?),
+            _ => return Err(invalid()),
+        };
+        let square = |chars: &[char]| {
+            chars
+                .iter()
+                .collect::<String>()
+                .parse::<Square>()
+                .map_err(|_| invalid())
+        };
+        let (from, to) = (square(&chars[..2])?, square(&chars[2..4])?);
+        if let Some(figure) = figure
+            && self.board.get_sq(from).map(|p| p.figure) != Some(figure)
+        {
+            return Err(ParseSanError::IllegalMove(lan.to_owned()));
+        }
+        self.legal_moves()
+            .into_iter()
+            .find(|mv| mv.from == from && mv.to == to && mv.promotion() == promotion)
+            .ok_or_else(|| ParseSanError::IllegalMove(lan.to_owned()))
+    }
+
+    fn check_suffix(&self, mv: Move) -> Option<char> {
+        let mut child = *self;
+        child.play(mv);
+        if !child.board.is_in_check(child.turn) {
+            return None;
+        }
+        Some(if child.legal_move_count() == 0 {
+            '#'
+        } else {
+            '+'
+        })
+    }
+
+    fn piece_san(&self, mv: Move) -> String {
+        let Some(Piece { figure, .. }) = self.board.get_sq(mv.from) else {
+            return mv.to_string();
+        };
+        let from = mv.from.to_string();
+        let mut san = String::new();
+        if figure == Figure::Pawn {
+            if mv.is_capture() {
+                san.push_str(&from[..1]);
+            }
+        } else {
+            san.push(char::from(Piece {
+                color: Color::White,
+                figure,
+            }));
+            let rivals: Vec<Move> = self
+                .legal_moves()
+                .into_iter()
+                .filter(|other| {
+                    other.to == mv.to
+                        && other.from != mv.from
+                        && self.board.get_sq(other.from).map(|p| p.figure) == Some(figure)
+                })
+                .collect();
+            if !rivals.is_empty() {
+                if rivals.iter().all(|other| other.from.col() != mv.from.col()) {
+                    san.push_str(&from[..1]);
+                } else if rivals.iter().all(|other| other.from.row() != mv.from.row()) {
+                    san.push_str(&from[1..]);
+                } else {
+                    san.push_str(&from);
+                }
+            }
+        }
+        if mv.is_capture() {
+            san.push('x');
+        }
+        san.push_str(&mv.to.to_string());
+        if let Some(figure) = mv.promotion() {
+            san.push('=');
+            san.push(char::from(Piece {
+                color: Color::White,
+                figure,
+            }));
+        }
+        san
+    }
+}
+
+fn piece_from_letter(c: char) -> Option<Figure> {
+    match c {
+        'N' => Some(Figure::Knight),
+        'B' => Some(Figure::Bishop),
+        'R' => Some(Figure::Rook),
+        'Q' => Some(Figure::Queen),
+        _ => None,
+    }
+}
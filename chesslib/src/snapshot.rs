@@ -0,0 +1,81 @@
+use crate::{
+    GameState,
+    board::Square,
+    castle::Castle,
+    movegen::castle_rook_squares,
+    moves::{Move, MoveKind},
+    pieces::{Color, Figure, Piece},
+};
+
+// everything a move can't give back on its own, plus the hash to check the rollback against
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct StateToken {
+    turn: Color,
+    castle: Castle,
+    ep_square: Option<Square>,
+    half_move: u16,
+    full_move: u16,
+    hash: u64,
+}
+
+impl StateToken {
+    pub fn hash(&self) -> u64 {
+        self.hash
+    }
+}
+
+impl GameState {
+    pub fn snapshot(&self) -> StateToken {
+        StateToken {
+            turn: self.turn,
+            castle: self.castle,
+            ep_square: self.ep_square,
+            half_move: self.half_move,
+            full_move: self.full_move,
+            hash: self.zobrist(),
+        }
+    }
+
+    // rolls back turn, castling, ep and counters; the board must already be back in place
+    pub fn restore(&mut self, token: StateToken) {
+        self.turn = token.turn;
+        self.castle = token.castle;
+        self.ep_square = token.ep_square;
+        self.half_move = token.half_move;
+        self.full_move = token.full_move;
+        debug_assert_eq!(self.zobrist(), token.hash, "board doesn't match snapshot");
+    }
+
+    // takes back `mv`, which captured `captured`, to the position `token` was taken in
+    pub fn unmake_move(&mut self, mv: Move, captured: Option<Piece>, token: StateToken) {
+        let Move { from, to, kind } = mv;
+        match kind {
+            MoveKind::EnPassant => {
+                self.board.move_piece(to, from);
+                if let Some(captured) = captured {
+                    self.board
+                        .set_sq(Square::from_coords(to.col(), from.row()), captured);
+                }
+            }
+            MoveKind::KingCastle | MoveKind::QueenCastle => {
+                let (rook_from, rook_to) = castle_rook_squares(kind, from.row());
+                self.board.move_piece(to, from);
+                self.board.move_piece(rook_to, rook_from);
+            }
+            MoveKind::Promotion(_) | MoveKind::PromotionCapture(_) => {
+                self.board.clear_sq(to);
+                self.board.set_sq(from, Piece {
+                    color: token.turn,
+                    figure: Figure::Pawn,
+                });
+                if let Some(captured) = captured {
+                    self.board.set_sq(to, captured);
+                }
+            }
+            MoveKind::Quiet | MoveKind::DoublePush | MoveKind::Capture => {
+                self.board.unmove_piece(from, to, captured)
+            }
+        }
+        self.restore(token);
+    }
+}
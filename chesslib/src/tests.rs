@@ -1,6 +1,37 @@
 use super::*;
 use Square::*;
-use prelude::{BLACK_PAWN, WHITE_PAWN};
+use analysis::influence_map;
+use analysis::motifs::{Motif, find_motifs};
+use analysis::review::{GameReview, Judgement, review_move, win_percent};
+use clock::{Clock, TimeControl};
+use constants::{DEFAULT_FEN, EPCHECK, KINGS_ONLY, KNPR};
+use database::{GameDatabase, GameIndex};
+use drill::{Repertoire, Schedule};
+use eco::EcoTable;
+use endgames::{Endgame, Goal, defend};
+use engine_match::{MatchScore, OpeningFormat, accepts_draw, armageddon_result, load_openings};
+use errors::{ClaimError, DatabaseError, MoveError, ParsePositionError};
+use explain::IllegalReason;
+use fairy::{FairyFigure, FairyPosition, Movement, Step, Variant};
+use game::Game;
+use geometry::{Geometry, WideBitBoard};
+use movegen::MoveGen;
+use moves::{CheckKind, DrawClaim, GameStatus, Move, Move16, MoveKind};
+use netplay::{NetMessage, peer_move};
+use odds::Odds;
+use pgn::{
+    Arrow, GameResult, MarkColor, PgnCollection, PgnGame, PgnReader, SquareMark, expand_variations,
+    nag_glyph, parse_pgn,
+};
+use prelude::{BLACK_PAWN, BLACK_QUEEN, WHITE_KING, WHITE_KNIGHT, WHITE_PAWN, WHITE_QUEEN};
+use puzzles::parse_lichess_csv;
+use random::{RandomSetup, Rng};
+use ratings::{DEFAULT_K, elo_difference, elo_update, expected_score, performance_rating, score};
+use repertoire::RepertoireBuilder;
+use std::time::Duration;
+use training::{CSV_HEADER, RECORD_SIZE, samples};
+use uci::{OptionKind, Score, SearchResult};
+use variant_state::{VariantPosition, VariantState};
 
 #[test]
 fn test_pawn_moves() {
@@ -41,41 +72,41 @@ fn test_pawn_moves() {
     assert!(res.is_err());
 
     let res = gs.make_move(B2, A3).unwrap();
-    assert_eq!(res, Some(BLACK_PAWN));
+    assert_eq!(res.captured, Some(BLACK_PAWN));
 
     let mut gs = GameState::try_from_fen(WHITE_FEN).unwrap();
     let res = gs.make_move(B2, B3).unwrap();
-    assert_eq!(res, None);
+    assert_eq!(res.captured, None);
 
     let mut gs = GameState::try_from_fen(WHITE_FEN).unwrap();
     let res = gs.make_move(B2, C3).unwrap();
-    assert_eq!(res, Some(BLACK_PAWN));
+    assert_eq!(res.captured, Some(BLACK_PAWN));
 
     let mut gs = GameState::try_from_fen(WHITE_FEN).unwrap();
     let res = gs.make_move(G2, G3).unwrap();
-    assert_eq!(res, None);
+    assert_eq!(res.captured, None);
 
     let mut gs = GameState::try_from_fen(WHITE_FEN).unwrap();
     let res = gs.make_move(G2, G4).unwrap();
-    assert_eq!(res, None);
+    assert_eq!(res.captured, None);
 
     const BLACK_FEN: &str = "4k3/2p5/1P1P4/8/8/8/8/4K3 b - - 0 1";
 
     let mut gs = GameState::try_from_fen(BLACK_FEN).unwrap();
     let res = gs.make_move(C7, C6).unwrap();
-    assert_eq!(res, None);
+    assert_eq!(res.captured, None);
 
     let mut gs = GameState::try_from_fen(BLACK_FEN).unwrap();
     let res = gs.make_move(C7, C5).unwrap();
-    assert_eq!(res, None);
+    assert_eq!(res.captured, None);
 
     let mut gs = GameState::try_from_fen(BLACK_FEN).unwrap();
     let res = gs.make_move(C7, B6).unwrap();
-    assert_eq!(res, Some(WHITE_PAWN));
+    assert_eq!(res.captured, Some(WHITE_PAWN));
 
     let mut gs = GameState::try_from_fen(BLACK_FEN).unwrap();
     let res = gs.make_move(C7, D6).unwrap();
-    assert_eq!(res, Some(WHITE_PAWN));
+    assert_eq!(res.captured, Some(WHITE_PAWN));
 }
 
 #[test]
@@ -88,7 +119,7 @@ fn test_ep_pawn_moves() {
     assert!(res.is_err());
 
     let res = gs.make_move(E5, D6).unwrap();
-    assert_eq!(res, Some(BLACK_PAWN));
+    assert_eq!(res.captured, Some(BLACK_PAWN));
 
     const BLACK_FEN: &str = "4k3/8/8/8/1Pp5/8/8/4K3 b - b3 0 1";
 
@@ -98,5 +129,1782 @@ fn test_ep_pawn_moves() {
     assert!(res.is_err());
 
     let res = gs.make_move(C4, B3).unwrap();
-    assert_eq!(res, Some(WHITE_PAWN));
+    assert_eq!(res.captured, Some(WHITE_PAWN));
+}
+
+#[test]
+fn test_move_outcome() {
+    let mut gs = GameState::try_from_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1").unwrap();
+    let outcome = gs.make_move(A1, A8).unwrap();
+    assert!(outcome.check);
+    assert_eq!(outcome.status, GameStatus::Checkmate);
+
+    let mut gs = GameState::try_from_fen("7k/8/5QK1/8/8/8/8/8 w - - 0 1").unwrap();
+    let outcome = gs.make_move(F6, F7).unwrap();
+    assert!(!outcome.check);
+    assert_eq!(outcome.status, GameStatus::Stalemate);
+
+    let mut gs = GameState::try_from_fen("4k3/8/8/8/8/8/3q4/4K3 w - - 0 1").unwrap();
+    let outcome = gs.make_move(E1, D2).unwrap();
+    assert_eq!(outcome.captured, Some(BLACK_QUEEN));
+    assert!(!outcome.check);
+    assert_eq!(outcome.status, GameStatus::InsufficientMaterial);
+    assert!(outcome.status.is_draw());
+
+    let mut gs = GameState::default();
+    let outcome = gs.make_move(E2, E4).unwrap();
+    assert_eq!(outcome.status, GameStatus::Ongoing);
+}
+
+#[test]
+fn test_explain_illegal() {
+    let gs = GameState::default();
+    assert_eq!(gs.explain_illegal(E2, E4), None);
+    assert_eq!(gs.explain_illegal(E4, E5), Some(IllegalReason::EmptySquare));
+    assert_eq!(gs.explain_illegal(E7, E5), Some(IllegalReason::WrongTurn));
+    assert_eq!(gs.explain_illegal(D1, D2), Some(IllegalReason::OwnPiece));
+    assert_eq!(gs.explain_illegal(D1, D3), Some(IllegalReason::PathBlocked));
+    assert_eq!(
+        gs.explain_illegal(G1, G3),
+        Some(IllegalReason::WrongPattern(Figure::Knight))
+    );
+    assert_eq!(
+        gs.explain_illegal(E2, D3),
+        Some(IllegalReason::PawnNeedsCapture)
+    );
+    assert_eq!(
+        gs.explain_illegal(E2, E5),
+        Some(IllegalReason::WrongPattern(Figure::Pawn))
+    );
+    assert_eq!(
+        gs.explain_illegal(E1, G1),
+        Some(IllegalReason::CastlingBlocked)
+    );
+
+    let gs = GameState::try_from_fen("4k3/4r3/8/8/8/8/4B3/4K2R w K - 0 1").unwrap();
+    assert_eq!(gs.explain_illegal(E2, D3), Some(IllegalReason::Pinned));
+    assert_eq!(gs.explain_illegal(E1, F2), None);
+    let gs = GameState::try_from_fen("4k3/8/8/8/8/8/4P3/r3K2R w K - 0 1").unwrap();
+    assert_eq!(
+        gs.explain_illegal(E1, G1),
+        Some(IllegalReason::CastlingOutOfCheck)
+    );
+    assert_eq!(
+        gs.explain_illegal(E2, E3),
+        Some(IllegalReason::IgnoresCheck)
+    );
+    assert_eq!(
+        gs.explain_illegal(E1, D1),
+        Some(IllegalReason::KingIntoCheck)
+    );
+    assert_eq!(
+        gs.explain_illegal(E1, C1),
+        Some(IllegalReason::CastlingRightsLost)
+    );
+    let gs = GameState::try_from_fen("4k3/8/8/8/8/5r2/4P3/4K2R w K - 0 1").unwrap();
+    assert_eq!(
+        gs.explain_illegal(E1, G1),
+        Some(IllegalReason::CastlingThroughCheck)
+    );
+    let gs = GameState::try_from_fen("4k3/8/8/8/8/4n3/4P3/4K3 w - - 0 1").unwrap();
+    assert_eq!(gs.explain_illegal(E2, E3), Some(IllegalReason::PawnBlocked));
+    assert_eq!(gs.explain_illegal(E2, E4), Some(IllegalReason::PathBlocked));
+    assert_eq!(
+        IllegalReason::WrongPattern(Figure::Bishop).to_string(),
+        "a bishop doesn't move that way"
+    );
+}
+
+#[test]
+fn test_influence_map() {
+    let gs = GameState::default();
+    let map = influence_map(gs.board());
+    let (white, black) = (&map[Color::White as usize], &map[Color::Black as usize]);
+    assert_eq!(white[D3], 2);
+    assert_eq!(white[F3], 3);
+    // the e-pawn's defenders
+    assert_eq!(white[E2], 4);
+    assert_eq!(black[F3], 0);
+    assert_eq!(black[F6], 3);
+    assert_eq!(white[E4], 0);
+
+    // the rook sees through nothing, so the queen behind it doesn't count on e8
+    let gs = GameState::try_from_fen("4k3/8/8/8/8/8/4R3/4QK2 w - - 0 1").unwrap();
+    let map = influence_map(gs.board());
+    assert_eq!(map[Color::White as usize][E8], 1);
+    assert_eq!(map[Color::White as usize][E2], 2);
+    assert_eq!(map[Color::Black as usize][E7], 1);
+}
+
+#[test]
+fn test_endgames() {
+    let mut rng = Rng::new(11);
+    for endgame in Endgame::ALL {
+        for _ in 0..20 {
+            let gs = endgame.position(&mut rng);
+            assert!(!gs.status().is_over());
+            assert!(!gs.board().is_in_check(!gs.get_turn()));
+            assert_eq!(gs.board().count_pieces(BLACK_KING), 1);
+        }
+    }
+    assert_eq!(Endgame::Philidor.goal(), Goal::Draw);
+    assert_eq!(Endgame::Philidor.trainee(), Color::Black);
+    for _ in 0..50 {
+        let gs = Endgame::PawnVsKing.position(&mut rng);
+        let pawn = gs.board().iter_piece(WHITE_PAWN).next().unwrap();
+        let king = gs.board().iter_piece(WHITE_KING).next().unwrap();
+        assert!(!matches!(pawn.col(), Column::A | Column::H));
+        assert_eq!(king.row() as u8, pawn.row() as u8 + 2);
+        assert!((king.col() as i8 - pawn.col() as i8).abs() <= 1);
+    }
+
+    // the defender steps out of a mate in one rather than into it
+    let gs = GameState::try_from_fen("8/8/8/8/8/1K6/7R/1k6 b - - 0 1").unwrap();
+    assert_eq!(defend(&gs).unwrap().to, C1);
+    // a loose queen gets taken
+    let gs = GameState::try_from_fen("8/8/8/8/8/8/1Q6/k3K3 b - - 0 1").unwrap();
+    assert_eq!(defend(&gs).unwrap().to, B2);
+    // nothing to play when stalemated
+    let gs = GameState::try_from_fen("8/8/8/8/8/1Q6/2K5/k7 b - - 0 1").unwrap();
+    assert_eq!(defend(&gs), None);
+}
+
+#[test]
+fn test_random_positions() {
+    let setup = RandomSetup::new(&[Figure::Queen, Figure::Pawn, Figure::Pawn], &[
+        Figure::Rook,
+    ]);
+    let fens: Vec<String> = (0..50)
+        .map(|_| setup.generate(&mut Rng::new(7)).unwrap().to_fen())
+        .collect();
+    // the same seed, the same position
+    assert!(fens.iter().all(|fen| *fen == fens[0]));
+
+    let mut rng = Rng::new(2024);
+    for _ in 0..200 {
+        let gs = setup.generate(&mut rng).unwrap();
+        let turn = gs.get_turn();
+        assert!(!gs.board().is_in_check(!turn));
+        assert!(!gs.board().is_in_check(turn));
+        assert!(!gs.status().is_over());
+        assert_eq!(gs.board().count_pieces(WHITE_PAWN), 2);
+        assert_eq!(gs.board().count_pieces(BLACK_KING), 1);
+        for (square, piece) in gs.iter() {
+            if piece.figure == Figure::Pawn {
+                assert!(!matches!(square.row(), Row::One | Row::Eight));
+            }
+        }
+        // every generated position survives a trip through FEN
+        assert_eq!(GameState::try_from_fen(&gs.to_fen()).unwrap(), gs);
+    }
+
+    let mut checks = RandomSetup::new(&[Figure::Queen, Figure::Queen], &[]);
+    checks.turn = Some(Color::Black);
+    checks.allow_check = true;
+    checks.ongoing = false;
+    let positions: Vec<GameState> = (0..100).filter_map(|_| checks.generate(&mut rng)).collect();
+    assert_eq!(positions.len(), 100);
+    assert!(positions.iter().all(|gs| gs.get_turn() == Color::Black));
+    assert!(
+        positions
+            .iter()
+            .any(|gs| gs.board().is_in_check(Color::Black))
+    );
+
+    let crowded = RandomSetup::new(&[Figure::Pawn; 49], &[]);
+    assert!(crowded.generate(&mut rng).is_none());
+}
+
+#[test]
+fn test_draw_claims() {
+    let mut game = Game::new(GameState::default());
+    let shuffle = [(G1, F3), (G8, F6), (F3, G1), (F6, G8)];
+    for (from, to) in shuffle.iter().cycle().take(8) {
+        assert!(game.claimable_draws().is_empty());
+        game.make_move(*from, *to).unwrap();
+    }
+    assert_eq!(game.repetitions(), 3);
+    assert_eq!(game.claimable_draws(), vec![DrawClaim::Repetition]);
+    assert_eq!(
+        game.claim_draw(DrawClaim::FiftyMoves),
+        Err(ClaimError(DrawClaim::FiftyMoves))
+    );
+    let mut claimed = game.clone();
+    claimed.claim_draw(DrawClaim::Repetition).unwrap();
+    assert!(claimed.is_over());
+    assert!(matches!(
+        claimed.make_move(E2, E4),
+        Err(MoveError::GameOver)
+    ));
+
+    // nobody claims, so play runs on to the automatic draw
+    let mut last = None;
+    for (from, to) in shuffle.iter().cycle().take(8) {
+        last = Some(game.make_move(*from, *to).unwrap());
+    }
+    assert_eq!(last.unwrap().status, GameStatus::FivefoldRepetition);
+    assert_eq!(game.status(), GameStatus::FivefoldRepetition);
+    assert!(game.claimable_draws().is_empty());
+    assert!(matches!(game.make_move(E2, E4), Err(MoveError::GameOver)));
+
+    let mut gs = GameState::try_from_fen("4k3/8/8/8/8/8/4P3/R3K3 w - - 99 80").unwrap();
+    assert!(gs.claimable_draws().is_empty());
+    let mut pushed = gs;
+    pushed.make_move(E2, E4).unwrap();
+    assert!(pushed.claimable_draws().is_empty());
+    gs.make_move(A1, A2).unwrap();
+    assert_eq!(gs.claimable_draws(), vec![DrawClaim::FiftyMoves]);
+    assert_eq!(gs.status(), GameStatus::Ongoing);
+
+    let mut gs = GameState::try_from_fen("4k3/8/8/8/8/8/4P3/R3K3 w - - 149 100").unwrap();
+    let outcome = gs.make_move(A1, A2).unwrap();
+    assert_eq!(outcome.status, GameStatus::SeventyFiveMoves);
+    assert!(outcome.status.is_draw());
+    assert!(gs.claimable_draws().is_empty());
+}
+
+#[test]
+fn test_castle_path() {
+    // b1 is covered but the king never walks over it
+    let mut gs = GameState::try_from_fen("1r2k3/8/8/8/8/8/8/R3K3 w Q - 0 1").unwrap();
+    assert!(
+        gs.legal_moves()
+            .iter()
+            .any(|mv| mv.kind == MoveKind::QueenCastle)
+    );
+    gs.make_move(E1, C1).unwrap();
+    assert_eq!(gs.get_sq(D1), Some(prelude::WHITE_ROOK));
+
+    let mut gs = GameState::try_from_fen("3rk3/8/8/8/8/8/8/R3K3 w Q - 0 1").unwrap();
+    assert!(matches!(gs.make_move(E1, C1), Err(MoveError::KingInCheck)));
+    let mut gs = GameState::try_from_fen("2r1k3/8/8/8/8/8/8/R3K3 w Q - 0 1").unwrap();
+    assert!(matches!(gs.make_move(E1, C1), Err(MoveError::KingInCheck)));
+
+    let mut gs = GameState::try_from_fen("4k3/8/8/8/8/8/8/RN2K3 w Q - 0 1").unwrap();
+    assert!(matches!(gs.make_move(E1, C1), Err(MoveError::IllegalMove)));
+    assert!(
+        !gs.legal_moves()
+            .iter()
+            .any(|mv| mv.kind == MoveKind::QueenCastle)
+    );
+    let mut gs = GameState::try_from_fen("4k3/8/8/8/8/8/8/4K1NR w K - 0 1").unwrap();
+    assert!(matches!(gs.make_move(E1, G1), Err(MoveError::IllegalMove)));
+
+    // a right with no rook behind it is dropped, and castling into the corner refused
+    let mut gs = GameState::try_from_fen("4k3/8/8/8/8/8/8/4K3 w K - 0 1").unwrap();
+    assert_eq!(gs.to_fen(), "4k3/8/8/8/8/8/8/4K3 w - - 0 1");
+    assert!(
+        !gs.legal_moves()
+            .iter()
+            .any(|mv| mv.kind == MoveKind::KingCastle)
+    );
+    assert!(matches!(gs.make_move(E1, G1), Err(MoveError::IllegalMove)));
+}
+
+#[test]
+fn test_promotion_checks_piece() {
+    const FEN: &str = "4k3/1P6/8/8/8/8/4P3/4K3 w - - 0 1";
+    for piece in [WHITE_KING, WHITE_PAWN, BLACK_QUEEN] {
+        let mut gs = GameState::try_from_fen(FEN).unwrap();
+        assert!(matches!(
+            gs.make_promotion(B7, B8, piece),
+            Err(MoveError::IllegalMove)
+        ));
+    }
+    let mut gs = GameState::try_from_fen(FEN).unwrap();
+    assert!(matches!(
+        gs.make_promotion(E2, E3, WHITE_QUEEN),
+        Err(MoveError::IllegalMove)
+    ));
+    assert_eq!(
+        gs.make_promotion(B7, B8, WHITE_KNIGHT).unwrap().captured,
+        None
+    );
+}
+
+// whatever the squares and pieces, the move API answers with an error rather than a panic
+#[test]
+fn test_move_api_is_total() {
+    let pieces: Vec<Piece> = [Color::White, Color::Black]
+        .into_iter()
+        .flat_map(|color| Figure::iter().map(move |&figure| Piece { color, figure }))
+        .collect();
+    // counters at their limit carry on at it
+    let maxed = [
+        "4k3/8/8/8/8/8/8/4K1N1 w - - 65535 1",
+        "4k1n1/8/8/8/8/8/8/4K3 b - - 0 65535",
+    ];
+    for fen in [DEFAULT_FEN, KIWIPETE, PERFT_POS_4, KNPR]
+        .into_iter()
+        .chain(maxed)
+    {
+        let gs = GameState::try_from_fen(fen).unwrap();
+        let squares = || (0..64).filter_map(Square::new);
+        for from in squares() {
+            for to in squares() {
+                let _ = { gs }.make_move(from, to);
+                for &piece in &pieces {
+                    let _ = { gs }.make_promotion(from, to, piece);
+                }
+            }
+        }
+        for mv in gs.legal_moves() {
+            ({ gs }).play(mv);
+        }
+    }
+    let mut gs = GameState::try_from_fen(maxed[0]).unwrap();
+    gs.make_move(Square::G1, Square::F3).unwrap();
+    assert_eq!(gs.get_half_move(), 65535);
+    let mut gs = GameState::try_from_fen(maxed[1]).unwrap();
+    gs.make_move(Square::G8, Square::F6).unwrap();
+    assert_eq!(gs.get_full_move(), 65535);
+}
+
+const KIWIPETE: &str = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+const PERFT_POS_3: &str = "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1";
+const PERFT_POS_4: &str = "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1";
+const PERFT_POS_5: &str = "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8";
+
+#[test]
+fn test_perft_default() {
+    let gs = GameState::default();
+    assert_eq!(gs.perft(0), 1);
+    assert_eq!(gs.perft(1), 20);
+    assert_eq!(gs.perft(2), 400);
+    assert_eq!(gs.perft(3), 8902);
+    assert_eq!(gs.perft(4), 197281);
+}
+
+#[test]
+fn test_perft_kiwipete() {
+    let gs = GameState::try_from_fen(KIWIPETE).unwrap();
+    assert_eq!(gs.perft(1), 48);
+    assert_eq!(gs.perft(2), 2039);
+    assert_eq!(gs.perft(3), 97862);
+}
+
+#[test]
+fn test_perft_positions() {
+    let gs = GameState::try_from_fen(PERFT_POS_3).unwrap();
+    assert_eq!(gs.perft(1), 14);
+    assert_eq!(gs.perft(2), 191);
+    assert_eq!(gs.perft(3), 2812);
+    assert_eq!(gs.perft(4), 43238);
+
+    let gs = GameState::try_from_fen(PERFT_POS_4).unwrap();
+    assert_eq!(gs.perft(1), 6);
+    assert_eq!(gs.perft(2), 264);
+    assert_eq!(gs.perft(3), 9467);
+
+    let gs = GameState::try_from_fen(PERFT_POS_5).unwrap();
+    assert_eq!(gs.perft(1), 44);
+    assert_eq!(gs.perft(2), 1486);
+    assert_eq!(gs.perft(3), 62379);
+}
+
+#[test]
+fn test_perft_divide() {
+    let gs = GameState::default();
+    let divide = gs.perft_divide(3);
+    assert_eq!(divide.len(), 20);
+    assert_eq!(divide.iter().map(|(_, n)| n).sum::<u64>(), 8902);
+    let e2e4 = divide
+        .iter()
+        .find(|(mv, _)| mv.to_string() == "e2e4")
+        .unwrap();
+    assert_eq!(e2e4.1, 600);
+}
+
+#[test]
+fn test_move16_round_trip() {
+    for fen in [DEFAULT_FEN, KIWIPETE, PERFT_POS_4, PERFT_POS_5, EPCHECK] {
+        let gs = GameState::try_from_fen(fen).unwrap();
+        for mv in gs.legal_moves() {
+            let packed = Move16::from(mv);
+            assert_eq!(packed.from_square(), mv.from);
+            assert_eq!(packed.to_square(), mv.to);
+            assert_eq!(Move::try_from(packed).unwrap(), mv);
+            assert_eq!(Move16::from(u16::from(packed)), packed);
+        }
+    }
+    assert!(Move::try_from(Move16::from(6 << 12)).is_err());
+}
+
+#[test]
+fn test_state_is_compact() {
+    assert!(size_of::<GameState>() <= 136);
+    let gs = GameState::try_from_fen(KIWIPETE).unwrap();
+    let mut copy = gs;
+    copy.make_move(E2, A6).unwrap();
+    assert_ne!(copy, gs);
+    assert_eq!(gs, GameState::try_from_fen(KIWIPETE).unwrap());
+}
+
+#[test]
+fn test_move_gen_yields_legal_moves() {
+    let bogus_killer = Move {
+        from: A1,
+        to: H8,
+        kind: MoveKind::Quiet,
+    };
+    for fen in [
+        DEFAULT_FEN,
+        KIWIPETE,
+        PERFT_POS_3,
+        PERFT_POS_4,
+        PERFT_POS_5,
+        EPCHECK,
+    ] {
+        let gs = GameState::try_from_fen(fen).unwrap();
+        let legal = gs.legal_moves();
+        let hash_move = legal.last().copied();
+        let killers = [legal.first().copied(), Some(bogus_killer)];
+        let staged: Vec<Move> = MoveGen::new(&gs, hash_move, killers).collect();
+        assert_eq!(staged.len(), legal.len());
+        assert!(legal.iter().all(|mv| staged.contains(mv)));
+        assert_eq!(staged.first(), hash_move.as_ref());
+    }
+
+    // killers the killer stage passes over, a promotion and a capture, come out later
+    for (fen, killer) in [
+        ("8/4P3/8/8/8/8/k7/4K3 w - - 0 1", "e7e8q"),
+        (KIWIPETE, "e2a6"),
+    ] {
+        let gs = GameState::try_from_fen(fen).unwrap();
+        let killer = gs.parse_uci_move(killer).unwrap();
+        let legal = gs.legal_moves();
+        let staged: Vec<Move> = MoveGen::new(&gs, None, [Some(killer), None]).collect();
+        assert_eq!(staged.len(), legal.len());
+        assert!(legal.iter().all(|mv| staged.contains(mv)));
+    }
+}
+
+#[test]
+fn test_move_gen_order() {
+    let gs = GameState::try_from_fen(KIWIPETE).unwrap();
+    let killer = Move {
+        from: A2,
+        to: A3,
+        kind: MoveKind::Quiet,
+    };
+    let staged: Vec<String> = MoveGen::new(&gs, None, [Some(killer), None])
+        .map(|mv| mv.to_string())
+        .collect();
+    assert_eq!(staged[0], "e2a6");
+    let mut good_captures = staged[1..3].to_vec();
+    good_captures.sort();
+    assert_eq!(good_captures, ["d5e6", "g2h3"]);
+    assert_eq!(staged[3], "a2a3");
+    assert_eq!(staged[staged.len() - 5], "f3f6");
+    assert_eq!(staged[staged.len() - 1], "f3h3");
+}
+
+#[test]
+fn test_gives_check() {
+    let find = |gs: &GameState, uci: &str| {
+        gs.legal_moves()
+            .into_iter()
+            .find(|mv| mv.to_string() == uci)
+            .unwrap()
+    };
+    let gs = GameState::try_from_fen("4k3/8/8/8/4N3/8/8/4R1K1 w - - 0 1").unwrap();
+    assert_eq!(gs.gives_check(find(&gs, "e4c3")), CheckKind::Discovered);
+    assert_eq!(gs.gives_check(find(&gs, "e4d6")), CheckKind::Double);
+    assert_eq!(gs.gives_check(find(&gs, "e1e2")), CheckKind::None);
+
+    let gs = GameState::try_from_fen("4k3/1P6/8/8/8/8/8/R3K3 w - - 0 1").unwrap();
+    assert_eq!(gs.gives_check(find(&gs, "a1a8")), CheckKind::Direct);
+    assert_eq!(gs.gives_check(find(&gs, "b7b8q")), CheckKind::Direct);
+    assert_eq!(gs.gives_check(find(&gs, "b7b8n")), CheckKind::None);
+
+    let gs = GameState::try_from_fen("5k2/8/8/8/8/8/8/4K2R w K - 0 1").unwrap();
+    assert_eq!(gs.gives_check(find(&gs, "e1g1")), CheckKind::Direct);
+
+    let gs = GameState::try_from_fen("7k/8/8/3pP3/8/8/8/B3K3 w - d6 0 1").unwrap();
+    assert_eq!(gs.gives_check(find(&gs, "e5d6")), CheckKind::Discovered);
+}
+
+#[test]
+fn test_gives_check_matches_made_moves() {
+    for fen in [
+        DEFAULT_FEN,
+        KIWIPETE,
+        PERFT_POS_3,
+        PERFT_POS_4,
+        PERFT_POS_5,
+        EPCHECK,
+        KNPR,
+    ] {
+        let gs = GameState::try_from_fen(fen).unwrap();
+        for mv in gs.legal_moves() {
+            let mut child = gs;
+            child.play(mv);
+            let is_check = child.board.is_in_check(child.turn);
+            assert_eq!(
+                gs.gives_check(mv) != CheckKind::None,
+                is_check,
+                "{fen} {mv}"
+            );
+        }
+    }
+}
+
+#[test]
+fn test_find_motifs() {
+    let gs = GameState::try_from_fen("r3k3/2N5/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+    let motifs = find_motifs(&gs, Color::White);
+    assert!(motifs.contains(&Motif::Fork {
+        knight: C7,
+        targets: vec![A8, E8],
+    }));
+    assert!(motifs.contains(&Motif::Hanging { square: A8 }));
+
+    let gs = GameState::try_from_fen("4k3/4n3/8/8/8/8/8/4R1K1 w - - 0 1").unwrap();
+    assert!(find_motifs(&gs, Color::White).contains(&Motif::Pin {
+        pinner: E1,
+        pinned: E7,
+        target: E8,
+        absolute: true,
+    }));
+
+    let gs = GameState::try_from_fen("3q3k/8/8/3n4/8/8/8/3R2K1 w - - 0 1").unwrap();
+    assert!(find_motifs(&gs, Color::White).contains(&Motif::Pin {
+        pinner: D1,
+        pinned: D5,
+        target: D8,
+        absolute: false,
+    }));
+
+    let gs = GameState::try_from_fen("3q4/8/8/3k4/8/8/8/3R2K1 b - - 0 1").unwrap();
+    assert!(find_motifs(&gs, Color::White).contains(&Motif::Skewer {
+        attacker: D1,
+        front: D5,
+        behind: D8,
+    }));
+
+    let gs = GameState::try_from_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1").unwrap();
+    assert!(find_motifs(&gs, Color::White).contains(&Motif::BackRank {
+        king: G8,
+        entries: vec![A8],
+    }));
+    // with luft there's nothing to it
+    let gs = GameState::try_from_fen("6k1/5pp1/7p/8/8/8/8/R5K1 w - - 0 1").unwrap();
+    assert!(find_motifs(&gs, Color::White).is_empty());
+
+    let gs = GameState::try_from_fen("6rk/6pp/7N/8/8/8/8/6K1 w - - 0 1").unwrap();
+    assert!(find_motifs(&gs, Color::White).contains(&Motif::Smothered {
+        king: H8,
+        knight: H6,
+        square: F7,
+    }));
+    assert_eq!(
+        Motif::Smothered {
+            king: H8,
+            knight: H6,
+            square: F7,
+        }
+        .theme(),
+        "smotheredMate"
+    );
+
+    let gs = GameState::default();
+    assert!(find_motifs(&gs, Color::White).is_empty());
+    assert!(find_motifs(&gs, Color::Black).is_empty());
+}
+
+#[test]
+fn test_san_round_trip() {
+    for fen in [
+        DEFAULT_FEN,
+        KIWIPETE,
+        PERFT_POS_3,
+        PERFT_POS_4,
+        PERFT_POS_5,
+        KNPR,
+    ] {
+        let gs = GameState::try_from_fen(fen).unwrap();
+        for mv in gs.legal_moves() {
+            let san = gs.to_san(mv);
+            assert_eq!(gs.parse_san(&san).unwrap(), mv, "{fen} {san}");
+        }
+    }
+    let gs = GameState::try_from_fen(KIWIPETE).unwrap();
+    assert_eq!(gs.to_san(gs.parse_san("O-O-O").unwrap()), "O-O-O");
+    assert_eq!(gs.to_san(gs.parse_san("Qxf6").unwrap()), "Qxf6");
+    assert!(gs.parse_san("Nf3").is_err());
+    assert!(gs.parse_san("e9").is_err());
+    let gs = GameState::try_from_fen("4k3/8/8/8/8/8/8/R4RK1 w - - 0 1").unwrap();
+    assert_eq!(gs.to_san(gs.parse_san("Rad1").unwrap()), "Rad1");
+    assert!(gs.parse_san("Rd1").is_err());
+
+    let pv = ["e2e4", "e7e5", "g1f3", "e1e8", "b8c6"].map(str::to_owned);
+    assert_eq!(GameState::default().pv_to_san(&pv), ["e4", "e5", "Nf3"]);
+    let end = GameState::default().pv_end(&pv);
+    assert_eq!(
+        end.to_fen(),
+        "rnbqkbnr/pppp1ppp/8/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 1 2"
+    );
+}
+
+#[test]
+fn test_describe_move() {
+    let describe = |fen: &str, san: &str| {
+        let gs = GameState::try_from_fen(fen).unwrap();
+        gs.describe_move(gs.parse_san(san).unwrap())
+    };
+    assert_eq!(describe(DEFAULT_FEN, "Nf3"), "Knight from g1 to f3");
+    assert_eq!(describe(KIWIPETE, "O-O-O"), "Castles queenside");
+    assert_eq!(
+        describe(KIWIPETE, "Qxf6"),
+        "Queen from f3 takes knight on f6"
+    );
+    assert_eq!(
+        describe("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1", "exd6"),
+        "Pawn from e5 takes pawn on d6 en passant"
+    );
+    assert_eq!(
+        describe("4k3/1P6/8/8/8/8/8/4K3 w - - 0 1", "b8=Q+"),
+        "Pawn from b7 to b8, promotes to queen, check"
+    );
+    assert_eq!(
+        describe("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1", "Ra8#"),
+        "Rook from a1 to a8, checkmate"
+    );
+}
+
+#[test]
+fn test_zobrist_transpositions() {
+    let play = |sans: &[&str]| {
+        let mut gs = GameState::default();
+        for san in sans {
+            let mv = gs.parse_san(san).unwrap();
+            gs.play(mv);
+        }
+        gs
+    };
+    let a = play(&["e4", "e5", "Nf3", "Nc6"]);
+    let b = play(&["Nf3", "Nc6", "e4", "e5"]);
+    assert_eq!(a.zobrist(), b.zobrist());
+    assert_ne!(a.zobrist(), play(&["e4", "e5", "Nf3"]).zobrist());
+    // an ep square nobody can capture on doesn't change the hash
+    let mut gs = play(&["e4"]);
+    gs.ep_square = None;
+    assert_eq!(play(&["e4"]).zobrist(), gs.zobrist());
+    let mut gs = play(&["e4", "a6", "e5", "d5"]);
+    gs.ep_square = None;
+    assert_ne!(play(&["e4", "a6", "e5", "d5"]).zobrist(), gs.zobrist());
+}
+
+#[test]
+fn test_parse_pgn() {
+    let text = r#"[Event "Casual"]
+[White "A"]
+[Black "B"]
+[Result "1-0"]
+
+1. e4 {best by test} e5 2. Nf3 (2. f4 exf4) Nc6 $1 3. Bb5 a6 ; Ruy Lopez
+4. Ba4 1-0
+
+[Event "Second"]
+1. d4 d5 2... *
+"#;
+    let games = parse_pgn(text).unwrap();
+    assert_eq!(games.len(), 2);
+    assert_eq!(games[0].tag("White"), Some("A"));
+    assert_eq!(games[0].moves, [
+        "e4", "e5", "Nf3", "Nc6", "Bb5", "a6", "Ba4"
+    ]);
+    assert_eq!(games[0].result, Some(GameResult::WhiteWins));
+    assert_eq!(games[0].replay().unwrap().len(), 7);
+    assert_eq!(games[1].moves, ["d4", "d5"]);
+    assert_eq!(games[1].result, None);
+    assert!(parse_pgn("1. e4 {unterminated").is_err());
+    assert!(parse_pgn("1. e4 e4 *").unwrap()[0].replay().is_err());
+
+    let lines = expand_variations(text).unwrap();
+    assert_eq!(lines.len(), 3);
+    assert_eq!(lines[1].moves, ["e4", "e5", "f4", "exf4"]);
+    assert_eq!(lines[1].tag("White"), Some("A"));
+    assert_eq!(lines[2].moves, ["d4", "d5"]);
+    let lines = expand_variations("1. e4 (1. d4 d5 (1... Nf6 2. c4)) e5 *").unwrap();
+    let moves: Vec<_> = lines.iter().map(|line| line.moves.join(" ")).collect();
+    assert_eq!(moves, ["e4 e5", "d4 Nf6 c4", "d4 d5"]);
+    assert!(expand_variations("1. e4 (1. d4 *").is_err());
+}
+
+#[test]
+fn test_repertoire_tree() {
+    let text = "1. e4 e5 2. Nf3 Nc6 1-0\n1. Nf3 Nc6 2. e4 e5 3. Bc4 1/2-1/2\n1. e4 c5 0-1\n";
+    let mut builder = RepertoireBuilder::new().max_ply(4);
+    assert_eq!(builder.add_pgn(text).unwrap(), 3);
+    let tree = builder.build();
+
+    let root = tree.get(&GameState::default()).unwrap();
+    assert_eq!(root.stats.games, 3);
+    assert_eq!(root.moves[0].mv.to_string(), "e2e4");
+    assert_eq!(root.moves[0].stats.games, 2);
+    assert_eq!(root.moves[0].stats.white_wins, 1);
+    assert_eq!(root.moves[0].stats.black_wins, 1);
+
+    let mut gs = GameState::default();
+    for san in ["e4", "e5", "Nf3"] {
+        gs.play(gs.parse_san(san).unwrap());
+    }
+    let node = tree.get(&gs).unwrap();
+    assert_eq!(node.stats.games, 1);
+    gs.play(gs.parse_san("Nc6").unwrap());
+    // reached by both move orders, but the fifth ply is past max_ply
+    assert!(tree.get(&gs).is_none());
+    assert!(tree.get_hash(gs.zobrist()).is_none());
+}
+
+#[test]
+fn test_game_database() {
+    let text = "[Event \"One\"]\n1. e4 e5 2. Nf3 Nc6 1-0\n\n[Event \"Two\"]\n1. Nf3 Nc6 2. e4 e5 3. Bb5 0-1\n\n[Event \"Three\"]\n1. d4 d5 *\n";
+    let dir = std::env::temp_dir().join(format!("chesslib-db-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let pgn_path = dir.join("games.pgn");
+    std::fs::write(&pgn_path, text).unwrap();
+
+    let db = GameDatabase::open(&pgn_path).unwrap();
+    assert_eq!(db.game_count(), 3);
+    let index_path = GameDatabase::index_path(&pgn_path);
+    assert!(index_path.exists());
+    let (loaded, built) = (
+        GameIndex::load(&index_path).unwrap(),
+        GameIndex::build(text).unwrap(),
+    );
+    assert_eq!(loaded.game_count(), built.game_count());
+    assert_eq!(
+        loaded.explore(&GameState::default()),
+        built.explore(&GameState::default())
+    );
+    // an index whose first game starts past the end of the source is turned down
+    let mut bytes = std::fs::read(&index_path).unwrap();
+    bytes[32..40].copy_from_slice(&u64::MAX.to_le_bytes());
+    let bad_path = dir.join("bad.idx");
+    std::fs::write(&bad_path, &bytes).unwrap();
+    assert!(matches!(
+        GameIndex::load(&bad_path),
+        Err(DatabaseError::InvalidIndex)
+    ));
+    // as is one claiming more games than it has bytes for
+    bytes[24..32].copy_from_slice(&u64::MAX.to_le_bytes());
+    std::fs::write(&bad_path, &bytes).unwrap();
+    assert!(matches!(
+        GameIndex::load(&bad_path),
+        Err(DatabaseError::InvalidIndex)
+    ));
+
+    let mut gs = GameState::default();
+    for san in ["e4", "e5", "Nf3", "Nc6"] {
+        gs.play(gs.parse_san(san).unwrap());
+    }
+    let matches = GameDatabase::open(&pgn_path).unwrap().search(&gs).unwrap();
+    assert_eq!(matches.len(), 2);
+    assert_eq!(matches[0].game.tag("Event"), Some("One"));
+    assert_eq!(matches[0].next_move, None);
+    assert_eq!(matches[1].game.tag("Event"), Some("Two"));
+    assert_eq!(matches[1].ply, 4);
+    assert_eq!(matches[1].next_move.unwrap().to_string(), "f1b5");
+
+    let matches = db.search(&GameState::default()).unwrap();
+    assert_eq!(matches.len(), 3);
+    assert_eq!(matches[2].next_move.unwrap().to_string(), "d2d4");
+
+    let explored = db.explore(&GameState::default());
+    let moves: Vec<String> = explored.iter().map(|c| c.mv.to_string()).collect();
+    assert_eq!(moves, ["e2e4", "g1f3", "d2d4"]);
+    assert_eq!((explored[0].games, explored[0].white_wins), (1, 1));
+    assert_eq!((explored[1].black_wins, explored[2].draws), (1, 0));
+    let explored = db.explore(&gs);
+    assert_eq!(explored.len(), 1);
+    assert_eq!(explored[0].mv.to_string(), "f1b5");
+    assert_eq!(explored[0].black_wins, 1);
+
+    // an edit that keeps the file's length still gets the index rebuilt
+    std::fs::write(&pgn_path, text.replace("1. d4 d5", "1. c4 c5")).unwrap();
+    std::fs::File::options()
+        .write(true)
+        .open(&pgn_path)
+        .unwrap()
+        .set_modified(std::time::SystemTime::now() + Duration::from_secs(10))
+        .unwrap();
+    let explored = GameDatabase::open(&pgn_path)
+        .unwrap()
+        .explore(&GameState::default());
+    assert_eq!(explored[2].mv.to_string(), "c2c4");
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_ratings() {
+    assert_eq!(expected_score(1500.0, 1500.0), 0.5);
+    assert!((expected_score(1600.0, 1200.0) - 0.909).abs() < 1e-3);
+    assert_eq!(elo_update(1500.0, 1500.0, 1.0, 32.0), 1516.0);
+    assert_eq!(elo_update(1500.0, 1500.0, 0.5, DEFAULT_K), 1500.0);
+    assert_eq!(elo_difference(0.5), Some(0.0));
+    assert!((elo_difference(0.75).unwrap() - 190.85).abs() < 1e-2);
+    assert_eq!(elo_difference(1.0), None);
+    assert_eq!(score(GameResult::BlackWins, Color::Black), 1.0);
+    assert_eq!(score(GameResult::Draw, Color::White), 0.5);
+
+    assert_eq!(performance_rating(&[]), None);
+    let even = performance_rating(&[(1400.0, 1.0), (1600.0, 0.0)]).unwrap();
+    assert!((even - 1500.0).abs() < 1e-6);
+    let perfect = performance_rating(&[(1500.0, 1.0), (1700.0, 1.0)]).unwrap();
+    assert!((perfect - 2500.0).abs() < 1e-6);
+}
+
+#[test]
+fn test_time_control() {
+    let tc: TimeControl = "40/60+0.5".parse().unwrap();
+    assert_eq!(tc.moves_per_period, Some(40));
+    assert_eq!(tc.base, Duration::from_secs(60));
+    assert_eq!(tc.increment, Duration::from_millis(500));
+    assert_eq!(tc.to_string(), "40/60+0.5");
+    assert_eq!(
+        "300".parse::<TimeControl>().unwrap().increment,
+        Duration::ZERO
+    );
+    assert!("40/abc".parse::<TimeControl>().is_err());
+    assert!("0/60".parse::<TimeControl>().is_err());
+    assert!("-5+1".parse::<TimeControl>().is_err());
+    // more seconds than a Duration holds
+    assert!("1e30".parse::<TimeControl>().is_err());
+    assert!("60+1e25".parse::<TimeControl>().is_err());
+
+    let mut clock = Clock::new("2/10+1".parse().unwrap());
+    assert_eq!(clock.moves_to_go(Color::White), Some(2));
+    clock.start(Color::White);
+    assert!(clock.stop());
+    assert!(clock.remaining(Color::White) > Duration::from_secs(10));
+    assert_eq!(clock.remaining(Color::Black), Duration::from_secs(10));
+    assert_eq!(clock.moves_to_go(Color::White), Some(1));
+    clock.start(Color::White);
+    assert!(clock.stop());
+    // a new period starts after the second move
+    assert!(clock.remaining(Color::White) > Duration::from_secs(21));
+
+    let clock = Clock::restore(
+        ["2/10+1".parse().unwrap(); 2],
+        [Duration::from_secs(7), Duration::from_secs(4)],
+        [1, 3],
+    );
+    assert_eq!(clock.remaining(Color::Black), Duration::from_secs(4));
+    assert_eq!(clock.moves_to_go(Color::White), Some(1));
+    assert_eq!(clock.moves_to_go(Color::Black), Some(1));
+
+    let mut clock = Clock::new("0".parse().unwrap());
+    clock.start(Color::Black);
+    std::thread::sleep(Duration::from_millis(1));
+    assert!(!clock.stop());
+
+    // Armageddon: less time for Black, who wins by drawing
+    let white: TimeControl = "300+2".parse().unwrap();
+    let black = white.armageddon_black();
+    assert_eq!(black.to_string(), "240+2");
+    let clock = Clock::with_time_controls([white, black]);
+    assert_eq!(clock.remaining(Color::White), Duration::from_secs(300));
+    assert_eq!(clock.remaining(Color::Black), Duration::from_secs(240));
+    assert_eq!(clock.time_control(), white);
+    // each side's time control survives being written to tags and read back
+    let [white_tag, black_tag] = clock.time_controls().map(|tc| tc.to_string());
+    let restored = Clock::restore(
+        [white_tag.parse().unwrap(), black_tag.parse().unwrap()],
+        [Duration::from_secs(120), Duration::from_secs(90)],
+        [10, 10],
+    );
+    assert_eq!(restored.time_controls(), [white, black]);
+    assert_eq!(restored.remaining(Color::Black), Duration::from_secs(90));
+    assert_eq!(armageddon_result(GameResult::Draw), GameResult::BlackWins);
+    assert_eq!(
+        armageddon_result(GameResult::WhiteWins),
+        GameResult::WhiteWins
+    );
+}
+
+#[test]
+fn test_pgn_round_trip() {
+    let game = PgnGame {
+        tags: vec![
+            ("Event".to_owned(), "Test \"quoted\"".to_owned()),
+            ("FEN".to_owned(), KIWIPETE.to_owned()),
+        ],
+        moves: ["Qxf6", "gxf6", "O-O-O", "Ke7"].map(str::to_owned).to_vec(),
+        annotations: Vec::new(),
+        result: Some(GameResult::Draw),
+    };
+    let text = game.to_string();
+    assert!(text.contains("1. Qxf6 gxf6 2. O-O-O Ke7 1/2-1/2"));
+    assert_eq!(parse_pgn(&text).unwrap(), vec![game]);
+
+    let black_first = PgnGame {
+        tags: vec![("FEN".to_owned(), PERFT_POS_3.replace(" w ", " b "))],
+        moves: vec!["Kb4".to_owned()],
+        annotations: Vec::new(),
+        result: None,
+    };
+    assert!(black_first.to_string().ends_with("1... Kb4 *\n"));
+
+    let text = "1. e4! {king's pawn} e5 2. Nf3 (2. f4?! exf4 $10 (2... d5)) Nc6 *";
+    let game = &parse_pgn(text).unwrap()[0];
+    assert_eq!(game.moves, ["e4", "e5", "Nf3", "Nc6"]);
+    assert_eq!(game.annotation(0).unwrap().nags, [1]);
+    assert_eq!(
+        game.annotation(0).unwrap().comment.as_deref(),
+        Some("king's pawn")
+    );
+    let variation = &game.annotation(2).unwrap().variations[0];
+    assert_eq!(variation.moves, ["f4", "exf4"]);
+    assert_eq!(variation.annotations[0].nags, [6]);
+    assert_eq!(variation.annotations[1].nags, [10]);
+    assert_eq!(variation.annotations[1].variations[0].moves, ["d5"]);
+    let written = game.to_string();
+    assert!(
+        written.contains(
+            "1. e4! {king's pawn} 1... e5 2. Nf3 (2. f4?! exf4 $10 (2... d5)) 2... Nc6 *"
+        )
+    );
+    assert_eq!(&parse_pgn(&written).unwrap()[0], game);
+    assert_eq!(nag_glyph(3), "!!");
+    assert_eq!(nag_glyph(14), "$14");
+}
+
+#[test]
+fn test_pgn_clock_annotations() {
+    // as lichess exports them
+    let text = "1. e4 { [%clk 0:03:00] } 1... e5 { [%clk 0:02:58.5] [%emt 0:00:04] } \
+        2. Nf3 { [%eval 0.2] [%clk 1:00:01] good } *";
+    let game = &parse_pgn(text).unwrap()[0];
+    let first = game.annotation(0).unwrap();
+    assert_eq!(first.clock, Some(Duration::from_secs(180)));
+    assert_eq!(first.elapsed, None);
+    assert_eq!(first.comment, None);
+    let second = game.annotation(1).unwrap();
+    assert_eq!(second.clock, Some(Duration::from_millis(178_500)));
+    assert_eq!(second.elapsed, Some(Duration::from_secs(4)));
+    let third = game.annotation(2).unwrap();
+    assert_eq!(third.clock, Some(Duration::from_secs(3601)));
+    assert_eq!(third.comment.as_deref(), Some("[%eval 0.2] good"));
+
+    let written = game.to_string();
+    assert!(written.contains("1. e4 {[%clk 0:03:00]} 1... e5 {[%clk 0:02:58.5] [%emt 0:00:04]}"));
+    assert_eq!(&parse_pgn(&written).unwrap()[0], game);
+
+    let game = &parse_pgn("1. e4 {[%clk soon]} *").unwrap()[0];
+    assert_eq!(game.annotation(0).unwrap().clock, None);
+    assert_eq!(
+        game.annotation(0).unwrap().comment.as_deref(),
+        Some("[%clk soon]")
+    );
+
+    let mut clock = Clock::new("60+1".parse().unwrap());
+    assert_eq!(clock.elapsed(), None);
+    clock.start(Color::White);
+    assert!(clock.elapsed().is_some());
+}
+
+#[test]
+fn test_pgn_collection() {
+    let text = "[White \"Carlsen\"]\n[Black \"Nakamura\"]\n\n1. e4 e5 1-0\n\n\
+        [White \"Nakamura\"]\n[Black \"Carlsen\"]\n\n1. d4 d5 0-1\n\n\
+        [White \"Carlsen\"]\n[Black \"Caruana\"]\n\n1. c4 *\n";
+    let mut games: PgnCollection = text.parse().unwrap();
+    assert_eq!(games.len(), 3);
+    assert_eq!(games.get(1).unwrap().moves, ["d4", "d5"]);
+    let carlsen_white: Vec<usize> = games
+        .with_tag("White", "Carlsen")
+        .map(|(idx, _)| idx)
+        .collect();
+    assert_eq!(carlsen_white, [0, 2]);
+    assert_eq!(games.with_tag("Event", "Olympiad").count(), 0);
+
+    games.push(PgnGame {
+        moves: vec!["Nf3".to_owned()],
+        ..PgnGame::default()
+    });
+    let written = games.to_string();
+    let reread: PgnCollection = written.parse().unwrap();
+    assert_eq!(reread, games);
+    assert_eq!(reread.iter().last().unwrap().moves, ["Nf3"]);
+    let collected: PgnCollection = reread.into_iter().take(2).collect();
+    assert_eq!(collected.len(), 2);
+    assert!(PgnCollection::default().is_empty());
+}
+
+#[test]
+fn test_pgn_reader() {
+    let text = "[White \"Carlsen\"]\n\n1. e4 {a comment\n[%clk 0:03:00] going on} e5 1-0\n\n\
+        [White \"Nakamura\"]\n\n1. d4 [bad tag\n\n\
+        [White \"Caruana\"]\n\n1. c4 *\n1. Nf3 *\n";
+    let games: Vec<_> = PgnReader::new(text.as_bytes()).collect();
+    assert_eq!(games.len(), 4);
+    let first = games[0].as_ref().unwrap();
+    assert_eq!(first.moves, ["e4", "e5"]);
+    assert_eq!(
+        first,
+        &parse_pgn(text.split("\n\n[White \"Nak").next().unwrap()).unwrap()[0]
+    );
+    assert!(games[1].is_err());
+    assert_eq!(games[2].as_ref().unwrap().tag("White"), Some("Caruana"));
+    assert_eq!(games[3].as_ref().unwrap().moves, ["Nf3"]);
+    assert_eq!(PgnReader::new(&b"\n\n"[..]).count(), 0);
+}
+
+#[test]
+fn test_training_samples() {
+    let game = &parse_pgn("1. e4 d5 2. exd5 0-1").unwrap()[0];
+    let mut samples = samples(game).unwrap();
+    assert_eq!(samples.len(), 3);
+    assert_eq!(CSV_HEADER.split(',').count(), 4);
+    assert_eq!(samples[0].to_csv(), format!("{DEFAULT_FEN},e2e4,0,"));
+    samples[2].eval = Some(Score::Mate(-3));
+    assert!(samples[2].to_csv().ends_with(",e4d5,0,#-3"));
+
+    let record = samples[1].to_record();
+    assert_eq!(record.len(), RECORD_SIZE);
+    // White's pawns, with e2 gone to e4
+    let pawns = u64::from_le_bytes(record[0..8].try_into().unwrap());
+    assert_eq!(pawns, 0x1000_ef00);
+    // Black's king on e8
+    let king = u64::from_le_bytes(record[88..96].try_into().unwrap());
+    assert_eq!(king, 1 << E8 as u64);
+    assert_eq!(record[96], 0b11111);
+    assert_eq!(record[97], E3 as u8);
+    let mv = Move16::from(u16::from_le_bytes([record[98], record[99]]));
+    assert_eq!((mv.from_square(), mv.to_square()), (D7, D5));
+    assert_eq!(record[100] as i8, -1);
+    assert_eq!(i16::from_le_bytes([record[101], record[102]]), i16::MIN);
+    samples[2].eval = Some(Score::Centipawns(-45));
+    let record = samples[2].to_record();
+    assert_eq!(i16::from_le_bytes([record[101], record[102]]), -45);
+}
+
+#[test]
+fn test_pgn_drawing_annotations() {
+    let text = "1. e4 { [%csl Gd4,Re5][%cal Gg1f3, Bf1c4] center } 1... e5 {[%cal Xe2e4]} *";
+    let game = &parse_pgn(text).unwrap()[0];
+    let first = game.annotation(0).unwrap();
+    assert_eq!(first.marks, [
+        SquareMark {
+            color: MarkColor::Green,
+            square: D4
+        },
+        SquareMark {
+            color: MarkColor::Red,
+            square: E5
+        },
+    ]);
+    assert_eq!(first.arrows, [
+        Arrow {
+            color: MarkColor::Green,
+            from: G1,
+            to: F3
+        },
+        Arrow {
+            color: MarkColor::Blue,
+            from: F1,
+            to: C4
+        },
+    ]);
+    assert_eq!(first.comment.as_deref(), Some("center"));
+    // an unknown color keeps the whole command as text
+    let second = game.annotation(1).unwrap();
+    assert!(second.arrows.is_empty());
+    assert_eq!(second.comment.as_deref(), Some("[%cal Xe2e4]"));
+
+    let written = game.to_string();
+    assert!(written.contains("{[%csl Gd4,Re5] [%cal Gg1f3,Bf1c4] center}"));
+    assert_eq!(&parse_pgn(&written).unwrap()[0], game);
+}
+
+#[test]
+fn test_engine_match_helpers() {
+    let openings = load_openings(
+        "# comment\n\n4k3/8/8/8/8/8/8/4K2R w K - bm O-O;\n",
+        OpeningFormat::Epd,
+    )
+    .unwrap();
+    assert_eq!(openings.len(), 1);
+    assert_eq!(
+        openings[0].fen.as_deref(),
+        Some("4k3/8/8/8/8/8/8/4K2R w K - 0 1")
+    );
+    let openings = load_openings("1. e4 e5 *\n1. d4 *", OpeningFormat::Pgn).unwrap();
+    assert_eq!(openings.len(), 2);
+    assert_eq!(openings[0].moves.len(), 2);
+    assert!(load_openings("not a fen", OpeningFormat::Epd).is_err());
+
+    let mut score = MatchScore::default();
+    score.record(GameResult::WhiteWins, Color::White);
+    score.record(GameResult::WhiteWins, Color::Black);
+    score.record(GameResult::Draw, Color::Black);
+    score.record(GameResult::BlackWins, Color::Black);
+    assert_eq!((score.wins, score.losses, score.draws), (2, 1, 1));
+    assert_eq!(score.score_fraction(), Some(0.625));
+    assert!(score.elo_difference().unwrap() > 0.0);
+
+    assert!(accepts_draw(Some(Score::Centipawns(-40))));
+    assert!(accepts_draw(Some(Score::Centipawns(10))));
+    assert!(!accepts_draw(Some(Score::Centipawns(150))));
+    assert!(accepts_draw(Some(Score::Mate(-3))));
+    assert!(!accepts_draw(Some(Score::Mate(2))));
+    assert!(!accepts_draw(None));
+
+    assert_eq!(
+        uci::option_name("option name UCI_Elo type spin default 1350 min 1350 max 2850"),
+        Some("UCI_Elo")
+    );
+    assert_eq!(
+        uci::option_name("option name Clear Hash type button"),
+        Some("Clear Hash")
+    );
+    assert_eq!(uci::option_name("id name Stockfish"), None);
+
+    let option = |line: &str| uci::parse_option(line).map(|option| option.kind);
+    assert_eq!(
+        option("option name Hash type spin default 16 min 1 max 33554432"),
+        Some(OptionKind::Spin {
+            default: 16,
+            min: 1,
+            max: 33554432
+        })
+    );
+    assert_eq!(
+        option("option name Ponder type check default false"),
+        Some(OptionKind::Check { default: false })
+    );
+    assert_eq!(
+        option("option name Style type combo default Normal var Solid var Normal var Risky Play"),
+        Some(OptionKind::Combo {
+            default: "Normal".to_owned(),
+            vars: ["Solid", "Normal", "Risky Play"]
+                .map(str::to_owned)
+                .to_vec()
+        })
+    );
+    assert_eq!(
+        option("option name SyzygyPath type string default <empty>"),
+        Some(OptionKind::String {
+            default: String::new()
+        })
+    );
+    assert_eq!(
+        option("option name Clear Hash type button"),
+        Some(OptionKind::Button)
+    );
+    assert_eq!(option("option name Skill type spin default 20"), None);
+
+    let mut result = SearchResult {
+        best_move: String::new(),
+        score: None,
+        depth: None,
+        pv: Vec::new(),
+        multipv: None,
+    };
+    let info = "depth 18 seldepth 24 multipv 2 score cp -31 nodes 120000 pv e7e5 g1f3";
+    uci::parse_info(info.split_whitespace(), &mut result);
+    assert_eq!(result.depth, Some(18));
+    assert_eq!(result.multipv, Some(2));
+    assert_eq!(result.score, Some(Score::Centipawns(-31)));
+    assert_eq!(result.pv, ["e7e5", "g1f3"]);
+
+    for (fen, insufficient) in [
+        (KINGS_ONLY, true),
+        ("4k3/8/8/8/8/8/8/2B1K3 w - - 0 1", true),
+        ("4kb2/8/8/8/8/8/8/2B1K3 w - - 0 1", true),
+        ("2b1k3/8/8/8/8/8/8/2B1K3 w - - 0 1", false),
+        ("4k3/8/8/8/8/8/8/1NN1K3 w - - 0 1", false),
+        ("4k3/8/8/8/8/8/4P3/4K3 w - - 0 1", false),
+    ] {
+        let gs = GameState::try_from_fen(fen).unwrap();
+        assert_eq!(gs.is_insufficient_material(), insufficient, "{fen}");
+    }
+}
+
+#[test]
+fn test_to_fen() {
+    for fen in [
+        DEFAULT_FEN,
+        KIWIPETE,
+        PERFT_POS_3,
+        PERFT_POS_4,
+        PERFT_POS_5,
+        KNPR,
+        EPCHECK,
+    ] {
+        assert_eq!(GameState::try_from_fen(fen).unwrap().to_fen(), fen);
+    }
+}
+
+#[test]
+fn test_to_string_pretty() {
+    let gs = GameState::try_from_fen("4k3/8/8/8/8/8/P7/4K3 w - - 0 1").unwrap();
+    let pretty = gs.to_string_pretty(DisplayOptions::default());
+    let lines: Vec<&str> = pretty.lines().collect();
+    assert_eq!(lines[0], "8 . . . . k . . .");
+    assert_eq!(lines[6], "2 P . . . . . . .");
+    assert_eq!(lines[8], "  a b c d e f g h");
+    assert_eq!(lines[9], "FEN: 4k3/8/8/8/8/8/P7/4K3 w - - 0 1");
+
+    let flipped = gs.to_string_pretty(DisplayOptions {
+        fen: false,
+        coordinates: true,
+        flipped: true,
+    });
+    let lines: Vec<&str> = flipped.lines().collect();
+    assert_eq!(lines[0], "1 . . . K . . . .");
+    assert_eq!(lines[1], "2 . . . . . . . P");
+    assert_eq!(lines[8], "  h g f e d c b a");
+    assert_eq!(lines.len(), 9);
+
+    let bare = gs.to_string_pretty(DisplayOptions {
+        fen: false,
+        coordinates: false,
+        flipped: false,
+    });
+    assert_eq!(bare.lines().next(), Some(". . . . k . . ."));
+}
+
+#[test]
+fn test_from_position_command() {
+    let gs = GameState::from_position_command("position startpos moves e2e4 e7e5 g1f3").unwrap();
+    assert_eq!(
+        gs.to_fen(),
+        "rnbqkbnr/pppp1ppp/8/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 1 2"
+    );
+    assert_eq!(
+        GameState::from_position_command("startpos").unwrap(),
+        GameState::default()
+    );
+
+    let command = format!("position fen {KIWIPETE} moves e1c1 h3g2");
+    let gs = GameState::from_position_command(&command).unwrap();
+    assert_eq!(gs.get_sq(C1), Some(WHITE_KING));
+    assert_eq!(gs.get_turn(), Color::White);
+    let gs = GameState::from_position_command(&format!("fen {KIWIPETE}")).unwrap();
+    assert_eq!(gs.to_fen(), KIWIPETE);
+
+    assert!(matches!(
+        GameState::from_position_command("position"),
+        Err(ParsePositionError::MissingPosition)
+    ));
+    assert!(matches!(
+        GameState::from_position_command("position startpos e2e4"),
+        Err(ParsePositionError::InvalidToken(_))
+    ));
+    assert!(matches!(
+        GameState::from_position_command("position startpos moves e2e5"),
+        Err(ParsePositionError::IllegalMove(_))
+    ));
+    assert!(matches!(
+        GameState::from_position_command("position fen 8/8 w - - 0 1"),
+        Err(ParsePositionError::ParseFenError(_))
+    ));
+}
+
+#[test]
+fn test_fen_error_context() {
+    use errors::{FenField, InvalidCharError, ParseFenError};
+    use std::error::Error;
+
+    let err = |fen: &str| GameState::try_from_fen(fen).unwrap_err();
+    assert_eq!(
+        err("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP"),
+        ParseFenError::WrongRowCount(7)
+    );
+    let invalid_piece = err("rnbqkbnr/ppppxppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+    assert_eq!(invalid_piece, ParseFenError::InvalidPiece {
+        rank: 7,
+        index: 5,
+        source: InvalidCharError('x'),
+    });
+    assert_eq!(
+        invalid_piece.source().unwrap().to_string(),
+        "Invalid character: 'x'"
+    );
+    assert_eq!(
+        err("rnbqkbnr/pppppppp/9/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"),
+        ParseFenError::InvalidPiece {
+            rank: 6,
+            index: 1,
+            source: InvalidCharError('9'),
+        }
+    );
+    assert_eq!(
+        err("rnbqkbnr/pppppppp/7/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"),
+        ParseFenError::WrongRowLength { rank: 6 }
+    );
+    // far too many empty squares to add up in a byte
+    assert_eq!(
+        err(&format!("{}/8/8/8/8/8/8/8 w - - 0 1", "8".repeat(40))),
+        ParseFenError::WrongRowLength { rank: 8 }
+    );
+    assert_eq!(
+        err("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR"),
+        ParseFenError::MissingField(FenField::Turn)
+    );
+    assert_eq!(
+        err("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR x KQkq - 0 1").to_string(),
+        "Invalid side to move field: \"x\""
+    );
+    assert_eq!(
+        err("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkx - 0 1"),
+        ParseFenError::InvalidField {
+            field: FenField::Castling,
+            value: "KQkx".to_owned(),
+        }
+    );
+    assert_eq!(
+        err("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq z9 0 1"),
+        ParseFenError::InvalidField {
+            field: FenField::EnPassant,
+            value: "z9".to_owned(),
+        }
+    );
+    let bad_counter = err("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 one");
+    assert!(matches!(bad_counter, ParseFenError::InvalidNumber {
+        field: FenField::FullMove,
+        ..
+    }));
+    assert!(bad_counter.source().is_some());
+    assert_eq!(
+        err("8/8/8/8/8/8/8/8 w - - 0 1"),
+        ParseFenError::IllegalState
+    );
+    assert!("i9".parse::<Square>().is_err());
+    assert!("`1".parse::<Square>().is_err());
+}
+
+#[test]
+fn test_legal_ep_capture() {
+    let gs = GameState::try_from_fen("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1").unwrap();
+    assert_eq!(gs.legal_ep_capture(), Some((E5, D6)));
+    // capturing would expose the king along the rank
+    let gs = GameState::try_from_fen(EPCHECK).unwrap();
+    assert_eq!(gs.legal_ep_capture(), None);
+    let mut pinned = gs;
+    pinned.ep_square = None;
+    assert_eq!(gs.zobrist(), pinned.zobrist());
+    // no pawn next to the double-pushed one
+    let gs = GameState::try_from_fen("4k3/8/8/3p4/8/8/8/4K3 w - d6 0 1").unwrap();
+    assert_eq!(gs.legal_ep_capture(), None);
+    // either of two pawns may take
+    let gs = GameState::try_from_fen("4k3/8/8/2PpP3/8/8/8/4K3 w - d6 0 1").unwrap();
+    assert!(matches!(gs.legal_ep_capture(), Some((C5 | E5, D6))));
+}
+
+#[test]
+fn test_long_algebraic() {
+    let gs = GameState::default();
+    let mv = gs.parse_lan("Ng1-f3").unwrap();
+    assert_eq!(gs.to_lan(mv), "Ng1-f3");
+    assert_eq!(gs.parse_lan("g1f3").unwrap(), mv);
+    assert_eq!(gs.to_lan(gs.parse_lan("e2e4").unwrap()), "e2-e4");
+    assert!(gs.parse_lan("Bg1-f3").is_err());
+    assert!(gs.parse_lan("e2-e5").is_err());
+    assert!(gs.parse_lan("e2").is_err());
+
+    let gs = GameState::try_from_fen("3r3k/4P3/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+    let mv = gs.parse_lan("e7xd8=Q+").unwrap();
+    assert_eq!(mv.kind, MoveKind::PromotionCapture(Figure::Queen));
+    assert_eq!(gs.to_lan(mv), "e7xd8=Q+");
+    assert_eq!(
+        gs.parse_lan("e7d8n").unwrap().promotion(),
+        Some(Figure::Knight)
+    );
+
+    for fen in [KIWIPETE, PERFT_POS_4] {
+        let gs = GameState::try_from_fen(fen).unwrap();
+        for mv in gs.legal_moves() {
+            assert_eq!(gs.parse_lan(&gs.to_lan(mv)).unwrap(), mv);
+        }
+    }
+
+    let games = parse_pgn("1. e2-e4 e7-e5 2. Ng1-f3 Nb8-c6 *").unwrap();
+    assert_eq!(games[0].replay().unwrap().len(), 4);
+}
+
+#[test]
+fn test_odds_positions() {
+    let fen = |odds, giver| GameState::with_odds(odds, giver).to_fen();
+    assert_eq!(
+        fen(Odds::PawnAndMove, Color::Black),
+        "rnbqkbnr/ppppp1pp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"
+    );
+    assert_eq!(
+        fen(Odds::PawnAndMove, Color::White),
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPP1PP/RNBQKBNR b KQkq - 0 1"
+    );
+    assert_eq!(
+        fen(Odds::Knight, Color::White),
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/R1BQKBNR w KQkq - 0 1"
+    );
+    assert_eq!(
+        fen(Odds::Rook, Color::White),
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/1NBQKBNR w Kkq - 0 1"
+    );
+    assert_eq!(
+        fen(Odds::RookAndKnight, Color::Black),
+        "2bqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQk - 0 1"
+    );
+    assert_eq!(
+        fen(Odds::Queen, Color::White),
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNB1KBNR w KQkq - 0 1"
+    );
+    for odds in Odds::ALL {
+        for giver in [Color::White, Color::Black] {
+            let gs = GameState::with_odds(odds, giver);
+            assert_eq!(GameState::try_from_fen(&gs.to_fen()).unwrap(), gs);
+            assert!(gs.perft(2) > 0);
+        }
+    }
+}
+
+#[test]
+fn test_mobility() {
+    let gs = GameState::default();
+    assert_eq!(gs.legal_move_count(), 20);
+    let mobility = gs.mobility();
+    assert_eq!(mobility[G1], 2);
+    assert_eq!(mobility[E2], 2);
+    assert_eq!(mobility[A1], 0);
+    assert_eq!(mobility[E4], 0);
+    assert_eq!(mobility.iter().map(|&n| n as usize).sum::<usize>(), 20);
+
+    // pinned knight can't move, promotions count once per piece
+    let gs = GameState::try_from_fen("4k3/1P6/8/8/4r3/8/4N3/4K3 w - - 0 1").unwrap();
+    let mobility = gs.mobility();
+    assert_eq!(mobility[E2], 0);
+    assert_eq!(mobility[B7], 4);
+    assert_eq!(gs.legal_move_count(), 8);
+}
+
+#[test]
+fn test_snapshot_restore() {
+    let start = GameState::try_from_fen(KNPR).unwrap();
+    let mut gs = start;
+    for mv in start.legal_moves() {
+        let token = gs.snapshot();
+        let captured = gs.play(mv);
+        gs.unmake_move(mv, captured, token);
+        assert_eq!(gs, start, "{mv}");
+    }
+
+    // takeback through make_move
+    let mut gs = GameState::default();
+    let token = gs.snapshot();
+    let captured = gs.make_move(G1, F3).unwrap().captured;
+    let mv = Move {
+        from: G1,
+        to: F3,
+        kind: MoveKind::Quiet,
+    };
+    gs.unmake_move(mv, captured, token);
+    assert_eq!(gs, GameState::default());
+    assert_eq!(token.hash(), gs.zobrist());
+}
+
+#[test]
+fn test_lichess_puzzles() {
+    let csv = "\
+PuzzleId,FEN,Moves,Rating,RatingDeviation,Popularity,NbPlays,Themes,GameUrl,OpeningTags
+00sHx,q3k1nr/1pp1nQpp/3p4/1P2p3/4P3/B1PP1b2/B5PP/5K2 b k - 0 17,e8d7 a2e6 d7d8 f7f8,1760,80,83,72,mate mateIn2 middlegame short,https://lichess.org/yyznGmXs/black#34,Italian_Game
+";
+    let puzzles = parse_lichess_csv(csv).unwrap();
+    assert_eq!(puzzles.len(), 1);
+    let puzzle = &puzzles[0];
+    assert_eq!(puzzle.id, "00sHx");
+    assert_eq!(puzzle.rating, 1760);
+    assert_eq!(puzzle.moves.len(), 4);
+    assert!(puzzle.themes.iter().any(|t| t == "mateIn2"));
+
+    let mut gs = puzzle.start_position().unwrap();
+    gs.play(puzzle.moves[0]);
+    assert!(puzzle.accepts(&gs, 1, puzzle.moves[1]));
+    let wrong = gs.parse_uci_move("f7g7").unwrap();
+    assert!(!puzzle.accepts(&gs, 1, wrong));
+
+    let err = parse_lichess_csv("abc,8/8/8/8/8/8/8/8 w - - 0 1").unwrap_err();
+    assert!(err.to_string().starts_with("Line 1"));
+    let err = parse_lichess_csv("abc,4k3/8/8/8/8/8/8/4K3 w - - 0 1,e1e3,1500").unwrap_err();
+    assert_eq!(err.to_string(), "Line 1: illegal move e1e3");
+
+    // the back rank opens up once the opponent's pawn moves
+    let puzzles =
+        parse_lichess_csv("abc,6k1/5ppp/1p6/8/8/8/8/R5K1 b - - 0 1,b6b5 a1a8,1200").unwrap();
+    assert!(puzzles[0].themes.is_empty());
+    assert_eq!(puzzles[0].detected_themes(), ["backRankMate"]);
+}
+
+#[test]
+fn test_net_messages() {
+    let messages = [
+        NetMessage::Move {
+            seq: 3,
+            uci: "e7e8q".to_owned(),
+        },
+        NetMessage::Sync {
+            seq: 0,
+            fen: DEFAULT_FEN.to_owned(),
+        },
+        NetMessage::Resync,
+        NetMessage::Chat("good luck".to_owned()),
+        NetMessage::Draw,
+        NetMessage::Resign,
+        NetMessage::Rematch,
+    ];
+    for message in messages {
+        assert_eq!(message.to_string().parse::<NetMessage>().unwrap(), message);
+    }
+    assert_eq!(
+        NetMessage::Chat("two\nlines".to_owned()).to_string(),
+        "chat two lines"
+    );
+    assert!("move e2e4".parse::<NetMessage>().is_err());
+    assert!("hello".parse::<NetMessage>().is_err());
+
+    // playing White here, so the peer's move only counts on Black's turn
+    let gs = GameState::default();
+    assert_eq!(peer_move(&gs, Color::White, 0, 0, "e2e4"), None);
+    assert!(peer_move(&gs, Color::Black, 0, 0, "e2e4").is_some());
+    assert_eq!(peer_move(&gs, Color::Black, 1, 0, "e2e4"), None);
+}
+
+#[test]
+fn test_eco() {
+    let mut table = EcoTable::default();
+    for &(eco, name, moves) in eco::BUILTIN {
+        assert!(table.add(eco, name, moves).is_ok(), "{eco} {name}");
+    }
+    let table = EcoTable::builtin();
+    assert!(!table.is_empty());
+    let mut gs = GameState::default();
+    let mut positions = vec![gs];
+    for san in "e4 c5 Nf3 d6 d4 cxd4 Nxd4 Nf6 Nc3 a6 Be3 e5".split(' ') {
+        gs.play(gs.parse_san(san).unwrap());
+        positions.push(gs);
+    }
+    // out of book, but still the Najdorf
+    let opening = table.classify(&positions).unwrap();
+    assert_eq!(opening.eco, "B90");
+    assert_eq!(opening.name, "Sicilian Defense: Najdorf Variation");
+    // the Queen's Gambit Declined reached via 1. c4
+    let mut gs = GameState::default();
+    for san in "c4 e6 d4 d5".split(' ') {
+        gs.play(gs.parse_san(san).unwrap());
+    }
+    assert_eq!(table.lookup(&gs).unwrap().eco, "D30");
+
+    let tsv = "eco\tname\tpgn\nB01\tScandinavian Defense\t1. e4 d5\n";
+    let table = EcoTable::parse_tsv(tsv).unwrap();
+    assert_eq!(table.len(), 1);
+    let err = EcoTable::parse_tsv("A00\tBad\t1. e5").unwrap_err();
+    assert_eq!(err.to_string(), "Line 1: illegal move e5");
+    let err = EcoTable::parse_tsv("A00\tNo moves").unwrap_err();
+    assert_eq!(err.to_string(), "Line 1: missing pgn");
+}
+
+#[test]
+fn test_game_review() {
+    assert_eq!(win_percent(Score::Centipawns(0)), 50.0);
+    assert_eq!(win_percent(Score::Mate(3)), 100.0);
+    assert_eq!(win_percent(Score::Mate(-1)), 0.0);
+    assert!(win_percent(Score::Centipawns(300)) > 70.0);
+
+    let kept = review_move(Score::Centipawns(30), Score::Centipawns(25), Color::White);
+    assert_eq!(kept.judgement, None);
+    assert!(kept.accuracy > 95.0);
+    // a dropped queen, for Black
+    let dropped = review_move(Score::Centipawns(-20), Score::Centipawns(900), Color::Black);
+    assert_eq!(dropped.judgement, Some(Judgement::Blunder));
+    assert!(dropped.accuracy < 10.0);
+    // Black's losing move is White's gain
+    let gain = review_move(Score::Centipawns(0), Score::Centipawns(500), Color::White);
+    assert_eq!(gain.judgement, None);
+    assert!(gain.accuracy > 99.9);
+
+    let scores = [20, 30, 180, 170, 160, -400, -390]
+        .map(Score::Centipawns)
+        .to_vec();
+    let review = GameReview::new(scores, Color::White);
+    assert_eq!(review.moves.len(), 6);
+    assert_eq!(review.mover(1), Color::Black);
+    assert_eq!(review.moves[1].judgement, Some(Judgement::Mistake));
+    assert_eq!(review.moves[4].judgement, Some(Judgement::Blunder));
+    assert_eq!(review.count(Color::White, Judgement::Blunder), 1);
+    assert_eq!(review.count(Color::Black, Judgement::Blunder), 0);
+    assert!(review.accuracy(Color::Black).unwrap() > review.accuracy(Color::White).unwrap());
+    assert_eq!(
+        GameReview::new(vec![Score::Centipawns(0)], Color::White).accuracy(Color::White),
+        None
+    );
+}
+
+#[test]
+fn test_repertoire_drill() {
+    let text =
+        "1. e4 e5 (1... c5 2. Nf3 d6 3. d4 cxd4 4. Nxd4) (1... e6 2. d4) 2. Nf3 Nc6 3. Bb5 *";
+    let repertoire = Repertoire::from_pgn(text, Color::White).unwrap();
+    // e4, Nf3 and Bb5 against e5, Nf3, d4 and Nxd4 against the Sicilian, d4 against the French
+    assert_eq!(repertoire.positions().count(), 7);
+    let mut gs = GameState::default();
+    assert_eq!(repertoire.moves(&gs), [gs.parse_san("e4").unwrap()]);
+    gs.play(gs.parse_san("e4").unwrap());
+    assert_eq!(repertoire.moves(&gs).len(), 3);
+
+    let mut schedule = Schedule::default();
+    let now = 1_000_000;
+    assert_eq!(schedule.due_count(repertoire.positions(), now), 7);
+    // the Sicilian has the most left to practise
+    let reply = repertoire.pick_reply(&gs, &schedule, now).unwrap();
+    assert_eq!(gs.to_san(reply), "c5");
+
+    let mut after = gs;
+    after.play(gs.parse_san("c5").unwrap());
+    let nf3 = after.zobrist();
+    schedule.review(nf3, true, now);
+    assert!(!schedule.is_due(nf3, now));
+    assert!(schedule.is_due(nf3, now + 86_400));
+    schedule.review(nf3, false, now);
+    assert!(schedule.is_due(nf3, now));
+    schedule.review(nf3, true, now);
+    schedule.review(nf3, true, now);
+    assert!(!schedule.is_due(nf3, now + 2 * 86_400));
+    assert_eq!(Schedule::parse(&schedule.to_string()), schedule);
+    assert_eq!(Schedule::parse("junk\n12 x 3\n"), Schedule::default());
+}
+
+#[test]
+fn test_fairy_pieces() {
+    let archbishop: Movement = "BN".parse().unwrap();
+    assert_eq!(archbishop.steps().len(), 12);
+    assert!(archbishop.steps().contains(&Step::Ride(-1, 1)));
+    assert!(archbishop.steps().contains(&Step::Leap(1, -2)));
+    assert_eq!("NN".parse::<Movement>().unwrap(), Movement::rider(2, 1));
+    assert_eq!("WF".parse::<Movement>().unwrap(), "K".parse().unwrap());
+    assert!("X".parse::<Movement>().is_err());
+
+    // the same moves as the real move generator, castling and en passant aside
+    let standard = Variant::standard();
+    assert_eq!(standard.start_position().unwrap().perft(3), 8902);
+    let capablanca = Variant::capablanca();
+    let start = capablanca.start_position().unwrap();
+    assert_eq!(start.perft(1), 28);
+    assert_eq!(start.perft(2), 784);
+
+    // a chancellor checks like a knight and gets stopped like a rook
+    let mut variant = Variant::standard();
+    variant
+        .figures
+        .push(FairyFigure::new("chancellor", 'C', "RN").unwrap());
+    let gs = FairyPosition::parse(&variant, "4k3/8/3C4/8/8/8/4P3/4K3 b").unwrap();
+    assert!(gs.in_check());
+    let mut moves: Vec<String> = gs.legal_moves().iter().map(|&mv| gs.to_uci(mv)).collect();
+    moves.sort();
+    assert_eq!(moves, ["e8e7", "e8f8"]);
+    let gs = FairyPosition::parse(&variant, "8/2P1k3/8/8/8/8/8/4K3 w").unwrap();
+    let promotions: Vec<String> = (gs.legal_moves().into_iter())
+        .filter(|mv| mv.promotion.is_some())
+        .map(|mv| gs.to_uci(mv))
+        .collect();
+    assert_eq!(promotions, ["c7c8r", "c7c8n", "c7c8b", "c7c8q", "c7c8c"]);
+    assert!(FairyPosition::parse(&variant, "8/8/8 w").is_err());
+    let long_run = format!("{}/8/8/8/8/8/8/8 w", "9".repeat(20));
+    assert!(FairyPosition::parse(&variant, &long_run).is_err());
+}
+
+#[test]
+fn test_board_geometry() {
+    let geometry = Geometry::NINE_BY_NINE;
+    assert_eq!(geometry.squares(), 81);
+    assert_eq!(Geometry::new(10, 8), Some(Geometry::CAPABLANCA));
+    assert!(Geometry::new(12, 12).is_none());
+    let corner = WideBitBoard::from_index(geometry.index(8, 0).unwrap());
+    // off the i-file edge and back on the next rank would be a wrap
+    assert!(corner.shift(geometry, 1, 0).empty());
+    assert_eq!(corner.shift(geometry, -1, 1), WideBitBoard::from_index(16));
+    let ray = WideBitBoard::ray(geometry, 0, 1, 1, WideBitBoard::default());
+    assert_eq!(ray.count_squares(), 8);
+    assert!(ray.contains(80));
+    assert_eq!(
+        geometry.rank_mask(8).iter().collect::<Vec<_>>(),
+        (72..81).collect::<Vec<_>>()
+    );
+
+    // the standard pieces on a 9x9 board
+    let variant = Variant {
+        name: "9x9".to_owned(),
+        geometry,
+        figures: Variant::standard().figures,
+        start: "rnbqkqbnr/ppppppppp/9/9/9/9/9/PPPPPPPPP/RNBQKQBNR w".to_owned(),
+    };
+    let gs = variant.start_position().unwrap();
+    // nine pawns with one or two steps each, and two knights with two moves each
+    assert_eq!(gs.perft(1), 22);
+    assert_eq!(gs.perft(2), 22 * 22);
+    let capablanca = Variant::capablanca();
+    let gs = FairyPosition::parse(&capablanca, "k9/10/10/10/10/10/10/9C w").unwrap();
+    assert_eq!(gs.legal_moves().len(), 9 + 7 + 2);
+}
+
+#[test]
+fn test_variant_fen_fields() {
+    let fens = [
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 3+3 0 1",
+        "rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR[Qn] w KQkq - 0 3",
+        "k6Q~/8/8/8/8/8/8/K7[RBppp] b - - 0 40",
+        DEFAULT_FEN,
+    ];
+    for fen in fens {
+        assert_eq!(VariantPosition::try_from_fen(fen).unwrap().to_fen(), fen);
+    }
+    let pos = VariantPosition::try_from_fen(fens[2]).unwrap();
+    let VariantState::Crazyhouse { pockets, promoted } = *pos.variant() else {
+        panic!("not crazyhouse");
+    };
+    assert!(promoted.contains(H8));
+    assert_eq!(pockets[Color::Black].count(Figure::Pawn), 3);
+    assert_eq!(pockets[Color::White].count(Figure::Queen), 0);
+    // lichess' checks given and a ninth rank for the pockets
+    let pos = VariantPosition::try_from_fen("4k3/8/8/8/8/8/8/4K2R w - - 0 1 +1+0").unwrap();
+    assert_eq!(pos.to_fen(), "4k3/8/8/8/8/8/8/4K2R w - - 2+3 0 1");
+    let pos = VariantPosition::try_from_fen("4k3/8/8/8/8/8/8/4K3/Nq w - - 0 1").unwrap();
+    assert_eq!(pos.to_fen(), "4k3/8/8/8/8/8/8/4K3[Nq] w - - 0 1");
+    assert!(VariantPosition::try_from_fen("4k3/8/8/8/8/8/8/4K3[Nx] w - - 0 1").is_err());
+    // counts past what the parsers can hold are errors, not overflows
+    let long_row = format!("{}/8/8/8/8/8/8/4K3[] w - - 0 1", "9".repeat(30));
+    assert!(VariantPosition::try_from_fen(&long_row).is_err());
+    let full_pocket = format!("4k3/8/8/8/8/8/8/4K3[{}] w - - 0 1", "P".repeat(256));
+    assert!(VariantPosition::try_from_fen(&full_pocket).is_err());
+    // the state stays out of the standard position
+    assert!(GameState::try_from_fen(fens[0]).is_err());
+
+    // captures go in the pocket, a promoted piece's as a pawn, and the promoted mark
+    // moves with the piece
+    let mut pos = VariantPosition::try_from_fen("r3k3/8/8/8/8/8/8/4K2Q~[] w - - 0 1").unwrap();
+    pos.make_move(H1, A8).unwrap();
+    assert_eq!(pos.to_fen(), "Q~3k3/8/8/8/8/8/8/4K3[R] b - - 0 1");
+    pos.play_uci("e8d7").unwrap();
+    pos.play_uci("a8a1").unwrap();
+    assert_eq!(pos.to_fen(), "8/3k4/8/8/8/8/8/Q~3K3[R] b - - 2 2");
+    let mut pos = VariantPosition::try_from_fen("4k3/8/8/8/8/8/1q6/Q~3K3[] b - - 0 1").unwrap();
+    pos.play_uci("b2a1").unwrap();
+    assert_eq!(pos.to_fen(), "4k3/8/8/8/8/8/8/q3K3[p] w - - 0 2");
+    let mut pos = VariantPosition::try_from_fen("4k3/P7/8/8/8/8/8/4K3[] w - - 0 1").unwrap();
+    pos.play_uci("a7a8q").unwrap();
+    assert_eq!(pos.to_fen(), "Q~3k3/8/8/8/8/8/8/4K3[] b - - 0 1");
+
+    // checks are counted down by both ways of playing a move
+    let mut pos = VariantPosition::try_from_fen("4k3/8/8/8/8/8/8/4K2R w - - 3+3 0 1").unwrap();
+    pos.make_move(H1, H8).unwrap();
+    assert_eq!(*pos.variant(), VariantState::ThreeCheck {
+        checks_left: [2, 3]
+    });
+    pos.play_uci("e8e7").unwrap();
+    pos.play_uci("h8h7").unwrap();
+    assert_eq!(pos.to_fen(), "8/4k2R/8/8/8/8/8/4K3 b - - 1+3 3 2");
+    assert!(pos.play_uci("e2e4").is_none());
 }
@@ -0,0 +1,118 @@
+use crate::{
+    GameState,
+    errors::ParsePgnError,
+    moves::{Move, Move16},
+    pgn::{GameResult, PgnGame},
+    pieces::{Color, Figure},
+    uci::Score,
+};
+
+pub const CSV_HEADER: &str = "fen,move,result,eval";
+// the figures in the order their planes come, White's six and then Black's
+const PLANE_FIGURES: [Figure; 6] = [
+    Figure::Pawn,
+    Figure::Knight,
+    Figure::Bishop,
+    Figure::Rook,
+    Figure::Queen,
+    Figure::King,
+];
+// 12 piece planes of 8 bytes, flags, en passant square, move, result, eval
+pub const RECORD_SIZE: usize = 12 * 8 + 1 + 1 + 2 + 1 + 2;
+// mates, whatever their distance, as centipawns in a record
+const MATE_CP: i16 = 32000;
+const NO_EVAL: i16 = i16::MIN;
+
+// a position from a game with the move played from it and how the game ended, for
+// training a value or policy network; the eval is the engine's, from White's side
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Sample {
+    pub gs: GameState,
+    pub mv: Move,
+    pub result: Option<GameResult>,
+    pub eval: Option<Score>,
+}
+
+// every position of `game` that had a move played from it
+pub fn samples(game: &PgnGame) -> Result<Vec<Sample>, ParsePgnError> {
+    Ok(game
+        .replay()?
+        .into_iter()
+        .map(|(gs, mv)| Sample {
+            gs,
+            mv,
+            result: game.result,
+            eval: None,
+        })
+        .collect())
+}
+
+impl Sample {
+    // the result as White's score, 1, 0.5 or 0, and the eval in centipawns or as `#n`;
+    // either is left empty when it isn't known
+    pub fn to_csv(&self) -> String {
+        let result = match self.result {
+            Some(GameResult::WhiteWins) => "1",
+            Some(GameResult::Draw) => "0.5",
+            Some(GameResult::BlackWins) => "0",
+            None => "",
+        };
+        let eval = match self.eval {
+            Some(Score::Centipawns(cp)) => cp.to_string(),
+            Some(Score::Mate(n)) => format!("#{n}"),
+            None => String::new(),
+        };
+        format!("{},{},{result},{eval}", self.gs.to_fen(), self.mv)
+    }
+
+    // a fixed-size little-endian record:
+    //  - 12 bitboards of 8 bytes, a1 in the lowest bit, White's P N B R Q K then Black's
+    //  - flags: 1 for Black to move, then 2, 4, 8 and 16 for White's and Black's
+    //    kingside and queenside castling
+    //  - the en passant square, 64 for none
+    //  - the move played as a `Move16`
+    //  - the result from White's side as 1, 0 or -1, and 0 when it isn't known
+    //  - the eval in centipawns from White's side, mates at +-32000 and i16::MIN for none
+    pub fn to_record(&self) -> [u8; RECORD_SIZE] {
+        let mut record = [0; RECORD_SIZE];
+        let mut planes = [0u64; 12];
+        for (square, piece) in self.gs.iter() {
+            let figure = PLANE_FIGURES.iter().position(|&f| f == piece.figure);
+            let color = match piece.color {
+                Color::White => 0,
+                Color::Black => 6,
+            };
+            if let Some(figure) = figure {
+                planes[color + figure] |= 1 << square as u64;
+            }
+        }
+        for (idx, plane) in planes.iter().enumerate() {
+            record[idx * 8..idx * 8 + 8].copy_from_slice(&plane.to_le_bytes());
+        }
+        let castle = &self.gs.castle;
+        let flags = [
+            self.gs.turn == Color::Black,
+            castle.can_king_castle(Color::White),
+            castle.can_queen_castle(Color::White),
+            castle.can_king_castle(Color::Black),
+            castle.can_queen_castle(Color::Black),
+        ];
+        record[96] =
+            (flags.iter().enumerate()).fold(0, |bits, (idx, &set)| bits | (set as u8) << idx);
+        record[97] = self.gs.ep_square.map_or(64, |square| square as u8);
+        record[98..100].copy_from_slice(&u16::from(Move16::from(self.mv)).to_le_bytes());
+        record[100] = match self.result {
+            Some(GameResult::WhiteWins) => 1,
+            Some(GameResult::BlackWins) => -1i8 as u8,
+            Some(GameResult::Draw) | None => 0,
+        };
+        let eval = match self.eval {
+            Some(Score::Centipawns(cp)) => cp.clamp(-MATE_CP as i32 + 1, MATE_CP as i32 - 1) as i16,
+            Some(Score::Mate(n)) if n < 0 => -MATE_CP,
+            Some(Score::Mate(_)) => MATE_CP,
+            None => NO_EVAL,
+        };
+        record[101..103].copy_from_slice(&eval.to_le_bytes());
+        record
+    }
+}
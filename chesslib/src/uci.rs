@@ -0,0 +1,420 @@
+use crate::{
+    GameState,
+    errors::{ParsePositionError, UciError},
+    moves::Move,
+};
+use std::{
+    io::{BufRead, BufReader, Write},
+    process::{Child, ChildStdin, Command, Stdio},
+    sync::mpsc::{self, Receiver, RecvTimeoutError},
+    thread,
+    time::{Duration, Instant},
+};
+
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Score {
+    Centipawns(i32),
+    Mate(i32),
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GoLimits {
+    pub wtime: Option<Duration>,
+    pub btime: Option<Duration>,
+    pub winc: Option<Duration>,
+    pub binc: Option<Duration>,
+    pub moves_to_go: Option<u32>,
+    pub depth: Option<u32>,
+    pub nodes: Option<u64>,
+    pub move_time: Option<Duration>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchResult {
+    pub best_move: String,
+    pub score: Option<Score>,
+    pub depth: Option<u32>,
+    pub pv: Vec<String>,
+    // which of the lines the latest info was about, counting from 1, when asked for several
+    pub multipv: Option<u32>,
+}
+
+// one of the settings an engine offers, as declared in its `option` line
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UciOption {
+    pub name: String,
+    pub kind: OptionKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OptionKind {
+    Check { default: bool },
+    Spin { default: i64, min: i64, max: i64 },
+    Combo { default: String, vars: Vec<String> },
+    String { default: String },
+    // does something when sent, holds no value
+    Button,
+}
+
+pub struct UciEngine {
+    name: String,
+    // how it was started and the options set since, for starting it again
+    program: String,
+    args: Vec<String>,
+    set: Vec<(String, String)>,
+    // the options it offered during the handshake
+    options: Vec<UciOption>,
+    child: Child,
+    stdin: ChildStdin,
+    lines: Receiver<String>,
+}
+
+impl UciEngine {
+    pub fn spawn(program: &str, args: &[String]) -> Result<Self, UciError> {
+        let mut child = Command::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()?;
+        let stdin = child.stdin.take().ok_or(UciError::Disconnected)?;
+        let stdout = child.stdout.take().ok_or(UciError::Disconnected)?;
+        let (tx, lines) = mpsc::channel();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+                if tx.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+        let mut engine = Self {
+            name: program.to_owned(),
+            program: program.to_owned(),
+            args: args.to_vec(),
+            set: Vec::new(),
+            options: Vec::new(),
+            child,
+            stdin,
+            lines,
+        };
+        engine.send("uci")?;
+        let deadline = Instant::now() + HANDSHAKE_TIMEOUT;
+        loop {
+            let line = engine.recv(deadline)?;
+            if let Some(name) = line.strip_prefix("id name ") {
+                engine.name = name.trim().to_owned();
+            } else if let Some(option) = parse_option(&line) {
+                engine.options.push(option);
+            } else if line.trim() == "uciok" {
+                break;
+            }
+        }
+        Ok(engine)
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    // swaps a crashed or hung process for a fresh one of the same program, with the
+    // options it had; the position and search are up to the caller to send again
+    pub fn restart(&mut self) -> Result<(), UciError> {
+        log::warn!("restarting {}", self.name);
+        let mut engine = Self::spawn(&self.program, &self.args)?;
+        for (name, value) in &self.set {
+            engine.set_option(name, value)?;
+        }
+        // the old process goes with the old value
+        *self = engine;
+        Ok(())
+    }
+
+    pub fn send(&mut self, command: &str) -> Result<(), UciError> {
+        log::debug!("{} < {command}", self.name);
+        writeln!(self.stdin, "{command}")?;
+        self.stdin.flush()?;
+        Ok(())
+    }
+
+    fn recv(&self, deadline: Instant) -> Result<String, UciError> {
+        let timeout = deadline.saturating_duration_since(Instant::now());
+        let line = self.lines.recv_timeout(timeout).map_err(|err| match err {
+            RecvTimeoutError::Timeout => UciError::Timeout,
+            RecvTimeoutError::Disconnected => UciError::Disconnected,
+        })?;
+        // search info arrives many times a second
+        if line.starts_with("info") {
+            log::trace!("{} > {line}", self.name);
+        } else {
+            log::debug!("{} > {line}", self.name);
+        }
+        Ok(line)
+    }
+
+    pub fn options(&self) -> &[UciOption] {
+        &self.options
+    }
+
+    // option names aren't case sensitive
+    pub fn has_option(&self, name: &str) -> bool {
+        self.options
+            .iter()
+            .any(|option| option.name.eq_ignore_ascii_case(name))
+    }
+
+    pub fn set_option(&mut self, name: &str, value: &str) -> Result<(), UciError> {
+        self.set.retain(|(set, _)| !set.eq_ignore_ascii_case(name));
+        self.set.push((name.to_owned(), value.to_owned()));
+        self.send(&format!("setoption name {name} value {value}"))
+    }
+
+    pub fn is_ready(&mut self) -> Result<(), UciError> {
+        self.send("isready")?;
+        let deadline = Instant::now() + HANDSHAKE_TIMEOUT;
+        while self.recv(deadline)?.trim() != "readyok" {}
+        Ok(())
+    }
+
+    pub fn new_game(&mut self) -> Result<(), UciError> {
+        self.send("ucinewgame")?;
+        self.is_ready()
+    }
+
+    // `fen` of None means the standard start position
+    pub fn set_position(&mut self, fen: Option<&str>, moves: &[Move]) -> Result<(), UciError> {
+        let mut command = match fen {
+            Some(fen) => format!("position fen {fen}"),
+            None => "position startpos".to_owned(),
+        };
+        if !moves.is_empty() {
+            command.push_str(" moves");
+            for mv in moves {
+                command.push_str(&format!(" {mv}"));
+            }
+        }
+        self.send(&command)
+    }
+
+    pub fn go(&mut self, limits: &GoLimits, timeout: Duration) -> Result<SearchResult, UciError> {
+        self.go_with_info(limits, timeout, |_| true)
+    }
+
+    // like `go`, but reports the running result after every info line; returning false
+    // from `on_info` sends "stop" and the search winds down to its bestmove
+    pub fn go_with_info(
+        &mut self,
+        limits: &GoLimits,
+        timeout: Duration,
+        mut on_info: impl FnMut(&SearchResult) -> bool,
+    ) -> Result<SearchResult, UciError> {
+        let mut command = "go".to_owned();
+        let millis = |d: Duration| d.as_millis();
+        if let Some(t) = limits.wtime {
+            command.push_str(&format!(" wtime {}", millis(t)));
+        }
+        if let Some(t) = limits.btime {
+            command.push_str(&format!(" btime {}", millis(t)));
+        }
+        if let Some(t) = limits.winc {
+            command.push_str(&format!(" winc {}", millis(t)));
+        }
+        if let Some(t) = limits.binc {
+            command.push_str(&format!(" binc {}", millis(t)));
+        }
+        if let Some(n) = limits.moves_to_go {
+            command.push_str(&format!(" movestogo {n}"));
+        }
+        if let Some(n) = limits.depth {
+            command.push_str(&format!(" depth {n}"));
+        }
+        if let Some(n) = limits.nodes {
+            command.push_str(&format!(" nodes {n}"));
+        }
+        if let Some(t) = limits.move_time {
+            command.push_str(&format!(" movetime {}", millis(t)));
+        }
+        if command == "go" {
+            command.push_str(" infinite");
+        }
+        self.send(&command)?;
+
+        let deadline = Instant::now() + timeout;
+        let mut result = SearchResult {
+            best_move: String::new(),
+            score: None,
+            depth: None,
+            pv: Vec::new(),
+            multipv: None,
+        };
+        let mut stopping = false;
+        loop {
+            let line = self.recv(deadline)?;
+            let mut tokens = line.split_whitespace();
+            match tokens.next() {
+                Some("info") => {
+                    parse_info(tokens, &mut result);
+                    if !stopping && !on_info(&result) {
+                        stopping = true;
+                        self.stop()?;
+                    }
+                }
+                Some("bestmove") => {
+                    result.best_move = tokens
+                        .next()
+                        .ok_or_else(|| UciError::Protocol(line.clone()))?
+                        .to_owned();
+                    return Ok(result);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    pub fn stop(&mut self) -> Result<(), UciError> {
+        self.send("stop")
+    }
+
+    pub fn quit(mut self) {
+        let _ = self.send("quit");
+        let _ = self.child.wait();
+    }
+}
+
+impl Drop for UciEngine {
+    fn drop(&mut self) {
+        if let Ok(None) = self.child.try_wait() {
+            let _ = self.child.kill();
+            let _ = self.child.wait();
+        }
+    }
+}
+
+impl GameState {
+    // accepts "position startpos moves ..." or "position fen <fen> moves ...",
+    // with or without the leading "position"
+    pub fn from_position_command(command: &str) -> Result<Self, ParsePositionError> {
+        let mut tokens = command.split_whitespace().peekable();
+        if tokens.peek() == Some(&"position") {
+            tokens.next();
+        }
+        let mut gs = match tokens.next() {
+            Some("startpos") => match tokens.next() {
+                Some("moves") | None => GameState::default(),
+                Some(other) => return Err(ParsePositionError::InvalidToken(other.to_owned())),
+            },
+            Some("fen") => {
+                let fen: Vec<&str> = tokens.by_ref().take_while(|&t| t != "moves").collect();
+                GameState::try_from_fen(&fen.join(" "))?
+            }
+            Some(other) => return Err(ParsePositionError::InvalidToken(other.to_owned())),
+            None => return Err(ParsePositionError::MissingPosition),
+        };
+        for token in tokens {
+            let mv = gs
+                .parse_uci_move(token)
+                .ok_or_else(|| ParsePositionError::IllegalMove(token.to_owned()))?;
+            gs.play(mv);
+        }
+        Ok(gs)
+    }
+
+    pub fn parse_uci_move(&self, uci: &str) -> Option<Move> {
+        self.legal_moves()
+            .into_iter()
+            .find(|mv| mv.to_string() == uci)
+    }
+}
+
+pub(crate) fn parse_info<'a>(mut tokens: impl Iterator<Item = &'a str>, result: &mut SearchResult) {
+    while let Some(token) = tokens.next() {
+        match token {
+            "depth" => result.depth = tokens.next().and_then(|t| t.parse().ok()),
+            "multipv" => result.multipv = tokens.next().and_then(|t| t.parse().ok()),
+            "score" => {
+                let kind = tokens.next();
+                let value = tokens.next().and_then(|t| t.parse().ok());
+                result.score = match (kind, value) {
+                    (Some("cp"), Some(v)) => Some(Score::Centipawns(v)),
+                    (Some("mate"), Some(v)) => Some(Score::Mate(v)),
+                    _ => result.score,
+                };
+            }
+            "pv" => {
+                result.pv = tokens.map(str::to_owned).collect();
+                return;
+            }
+            "string" => return,
+            _ => {}
+        }
+    }
+}
+
+// the name from an `option name <name> type <type> ...` line; names can have spaces
+pub(crate) fn option_name(line: &str) -> Option<&str> {
+    let rest = line.strip_prefix("option name ")?;
+    let end = rest.find(" type ").unwrap_or(rest.len());
+    Some(rest[..end].trim())
+}
+
+// a whole `option` line; None for types we don't know or spins missing their bounds
+pub fn parse_option(line: &str) -> Option<UciOption> {
+    let name = option_name(line)?;
+    let (_, rest) = line.split_once(" type ")?;
+    let mut tokens = rest.split_whitespace();
+    let kind = tokens.next()?;
+    // a value runs on to the next keyword, spaces and all
+    let mut fields: Vec<(&str, String)> = Vec::new();
+    for token in tokens {
+        match token {
+            "default" | "min" | "max" | "var" => fields.push((token, String::new())),
+            word => {
+                if let Some((_, value)) = fields.last_mut() {
+                    if !value.is_empty() {
+                        value.push(' ');
+                    }
+                    value.push_str(word);
+                }
+            }
+        }
+    }
+    let field = |key: &str| {
+        fields
+            .iter()
+            .find(|(k, _)| *k == key)
+            .map(|(_, value)| value.as_str())
+    };
+    let number = |key: &str| field(key)?.parse().ok();
+    let kind = match kind {
+        "check" => OptionKind::Check {
+            default: field("default") == Some("true"),
+        },
+        "spin" => OptionKind::Spin {
+            default: number("default")?,
+            min: number("min")?,
+            max: number("max")?,
+        },
+        "combo" => OptionKind::Combo {
+            default: field("default").unwrap_or_default().to_owned(),
+            vars: fields
+                .iter()
+                .filter(|(k, _)| *k == "var")
+                .map(|(_, value)| value.clone())
+                .collect(),
+        },
+        // engines write an empty default as <empty>
+        "string" => OptionKind::String {
+            default: field("default")
+                .filter(|&value| value != "<empty>")
+                .unwrap_or_default()
+                .to_owned(),
+        },
+        "button" => OptionKind::Button,
+        _ => return None,
+    };
+    Some(UciOption {
+        name: name.to_owned(),
+        kind,
+    })
+}
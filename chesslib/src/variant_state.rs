@@ -0,0 +1,326 @@
+use crate::{
+    GameState,
+    board::{Square, bitboard::BitBoard},
+    errors::{FenField, MoveError, ParseFenError},
+    moves::{Move, MoveKind, MoveOutcome},
+    pieces::{Color, Figure, Piece},
+};
+
+// the figures a crazyhouse pocket holds, in the order a FEN lists them
+const POCKET_FIGURES: [Figure; 5] = [
+    Figure::Queen,
+    Figure::Rook,
+    Figure::Bishop,
+    Figure::Knight,
+    Figure::Pawn,
+];
+
+// what some variants keep track of beyond the board, carried in FEN fields of their own
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum VariantState {
+    #[default]
+    Standard,
+    // the checks each side still has to give to win, White's first
+    ThreeCheck {
+        checks_left: [u8; 2],
+    },
+    // the pieces each side has in hand, and the pieces on the board that were promoted,
+    // which go back to being pawns when they're taken. Captures fill the pockets, but
+    // drops aren't played yet.
+    Crazyhouse {
+        pockets: [Pocket; 2],
+        promoted: BitBoard,
+    },
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct Pocket([u8; 5]);
+
+impl Pocket {
+    pub fn count(&self, figure: Figure) -> u8 {
+        POCKET_FIGURES
+            .iter()
+            .position(|&f| f == figure)
+            .map_or(0, |idx| self.0[idx])
+    }
+
+    // the king never goes in; false when there are already as many as it can count
+    pub fn add(&mut self, figure: Figure) -> bool {
+        let Some(idx) = POCKET_FIGURES.iter().position(|&f| f == figure) else {
+            return true;
+        };
+        match self.0[idx].checked_add(1) {
+            Some(count) => {
+                self.0[idx] = count;
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+// a position of a variant with state beyond the board, kept beside its `GameState` rather
+// than in it so standard play and search don't copy it around with every move
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct VariantPosition {
+    gs: GameState,
+    variant: VariantState,
+}
+
+impl VariantPosition {
+    // a standard FEN gives a `VariantState::Standard` position
+    pub fn try_from_fen(fen: &str) -> Result<Self, ParseFenError> {
+        let (variant, parts) = split_fen(fen)?;
+        let gs = GameState::try_from_fen(&parts.join())?;
+        Ok(Self { gs, variant })
+    }
+
+    pub fn to_fen(&self) -> String {
+        let mut parts = FenParts::new(&self.gs.to_fen());
+        self.variant.write_fen(&mut parts);
+        parts.join()
+    }
+
+    pub fn gs(&self) -> &GameState {
+        &self.gs
+    }
+
+    pub fn variant(&self) -> &VariantState {
+        &self.variant
+    }
+
+    pub fn make_move(&mut self, from: Square, to: Square) -> Result<MoveOutcome, MoveError> {
+        let mover = self.gs.turn;
+        let outcome = self.gs.make_move(from, to)?;
+        self.follow(mover, from, to, outcome.captured, false);
+        Ok(outcome)
+    }
+
+    pub fn make_promotion(
+        &mut self,
+        from: Square,
+        to: Square,
+        promotion_piece: Piece,
+    ) -> Result<MoveOutcome, MoveError> {
+        let mover = self.gs.turn;
+        let outcome = self.gs.make_promotion(from, to, promotion_piece)?;
+        self.follow(mover, from, to, outcome.captured, true);
+        Ok(outcome)
+    }
+
+    // a UCI move, if it's legal here
+    pub fn play_uci(&mut self, uci: &str) -> Option<Move> {
+        let mv = self.gs.parse_uci_move(uci)?;
+        let mover = self.gs.turn;
+        let captured = self.gs.play(mv);
+        let promotion = matches!(
+            mv.kind,
+            MoveKind::Promotion(_) | MoveKind::PromotionCapture(_)
+        );
+        self.follow(mover, mv.from, mv.to, captured, promotion);
+        Some(mv)
+    }
+
+    fn follow(
+        &mut self,
+        mover: Color,
+        from: Square,
+        to: Square,
+        captured: Option<Piece>,
+        promotion: bool,
+    ) {
+        if self.gs.board.is_in_check(self.gs.turn) {
+            self.variant.count_check(mover);
+        }
+        self.variant
+            .follow_move(mover, from, to, captured, promotion);
+    }
+}
+
+// a FEN in pieces, for each variant to take its own fields out of before the rest is
+// read as a standard one
+struct FenParts {
+    pub placement: String,
+    pub fields: Vec<String>,
+}
+
+impl FenParts {
+    fn new(fen: &str) -> Self {
+        let mut fields = fen.split(' ').map(str::to_owned);
+        Self {
+            placement: fields.next().unwrap_or_default(),
+            fields: fields.collect(),
+        }
+    }
+
+    fn join(&self) -> String {
+        format!("{} {}", self.placement, self.fields.join(" "))
+    }
+}
+
+type FenPlug = fn(&mut FenParts) -> Result<Option<VariantState>, ParseFenError>;
+
+// each variant's reader, tried in turn: the first to find its fields has the FEN
+const PLUGS: [FenPlug; 2] = [read_crazyhouse, read_three_check];
+
+// the variant state in `fen` and what's left of it once that's been taken out
+fn split_fen(fen: &str) -> Result<(VariantState, FenParts), ParseFenError> {
+    let mut parts = FenParts::new(fen);
+    for plug in PLUGS {
+        if let Some(state) = plug(&mut parts)? {
+            return Ok((state, parts));
+        }
+    }
+    Ok((VariantState::Standard, parts))
+}
+
+impl VariantState {
+    // the variant's fields put into a standard FEN's placement and other fields
+    fn write_fen(&self, parts: &mut FenParts) {
+        match self {
+            VariantState::Standard => {}
+            VariantState::ThreeCheck { checks_left } => {
+                let checks = format!("{}+{}", checks_left[0], checks_left[1]);
+                parts.fields.insert(3, checks);
+            }
+            VariantState::Crazyhouse { pockets, promoted } => {
+                let mut placement = String::new();
+                let squares = placement_squares(&parts.placement);
+                for (c, square) in parts.placement.chars().zip(squares) {
+                    placement.push(c);
+                    if square.is_some_and(|sq| promoted.contains(sq)) {
+                        placement.push('~');
+                    }
+                }
+                placement.push('[');
+                for (color, pocket) in [Color::White, Color::Black].into_iter().zip(pockets) {
+                    for figure in POCKET_FIGURES {
+                        let letter = char::from(Piece { color, figure });
+                        let count = pocket.count(figure) as usize;
+                        placement.extend(std::iter::repeat_n(letter, count));
+                    }
+                }
+                placement.push(']');
+                parts.placement = placement;
+            }
+        }
+    }
+
+    // counts a check given by `mover`, in the variants that count them
+    fn count_check(&mut self, mover: Color) {
+        if let VariantState::ThreeCheck { checks_left } = self {
+            checks_left[mover] = checks_left[mover].saturating_sub(1);
+        }
+    }
+
+    // a crazyhouse move: what `mover` took goes in their pocket, a promoted piece going
+    // back in as a pawn, and the promoted mark goes along with the piece
+    fn follow_move(
+        &mut self,
+        mover: Color,
+        from: Square,
+        to: Square,
+        captured: Option<Piece>,
+        promotion: bool,
+    ) {
+        let VariantState::Crazyhouse { pockets, promoted } = self else {
+            return;
+        };
+        if let Some(piece) = captured {
+            let figure = match promoted.contains(to) {
+                true => Figure::Pawn,
+                false => piece.figure,
+            };
+            // a full pocket keeps the count it has
+            let _ = pockets[mover].add(figure);
+        }
+        let was_promoted = promoted.contains(from);
+        *promoted &= !(BitBoard::from(from) | BitBoard::from(to));
+        if was_promoted || promotion {
+            *promoted |= BitBoard::from(to);
+        }
+    }
+}
+
+// the square of each character of a placement that stands for a piece, None for the rest
+fn placement_squares(placement: &str) -> impl Iterator<Item = Option<Square>> + '_ {
+    let (mut row, mut col) = (7u8, 0u8);
+    placement.chars().map(move |c| match c {
+        '/' => {
+            row = row.saturating_sub(1);
+            col = 0;
+            None
+        }
+        c if c.is_ascii_digit() => {
+            col = col.saturating_add(c.to_digit(10).unwrap_or_default() as u8);
+            None
+        }
+        c if c.is_ascii_alphabetic() && col < 8 => {
+            col += 1;
+            Some(Square::from_coords(
+                (col - 1).try_into().ok()?,
+                row.try_into().ok()?,
+            ))
+        }
+        _ => None,
+    })
+}
+
+// pockets in brackets after the placement, or as a ninth rank, and `~` after a promoted
+// piece: "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR[Qn] w KQkq - 0 1"
+fn read_crazyhouse(parts: &mut FenParts) -> Result<Option<VariantState>, ParseFenError> {
+    let (board, pocket) = match parts.placement.split_once('[') {
+        Some((board, pocket)) => (board.to_owned(), pocket.trim_end_matches(']').to_owned()),
+        None if parts.placement.split('/').count() == 9 => {
+            let (board, pocket) = parts.placement.rsplit_once('/').unwrap_or_default();
+            (board.to_owned(), pocket.to_owned())
+        }
+        None => return Ok(None),
+    };
+    let mut pockets = [Pocket::default(); 2];
+    let invalid = || ParseFenError::InvalidField {
+        field: FenField::Placement,
+        value: pocket.clone(),
+    };
+    for c in pocket.chars() {
+        let piece = Piece::try_from(c).map_err(|_| invalid())?;
+        if !pockets[piece.color].add(piece.figure) {
+            return Err(invalid());
+        }
+    }
+    let mut promoted = BitBoard::default();
+    let mut last = None;
+    for (c, square) in board.chars().zip(placement_squares(&board)) {
+        match (c, last) {
+            ('~', Some(square)) => promoted |= BitBoard::from(square),
+            _ => last = square,
+        }
+    }
+    parts.placement = board.replace('~', "");
+    Ok(Some(VariantState::Crazyhouse { pockets, promoted }))
+}
+
+// the checks left to give as a field after en passant, "3+3", or the checks already
+// given after the move counters, lichess' "+0+0"
+fn read_three_check(parts: &mut FenParts) -> Result<Option<VariantState>, ParseFenError> {
+    let invalid = |value: &str| ParseFenError::InvalidField {
+        field: FenField::EnPassant,
+        value: value.to_owned(),
+    };
+    let counts = |text: &str| -> Option<[u8; 2]> {
+        let (white, black) = text.split_once('+')?;
+        Some([white.parse().ok()?, black.parse().ok()?])
+    };
+    if parts.fields.len() == 6 && parts.fields[3].contains('+') {
+        let field = parts.fields.remove(3);
+        let checks_left = counts(&field).ok_or_else(|| invalid(&field))?;
+        return Ok(Some(VariantState::ThreeCheck { checks_left }));
+    }
+    if parts.fields.last().is_some_and(|f| f.starts_with('+')) {
+        let field = parts.fields.pop().unwrap_or_default();
+        let [white, black] = counts(&field[1..]).ok_or_else(|| invalid(&field))?;
+        let checks_left = [3u8.saturating_sub(white), 3u8.saturating_sub(black)];
+        return Ok(Some(VariantState::ThreeCheck { checks_left }));
+    }
+    Ok(None)
+}
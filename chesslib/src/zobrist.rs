@@ -0,0 +1,41 @@
+use crate::{GameState, pieces::Color};
+
+const fn gen_keys<const N: usize>(seed: u64) -> [u64; N] {
+    // splitmix64
+    let mut keys = [0; N];
+    let mut state = seed;
+    let mut idx = 0;
+    while idx < N {
+        state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        keys[idx] = z ^ (z >> 31);
+        idx += 1;
+    }
+    keys
+}
+
+static PIECE_KEYS: [u64; 768] = gen_keys(0x2545_f491_4f6c_dd1d);
+static CASTLE_KEYS: [u64; 16] = gen_keys(0x7c3a_1e5b_d2f0_8a64);
+static EP_KEYS: [u64; 8] = gen_keys(0x5e1d_93c7_0b4a_f628);
+const BLACK_TO_MOVE: u64 = 0xf8d6_26aa_af27_8509;
+
+impl GameState {
+    pub fn zobrist(&self) -> u64 {
+        let mut hash = 0;
+        for (square, piece) in self.board.iter() {
+            hash ^= PIECE_KEYS
+                [(piece.color as usize * 6 + piece.figure as usize) * 64 + square.index()];
+        }
+        hash ^= CASTLE_KEYS[self.castle as usize];
+        // ep only matters if it can actually be captured
+        if let Some((_, ep)) = self.legal_ep_capture() {
+            hash ^= EP_KEYS[ep.col() as usize];
+        }
+        if self.turn == Color::Black {
+            hash ^= BLACK_TO_MOVE;
+        }
+        hash
+    }
+}
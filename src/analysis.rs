@@ -0,0 +1,197 @@
+use crate::{engine_options, supervisor::Supervisor};
+use chesslib::{
+    errors::UciError,
+    uci::{GoLimits, Score, UciEngine},
+};
+use std::{
+    sync::mpsc::{self, Receiver, Sender},
+    thread,
+    time::Duration,
+};
+
+const MAX_DEPTH: u32 = 30;
+const SEARCH_TIMEOUT: Duration = Duration::from_secs(60 * 60);
+pub const ANALYSIS_LINES: usize = 3;
+
+// one of the engine's candidate lines, from White's point of view
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Line {
+    pub score: Score,
+    pub depth: u32,
+    // in UCI notation
+    pub pv: Vec<String>,
+}
+
+// the engine's opinion of a position, from White's point of view
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Evaluation {
+    pub fen: String,
+    pub score: Score,
+    pub depth: u32,
+    // first move of the principal variation, in UCI notation
+    pub best_move: Option<String>,
+    // the best few lines, best first, when the engine can look at several at once
+    pub lines: Vec<Line>,
+}
+
+impl Evaluation {
+    // share of the eval bar that belongs to White
+    pub fn white_share(&self) -> f32 {
+        match self.score {
+            Score::Mate(n) if n > 0 => 1.0,
+            Score::Mate(n) if n < 0 => 0.0,
+            Score::Mate(_) => 0.5,
+            Score::Centipawns(cp) => 1.0 / (1.0 + (-0.004 * cp as f32).exp()),
+        }
+    }
+
+    pub fn label(&self) -> String {
+        match self.score {
+            Score::Mate(n) => format!("M{}", n.abs()),
+            Score::Centipawns(cp) => format!("{:+.1}", cp as f32 / 100.0),
+        }
+    }
+}
+
+impl Line {
+    pub fn label(&self) -> String {
+        match self.score {
+            Score::Mate(n) => format!("#{n}"),
+            Score::Centipawns(cp) => format!("{:+.2}", cp as f32 / 100.0),
+        }
+    }
+}
+
+// a UCI engine on its own thread, always working on the latest position it was given
+pub struct Analyzer {
+    positions: Sender<String>,
+    evaluations: Receiver<Evaluation>,
+    warnings: Receiver<String>,
+    current: String,
+    latest: Option<Evaluation>,
+}
+
+impl Analyzer {
+    pub fn spawn(program: String) -> Self {
+        let (positions, position_rx) = mpsc::channel();
+        let (evaluation_tx, evaluations) = mpsc::channel();
+        let (warning_tx, warnings) = mpsc::channel();
+        thread::spawn(move || {
+            let mut supervisor = Supervisor::new(warning_tx.clone());
+            let result = engine_options::spawn(&program).and_then(|mut engine| {
+                run(&mut engine, &position_rx, &evaluation_tx, &mut supervisor)
+            });
+            if let Err(err) = result {
+                log::warn!("analysis engine {program} stopped: {err}");
+                let _ = warning_tx.send(format!("Analysis stopped: {err}"));
+            }
+        });
+        Self {
+            positions,
+            evaluations,
+            warnings,
+            current: String::new(),
+            latest: None,
+        }
+    }
+
+    pub fn analyse(&mut self, fen: &str) {
+        if self.current == fen {
+            return;
+        }
+        self.current = fen.to_owned();
+        self.latest = None;
+        let _ = self.positions.send(self.current.clone());
+    }
+
+    // newest evaluation of the position last passed to `analyse`
+    pub fn evaluation(&mut self) -> Option<&Evaluation> {
+        for evaluation in self.evaluations.try_iter() {
+            if evaluation.fen == self.current {
+                self.latest = Some(evaluation);
+            }
+        }
+        self.latest.as_ref()
+    }
+
+    // what's gone wrong with the engine since last asked, for the GUI to pass on
+    pub fn warnings(&self) -> impl Iterator<Item = String> + '_ {
+        self.warnings.try_iter()
+    }
+
+    // the evaluation as of the last `evaluation`, for drawing
+    pub fn latest(&self) -> Option<&Evaluation> {
+        self.latest.as_ref()
+    }
+}
+
+fn run(
+    engine: &mut UciEngine,
+    positions: &Receiver<String>,
+    evaluations: &Sender<Evaluation>,
+    supervisor: &mut Supervisor,
+) -> Result<(), UciError> {
+    let limits = GoLimits {
+        depth: Some(MAX_DEPTH),
+        ..Default::default()
+    };
+    if engine.has_option("MultiPV") {
+        engine.set_option("MultiPV", &ANALYSIS_LINES.to_string())?;
+    }
+    let mut next = positions.recv().ok();
+    while let Some(fen) = next.take() {
+        let white_to_move = fen.split(' ').nth(1) == Some("w");
+        let mut lines: Vec<Line> = Vec::new();
+        let searched = engine.set_position(Some(&fen), &[]).and_then(|_| {
+            engine.go_with_info(&limits, SEARCH_TIMEOUT, |result| {
+                if let (Some(score), Some(depth)) = (result.score, result.depth) {
+                    let score = match score {
+                        _ if white_to_move => score,
+                        Score::Centipawns(cp) => Score::Centipawns(-cp),
+                        Score::Mate(n) => Score::Mate(-n),
+                    };
+                    let idx = result.multipv.unwrap_or(1).saturating_sub(1) as usize;
+                    let line = Line {
+                        score,
+                        depth,
+                        pv: result.pv.clone(),
+                    };
+                    // a line further down can't come before the ones above it
+                    if idx < lines.len() {
+                        lines[idx] = line;
+                    } else if idx == lines.len() {
+                        lines.push(line);
+                    }
+                    if let Some(best) = lines.first() {
+                        let evaluation = Evaluation {
+                            fen: fen.clone(),
+                            score: best.score,
+                            depth: best.depth,
+                            best_move: best.pv.first().cloned(),
+                            lines: lines.clone(),
+                        };
+                        if evaluations.send(evaluation).is_err() {
+                            return false;
+                        }
+                    }
+                }
+                // drop the search as soon as a newer position comes in
+                if let Some(fen) = positions.try_iter().last() {
+                    next = Some(fen);
+                }
+                next.is_none()
+            })
+        });
+        if let Err(err) = searched {
+            supervisor.recover(engine, err)?;
+            // the same position again, unless a newer one came in meanwhile
+            next.get_or_insert(fen);
+            continue;
+        }
+        supervisor.succeeded();
+        if next.is_none() {
+            next = positions.recv().ok();
+        }
+    }
+    Ok(())
+}
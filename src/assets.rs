@@ -0,0 +1,104 @@
+use macroquad::{
+    audio::{Sound, load_sound, load_sound_from_bytes},
+    file::load_string,
+    prelude::ImageFormat,
+    texture::{Image, Texture2D, load_texture},
+};
+
+// the shipped defaults, copied into the program by the embed-assets feature (on by
+// default) so it runs without assets/; a file of the same name on disk wins
+pub const BUILTIN_BOARD: &str = "assets/boards/default.png";
+pub const BUILTIN_PIECES: &str = "assets/pieces/wiki_chess.png";
+
+pub fn builtin_asset(path: &str) -> Option<&'static [u8]> {
+    #[cfg(feature = "embed-assets")]
+    {
+        Some(match path {
+            "assets/boards/default.png" => include_bytes!("../assets/boards/default.png"),
+            "assets/pieces/wiki_chess.png" => include_bytes!("../assets/pieces/wiki_chess.png"),
+            "assets/sounds/Capture.ogg" => include_bytes!("../assets/sounds/Capture.ogg"),
+            "assets/sounds/Confirmation.ogg" => {
+                include_bytes!("../assets/sounds/Confirmation.ogg")
+            }
+            "assets/sounds/Error.ogg" => include_bytes!("../assets/sounds/Error.ogg"),
+            "assets/sounds/Move.ogg" => include_bytes!("../assets/sounds/Move.ogg"),
+            "assets/sounds/Select.ogg" => include_bytes!("../assets/sounds/Select.ogg"),
+            _ => return None,
+        })
+    }
+    #[cfg(not(feature = "embed-assets"))]
+    {
+        let _ = path;
+        None
+    }
+}
+
+// puts a built-in asset at the front of a listing that doesn't have it on disk
+pub fn add_builtin(paths: &mut Vec<String>, builtin: &str) {
+    if builtin_asset(builtin).is_some() && !paths.iter().any(|path| path == builtin) {
+        paths.insert(0, builtin.to_owned());
+    }
+}
+
+// a PNG from disk, or its built-in copy
+pub async fn load_texture_asset(path: &str) -> Option<Texture2D> {
+    match load_texture(path).await {
+        Ok(texture) => Some(texture),
+        Err(_) => {
+            let image = Image::from_file_with_format(builtin_asset(path)?, Some(ImageFormat::Png));
+            image.ok().map(|image| Texture2D::from_image(&image))
+        }
+    }
+}
+
+pub async fn load_sound_asset(path: &str) -> Option<Sound> {
+    match load_sound(path).await {
+        Ok(sound) => Some(sound),
+        Err(_) => load_sound_from_bytes(builtin_asset(path)?).await.ok(),
+    }
+}
+
+// paths of the files in an asset directory with the given extension, sorted. A browser
+// can't list a directory, so the web build reads them from the directory's index.txt,
+// one file name per line.
+pub async fn list_assets(dir: &str, extension: &str) -> Vec<String> {
+    let mut names = list_dir(dir).await;
+    names.retain(|name| {
+        name.rsplit_once('.')
+            .is_some_and(|(_, ext)| ext == extension)
+    });
+    names.sort();
+    names
+        .into_iter()
+        .map(|name| format!("{dir}/{name}"))
+        .collect()
+}
+
+// a text asset, fetched over HTTP on the web
+pub async fn read_asset(path: &str) -> Option<String> {
+    load_string(path).await.ok()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+async fn list_dir(dir: &str) -> Vec<String> {
+    std::fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok()?.file_name().into_string().ok())
+        .collect()
+}
+
+#[cfg(target_arch = "wasm32")]
+async fn list_dir(dir: &str) -> Vec<String> {
+    read_asset(&format!("{dir}/index.txt"))
+        .await
+        .map(|index| {
+            index
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty())
+                .map(str::to_owned)
+                .collect()
+        })
+        .unwrap_or_default()
+}
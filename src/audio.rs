@@ -0,0 +1,75 @@
+use crate::assets::load_sound_asset;
+use macroquad::audio::{PlaySoundParams, Sound, play_sound};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum SoundEffect {
+    Move,
+    Capture,
+    Castle,
+    Promotion,
+    Check,
+    GameEnd,
+    Illegal,
+}
+
+impl SoundEffect {
+    const ALL: [SoundEffect; 7] = [
+        SoundEffect::Move,
+        SoundEffect::Capture,
+        SoundEffect::Castle,
+        SoundEffect::Promotion,
+        SoundEffect::Check,
+        SoundEffect::GameEnd,
+        SoundEffect::Illegal,
+    ];
+
+    // its own file, then the stock sound to use when that file isn't shipped
+    fn files(self) -> (&'static str, &'static str) {
+        match self {
+            SoundEffect::Move => ("Move.ogg", "Move.ogg"),
+            SoundEffect::Capture => ("Capture.ogg", "Capture.ogg"),
+            SoundEffect::Castle => ("Castle.ogg", "Move.ogg"),
+            SoundEffect::Promotion => ("Promote.ogg", "Confirmation.ogg"),
+            SoundEffect::Check => ("Check.ogg", "Select.ogg"),
+            SoundEffect::GameEnd => ("GameEnd.ogg", "Confirmation.ogg"),
+            SoundEffect::Illegal => ("Error.ogg", "Error.ogg"),
+        }
+    }
+}
+
+pub struct Audio {
+    sounds: Vec<Option<Sound>>,
+    pub volume: f32,
+    pub muted: bool,
+}
+
+impl Audio {
+    pub async fn load() -> Self {
+        let mut sounds = Vec::with_capacity(SoundEffect::ALL.len());
+        for effect in SoundEffect::ALL {
+            let (file, fallback) = effect.files();
+            let sound = match load_sound_asset(&format!("assets/sounds/{file}")).await {
+                Some(sound) => Some(sound),
+                None => load_sound_asset(&format!("assets/sounds/{fallback}")).await,
+            };
+            sounds.push(sound);
+        }
+        Self {
+            sounds,
+            volume: 1.0,
+            muted: false,
+        }
+    }
+
+    pub fn play(&self, effect: SoundEffect) {
+        if self.muted {
+            return;
+        }
+        if let Some(sound) = &self.sounds[effect as usize] {
+            play_sound(sound, PlaySoundParams {
+                looped: false,
+                volume: self.volume,
+            });
+        }
+    }
+}
@@ -0,0 +1,444 @@
+use anyhow::{Context, anyhow, bail};
+use chesslib::{
+    eco::EcoTable,
+    pgn::{GameResult, PgnGame, PgnReader},
+    prelude::*,
+    training::{CSV_HEADER, samples},
+    uci::{GoLimits, Score, UciEngine},
+};
+use std::{
+    collections::HashSet,
+    fs,
+    io::{self, BufRead, BufReader, BufWriter, Write},
+    time::Duration,
+};
+
+const USAGE: &str = "usage: chesscli perft DEPTH [-divide] [FEN]
+       chesscli fen [FEN]
+       chesscli san MOVE [FEN]
+       chesscli pgn-to-fens [FILE]
+       chesscli eval -engine PATH [-depth N | -movetime MS] [FEN]
+       chesscli bestmove -engine PATH [-depth N | -movetime MS] [FEN]
+       chesscli filter [FILE] [-eco C20-C99] [-rating MIN-MAX] [-result 1-0]
+                       [-position FEN] [-fens]
+       chesscli export [FILE] [-format csv|planes] [-out PATH]
+                       [-engine PATH [-depth N | -movetime MS]]
+
+FEN defaults to the starting position; fen without one normalizes each line
+of stdin, and pgn-to-fens and filter read stdin without a FILE. filter writes
+the games that pass every test given, or with -fens each position in them once.
+export writes training data: a CSV of FEN, move, result and eval, or with
+-format planes a 103-byte record per position, as laid out in chesslib's
+training module.";
+
+const DEFAULT_DEPTH: u32 = 18;
+// how long past its own limit an engine gets before it's given up on
+const TIMEOUT_MARGIN: Duration = Duration::from_secs(5);
+const DEPTH_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+
+#[derive(Debug, Default)]
+struct Args {
+    command: String,
+    positional: Vec<String>,
+    divide: bool,
+    engine: Option<String>,
+    depth: Option<u32>,
+    move_time: Option<Duration>,
+    eco: Option<(String, String)>,
+    rating: Option<(u32, u32)>,
+    result: Option<GameResult>,
+    reached: Option<GameState>,
+    fens: bool,
+    format: ExportFormat,
+    out: Option<String>,
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
+enum ExportFormat {
+    #[default]
+    Csv,
+    Planes,
+}
+
+impl Args {
+    // a FEN may come as one quoted argument or spread over several
+    fn position(&self, skip: usize) -> Result<GameState, anyhow::Error> {
+        let fen = self.positional[skip.min(self.positional.len())..].join(" ");
+        match fen.is_empty() {
+            true => Ok(GameState::default()),
+            false => parse_fen(&fen),
+        }
+    }
+}
+
+// an EPD-style FEN without its move counters is taken as at the start of a game
+fn parse_fen(fen: &str) -> Result<GameState, anyhow::Error> {
+    let fen = fen.split_whitespace().collect::<Vec<_>>().join(" ");
+    let fen = match fen.split(' ').count() {
+        4 => format!("{fen} 0 1"),
+        _ => fen,
+    };
+    GameState::try_from_fen(&fen).with_context(|| format!("bad FEN {fen}"))
+}
+
+fn main() -> Result<(), anyhow::Error> {
+    let args = parse_args(std::env::args().skip(1))?;
+    match args.command.as_str() {
+        "perft" => perft(&args),
+        "fen" => fen(&args),
+        "san" => san(&args),
+        "pgn-to-fens" => pgn_to_fens(&args),
+        "eval" => eval(&args),
+        "bestmove" => best_move(&args),
+        "filter" => filter(&args),
+        "export" => export(&args),
+        "" | "help" => bail!("{USAGE}"),
+        other => bail!("unknown command {other}\n{USAGE}"),
+    }
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Args, anyhow::Error> {
+    let mut parsed = Args {
+        command: args.next().unwrap_or_default(),
+        ..Default::default()
+    };
+    while let Some(arg) = args.next() {
+        let mut value = |flag: &str| args.next().ok_or(anyhow!("{flag} needs a value"));
+        match arg.as_str() {
+            "-divide" => parsed.divide = true,
+            "-engine" => parsed.engine = Some(value(&arg)?),
+            "-depth" => parsed.depth = Some(value(&arg)?.parse()?),
+            "-movetime" => {
+                parsed.move_time = Some(Duration::from_millis(value(&arg)?.parse()?));
+            }
+            "-eco" => {
+                let eco = value(&arg)?.to_ascii_uppercase();
+                parsed.eco = Some(match eco.split_once('-') {
+                    Some((first, last)) => (first.to_owned(), last.to_owned()),
+                    None => (eco.clone(), eco),
+                });
+            }
+            "-rating" => {
+                let range = value(&arg)?;
+                let (min, max) = range.split_once('-').unwrap_or((&range, ""));
+                let bound = |text: &str, or| match text {
+                    "" => Ok(or),
+                    text => text
+                        .parse()
+                        .with_context(|| format!("bad rating range {range}")),
+                };
+                parsed.rating = Some((bound(min, 0)?, bound(max, u32::MAX)?));
+            }
+            "-result" => parsed.result = Some(value(&arg)?.parse()?),
+            "-position" => parsed.reached = Some(parse_fen(&value(&arg)?)?),
+            "-fens" => parsed.fens = true,
+            "-format" => {
+                parsed.format = match value(&arg)?.as_str() {
+                    "csv" => ExportFormat::Csv,
+                    "planes" => ExportFormat::Planes,
+                    other => bail!("unknown export format {other}"),
+                };
+            }
+            "-out" => parsed.out = Some(value(&arg)?),
+            "-h" | "-help" | "--help" => bail!("{USAGE}"),
+            // a lone "-" is stdin, or a FEN field with no castling or en passant
+            flag if flag.starts_with('-') && flag.len() > 1 => {
+                bail!("unknown option {flag}\n{USAGE}")
+            }
+            _ => parsed.positional.push(arg),
+        }
+    }
+    Ok(parsed)
+}
+
+fn perft(args: &Args) -> Result<(), anyhow::Error> {
+    let depth: u8 = (args.positional.first())
+        .ok_or(anyhow!("perft needs a depth\n{USAGE}"))?
+        .parse()?;
+    let gs = args.position(1)?;
+    if !args.divide {
+        println!("{}", gs.perft(depth));
+        return Ok(());
+    }
+    let mut total = 0;
+    for (mv, nodes) in gs.perft_divide(depth) {
+        println!("{mv}: {nodes}");
+        total += nodes;
+    }
+    println!("\n{total}");
+    Ok(())
+}
+
+// the FEN as the library writes it back, with the move counters filled in when missing
+fn fen(args: &Args) -> Result<(), anyhow::Error> {
+    if !args.positional.is_empty() {
+        println!("{}", args.position(0)?.to_fen());
+        return Ok(());
+    }
+    let mut bad = 0;
+    for line in io::stdin().lock().lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        match parse_fen(&line) {
+            Ok(gs) => println!("{}", gs.to_fen()),
+            Err(err) => {
+                eprintln!("{err:#}");
+                bad += 1;
+            }
+        }
+    }
+    if bad > 0 {
+        bail!("{bad} bad FEN(s)");
+    }
+    Ok(())
+}
+
+// a move in SAN, UCI or long algebraic, written back as SAN
+fn san(args: &Args) -> Result<(), anyhow::Error> {
+    let text = (args.positional.first()).ok_or(anyhow!("san needs a move\n{USAGE}"))?;
+    let gs = args.position(1)?;
+    let mv = match gs.parse_uci_move(text) {
+        Some(mv) => mv,
+        None => gs
+            .parse_san(text)
+            .or_else(|err| gs.parse_lan(text).map_err(|_| err))
+            .with_context(|| format!("{text} isn't a legal move here"))?,
+    };
+    println!("{}", gs.to_san(mv));
+    Ok(())
+}
+
+// the games in the FILE argument, or on stdin, read as they're needed
+fn games(args: &Args) -> Result<PgnReader<Box<dyn BufRead>>, anyhow::Error> {
+    let input: Box<dyn BufRead> = match args.positional.first().map(String::as_str) {
+        None | Some("-") => Box::new(io::stdin().lock()),
+        Some(path) => Box::new(BufReader::new(
+            fs::File::open(path).with_context(|| format!("couldn't read {path}"))?,
+        )),
+    };
+    Ok(PgnReader::new(input))
+}
+
+// the position before each move and the one the game ends in
+fn positions(game: &PgnGame) -> Result<Vec<GameState>, anyhow::Error> {
+    let played = game.replay()?;
+    let end = match played.last() {
+        Some((gs, mv)) => gs.pv_end(&[mv.to_string()]),
+        None => game.start_position()?,
+    };
+    let mut positions: Vec<_> = played.into_iter().map(|(gs, _)| gs).collect();
+    positions.push(end);
+    Ok(positions)
+}
+
+// every position of every game, a blank line between games
+fn pgn_to_fens(args: &Args) -> Result<(), anyhow::Error> {
+    for (idx, game) in games(args)?.enumerate() {
+        if idx > 0 {
+            println!();
+        }
+        for gs in positions(&game?)? {
+            println!("{}", gs.to_fen());
+        }
+    }
+    Ok(())
+}
+
+// each position played from, with the move and the game's result, and the engine's eval
+// with -engine; as CSV or as fixed-size records for -format planes
+fn export(args: &Args) -> Result<(), anyhow::Error> {
+    let mut engine = args
+        .engine
+        .as_ref()
+        .map(|_| start_engine(args))
+        .transpose()?;
+    let out: Box<dyn Write> = match &args.out {
+        Some(path) => {
+            Box::new(fs::File::create(path).with_context(|| format!("couldn't write {path}"))?)
+        }
+        None => Box::new(io::stdout().lock()),
+    };
+    let mut out = BufWriter::new(out);
+    if args.format == ExportFormat::Csv {
+        writeln!(out, "{CSV_HEADER}")?;
+    }
+    let mut bad = 0;
+    for game in games(args)? {
+        let Ok(mut samples) = game
+            .map_err(anyhow::Error::from)
+            .and_then(|game| Ok(samples(&game)?))
+        else {
+            bad += 1;
+            continue;
+        };
+        for sample in &mut samples {
+            if let Some(engine) = &mut engine {
+                let (score, _, _) = search(engine, args, &sample.gs)?;
+                sample.eval = Some(white_score(score, sample.gs.get_turn()));
+            }
+            match args.format {
+                ExportFormat::Csv => writeln!(out, "{}", sample.to_csv())?,
+                ExportFormat::Planes => out.write_all(&sample.to_record())?,
+            }
+        }
+    }
+    out.flush()?;
+    if let Some(engine) = engine {
+        engine.quit();
+    }
+    if bad > 0 {
+        eprintln!("skipped {bad} game(s) that couldn't be read");
+    }
+    Ok(())
+}
+
+// games that don't read or replay are skipped, with a count at the end
+fn filter(args: &Args) -> Result<(), anyhow::Error> {
+    let table = EcoTable::builtin();
+    let mut seen = HashSet::new();
+    let (mut written, mut bad) = (0, 0);
+    for game in games(args)? {
+        let Ok(game) = game else {
+            bad += 1;
+            continue;
+        };
+        let Ok(positions) = positions(&game) else {
+            bad += 1;
+            continue;
+        };
+        if !passes(args, &table, &game, &positions) {
+            continue;
+        }
+        if !args.fens {
+            if written > 0 {
+                println!();
+            }
+            print!("{game}");
+            written += 1;
+            continue;
+        }
+        for gs in positions {
+            if seen.insert(gs.zobrist()) {
+                println!("{}", gs.to_fen());
+            }
+        }
+    }
+    if bad > 0 {
+        eprintln!("skipped {bad} game(s) that couldn't be read");
+    }
+    Ok(())
+}
+
+fn passes(args: &Args, table: &EcoTable, game: &PgnGame, positions: &[GameState]) -> bool {
+    // the game's own ECO tag, or where the built-in table places it
+    if let Some((first, last)) = &args.eco {
+        let eco = match game.tag("ECO") {
+            Some(eco) => Some(eco.to_ascii_uppercase()),
+            None => table.classify(positions).map(|opening| opening.eco.clone()),
+        };
+        let within = |eco: &String| {
+            (eco >= first || eco.starts_with(first.as_str()))
+                && (eco <= last || eco.starts_with(last.as_str()))
+        };
+        if !eco.is_some_and(|eco| within(&eco)) {
+            return false;
+        }
+    }
+    // both players, so a game without their ratings never passes
+    if let Some((min, max)) = args.rating {
+        let rated = |tag| {
+            game.tag(tag)
+                .and_then(|elo| elo.parse::<u32>().ok())
+                .is_some_and(|elo| (min..=max).contains(&elo))
+        };
+        if !rated("WhiteElo") || !rated("BlackElo") {
+            return false;
+        }
+    }
+    if args.result.is_some() && game.result != args.result {
+        return false;
+    }
+    match &args.reached {
+        Some(reached) => positions.iter().any(|gs| gs.zobrist() == reached.zobrist()),
+        None => true,
+    }
+}
+
+// the engine's score from White's side, its depth and its line in SAN
+fn eval(args: &Args) -> Result<(), anyhow::Error> {
+    let gs = args.position(0)?;
+    let mut engine = start_engine(args)?;
+    let (score, depth, pv) = search(&mut engine, args, &gs)?;
+    engine.quit();
+    let score = match white_score(score, gs.get_turn()) {
+        Score::Mate(n) => format!("#{n}"),
+        Score::Centipawns(cp) => format!("{:+.2}", cp as f32 / 100.0),
+    };
+    let depth = depth.map_or(String::new(), |depth| format!(" depth {depth}"));
+    println!("{score}{depth} {}", gs.pv_to_san(&pv).join(" "));
+    Ok(())
+}
+
+fn best_move(args: &Args) -> Result<(), anyhow::Error> {
+    let gs = args.position(0)?;
+    let mut engine = start_engine(args)?;
+    let (_, _, pv) = search(&mut engine, args, &gs)?;
+    engine.quit();
+    let best = pv.first().ok_or(anyhow!("the engine has no move here"))?;
+    println!("{best}");
+    Ok(())
+}
+
+fn start_engine(args: &Args) -> Result<UciEngine, anyhow::Error> {
+    let program = (args.engine.as_ref()).ok_or(anyhow!("-engine is needed\n{USAGE}"))?;
+    UciEngine::spawn(program, &[]).with_context(|| format!("failed to start {program}"))
+}
+
+// one search by the engine, to -depth or for -movetime
+fn search(
+    engine: &mut UciEngine,
+    args: &Args,
+    gs: &GameState,
+) -> Result<(Score, Option<u32>, Vec<String>), anyhow::Error> {
+    let (limits, timeout) = match args.move_time {
+        Some(move_time) => (
+            GoLimits {
+                move_time: Some(move_time),
+                depth: args.depth,
+                ..Default::default()
+            },
+            move_time + TIMEOUT_MARGIN,
+        ),
+        None => (
+            GoLimits {
+                depth: Some(args.depth.unwrap_or(DEFAULT_DEPTH)),
+                ..Default::default()
+            },
+            DEPTH_TIMEOUT,
+        ),
+    };
+    engine.set_position(Some(&gs.to_fen()), &[])?;
+    let result = engine.go(&limits, timeout)?;
+    let mut pv = result.pv;
+    if pv.first() != Some(&result.best_move) {
+        pv = vec![result.best_move];
+    }
+    // all an engine has to say about a finished game is "(none)"
+    pv.retain(|uci| uci != "(none)");
+    Ok((
+        result.score.unwrap_or(Score::Centipawns(0)),
+        result.depth,
+        pv,
+    ))
+}
+
+// an engine's score for the side to move, from White's side
+fn white_score(score: Score, turn: Color) -> Score {
+    match (score, turn) {
+        (score, Color::White) => score,
+        (Score::Centipawns(cp), Color::Black) => Score::Centipawns(-cp),
+        (Score::Mate(n), Color::Black) => Score::Mate(-n),
+    }
+}
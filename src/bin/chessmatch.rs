@@ -0,0 +1,264 @@
+use anyhow::{Context, anyhow, bail};
+use chesslib::{
+    clock::TimeControl,
+    engine_match::{
+        DrawAdjudication, GameRecord, MatchConfig, MatchScore, Opening, OpeningFormat,
+        ResignAdjudication, load_openings, play_game,
+    },
+    prelude::*,
+    uci::UciEngine,
+};
+use std::{collections::HashMap, fs, io::Write};
+
+const USAGE: &str =
+    "usage: chessmatch -engine cmd=PATH [name=NAME] [arg=ARG]... [option.NAME=VALUE]...
+                  -engine cmd=PATH ...
+                  [-each tc=40/60+0.6 option.NAME=VALUE ...]
+                  [-blacktc TC] [-armageddon] [-tiebreak]
+                  [-games N] [-repeat] [-event NAME]
+                  [-openings file=PATH format=epd|pgn]
+                  [-draw movenumber=N movecount=N score=CP]
+                  [-resign movecount=N score=CP]
+                  [-maxmoves N] [-pgnout PATH]";
+
+#[derive(Debug, Default)]
+struct EngineConfig {
+    cmd: String,
+    name: Option<String>,
+    args: Vec<String>,
+    options: Vec<(String, String)>,
+}
+
+struct Args {
+    engines: Vec<EngineConfig>,
+    config: MatchConfig,
+    games: usize,
+    repeat: bool,
+    openings: Vec<Opening>,
+    pgn_out: Option<String>,
+    // an Armageddon game after the rest when the match is level, the first engine White
+    tiebreak: bool,
+}
+
+fn main() -> Result<(), anyhow::Error> {
+    let args = parse_args(std::env::args().skip(1))?;
+    let mut engines = args
+        .engines
+        .iter()
+        .map(|config| {
+            let mut engine = UciEngine::spawn(&config.cmd, &config.args)
+                .with_context(|| format!("failed to start {}", config.cmd))?;
+            for (name, value) in &config.options {
+                engine.set_option(name, value)?;
+            }
+            Ok(engine)
+        })
+        .collect::<Result<Vec<_>, anyhow::Error>>()?;
+    let names: Vec<String> = engines
+        .iter()
+        .zip(&args.engines)
+        .map(|(engine, config)| config.name.clone().unwrap_or(engine.name().to_owned()))
+        .collect();
+
+    let mut pgn_out = match &args.pgn_out {
+        Some(path) => Some(fs::File::create(path)?),
+        None => None,
+    };
+    let mut score = MatchScore::default();
+    let mut play_round = |round: usize, first_color, opening: &Opening, config: &MatchConfig| {
+        let (first, second) = engines.split_at_mut(1);
+        let players = match first_color {
+            Color::White => [&mut first[0], &mut second[0]],
+            Color::Black => [&mut second[0], &mut first[0]],
+        };
+        let mut record = play_game(players, opening, round + 1, config)?;
+        name_players(&mut record, &names, first_color);
+        score.record(record.result, first_color);
+        println!(
+            "Game {} ({} vs {}): {} {{{}}}",
+            round + 1,
+            record.game.tag("White").unwrap_or("?"),
+            record.game.tag("Black").unwrap_or("?"),
+            record.result,
+            record.termination
+        );
+        println!("Score of {} vs {}: {}", names[0], names[1], score);
+        if let Some(file) = pgn_out.as_mut() {
+            writeln!(file, "{}", record.game)?;
+        }
+        Ok::<_, anyhow::Error>(score)
+    };
+    let mut level = args.games == 0;
+    for round in 0..args.games {
+        // with -repeat each opening is played twice, once from each side
+        let opening_idx = if args.repeat { round / 2 } else { round };
+        let opening = &args.openings[opening_idx % args.openings.len()];
+        let first_color = match round % 2 {
+            0 => Color::White,
+            _ => Color::Black,
+        };
+        let score = play_round(round, first_color, opening, &args.config)?;
+        level = score.wins == score.losses;
+    }
+    if args.tiebreak && level {
+        let config = MatchConfig {
+            armageddon: true,
+            black_time_control: Some(args.config.time_control.armageddon_black()),
+            ..args.config.clone()
+        };
+        println!("Match level, Armageddon tie-break");
+        play_round(args.games, Color::White, &args.openings[0], &config)?;
+    }
+    for engine in engines {
+        engine.quit();
+    }
+    Ok(())
+}
+
+fn name_players(record: &mut GameRecord, names: &[String], first_color: Color) {
+    for (tag, value) in record.game.tags.iter_mut() {
+        match (tag.as_str(), first_color) {
+            ("White", Color::White) | ("Black", Color::Black) => *value = names[0].clone(),
+            ("White", Color::Black) | ("Black", Color::White) => *value = names[1].clone(),
+            _ => {}
+        }
+    }
+}
+
+fn parse_args(args: impl Iterator<Item = String>) -> Result<Args, anyhow::Error> {
+    let mut engines = Vec::new();
+    let mut each = Vec::new();
+    let mut config = MatchConfig {
+        event: "Engine match".to_owned(),
+        time_control: "60+0.6".parse()?,
+        draw: None,
+        resign: None,
+        max_moves: None,
+        black_time_control: None,
+        armageddon: false,
+    };
+    let mut games = 2;
+    let mut repeat = false;
+    let mut tiebreak = false;
+    let mut openings = vec![Opening::default()];
+    let mut pgn_out = None;
+
+    let mut args = args.peekable();
+    while let Some(flag) = args.next() {
+        // collect the key=value pairs following the flag
+        let mut pairs = Vec::new();
+        while let Some(arg) = args.peek()
+            && !arg.starts_with('-')
+        {
+            pairs.push(args.next().unwrap());
+        }
+        let value = || {
+            pairs
+                .first()
+                .cloned()
+                .ok_or(anyhow!("{flag} needs a value"))
+        };
+        match flag.as_str() {
+            "-engine" => engines.push(pairs),
+            "-each" => each.extend(pairs),
+            "-games" => games = value()?.parse()?,
+            "-repeat" => repeat = true,
+            "-blacktc" => config.black_time_control = Some(value()?.parse()?),
+            "-armageddon" => config.armageddon = true,
+            "-tiebreak" => tiebreak = true,
+            "-event" => config.event = value()?,
+            "-maxmoves" => config.max_moves = Some(value()?.parse()?),
+            "-pgnout" => pgn_out = Some(value()?),
+            "-openings" => {
+                let kv = key_values(&pairs)?;
+                let file = kv.get("file").ok_or(anyhow!("-openings needs file="))?;
+                let format = match kv.get("format").map(String::as_str) {
+                    Some("pgn") => OpeningFormat::Pgn,
+                    Some("epd") | None => OpeningFormat::Epd,
+                    Some(other) => bail!("unknown opening format {other}"),
+                };
+                openings = load_openings(&fs::read_to_string(file)?, format)?;
+                if openings.is_empty() {
+                    bail!("no openings in {file}");
+                }
+            }
+            "-draw" => {
+                let kv = key_values(&pairs)?;
+                config.draw = Some(DrawAdjudication {
+                    move_number: parse_key(&kv, "movenumber")?,
+                    move_count: parse_key(&kv, "movecount")?,
+                    score: parse_key(&kv, "score")?,
+                });
+            }
+            "-resign" => {
+                let kv = key_values(&pairs)?;
+                config.resign = Some(ResignAdjudication {
+                    move_count: parse_key(&kv, "movecount")?,
+                    score: parse_key(&kv, "score")?,
+                });
+            }
+            "-h" | "-help" | "--help" => bail!("{USAGE}"),
+            other => bail!("unknown option {other}\n{USAGE}"),
+        }
+    }
+    if engines.len() != 2 {
+        bail!("exactly two engines are needed\n{USAGE}");
+    }
+
+    let mut engine_configs = Vec::new();
+    for pairs in engines {
+        let mut engine = EngineConfig::default();
+        for pair in each.iter().chain(&pairs) {
+            let (key, value) = pair
+                .split_once('=')
+                .ok_or(anyhow!("expected key=value, got {pair}"))?;
+            match key {
+                "cmd" => engine.cmd = value.to_owned(),
+                "name" => engine.name = Some(value.to_owned()),
+                "arg" => engine.args.push(value.to_owned()),
+                "tc" => config.time_control = value.parse::<TimeControl>()?,
+                key => match key.strip_prefix("option.") {
+                    Some(option) => engine.options.push((option.to_owned(), value.to_owned())),
+                    None => bail!("unknown engine setting {key}"),
+                },
+            }
+        }
+        if engine.cmd.is_empty() {
+            bail!("-engine needs cmd=");
+        }
+        engine_configs.push(engine);
+    }
+    // Black gets less time for the draw odds unless told otherwise
+    if config.armageddon && config.black_time_control.is_none() {
+        config.black_time_control = Some(config.time_control.armageddon_black());
+    }
+    Ok(Args {
+        engines: engine_configs,
+        config,
+        games,
+        repeat,
+        openings,
+        pgn_out,
+        tiebreak,
+    })
+}
+
+fn key_values(pairs: &[String]) -> Result<HashMap<String, String>, anyhow::Error> {
+    pairs
+        .iter()
+        .map(|pair| {
+            pair.split_once('=')
+                .map(|(k, v)| (k.to_owned(), v.to_owned()))
+                .ok_or(anyhow!("expected key=value, got {pair}"))
+        })
+        .collect()
+}
+
+fn parse_key<T: std::str::FromStr>(
+    kv: &HashMap<String, String>,
+    key: &str,
+) -> Result<T, anyhow::Error> {
+    kv.get(key)
+        .and_then(|v| v.parse().ok())
+        .ok_or(anyhow!("missing or invalid {key}="))
+}
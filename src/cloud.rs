@@ -0,0 +1,197 @@
+use crate::analysis::Line;
+use chesslib::{GameState, database::Continuation, uci::Score};
+use serde_json::Value;
+use std::{
+    collections::HashMap,
+    sync::mpsc::{self, Receiver, Sender},
+    thread,
+};
+
+const CLOUD_EVAL: &str = "https://lichess.org/api/cloud-eval";
+const EXPLORER: &str = "https://explorer.lichess.ovh/lichess";
+// a position at a time, and no faster than this; a 429 means waiting a full minute
+const REQUEST_INTERVAL: f64 = 1.0;
+const RATE_LIMIT_BACKOFF: f64 = 60.0;
+// positions kept before the cache starts over
+const MAX_CACHED: usize = 500;
+
+// what lichess knows about a position: its cloud evaluation, if it has one, and the moves
+// played from it on the site
+#[derive(Debug, Clone, Default)]
+pub struct CloudInfo {
+    pub eval: Option<Line>,
+    pub moves: Vec<Continuation>,
+}
+
+enum FetchError {
+    #[cfg_attr(not(feature = "cloud"), allow(dead_code))]
+    RateLimited,
+    Failed(String),
+}
+
+// lichess' cloud evaluations and opening explorer for the position on the board, asked for
+// one position at a time within the rate limits and kept for going back to
+pub struct Cloud {
+    token: Option<String>,
+    cache: HashMap<String, CloudInfo>,
+    pending: Option<String>,
+    replies: Receiver<(String, Result<CloudInfo, FetchError>)>,
+    replies_tx: Sender<(String, Result<CloudInfo, FetchError>)>,
+    next_request: f64,
+}
+
+impl Cloud {
+    // the explorer only answers signed-in requests, so it goes with LICHESS_TOKEN when set
+    pub fn new(token: Option<String>) -> Self {
+        let (replies_tx, replies) = mpsc::channel();
+        Self {
+            token,
+            cache: HashMap::new(),
+            pending: None,
+            replies,
+            replies_tx,
+            next_request: 0.0,
+        }
+    }
+
+    // takes in what's come back and asks about `fen` when it's new and the limits allow
+    pub fn update(&mut self, fen: &str, now: f64) {
+        for (asked, reply) in self.replies.try_iter() {
+            self.pending = None;
+            let info = match reply {
+                Ok(info) => info,
+                Err(FetchError::RateLimited) => {
+                    log::warn!("lichess is rate limiting, waiting a minute");
+                    self.next_request = now + RATE_LIMIT_BACKOFF;
+                    continue;
+                }
+                // kept as nothing known, rather than asked again and again
+                Err(FetchError::Failed(err)) => {
+                    log::warn!("lichess lookup failed: {err}");
+                    CloudInfo::default()
+                }
+            };
+            if self.cache.len() >= MAX_CACHED {
+                self.cache.clear();
+            }
+            self.cache.insert(asked, info);
+        }
+        if self.cache.contains_key(fen) || self.pending.is_some() || now < self.next_request {
+            return;
+        }
+        self.pending = Some(fen.to_owned());
+        self.next_request = now + REQUEST_INTERVAL;
+        let (fen, token, replies) = (fen.to_owned(), self.token.clone(), self.replies_tx.clone());
+        thread::spawn(move || {
+            let info = fetch(&fen, token.as_deref());
+            let _ = replies.send((fen, info));
+        });
+    }
+
+    pub fn get(&self, fen: &str) -> Option<&CloudInfo> {
+        self.cache.get(fen)
+    }
+}
+
+fn fetch(fen: &str, token: Option<&str>) -> Result<CloudInfo, FetchError> {
+    let gs = GameState::try_from_fen(fen).map_err(|err| FetchError::Failed(err.to_string()))?;
+    let eval = get(CLOUD_EVAL, fen, token)?
+        .and_then(|json| parse_cloud_eval(&json))
+        .map(|mut line| {
+            // only as much of the line as is legal, in case it's for another position
+            line.pv.truncate(gs.pv_to_san(&line.pv).len());
+            line
+        });
+    let moves = get(EXPLORER, fen, token)?
+        .map(|json| parse_explorer(&json, &gs))
+        .unwrap_or_default();
+    Ok(CloudInfo { eval, moves })
+}
+
+// the JSON at `url` for `fen`, or None when there's nothing there
+#[cfg(feature = "cloud")]
+fn get(url: &str, fen: &str, token: Option<&str>) -> Result<Option<Value>, FetchError> {
+    use std::{
+        io::Write,
+        process::{Command, Stdio},
+    };
+    log::debug!("lichess get {url} {fen}");
+    let mut child = Command::new("curl")
+        .args(["-sS", "-H", "@-", "-w", "\n%{http_code}", "--get"])
+        .args(["--data-urlencode", &format!("fen={fen}"), url])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|err| FetchError::Failed(format!("couldn't run curl: {err}")))?;
+    if let (Some(mut stdin), Some(token)) = (child.stdin.take(), token) {
+        let _ = stdin.write_all(format!("Authorization: Bearer {token}\n").as_bytes());
+    }
+    let output = child
+        .wait_with_output()
+        .map_err(|err| FetchError::Failed(err.to_string()))?;
+    let text = String::from_utf8_lossy(&output.stdout);
+    let (body, status) = text.rsplit_once('\n').unwrap_or(("", &text));
+    match status.trim() {
+        "200" => serde_json::from_str(body)
+            .map(Some)
+            .map_err(|err| FetchError::Failed(err.to_string())),
+        "404" => Ok(None),
+        "429" => Err(FetchError::RateLimited),
+        status => Err(FetchError::Failed(format!(
+            "{url}: {status} {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))),
+    }
+}
+
+#[cfg(not(feature = "cloud"))]
+fn get(url: &str, _fen: &str, _token: Option<&str>) -> Result<Option<Value>, FetchError> {
+    Err(FetchError::Failed(format!(
+        "{url}: built without the cloud feature"
+    )))
+}
+
+// the first of the `pvs`, which lichess scores from White's side
+fn parse_cloud_eval(json: &Value) -> Option<Line> {
+    let pv = &json["pvs"][0];
+    let score = match (pv["cp"].as_i64(), pv["mate"].as_i64()) {
+        (Some(cp), _) => Score::Centipawns(cp as i32),
+        (None, Some(mate)) => Score::Mate(mate as i32),
+        (None, None) => return None,
+    };
+    Some(Line {
+        score,
+        depth: json["depth"].as_u64()? as u32,
+        pv: pv["moves"]
+            .as_str()?
+            .split_whitespace()
+            .map(str::to_owned)
+            .collect(),
+    })
+}
+
+fn parse_explorer(json: &Value, gs: &GameState) -> Vec<Continuation> {
+    let Some(moves) = json["moves"].as_array() else {
+        return Vec::new();
+    };
+    let count = |json: &Value, key: &str| json[key].as_u64().unwrap_or_default() as u32;
+    moves
+        .iter()
+        .filter_map(|json| {
+            let mv = gs.parse_uci_move(json["uci"].as_str()?)?;
+            let (white_wins, draws, black_wins) = (
+                count(json, "white"),
+                count(json, "draws"),
+                count(json, "black"),
+            );
+            Some(Continuation {
+                mv,
+                games: white_wins + draws + black_wins,
+                white_wins,
+                draws,
+                black_wins,
+            })
+        })
+        .collect()
+}
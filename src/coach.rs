@@ -0,0 +1,190 @@
+use crate::engine_options;
+use chesslib::{
+    analysis::review::{Judgement, review_move},
+    errors::UciError,
+    prelude::*,
+    uci::{GoLimits, Score, UciEngine},
+};
+use std::{
+    sync::mpsc::{self, Receiver, Sender},
+    thread,
+    time::Duration,
+};
+
+// a quick look, not an analysis: whichever runs out first
+const PROBE_DEPTH: u32 = 10;
+const PROBE_TIME: Duration = Duration::from_millis(150);
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+// a move held back until the engine has had a look at it
+#[derive(Debug, Clone)]
+pub struct Probe {
+    pub gs: GameState,
+    pub mv: Move,
+    pub san: String,
+}
+
+impl Probe {
+    // whether it was held in the position `gs` is in
+    pub fn is_for(&self, gs: &GameState) -> bool {
+        self.gs.to_fen() == gs.to_fen()
+    }
+}
+
+// a held move that throws the game away, with the engine's opinion before and after it,
+// both from White's point of view
+#[derive(Debug, Clone)]
+pub struct Blunder {
+    pub probe: Probe,
+    pub before: Score,
+    pub after: Score,
+}
+
+impl Blunder {
+    pub fn describe(&self) -> String {
+        let label = |score| match score {
+            Score::Mate(n) => format!("#{n}"),
+            Score::Centipawns(cp) => format!("{:+.1}", cp as f32 / 100.0),
+        };
+        format!(
+            "{} takes the engine's eval from {} to {}",
+            self.probe.san,
+            label(self.before),
+            label(self.after)
+        )
+    }
+}
+
+// a UCI engine of its own that looks over the player's moves before they're made and
+// asks about the ones that lose material or a won position, once per move
+pub struct Coach {
+    probes: Sender<(GameState, Move)>,
+    replies: Receiver<Result<(Score, Score), UciError>>,
+    pub pending: Option<Probe>,
+    pub asking: Option<Blunder>,
+    // the move that's been looked at or asked about, let through the next time it's tried
+    cleared: Option<(GameState, Move)>,
+    pub stopped: bool,
+}
+
+impl Coach {
+    pub fn spawn(program: String) -> Self {
+        let (probes, probe_rx) = mpsc::channel();
+        let (reply_tx, replies) = mpsc::channel();
+        thread::spawn(move || {
+            let result = engine_options::spawn(&program)
+                .and_then(|mut engine| run(&mut engine, &probe_rx, &reply_tx));
+            if let Err(err) = result {
+                let _ = reply_tx.send(Err(err));
+            }
+        });
+        Self {
+            probes,
+            replies,
+            pending: None,
+            asking: None,
+            cleared: None,
+            stopped: false,
+        }
+    }
+
+    pub fn waiting(&self) -> bool {
+        self.pending.is_some() || self.asking.is_some()
+    }
+
+    // true when `mv` can go ahead now; otherwise it's held for a probe
+    pub fn accepts(&mut self, gs: &GameState, mv: Move) -> bool {
+        if self.stopped || self.cleared.take() == Some((*gs, mv)) {
+            return true;
+        }
+        let mut after = *gs;
+        let over = match mv.promotion() {
+            Some(figure) => after.make_promotion(mv.from, mv.to, Piece {
+                color: gs.get_turn(),
+                figure,
+            }),
+            None => after.make_move(mv.from, mv.to),
+        }
+        .map_or(true, |outcome| outcome.status.is_over());
+        // nothing left to weigh up once the game ends on it
+        if over || self.probes.send((*gs, mv)).is_err() {
+            return true;
+        }
+        self.pending = Some(Probe {
+            gs: *gs,
+            mv,
+            san: gs.to_san(mv),
+        });
+        false
+    }
+
+    // the held move once it turns out fine, to be played; a blunder goes to `asking` instead.
+    // One held in a position the board has since left, by an undo or the opponent's move, is
+    // dropped
+    pub fn poll(&mut self, gs: &GameState) -> Option<Probe> {
+        let reply = self.replies.try_iter().last()?;
+        let probe = self.pending.take()?;
+        let stale = !probe.is_for(gs);
+        if stale {
+            log::debug!("coach dropped {} for a position left since", probe.san);
+        }
+        let (before, after) = match reply {
+            Ok(scores) if !stale => scores,
+            Ok(_) => return None,
+            Err(err) => {
+                log::warn!("coach engine stopped: {err}");
+                self.stopped = true;
+                return (!stale).then_some(probe);
+            }
+        };
+        let mover = probe.gs.get_turn();
+        if review_move(before, after, mover).judgement == Some(Judgement::Blunder) {
+            self.asking = Some(Blunder {
+                probe,
+                before,
+                after,
+            });
+            return None;
+        }
+        self.clear(&probe);
+        Some(probe)
+    }
+
+    // the next try of the move goes through without another look
+    pub fn clear(&mut self, probe: &Probe) {
+        self.cleared = Some((probe.gs, probe.mv));
+    }
+}
+
+fn run(
+    engine: &mut UciEngine,
+    probes: &Receiver<(GameState, Move)>,
+    replies: &Sender<Result<(Score, Score), UciError>>,
+) -> Result<(), UciError> {
+    let limits = GoLimits {
+        depth: Some(PROBE_DEPTH),
+        move_time: Some(PROBE_TIME),
+        ..Default::default()
+    };
+    while let Ok((gs, mv)) = probes.recv() {
+        let fen = gs.to_fen();
+        let mut score = |moves: &[Move]| -> Result<Score, UciError> {
+            engine.set_position(Some(&fen), moves)?;
+            let score = engine.go(&limits, PROBE_TIMEOUT)?.score;
+            Ok(score.unwrap_or(Score::Centipawns(0)))
+        };
+        let scores = score(&[]).and_then(|before| Ok((before, score(&[mv])?)));
+        // from the side to move to White's point of view, the move swapping sides
+        let white = |score: Score, turn: Color| match (score, turn) {
+            (score, Color::White) => score,
+            (Score::Centipawns(cp), Color::Black) => Score::Centipawns(-cp),
+            (Score::Mate(n), Color::Black) => Score::Mate(-n),
+        };
+        let turn = gs.get_turn();
+        let reply = scores.map(|(before, after)| (white(before, turn), white(after, !turn)));
+        if replies.send(reply).is_err() {
+            break;
+        }
+    }
+    Ok(())
+}
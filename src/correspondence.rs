@@ -0,0 +1,117 @@
+use crate::games;
+use chesslib::{
+    pgn::{PgnGame, parse_pgn},
+    pieces::Color,
+};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+// under the games directory, one file per game
+const CORRESPONDENCE_DIR: &str = "correspondence";
+// which side of the game is played from here
+const SIDE_TAG: &str = "Side";
+
+// a game played a move at a time over days, the opponent's replies typed in as they come
+pub struct Entry {
+    pub path: PathBuf,
+    pub opponent: String,
+    pub color: Color,
+    pub turn: Color,
+    pub plies: usize,
+}
+
+impl Entry {
+    pub fn summary(&self) -> String {
+        let turn = if self.turn == self.color {
+            "your move"
+        } else {
+            "their move"
+        };
+        format!(
+            "vs {} as {:?}, move {} - {turn}",
+            self.opponent,
+            self.color,
+            self.plies / 2 + 1
+        )
+    }
+}
+
+// the games still going, the ones waiting on a move from here first
+pub fn list(games_dir: &Path) -> Vec<Entry> {
+    let Ok(dir) = fs::read_dir(games_dir.join(CORRESPONDENCE_DIR)) else {
+        return Vec::new();
+    };
+    let mut entries: Vec<Entry> = dir
+        .filter_map(|file| file.ok().map(|file| file.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "pgn"))
+        .filter_map(|path| {
+            let text = fs::read_to_string(&path).ok()?;
+            let game = parse_pgn(&text).ok()?.into_iter().next()?;
+            entry(path, &game)
+        })
+        .collect();
+    entries.sort_by_key(|entry| (entry.turn != entry.color, entry.path.clone()));
+    entries
+}
+
+fn entry(path: PathBuf, game: &PgnGame) -> Option<Entry> {
+    if game.result.is_some() {
+        return None;
+    }
+    let color = match game.tag(SIDE_TAG)? {
+        "White" => Color::White,
+        "Black" => Color::Black,
+        _ => return None,
+    };
+    let start = game.start_position().ok()?.get_turn();
+    let plies = game.moves.len();
+    Some(Entry {
+        path,
+        opponent: game.tag(&format!("{:?}", !color)).unwrap_or("?").to_owned(),
+        color,
+        turn: if plies.is_multiple_of(2) {
+            start
+        } else {
+            !start
+        },
+        plies,
+    })
+}
+
+// a fresh game against `opponent`, with `name` playing `color` from here
+pub fn create(games_dir: &Path, name: &str, opponent: &str, color: Color) -> io::Result<PathBuf> {
+    let (date, _) = games::now();
+    let (white, black) = match color {
+        Color::White => (name, opponent),
+        Color::Black => (opponent, name),
+    };
+    let tags = [
+        ("Event", "Correspondence game"),
+        ("Site", "chessapp"),
+        ("Date", &date),
+        ("Round", "-"),
+        ("White", white),
+        ("Black", black),
+        ("Result", "*"),
+        (SIDE_TAG, &format!("{color:?}")),
+    ];
+    let game = PgnGame {
+        tags: tags
+            .into_iter()
+            .map(|(name, value)| (name.to_owned(), value.to_owned()))
+            .collect(),
+        moves: Vec::new(),
+        annotations: Vec::new(),
+        result: None,
+    };
+    games::save_game(&games_dir.join(CORRESPONDENCE_DIR), &game)
+}
+
+// written over in place, through a temporary file like the autosave
+pub fn save(path: &Path, game: &PgnGame) -> io::Result<()> {
+    let temp = path.with_extension("pgn.tmp");
+    fs::write(&temp, game.to_string())?;
+    fs::rename(temp, path)
+}
@@ -0,0 +1,132 @@
+use chesslib::{
+    drill::{Repertoire, Schedule},
+    prelude::*,
+};
+use macroquad::miniquad::date;
+use std::fs;
+
+const DRILL_FILE: &str = "drill.txt";
+// pause before the opponent's move so the trainee sees it happen
+const REPLY_DELAY: f64 = 0.5;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum DrillStatus {
+    Playing,
+    // played a move that isn't in the repertoire, still looking for one that is
+    Missed,
+    // the line has run out
+    Done,
+}
+
+// a repertoire played through one line at a time: the opponent's side comes from the
+// tree, steering towards the positions due for review, and the trainee has to find their
+// prepared moves
+pub struct Drill {
+    pub repertoire: Repertoire,
+    schedule: Schedule,
+    reply_at: Option<f64>,
+    // the position a miss was already counted for
+    missed: Option<u64>,
+    pub status: DrillStatus,
+    pub right: u32,
+    pub wrong: u32,
+}
+
+impl Drill {
+    // picks up where earlier sessions left the schedule
+    pub fn new(repertoire: Repertoire) -> Self {
+        let schedule = fs::read_to_string(DRILL_FILE)
+            .map(|text| Schedule::parse(&text))
+            .unwrap_or_default();
+        Self {
+            repertoire,
+            schedule,
+            reply_at: None,
+            missed: None,
+            status: DrillStatus::Playing,
+            right: 0,
+            wrong: 0,
+        }
+    }
+
+    pub fn start_line(&mut self, now: f64) {
+        self.status = DrillStatus::Playing;
+        self.missed = None;
+        self.reply_at = (self.repertoire.start.get_turn() != self.repertoire.color)
+            .then_some(now + REPLY_DELAY);
+    }
+
+    pub fn waiting(&self) -> bool {
+        self.reply_at.is_some()
+    }
+
+    pub fn due_count(&self) -> usize {
+        self.schedule
+            .due_count(self.repertoire.positions(), epoch())
+    }
+
+    pub fn position_count(&self) -> usize {
+        self.repertoire.positions().count()
+    }
+
+    // the opponent's next move once its delay is up
+    pub fn due_reply(&mut self, gs: &GameState, now: f64) -> Option<Move> {
+        if self.reply_at.is_none_or(|at| now < at) {
+            return None;
+        }
+        self.reply_at = None;
+        let reply = self.repertoire.pick_reply(gs, &self.schedule, epoch());
+        if reply.is_none() {
+            self.status = DrillStatus::Done;
+        }
+        reply
+    }
+
+    // judges the trainee's move in `gs`; a position only counts on the first try
+    pub fn submit(&mut self, gs: &GameState, mv: Move, now: f64) -> bool {
+        if self.status == DrillStatus::Done || self.reply_at.is_some() {
+            return true;
+        }
+        let hash = gs.zobrist();
+        if !self.repertoire.moves(gs).contains(&mv) {
+            if self.missed != Some(hash) {
+                self.missed = Some(hash);
+                self.wrong += 1;
+                self.schedule.review(hash, false, epoch());
+                self.save();
+            }
+            self.status = DrillStatus::Missed;
+            return false;
+        }
+        if self.missed != Some(hash) {
+            self.right += 1;
+            self.schedule.review(hash, true, epoch());
+            self.save();
+        }
+        self.status = DrillStatus::Playing;
+        self.reply_at = Some(now + REPLY_DELAY);
+        true
+    }
+
+    // the trainee to move with nothing prepared ends the line too
+    pub fn check_line_end(&mut self, gs: &GameState) {
+        if self.status != DrillStatus::Done
+            && self.reply_at.is_none()
+            && gs.get_turn() == self.repertoire.color
+            && self.repertoire.moves(gs).is_empty()
+        {
+            self.status = DrillStatus::Done;
+        }
+    }
+
+    fn save(&self) {
+        if let Err(err) = fs::write(DRILL_FILE, self.schedule.to_string()) {
+            log::warn!("couldn't save {DRILL_FILE}: {err}");
+        }
+    }
+}
+
+// the schedule keeps wall-clock seconds, which std can't give in the browser
+fn epoch() -> u64 {
+    date::now().max(0.0) as u64
+}
@@ -0,0 +1,141 @@
+use crate::opponent::{self, Opponent};
+use chesslib::{
+    endgames::{Endgame, Goal, defend},
+    prelude::*,
+    random::Rng,
+    uci::GoLimits,
+};
+
+// pause before the defender's move so the trainee sees it happen
+const REPLY_DELAY: f64 = 0.5;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum EndgameStatus {
+    Playing,
+    // mated in time, or held the draw
+    Done,
+    Failed(&'static str),
+}
+
+// theoretical endgames with the defending side played by the engine, or by a shallow search
+// of our own when there's none
+pub struct EndgameTrainer {
+    pub endgame: Endgame,
+    rng: Rng,
+    engine: Option<Opponent>,
+    reply_at: Option<f64>,
+    // a hash of every position so far, for the repetitions
+    positions: Vec<u64>,
+    pub status: EndgameStatus,
+    pub done: u32,
+    pub attempted: u32,
+}
+
+impl EndgameTrainer {
+    pub fn new(engine: Option<String>, seed: u64) -> Self {
+        let endgame = Endgame::ALL[0];
+        Self {
+            endgame,
+            rng: Rng::new(seed),
+            engine: engine.map(|program| Opponent::spawn(program, !endgame.trainee())),
+            reply_at: None,
+            positions: Vec::new(),
+            status: EndgameStatus::Playing,
+            done: 0,
+            attempted: 0,
+        }
+    }
+
+    // a fresh instance of `endgame`
+    pub fn start(&mut self, endgame: Endgame) -> GameState {
+        self.endgame = endgame;
+        self.status = EndgameStatus::Playing;
+        self.reply_at = None;
+        self.positions.clear();
+        if let Some(engine) = &mut self.engine {
+            engine.color = !endgame.trainee();
+        }
+        endgame.position(&mut self.rng)
+    }
+
+    // the next endgame in the list, starting over after the last
+    pub fn next_endgame(&self) -> Endgame {
+        let idx = Endgame::ALL.iter().position(|&e| e == self.endgame);
+        Endgame::ALL[idx.map_or(0, |idx| (idx + 1) % Endgame::ALL.len())]
+    }
+
+    pub fn waiting(&self, gs: &GameState) -> bool {
+        self.status == EndgameStatus::Playing && gs.get_turn() != self.endgame.trainee()
+    }
+
+    // the defender's move in the game from `fen` through `moves`, once it has one
+    pub fn due_reply(
+        &mut self,
+        gs: &GameState,
+        fen: &str,
+        moves: &[Move],
+        now: f64,
+    ) -> Option<Move> {
+        if !self.waiting(gs) {
+            return None;
+        }
+        if let Some(engine) = self.engine.as_mut().filter(|engine| !engine.stopped) {
+            let ply = moves.len();
+            match engine.poll(ply) {
+                Some(Ok(uci)) => return gs.parse_uci_move(&uci),
+                Some(Err(err)) => log::warn!("endgame engine stopped: {err}"),
+                None if !engine.is_thinking() => {
+                    let limits = GoLimits {
+                        move_time: Some(opponent::MOVE_TIME),
+                        ..Default::default()
+                    };
+                    engine.think(ply, fen, moves.to_vec(), limits);
+                    return None;
+                }
+                None => return None,
+            }
+        }
+        match self.reply_at {
+            None => {
+                self.reply_at = Some(now + REPLY_DELAY);
+                None
+            }
+            Some(at) if now < at => None,
+            Some(_) => {
+                self.reply_at = None;
+                defend(gs)
+            }
+        }
+    }
+
+    // settles the attempt once `gs`, `ply` moves in, decides it: a win has to be mated before
+    // the fifty-move rule, a draw only has to avoid being mated
+    pub fn judge(&mut self, gs: &GameState, ply: usize) {
+        if self.status != EndgameStatus::Playing || self.positions.len() > ply {
+            return;
+        }
+        let key = gs.zobrist();
+        self.positions.push(key);
+        let repeated = self.positions.iter().filter(|&&k| k == key).count() >= 3;
+        let status = gs.status();
+        let mated = status == GameStatus::Checkmate;
+        let fifty = gs.get_half_move() >= 100;
+        let drawn = status.is_draw() || fifty || repeated;
+        let trainee_moved = gs.get_turn() != self.endgame.trainee();
+        self.status = match self.endgame.goal() {
+            Goal::Win if mated && trainee_moved => EndgameStatus::Done,
+            Goal::Win if mated => EndgameStatus::Failed("Mated"),
+            Goal::Win if fifty => EndgameStatus::Failed("Fifty moves without mate"),
+            Goal::Win if drawn => EndgameStatus::Failed("Let it slip to a draw"),
+            Goal::Draw if mated && !trainee_moved => EndgameStatus::Failed("Mated"),
+            Goal::Draw if mated || drawn => EndgameStatus::Done,
+            _ => EndgameStatus::Playing,
+        };
+        if self.status != EndgameStatus::Playing {
+            self.attempted += 1;
+            if self.status == EndgameStatus::Done {
+                self.done += 1;
+            }
+        }
+    }
+}
@@ -0,0 +1,212 @@
+use chesslib::{
+    errors::UciError,
+    uci::{OptionKind, UciEngine, UciOption},
+};
+use std::{
+    fmt::Display,
+    fs,
+    sync::mpsc::{self, Receiver},
+    thread,
+};
+
+const OPTIONS_FILE: &str = "engine_options.txt";
+
+// the UCI options set for each engine, by the name it gives itself, kept between runs as
+// a `[name]` line followed by `option = value` lines. Only values changed from the
+// engine's defaults are kept.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct EngineOptions {
+    engines: Vec<(String, Vec<(String, String)>)>,
+}
+
+impl EngineOptions {
+    pub fn load() -> Self {
+        fs::read_to_string(OPTIONS_FILE)
+            .map(|text| Self::parse(&text))
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) {
+        if let Err(err) = fs::write(OPTIONS_FILE, self.to_string()) {
+            log::warn!("couldn't save {OPTIONS_FILE}: {err}");
+        }
+    }
+
+    fn parse(text: &str) -> Self {
+        let mut options = Self::default();
+        for line in text.lines().map(str::trim) {
+            if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                options.engines.push((name.to_owned(), Vec::new()));
+            } else if let Some((key, value)) = line.split_once('=')
+                && let Some((_, values)) = options.engines.last_mut()
+            {
+                values.push((key.trim().to_owned(), value.trim().to_owned()));
+            }
+        }
+        options
+    }
+
+    pub fn get(&self, engine: &str) -> &[(String, String)] {
+        self.engines
+            .iter()
+            .find(|(name, _)| name == engine)
+            .map_or(&[], |(_, values)| values)
+    }
+
+    pub fn set(&mut self, engine: &str, values: Vec<(String, String)>) {
+        self.engines.retain(|(name, _)| name != engine);
+        if !values.is_empty() {
+            self.engines.push((engine.to_owned(), values));
+        }
+    }
+}
+
+impl Display for EngineOptions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (engine, values) in &self.engines {
+            writeln!(f, "[{engine}]")?;
+            for (key, value) in values {
+                writeln!(f, "{key} = {value}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+// starts `program` with the values saved for it that it still offers
+pub fn spawn(program: &str) -> Result<UciEngine, UciError> {
+    let mut engine = UciEngine::spawn(program, &[])?;
+    let saved = EngineOptions::load();
+    for (key, value) in saved.get(engine.name()) {
+        if engine.has_option(key) {
+            engine.set_option(key, value)?;
+        }
+    }
+    Ok(engine)
+}
+
+// what one option is set to in the dialog
+pub enum FieldValue {
+    Check(bool),
+    Spin(f32),
+    Combo(usize),
+    Text(String),
+}
+
+pub struct Field {
+    pub option: UciOption,
+    pub value: FieldValue,
+}
+
+impl Field {
+    fn new(option: UciOption, saved: Option<&str>) -> Option<Self> {
+        let value = match &option.kind {
+            OptionKind::Check { default } => {
+                FieldValue::Check(saved.and_then(|s| s.parse().ok()).unwrap_or(*default))
+            }
+            &OptionKind::Spin { default, min, max } => {
+                let value = saved.and_then(|s| s.parse().ok()).unwrap_or(default);
+                FieldValue::Spin(value.clamp(min, max) as f32)
+            }
+            OptionKind::Combo { default, vars } => {
+                let chosen = saved.unwrap_or(default);
+                let idx = vars.iter().position(|var| var.eq_ignore_ascii_case(chosen));
+                FieldValue::Combo(idx.unwrap_or_default())
+            }
+            OptionKind::String { default } => FieldValue::Text(saved.unwrap_or(default).to_owned()),
+            OptionKind::Button => return None,
+        };
+        Some(Self { option, value })
+    }
+
+    // the value to send, None while it's still the engine's default
+    fn changed(&self) -> Option<String> {
+        match (&self.option.kind, &self.value) {
+            (OptionKind::Check { default }, &FieldValue::Check(on)) => {
+                (on != *default).then(|| on.to_string())
+            }
+            (&OptionKind::Spin { default, min, max }, &FieldValue::Spin(value)) => {
+                let value = (value.round() as i64).clamp(min, max);
+                (value != default).then(|| value.to_string())
+            }
+            (OptionKind::Combo { default, vars }, &FieldValue::Combo(idx)) => {
+                vars.get(idx).filter(|&var| var != default).cloned()
+            }
+            (OptionKind::String { default }, FieldValue::Text(text)) => {
+                (text.trim() != default).then(|| text.trim().to_owned())
+            }
+            _ => None,
+        }
+    }
+}
+
+type Offered = Result<(String, Vec<UciOption>), UciError>;
+
+// the options dialog's state: the engine is started just to hear which options it has
+pub struct EngineConfig {
+    offered: Receiver<Offered>,
+    pub engine: Option<String>,
+    pub fields: Vec<Field>,
+    pub error: Option<String>,
+}
+
+impl EngineConfig {
+    pub fn load(program: String) -> Self {
+        let (tx, offered) = mpsc::channel();
+        thread::spawn(move || {
+            let result = UciEngine::spawn(&program, &[]).map(|engine| {
+                let offered = (engine.name().to_owned(), engine.options().to_vec());
+                engine.quit();
+                offered
+            });
+            let _ = tx.send(result);
+        });
+        Self {
+            offered,
+            engine: None,
+            fields: Vec::new(),
+            error: None,
+        }
+    }
+
+    // fills in the fields once the engine has said what it offers
+    pub fn poll(&mut self) {
+        match self.offered.try_recv() {
+            Ok(Ok((engine, options))) => {
+                let saved = EngineOptions::load();
+                let saved = saved.get(&engine);
+                let value = |name: &str| {
+                    saved
+                        .iter()
+                        .find(|(key, _)| key.eq_ignore_ascii_case(name))
+                        .map(|(_, value)| value.as_str())
+                };
+                self.fields = options
+                    .into_iter()
+                    .filter_map(|option| {
+                        let saved = value(&option.name);
+                        Field::new(option, saved)
+                    })
+                    .collect();
+                self.engine = Some(engine);
+            }
+            Ok(Err(err)) => self.error = Some(format!("Couldn't start the engine: {err}")),
+            Err(_) => {}
+        }
+    }
+
+    // keeps what's been changed from the defaults for the next time the engine starts
+    pub fn save(&self) {
+        let Some(engine) = &self.engine else {
+            return;
+        };
+        let values = self
+            .fields
+            .iter()
+            .filter_map(|field| Some((field.option.name.clone(), field.changed()?)))
+            .collect();
+        let mut options = EngineOptions::load();
+        options.set(engine, values);
+        options.save();
+    }
+}
@@ -0,0 +1,34 @@
+use chesslib::{
+    GameState,
+    database::{Continuation, GameDatabase},
+};
+
+// the moves played from the position on the board in a database of the player's own,
+// worked out again only when the position changes
+pub struct Explorer {
+    db: GameDatabase,
+    key: Option<u64>,
+    pub moves: Vec<Continuation>,
+}
+
+impl Explorer {
+    pub fn new(db: GameDatabase) -> Self {
+        Self {
+            db,
+            key: None,
+            moves: Vec::new(),
+        }
+    }
+
+    pub fn update(&mut self, gs: &GameState) {
+        let key = gs.zobrist();
+        if self.key != Some(key) {
+            self.moves = self.db.explore(gs);
+            self.key = Some(key);
+        }
+    }
+
+    pub fn game_count(&self) -> usize {
+        self.db.game_count()
+    }
+}
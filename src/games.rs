@@ -0,0 +1,107 @@
+use chesslib::pgn::PgnGame;
+use macroquad::miniquad::date;
+use std::{
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+pub const DEFAULT_GAMES_DIR: &str = "games";
+// the game in progress, kept until it ends so a crash doesn't lose it
+const AUTOSAVE_FILE: &str = "autosave.pgn";
+// how often a running clock is written out between moves
+pub const AUTOSAVE_SECS: f64 = 10.0;
+
+// UTC date and time of day, as (`yyyy.mm.dd`, `hhmmss`)
+pub fn now() -> (String, String) {
+    // std's clock panics in the browser, miniquad asks the page instead
+    let secs = date::now().max(0.0) as u64;
+    let (days, secs) = (secs / 86_400, secs % 86_400);
+    let (year, month, day) = civil_from_days(days as i64);
+    (
+        format!("{year:04}.{month:02}.{day:02}"),
+        format!("{:02}{:02}{:02}", secs / 3600, secs / 60 % 60, secs % 60),
+    )
+}
+
+// writes the game to a new file in `dir`, named after when it was saved; games saved in
+// the same second get a number on the end rather than overwriting each other
+pub fn save_game(dir: &Path, game: &PgnGame) -> io::Result<PathBuf> {
+    fs::create_dir_all(dir)?;
+    let (date, time) = now();
+    let stem = format!("{}-{time}", date.replace('.', "-"));
+    let mut n = 1;
+    loop {
+        let path = match n {
+            1 => dir.join(format!("{stem}.pgn")),
+            n => dir.join(format!("{stem}-{n}.pgn")),
+        };
+        match OpenOptions::new().write(true).create_new(true).open(&path) {
+            Ok(mut file) => {
+                file.write_all(game.to_string().as_bytes())?;
+                return Ok(path);
+            }
+            Err(err) if err.kind() == io::ErrorKind::AlreadyExists => n += 1,
+            Err(err) => return Err(err),
+        }
+    }
+}
+
+// overwrites the last autosave; written to a temporary file first so a crash midway
+// leaves the old one whole
+pub fn autosave(dir: &Path, game: &PgnGame) -> io::Result<()> {
+    fs::create_dir_all(dir)?;
+    let temp = dir.join(format!("{AUTOSAVE_FILE}.tmp"));
+    fs::write(&temp, game.to_string())?;
+    fs::rename(temp, dir.join(AUTOSAVE_FILE))
+}
+
+pub fn load_autosave(dir: &Path) -> Option<String> {
+    fs::read_to_string(dir.join(AUTOSAVE_FILE)).ok()
+}
+
+pub fn clear_autosave(dir: &Path) {
+    match fs::remove_file(dir.join(AUTOSAVE_FILE)) {
+        Err(err) if err.kind() != io::ErrorKind::NotFound => {
+            log::warn!("couldn't remove the autosave in {}: {err}", dir.display());
+        }
+        _ => {}
+    }
+}
+
+// a fresh path in `dir` for the games of one sitting, named like the saved games
+pub fn session_path(dir: &Path) -> io::Result<PathBuf> {
+    fs::create_dir_all(dir)?;
+    let (date, time) = now();
+    Ok(dir.join(format!("session-{}-{time}.pgn", date.replace('.', "-"))))
+}
+
+// adds the game to the end of a collection, starting the file if it isn't there yet
+pub fn append_game(path: &Path, game: &PgnGame) -> io::Result<()> {
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?;
+    writeln!(file, "{game}")
+}
+
+// a fresh path in `dir` for a picture of the board, named like the saved games
+pub fn image_path(dir: &Path) -> io::Result<PathBuf> {
+    fs::create_dir_all(dir)?;
+    let (date, time) = now();
+    Ok(dir.join(format!("position-{}-{time}.png", date.replace('.', "-"))))
+}
+
+// days since 1970-01-01 to a proleptic Gregorian date
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = (if mp < 10 { mp + 3 } else { mp - 9 }) as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
@@ -0,0 +1,420 @@
+use macroquad::input::{KeyCode, is_key_down, is_key_pressed};
+use std::{fmt::Display, str::FromStr};
+
+// everything the keyboard does outside of the dialogs, which keep Enter and Escape
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Action {
+    Fullscreen,
+    Flip,
+    Mute,
+    Eval,
+    Lines,
+    Explorer,
+    Blindfold,
+    Overlay,
+    AutoOrient,
+    Hint,
+    Report,
+    Undo,
+    Redo,
+    NewGame,
+    Annotate,
+    LoadFen,
+    SavePgn,
+    ExportImage,
+    MyGames,
+    CopyFen,
+    CopyPgn,
+    Paste,
+    TypeMove,
+    Previous,
+    Next,
+    First,
+    Last,
+    Back,
+    Pause,
+}
+
+impl Action {
+    pub const ALL: [Action; 29] = [
+        Action::Fullscreen,
+        Action::Flip,
+        Action::Mute,
+        Action::Eval,
+        Action::Lines,
+        Action::Explorer,
+        Action::Blindfold,
+        Action::Overlay,
+        Action::AutoOrient,
+        Action::Hint,
+        Action::Report,
+        Action::Undo,
+        Action::Redo,
+        Action::NewGame,
+        Action::Annotate,
+        Action::LoadFen,
+        Action::SavePgn,
+        Action::ExportImage,
+        Action::MyGames,
+        Action::CopyFen,
+        Action::CopyPgn,
+        Action::Paste,
+        Action::TypeMove,
+        Action::Previous,
+        Action::Next,
+        Action::First,
+        Action::Last,
+        Action::Back,
+        Action::Pause,
+    ];
+
+    // the key in the settings file
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::Fullscreen => "fullscreen",
+            Action::Flip => "flip",
+            Action::Mute => "mute",
+            Action::Eval => "eval",
+            Action::Lines => "lines",
+            Action::Explorer => "explorer",
+            Action::Blindfold => "blindfold",
+            Action::Overlay => "overlay",
+            Action::AutoOrient => "auto_orient",
+            Action::Hint => "hint",
+            Action::Report => "report",
+            Action::Undo => "undo",
+            Action::Redo => "redo",
+            Action::NewGame => "new_game",
+            Action::Annotate => "annotate",
+            Action::LoadFen => "load_fen",
+            Action::SavePgn => "save_pgn",
+            Action::ExportImage => "export_image",
+            Action::MyGames => "my_games",
+            Action::CopyFen => "copy_fen",
+            Action::CopyPgn => "copy_pgn",
+            Action::Paste => "paste",
+            Action::TypeMove => "type_move",
+            Action::Previous => "previous",
+            Action::Next => "next",
+            Action::First => "first",
+            Action::Last => "last",
+            Action::Back => "back",
+            Action::Pause => "pause",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Action::Fullscreen => "Fullscreen",
+            Action::Flip => "Flip board",
+            Action::Mute => "Mute",
+            Action::Eval => "Eval bar",
+            Action::Lines => "Engine lines",
+            Action::Explorer => "Opening explorer",
+            Action::Blindfold => "Blindfold",
+            Action::Overlay => "Overlay",
+            Action::AutoOrient => "Auto-orient",
+            Action::Hint => "Hint",
+            Action::Report => "Game report",
+            Action::Undo => "Undo",
+            Action::Redo => "Redo",
+            Action::NewGame => "New game",
+            Action::Annotate => "Annotate move",
+            Action::LoadFen => "Load FEN",
+            Action::SavePgn => "Save PGN",
+            Action::ExportImage => "Export image",
+            Action::MyGames => "My games",
+            Action::CopyFen => "Copy FEN",
+            Action::CopyPgn => "Copy PGN",
+            Action::Paste => "Paste",
+            Action::TypeMove => "Type a move",
+            Action::Previous => "Previous move",
+            Action::Next => "Next move",
+            Action::First => "First move",
+            Action::Last => "Last move",
+            Action::Back => "Back to game",
+            Action::Pause => "Pause engines",
+        }
+    }
+
+    fn defaults(self) -> &'static [&'static str] {
+        match self {
+            Action::Fullscreen => &["F11", "Alt+Enter"],
+            Action::Flip => &["F"],
+            Action::Mute => &["M"],
+            Action::Eval => &["E"],
+            Action::Lines => &["L"],
+            Action::Explorer => &["O"],
+            Action::Blindfold => &["B"],
+            Action::Overlay => &["T"],
+            Action::AutoOrient => &["A"],
+            Action::Hint => &["H"],
+            Action::Report => &["G"],
+            Action::Undo => &["Ctrl+Z"],
+            Action::Redo => &["Ctrl+Y", "Ctrl+Shift+Z"],
+            Action::NewGame => &["Ctrl+N"],
+            Action::Annotate => &["C"],
+            Action::LoadFen => &["Ctrl+L"],
+            Action::SavePgn => &["Ctrl+S"],
+            Action::ExportImage => &["Ctrl+E"],
+            Action::MyGames => &["Ctrl+O"],
+            Action::CopyFen => &["Ctrl+C"],
+            Action::CopyPgn => &["Ctrl+Shift+C"],
+            Action::Paste => &["Ctrl+V"],
+            Action::TypeMove => &["Enter"],
+            Action::Previous => &["Left"],
+            Action::Next => &["Right"],
+            Action::First => &["Home"],
+            Action::Last => &["End"],
+            Action::Back => &["Escape"],
+            Action::Pause => &["Space"],
+        }
+    }
+}
+
+// the keys that can be bound; modifiers only go with another key
+const KEYS: [KeyCode; 70] = [
+    KeyCode::A,
+    KeyCode::B,
+    KeyCode::C,
+    KeyCode::D,
+    KeyCode::E,
+    KeyCode::F,
+    KeyCode::G,
+    KeyCode::H,
+    KeyCode::I,
+    KeyCode::J,
+    KeyCode::K,
+    KeyCode::L,
+    KeyCode::M,
+    KeyCode::N,
+    KeyCode::O,
+    KeyCode::P,
+    KeyCode::Q,
+    KeyCode::R,
+    KeyCode::S,
+    KeyCode::T,
+    KeyCode::U,
+    KeyCode::V,
+    KeyCode::W,
+    KeyCode::X,
+    KeyCode::Y,
+    KeyCode::Z,
+    KeyCode::Key0,
+    KeyCode::Key1,
+    KeyCode::Key2,
+    KeyCode::Key3,
+    KeyCode::Key4,
+    KeyCode::Key5,
+    KeyCode::Key6,
+    KeyCode::Key7,
+    KeyCode::Key8,
+    KeyCode::Key9,
+    KeyCode::F1,
+    KeyCode::F2,
+    KeyCode::F3,
+    KeyCode::F4,
+    KeyCode::F5,
+    KeyCode::F6,
+    KeyCode::F7,
+    KeyCode::F8,
+    KeyCode::F9,
+    KeyCode::F10,
+    KeyCode::F11,
+    KeyCode::F12,
+    KeyCode::Space,
+    KeyCode::Enter,
+    KeyCode::Escape,
+    KeyCode::Tab,
+    KeyCode::Backspace,
+    KeyCode::Insert,
+    KeyCode::Delete,
+    KeyCode::Left,
+    KeyCode::Right,
+    KeyCode::Up,
+    KeyCode::Down,
+    KeyCode::Home,
+    KeyCode::End,
+    KeyCode::PageUp,
+    KeyCode::PageDown,
+    KeyCode::Comma,
+    KeyCode::Period,
+    KeyCode::Slash,
+    KeyCode::Semicolon,
+    KeyCode::Minus,
+    KeyCode::Equal,
+    KeyCode::Apostrophe,
+];
+
+fn key_name(key: KeyCode) -> String {
+    let name = format!("{key:?}");
+    match name.strip_prefix("Key") {
+        Some(digit) => digit.to_owned(),
+        None => name,
+    }
+}
+
+// a key and the modifiers held with it, none more and none fewer
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct KeyBinding {
+    pub key: KeyCode,
+    pub ctrl: bool,
+    pub shift: bool,
+    pub alt: bool,
+}
+
+impl KeyBinding {
+    pub fn pressed(&self) -> bool {
+        is_key_pressed(self.key) && *self == Self::held(self.key)
+    }
+
+    // the key with whatever modifiers are down right now, if it's one that binds
+    pub fn held(key: KeyCode) -> Self {
+        let down = |left, right| is_key_down(left) || is_key_down(right);
+        Self {
+            key,
+            ctrl: down(KeyCode::LeftControl, KeyCode::RightControl),
+            shift: down(KeyCode::LeftShift, KeyCode::RightShift),
+            alt: down(KeyCode::LeftAlt, KeyCode::RightAlt),
+        }
+    }
+
+    pub fn bindable(key: KeyCode) -> bool {
+        KEYS.contains(&key)
+    }
+}
+
+impl FromStr for KeyBinding {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts: Vec<&str> = s.split('+').map(str::trim).collect();
+        let name = parts.pop().unwrap_or_default();
+        let key = KEYS
+            .into_iter()
+            .find(|&key| key_name(key).eq_ignore_ascii_case(name))
+            .ok_or_else(|| format!("no key called {name:?}"))?;
+        let mut binding = Self {
+            key,
+            ctrl: false,
+            shift: false,
+            alt: false,
+        };
+        for modifier in parts {
+            match modifier.to_ascii_lowercase().as_str() {
+                "ctrl" => binding.ctrl = true,
+                "shift" => binding.shift = true,
+                "alt" => binding.alt = true,
+                _ => return Err(format!("no modifier called {modifier:?}")),
+            }
+        }
+        Ok(binding)
+    }
+}
+
+impl Display for KeyBinding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (held, name) in [
+            (self.ctrl, "Ctrl+"),
+            (self.shift, "Shift+"),
+            (self.alt, "Alt+"),
+        ] {
+            if held {
+                f.write_str(name)?;
+            }
+        }
+        f.write_str(&key_name(self.key))
+    }
+}
+
+// the keys for every action, each action having any number of them
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeyBindings {
+    bindings: Vec<(Action, Vec<KeyBinding>)>,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        let bindings = Action::ALL
+            .into_iter()
+            .map(|action| {
+                let keys = action.defaults().iter().filter_map(|s| s.parse().ok());
+                (action, keys.collect())
+            })
+            .collect();
+        Self { bindings }
+    }
+}
+
+impl KeyBindings {
+    pub fn pressed(&self, action: Action) -> bool {
+        self.get(action).iter().any(KeyBinding::pressed)
+    }
+
+    pub fn get(&self, action: Action) -> &[KeyBinding] {
+        self.bindings
+            .iter()
+            .find(|(bound, _)| *bound == action)
+            .map_or(&[], |(_, keys)| keys)
+    }
+
+    // the action other than `action` that already has `binding`
+    pub fn conflict(&self, action: Action, binding: KeyBinding) -> Option<Action> {
+        self.bindings
+            .iter()
+            .find(|(bound, keys)| *bound != action && keys.contains(&binding))
+            .map(|(bound, _)| *bound)
+    }
+
+    // every pair of actions sharing a key
+    pub fn conflicts(&self) -> Vec<(Action, Action, KeyBinding)> {
+        let mut conflicts = Vec::new();
+        for (idx, (action, keys)) in self.bindings.iter().enumerate() {
+            for (other, other_keys) in &self.bindings[idx + 1..] {
+                for &binding in keys.iter().filter(|key| other_keys.contains(key)) {
+                    conflicts.push((*action, *other, binding));
+                }
+            }
+        }
+        conflicts
+    }
+
+    // replaces the action's keys
+    pub fn set(&mut self, action: Action, keys: Vec<KeyBinding>) {
+        match self.bindings.iter_mut().find(|(bound, _)| *bound == action) {
+            Some((_, bound)) => *bound = keys,
+            None => self.bindings.push((action, keys)),
+        }
+    }
+
+    // `key.<action> = <binding>, ...` lines from the settings file
+    pub fn parse_line(&mut self, name: &str, value: &str) {
+        let Some(action) = Action::ALL.into_iter().find(|a| a.name() == name) else {
+            log::warn!("no action called {name:?} to bind keys to");
+            return;
+        };
+        let mut keys = Vec::new();
+        for binding in value.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+            match binding.parse() {
+                Ok(binding) => keys.push(binding),
+                Err(err) => log::warn!("couldn't bind {binding:?} to {name}: {err}"),
+            }
+        }
+        self.set(action, keys);
+    }
+}
+
+// only the bindings changed from the defaults are written
+impl Display for KeyBindings {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let defaults = Self::default();
+        for (action, keys) in &self.bindings {
+            if defaults.get(*action) == keys.as_slice() {
+                continue;
+            }
+            let keys: Vec<String> = keys.iter().map(KeyBinding::to_string).collect();
+            writeln!(f, "key.{} = {}", action.name(), keys.join(", "))?;
+        }
+        Ok(())
+    }
+}
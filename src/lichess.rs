@@ -0,0 +1,373 @@
+use chesslib::prelude::*;
+use serde_json::Value;
+use std::{
+    io::{BufRead, BufReader, Write},
+    process::{Command, Stdio},
+    sync::mpsc::{self, Receiver, Sender},
+    thread,
+};
+
+const API: &str = "https://lichess.org/api";
+
+type Parser = Box<dyn Fn(&Value) -> Option<Event> + Send>;
+
+enum Event {
+    Account(String),
+    Challenge(Challenge),
+    ChallengeGone(String),
+    GameStart(String),
+    GameFull(LichessGame),
+    Board(String, BoardState),
+    Playing(Vec<OngoingGame>),
+    Failed(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct Challenge {
+    pub id: String,
+    pub summary: String,
+}
+
+// one of the account's games still going, from the list of them
+#[derive(Debug, Clone)]
+pub struct OngoingGame {
+    pub id: String,
+    pub opponent: String,
+    pub color: Color,
+    pub my_turn: bool,
+}
+
+impl OngoingGame {
+    pub fn summary(&self) -> String {
+        let turn = if self.my_turn {
+            "your move"
+        } else {
+            "their move"
+        };
+        format!("lichess: vs {} as {:?} - {turn}", self.opponent, self.color)
+    }
+}
+
+// the server's view of a game in progress; times in milliseconds
+#[derive(Debug, Clone, Default)]
+pub struct BoardState {
+    pub moves: Vec<String>,
+    pub wtime: u64,
+    pub btime: u64,
+    pub status: String,
+    pub winner: Option<Color>,
+}
+
+impl BoardState {
+    pub fn is_over(&self) -> bool {
+        !matches!(self.status.as_str(), "created" | "started")
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct LichessGame {
+    pub id: String,
+    pub white: (String, String),
+    pub black: (String, String),
+    pub initial_fen: String,
+    pub state: BoardState,
+    // get_time() when the clocks last came from the server
+    pub synced_at: f64,
+}
+
+// a lichess account played through the board API. The app has no HTTP client of its
+// own, so every request is a curl process, the token handed over on its stdin.
+pub struct Lichess {
+    token: String,
+    events_tx: Sender<Event>,
+    events: Receiver<Event>,
+    pub user: Option<String>,
+    pub challenges: Vec<Challenge>,
+    pub game: Option<LichessGame>,
+    // correspondence games, once asked for
+    pub playing: Vec<OngoingGame>,
+    pub error: Option<String>,
+}
+
+impl Lichess {
+    pub fn connect(token: String) -> Self {
+        let (events_tx, events) = mpsc::channel();
+        let lichess = Self {
+            token,
+            events_tx,
+            events,
+            user: None,
+            challenges: Vec::new(),
+            game: None,
+            playing: Vec::new(),
+            error: None,
+        };
+        lichess.stream("account", |json| {
+            Some(Event::Account(json["id"].as_str()?.to_owned()))
+        });
+        lichess.stream("stream/event", parse_event);
+        lichess
+    }
+
+    // our side in the current game, once the account is known
+    pub fn color(&self) -> Option<Color> {
+        let (game, user) = (self.game.as_ref()?, self.user.as_ref()?);
+        Some(if &game.white.0 == user {
+            Color::White
+        } else {
+            Color::Black
+        })
+    }
+
+    // takes in what the server sent since the last frame; true if the game moved on
+    pub fn update(&mut self, now: f64) -> bool {
+        let mut changed = false;
+        for event in self.events.try_iter().collect::<Vec<_>>() {
+            match event {
+                Event::Account(user) => {
+                    self.user = Some(user);
+                    changed = self.game.is_some();
+                }
+                Event::Challenge(challenge) => self.challenges.push(challenge),
+                Event::ChallengeGone(id) => self.challenges.retain(|c| c.id != id),
+                Event::GameStart(id) => {
+                    self.challenges.clear();
+                    self.open(&id);
+                }
+                Event::GameFull(mut game) => {
+                    game.synced_at = now;
+                    self.game = Some(game);
+                    changed = true;
+                }
+                Event::Board(id, state) => {
+                    // streams of games left for another keep talking
+                    if let Some(game) = &mut self.game
+                        && game.id == id
+                    {
+                        game.state = state;
+                        game.synced_at = now;
+                        changed = true;
+                    }
+                }
+                Event::Playing(playing) => self.playing = playing,
+                Event::Failed(err) => self.error = Some(err),
+            }
+        }
+        changed
+    }
+
+    // puts one of the account's games on the board
+    pub fn open(&self, id: &str) {
+        let id = id.to_owned();
+        self.stream(&format!("board/game/stream/{id}"), move |json| {
+            parse_game_event(json, &id)
+        });
+    }
+
+    // asks again for the account's correspondence games
+    pub fn refresh_playing(&self) {
+        self.stream("account/playing?nb=50", parse_playing);
+    }
+
+    pub fn seek(&self, minutes: u32, increment: u32) {
+        self.post("board/seek", &[
+            ("time", minutes.to_string()),
+            ("increment", increment.to_string()),
+            ("rated", "false".to_owned()),
+        ]);
+    }
+
+    pub fn accept(&self, challenge: &str) {
+        self.post(&format!("challenge/{challenge}/accept"), &[]);
+    }
+
+    pub fn play(&self, uci: &str) {
+        self.game_action(&format!("move/{uci}"));
+    }
+
+    pub fn resign(&self) {
+        self.game_action("resign");
+    }
+
+    pub fn offer_draw(&self) {
+        self.game_action("draw/yes");
+    }
+
+    fn game_action(&self, action: &str) {
+        if let Some(game) = &self.game {
+            self.post(&format!("board/game/{}/{action}", game.id), &[]);
+        }
+    }
+
+    fn curl(&self, path: &str, args: &[String]) -> Command {
+        let mut command = Command::new("curl");
+        command
+            .args(["-sSfN", "-H", "@-"])
+            .args(args)
+            .arg(format!("{API}/{path}"))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        command
+    }
+
+    // a request whose answer is a stream of JSON lines, read on its own thread
+    fn stream(&self, path: &str, parse: impl Fn(&Value) -> Option<Event> + Send + 'static) {
+        self.spawn(path, Vec::new(), Some(Box::new(parse)));
+    }
+
+    fn post(&self, path: &str, form: &[(&str, String)]) {
+        let mut args = vec!["-X".to_owned(), "POST".to_owned()];
+        for (name, value) in form {
+            args.push("-d".to_owned());
+            args.push(format!("{name}={value}"));
+        }
+        self.spawn(path, args, None);
+    }
+
+    fn spawn(&self, path: &str, args: Vec<String>, parse: Option<Parser>) {
+        log::debug!(
+            "lichess {} {path}",
+            if parse.is_some() { "stream" } else { "post" }
+        );
+        let mut command = self.curl(path, &args);
+        let header = format!("Authorization: Bearer {}\n", self.token);
+        let (events, path) = (self.events_tx.clone(), path.to_owned());
+        thread::spawn(move || {
+            let failed = |err: String| {
+                log::warn!("lichess {path}: {err}");
+                let _ = events.send(Event::Failed(format!("{path}: {err}")));
+            };
+            let mut child = match command.spawn() {
+                Ok(child) => child,
+                Err(err) => return failed(format!("couldn't run curl: {err}")),
+            };
+            if let Some(mut stdin) = child.stdin.take() {
+                let _ = stdin.write_all(header.as_bytes());
+            }
+            if let (Some(parse), Some(stdout)) = (parse, child.stdout.take()) {
+                let lines = BufReader::new(stdout).lines().map_while(Result::ok);
+                // the server keeps the stream open with empty lines
+                for line in lines.filter(|line| !line.trim().is_empty()) {
+                    log::trace!("lichess {path} > {line}");
+                    let Ok(json) = serde_json::from_str::<Value>(&line) else {
+                        continue;
+                    };
+                    if let Some(event) = parse(&json)
+                        && events.send(event).is_err()
+                    {
+                        break;
+                    }
+                }
+            }
+            match child.wait_with_output() {
+                Ok(output) if !output.status.success() => {
+                    failed(String::from_utf8_lossy(&output.stderr).trim().to_owned())
+                }
+                Err(err) => failed(err.to_string()),
+                Ok(_) => {}
+            }
+        });
+    }
+}
+
+fn parse_event(json: &Value) -> Option<Event> {
+    let challenge = &json["challenge"];
+    match json["type"].as_str()? {
+        // only plain chess fits on this board
+        "challenge" if challenge["variant"]["key"] == "standard" => {
+            let summary = format!(
+                "{} {} {}",
+                challenge["challenger"]["name"].as_str().unwrap_or("?"),
+                challenge["timeControl"]["show"].as_str().unwrap_or("-"),
+                if challenge["rated"] == true {
+                    "rated"
+                } else {
+                    "casual"
+                },
+            );
+            Some(Event::Challenge(Challenge {
+                id: challenge["id"].as_str()?.to_owned(),
+                summary,
+            }))
+        }
+        "challengeCanceled" | "challengeDeclined" => {
+            Some(Event::ChallengeGone(challenge["id"].as_str()?.to_owned()))
+        }
+        "gameStart" => {
+            let game = &json["game"];
+            let id = game["gameId"].as_str().or(game["id"].as_str())?;
+            Some(Event::GameStart(id.to_owned()))
+        }
+        _ => None,
+    }
+}
+
+fn parse_playing(json: &Value) -> Option<Event> {
+    let playing = json["nowPlaying"].as_array()?;
+    let playing = playing
+        .iter()
+        // only games of days per move are picked up again from a list
+        .filter(|game| game["speed"] == "correspondence" && game["variant"]["key"] == "standard")
+        .filter_map(|game| {
+            Some(OngoingGame {
+                id: game["gameId"].as_str()?.to_owned(),
+                opponent: game["opponent"]["username"]
+                    .as_str()
+                    .unwrap_or("?")
+                    .to_owned(),
+                color: match game["color"].as_str()? {
+                    "white" => Color::White,
+                    _ => Color::Black,
+                },
+                my_turn: game["isMyTurn"] == true,
+            })
+        })
+        .collect();
+    Some(Event::Playing(playing))
+}
+
+fn parse_game_event(json: &Value, id: &str) -> Option<Event> {
+    match json["type"].as_str()? {
+        "gameFull" => {
+            let player = |side: &Value| {
+                let id = side["id"].as_str().unwrap_or_default().to_owned();
+                let name = side["name"].as_str().unwrap_or("AI").to_owned();
+                (id, name)
+            };
+            let initial_fen = match json["initialFen"].as_str() {
+                Some("startpos") | None => DEFAULT_FEN.to_owned(),
+                Some(fen) => fen.to_owned(),
+            };
+            Some(Event::GameFull(LichessGame {
+                id: json["id"].as_str()?.to_owned(),
+                white: player(&json["white"]),
+                black: player(&json["black"]),
+                initial_fen,
+                state: parse_board_state(&json["state"]),
+                synced_at: 0.0,
+            }))
+        }
+        "gameState" => Some(Event::Board(id.to_owned(), parse_board_state(json))),
+        _ => None,
+    }
+}
+
+fn parse_board_state(json: &Value) -> BoardState {
+    BoardState {
+        moves: json["moves"]
+            .as_str()
+            .unwrap_or_default()
+            .split_whitespace()
+            .map(str::to_owned)
+            .collect(),
+        wtime: json["wtime"].as_u64().unwrap_or_default(),
+        btime: json["btime"].as_u64().unwrap_or_default(),
+        status: json["status"].as_str().unwrap_or("started").to_owned(),
+        winner: match json["winner"].as_str() {
+            Some("white") => Some(Color::White),
+            Some("black") => Some(Color::Black),
+            _ => None,
+        },
+    }
+}
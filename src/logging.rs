@@ -0,0 +1,61 @@
+use log::{LevelFilter, Log, Metadata, Record};
+use macroquad::miniquad::date;
+use std::{
+    fs::File,
+    io::{self, Write},
+    path::Path,
+    sync::Mutex,
+};
+
+// warnings and errors to stderr, everything down to engine and network traffic with
+// --verbose; a log file gets the same lines
+struct Logger {
+    level: LevelFilter,
+    file: Option<Mutex<File>>,
+}
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.level
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let line = format!(
+            "{:.3} {:<5} {}: {}",
+            date::now(),
+            record.level(),
+            record.target(),
+            record.args()
+        );
+        eprintln!("{line}");
+        if let Some(file) = &self.file
+            && let Ok(mut file) = file.lock()
+        {
+            let _ = writeln!(file, "{line}");
+        }
+    }
+
+    fn flush(&self) {
+        if let Some(file) = &self.file
+            && let Ok(mut file) = file.lock()
+        {
+            let _ = file.flush();
+        }
+    }
+}
+
+pub fn init(verbose: bool, path: Option<&Path>) -> io::Result<()> {
+    let level = if verbose {
+        LevelFilter::Trace
+    } else {
+        LevelFilter::Warn
+    };
+    let file = path.map(File::create).transpose()?.map(Mutex::new);
+    // only fails if a logger is already set, which leaves that one in charge
+    let _ = log::set_logger(Box::leak(Box::new(Logger { level, file })));
+    log::set_max_level(level);
+    Ok(())
+}
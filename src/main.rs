@@ -1,50 +1,2914 @@
+mod analysis;
+mod assets;
+mod audio;
+mod cloud;
+mod coach;
+mod correspondence;
+mod drill;
+mod endgame;
+mod engine_options;
+mod explorer;
+mod games;
+mod keys;
+mod lichess;
+mod logging;
+mod network;
+mod opponent;
+mod report;
+mod settings;
+mod spectator;
+mod speech;
+mod sprites;
+mod supervisor;
+mod themes;
+mod trainer;
 mod view;
 
-use chesslib::{errors::MoveError, prelude::*};
+use analysis::{ANALYSIS_LINES, Analyzer, Evaluation, Line};
+use audio::SoundEffect;
+use chesslib::{
+    analysis::{
+        influence_map,
+        motifs::{Motif, find_motifs},
+        review::{Judgement, win_percent},
+    },
+    board::{Board, bitboard::BitBoard},
+    clock::{Clock, TimeControl},
+    database::{Continuation, GameDatabase},
+    drill::Repertoire,
+    eco::{EcoOpening, EcoTable},
+    endgames::{Endgame, Goal},
+    engine_match::{Termination, accepts_draw, armageddon_result},
+    errors::MoveError,
+    netplay::{NetMessage, peer_move},
+    pgn::{Annotation, GameResult, PgnCollection, PgnGame, Variation, nag_glyph, parse_pgn},
+    prelude::*,
+    puzzles::parse_lichess_csv,
+    snapshot::StateToken,
+    uci::{GoLimits, Score},
+};
+use cloud::Cloud;
+use coach::{Coach, Probe};
+use drill::{Drill, DrillStatus};
+use endgame::{EndgameStatus, EndgameTrainer};
+use engine_options::EngineConfig;
 use errors::AppError;
-use macroquad::input::{
-    MouseButton, is_mouse_button_down, is_mouse_button_pressed, mouse_position,
+use explorer::Explorer;
+use keys::Action;
+use lichess::{Lichess, LichessGame};
+use macroquad::{
+    input::{
+        KeyCode, MouseButton, get_dropped_files, get_keys_down, is_key_pressed,
+        is_mouse_button_down, is_mouse_button_pressed, mouse_position, mouse_wheel,
+    },
+    miniquad::{
+        conf::Platform,
+        date,
+        window::{clipboard_get, clipboard_set},
+    },
+    time::{get_frame_time, get_time},
+    window::Conf,
+};
+use network::{NetGame, Peer, PeerEvent};
+use opponent::{EngineSetup, Opponent, Strength};
+use report::Report;
+use settings::Settings;
+use spectator::{Spectator, Watched};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::Duration,
 };
-use view::View;
+use trainer::{PuzzleStatus, Trainer};
+use view::{ChooserResponse, DialogResponse, GamesResponse, View};
+
+// what --watch plays on when no --clock is given
+const WATCH_TIME_CONTROL: &str = "60+1";
+// how much of each engine's principal variation the panel shows
+const PV_PLIES: usize = 6;
+// and of each of the analysis lines, which have to fit on a button
+const LINE_PLIES: usize = 4;
+// the most played moves the opening explorer lists
+const EXPLORER_MOVES: usize = 4;
+// games listed at once when choosing from a PGN file
+const CHOOSER_GAMES: usize = 200;
+
+// vsync has to be settled before the window exists
+fn window_conf() -> Conf {
+    let vsync = !std::env::args().any(|arg| arg == "--no-vsync");
+    Conf {
+        window_title: "Chess".to_owned(),
+        platform: Platform {
+            swap_interval: Some(vsync as i32),
+            ..Default::default()
+        },
+        ..Default::default()
+    }
+}
+
+#[macroquad::main(window_conf)]
+async fn main() -> Result<(), anyhow::Error> {
+    // chessapp [--engine <uci engine>] [--puzzles <lichess csv>] [--white <name>]
+    //     [--black <name>] [--games <dir>] [--clock <time control>] [--hotseat]
+    //     [--play <white | black>] [--drill <white | black> <repertoire.pgn>]
+    //     [--watch <white engine> <black engine>] [--verbose] [--log <file>]
+    //     [--host <addr> | --connect <addr>] [--lichess] [--correspondence]
+    //     [--endgames] [--explorer <games.pgn>] [--cloud] [--armageddon] [--no-vsync]
+    //     [--fps <cap>] [fen | game.pgn]
+    // --lichess signs in with the API token in LICHESS_TOKEN; --play takes one side
+    // against the --engine; --drill quizzes one side of a repertoire; --watch has two
+    // engines play each other on the --clock; --correspondence starts at the list of
+    // games played a move a day; --endgames has the --engine, if any, defend the textbook
+    // endings; --explorer lists the moves played from each position in a database of games;
+    // --cloud, in a build with the cloud feature, adds lichess' cloud evaluation and,
+    // without --explorer, its games;
+    // --armageddon gives Black draw odds for four fifths of White's time on the --clock
+    let (mut fen, mut engine, mut puzzles, mut pgn) = (None, None, None, None);
+    let (mut white, mut black, mut games_dir) = (None, None, None);
+    let (mut time_control, mut hotseat, mut peer) = (None, false, None);
+    let (mut lichess, mut fps_cap, mut play) = (None, None, None);
+    let (mut verbose, mut log_file, mut drill, mut watch) = (false, None, None, None);
+    let (mut my_games, mut endgames, mut explorer, mut cloud) = (false, false, None, false);
+    let mut armageddon = false;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--engine" => engine = args.next(),
+            "--white" => white = args.next(),
+            "--black" => black = args.next(),
+            "--games" => games_dir = args.next(),
+            "--clock" => time_control = args.next(),
+            "--hotseat" => hotseat = true,
+            "--correspondence" => my_games = true,
+            "--endgames" => endgames = true,
+            "--verbose" => verbose = true,
+            "--log" => log_file = args.next().map(PathBuf::from),
+            "--play" => {
+                play = match args.next().as_deref() {
+                    Some("white") => Some(Color::White),
+                    Some("black") => Some(Color::Black),
+                    _ => anyhow::bail!("--play takes white or black"),
+                }
+            }
+            "--drill" => {
+                let color = match args.next().as_deref() {
+                    Some("white") => Color::White,
+                    Some("black") => Color::Black,
+                    _ => anyhow::bail!("--drill takes white or black, then a PGN file"),
+                };
+                let Some(path) = args.next() else {
+                    anyhow::bail!("--drill needs a repertoire PGN file");
+                };
+                drill = Some((color, path));
+            }
+            "--watch" => {
+                let (Some(white), Some(black)) = (args.next(), args.next()) else {
+                    anyhow::bail!("--watch needs two engines, White's first");
+                };
+                watch = Some([white, black]);
+            }
+            "--no-vsync" => {}
+            "--fps" => fps_cap = args.next().and_then(|fps| fps.parse().ok()),
+            "--host" => {
+                let addr = args.next().unwrap_or_else(|| "0.0.0.0:7878".to_owned());
+                peer = Some(NetGame::new(Peer::host(&addr)?, Color::White));
+            }
+            "--lichess" => {
+                let token = std::env::var("LICHESS_TOKEN")
+                    .map_err(|_| anyhow::anyhow!("--lichess needs LICHESS_TOKEN set"))?;
+                lichess = Some(Lichess::connect(token));
+            }
+            "--connect" => {
+                let addr = args.next().unwrap_or_else(|| "127.0.0.1:7878".to_owned());
+                peer = Some(NetGame::new(Peer::connect(addr), Color::Black));
+            }
+            "--puzzles" => puzzles = args.next(),
+            "--explorer" => explorer = args.next(),
+            "--cloud" => cloud = true,
+            "--armageddon" => armageddon = true,
+            _ if arg.ends_with(".pgn") => pgn = Some(arg),
+            _ => fen = Some(arg),
+        }
+    }
+    logging::init(verbose, log_file.as_deref())?;
+    // the web has no `Instant` for the clocks to go by
+    #[cfg(target_arch = "wasm32")]
+    chesslib::clock::set_time_source(|| Duration::from_secs_f64(get_time()));
+    // an unfinished game is only offered when nothing else was asked for
+    let fresh = fen.is_none()
+        && pgn.is_none()
+        && puzzles.is_none()
+        && drill.is_none()
+        && !endgames
+        && watch.is_none()
+        && peer.is_none()
+        && lichess.is_none()
+        && !my_games;
+    let mut app = App::new(fen.as_deref().unwrap_or(DEFAULT_FEN), engine).await?;
+    app.players = [white, black].map(|name| name.unwrap_or_else(|| "?".to_owned()));
+    if let Some(dir) = games_dir {
+        app.games_dir = dir.into();
+    }
+    if fresh {
+        app.resume = games::load_autosave(&app.games_dir);
+    }
+    app.armageddon = armageddon;
+    if let Some(time_control) = time_control {
+        let time_control: TimeControl = time_control.parse()?;
+        app.clock = Some(app.new_clock(time_control));
+    }
+    app.lichess = lichess;
+    app.view.fps_cap = fps_cap;
+    if let Some(color) = play {
+        let Some(program) = app.engine.clone() else {
+            anyhow::bail!("--play needs an --engine to play against");
+        };
+        let engine_color = !color;
+        if app.players[engine_color as usize] == "?" {
+            let name = Path::new(&program).file_stem().map(|s| s.to_string_lossy());
+            app.players[engine_color as usize] = name.unwrap_or_default().into_owned();
+        }
+        app.opponent = Some(Opponent::spawn(program, engine_color));
+        app.view.set_flipped(color == Color::Black);
+        app.open_engine_dialog();
+    }
+    if let Some(programs) = watch {
+        let time_control = match &app.clock {
+            Some(clock) => clock.time_control(),
+            None => WATCH_TIME_CONTROL.parse()?,
+        };
+        app.clock = Some(Clock::new(time_control));
+        app.spectator = Some(Spectator::spawn(programs, time_control));
+    }
+    if let Some(net) = peer {
+        app.view.set_flipped(net.color == Color::Black);
+        app.net = Some(net);
+    }
+    // two players at one screen, each getting the board their way up
+    if hotseat {
+        app.auto_orient = true;
+        app.orient_board();
+    }
+    if let Some(path) = explorer {
+        app.explorer = Some(Explorer::new(GameDatabase::open(path)?));
+    }
+    if cloud {
+        if !cfg!(feature = "cloud") {
+            anyhow::bail!("--cloud needs a build with the cloud feature");
+        }
+        app.cloud = Some(Cloud::new(std::env::var("LICHESS_TOKEN").ok()));
+    }
+    if let Some(path) = pgn {
+        app.open_pgn(&fs::read_to_string(path)?)?;
+    }
+    if let Some(path) = puzzles {
+        let puzzles = parse_lichess_csv(&fs::read_to_string(&path)?)?;
+        if puzzles.is_empty() {
+            anyhow::bail!("no puzzles in {path}");
+        }
+        app.trainer = Some(Trainer::new(puzzles));
+        app.next_puzzle();
+    }
+    if let Some((color, path)) = drill {
+        let repertoire = Repertoire::from_pgn(&fs::read_to_string(&path)?, color)?;
+        if repertoire.positions().next().is_none() {
+            anyhow::bail!("no {color:?} moves in {path}");
+        }
+        app.drill = Some(Drill::new(repertoire));
+        app.next_line();
+    }
+    if endgames {
+        let seed = (date::now() * 1000.0).max(0.0) as u64;
+        app.endgames = Some(EndgameTrainer::new(app.engine.clone(), seed));
+        app.start_endgame(Endgame::ALL[0]);
+    }
+    if my_games {
+        app.open_games_dialog();
+    }
+    log::debug!("starting from {}\n{}", app.start_fen, app.gs);
+    loop {
+        app.update_state();
+        app.draw_state();
+        app.view.next_frame(app.busy).await;
+    }
+}
+
+struct App {
+    gs: GameState,
+    start_fen: String,
+    view: View,
+    mouse: (f32, f32),
+    // anything this frame that the next one should show promptly
+    busy: bool,
+    app_state: AppState,
+    last_move: Option<(Square, Square)>,
+    // keep the side to move at the bottom, for two humans sharing the board
+    auto_orient: bool,
+    history: Vec<Takeback>,
+    redo: Vec<Move>,
+    fen_dialog: Option<InputDialog>,
+    // a move typed in, for when the pieces can't be seen
+    move_dialog: Option<InputDialog>,
+    blindfold: Blindfold,
+    overlay: Overlay,
+    hint: Option<Hint>,
+    settings_open: bool,
+    analyzer: Option<Analyzer>,
+    show_eval: bool,
+    // the engine's best lines, docked above the move list
+    show_lines: bool,
+    // the moves played from here in the --explorer database, docked under those
+    explorer: Option<Explorer>,
+    // lichess' evaluation and games for the position, with --cloud
+    cloud: Option<Cloud>,
+    show_explorer: bool,
+    // eases towards the engine's latest opinion so the bar doesn't jump
+    eval_share: f32,
+    eval_label: String,
+    trainer: Option<Trainer>,
+    drill: Option<Drill>,
+    coach: Option<Coach>,
+    endgames: Option<EndgameTrainer>,
+    // two engines playing each other, with the board following along
+    spectator: Option<Spectator>,
+    // an earlier position from the history on show, the game itself left alone
+    review: Option<Review>,
+    // wheel and swipe travel not yet turned into whole plies
+    scroll: f32,
+    // headers of the PGN game the history came from
+    tags: Vec<(String, String)>,
+    // White's and Black's names for saved games
+    players: [String; 2],
+    engine: Option<String>,
+    games_dir: PathBuf,
+    clock: Option<Clock>,
+    flagged: Option<Color>,
+    // draws count as wins for Black, who has less time on the clock for it
+    armageddon: bool,
+    // a result reached off the board, by resignation or agreement
+    decided: Option<GameResult>,
+    net: Option<NetGame>,
+    chat_input: Option<String>,
+    lichess: Option<Lichess>,
+    // the lichess game on the board
+    lichess_game: Option<String>,
+    opponent: Option<Opponent>,
+    // how a game decided off the board ended
+    termination: Option<Termination>,
+    eco: EcoTable,
+    // the game's opening, worked out again whenever the position changes
+    opening: Option<EcoOpening>,
+    opening_key: Option<u64>,
+    report: Option<Report>,
+    // a finished game gets a report once, without asking
+    reported: bool,
+    // a game left unfinished last time, until it's resumed or thrown away
+    resume: Option<String>,
+    // the plies on disk and when they were written, while there's an autosave
+    autosaved: Option<(usize, f64)>,
+    // where this sitting's finished games against the engine or a person are kept
+    session: Option<PathBuf>,
+    // whether the game on the board went there yet
+    recorded: bool,
+    // the choices for the next game against the engine, while they're being made
+    engine_dialog: Option<EngineSetup>,
+    engine_config: Option<EngineConfig>,
+    // glyphs, comments and variations for the moves in `history`, by ply
+    annotations: Vec<Annotation>,
+    // games set aside while a side line is played out, innermost last
+    branches: Vec<Branch>,
+    annotation_dialog: Option<AnnotationDialog>,
+    // the correspondence games to pick from, while the list is up
+    games_dialog: Option<GamesDialog>,
+    pgn_chooser: Option<PgnChooser>,
+    // the correspondence game on the board, written back after every move
+    correspondence: Option<Correspondence>,
+}
+
+// the engine's move for a position, shown piece first and in full on a second ask
+struct Hint {
+    fen: String,
+    mv: Option<Move>,
+    full: bool,
+}
+
+struct Review {
+    ply: usize,
+    gs: GameState,
+}
+
+// the line a side line was started from, put back as it was once the side line is done
+struct Branch {
+    // the move the side line stands in for
+    ply: usize,
+    gs: GameState,
+    history: Vec<Takeback>,
+    redo: Vec<Move>,
+    annotations: Vec<Annotation>,
+    last_move: Option<(Square, Square)>,
+}
+
+impl Branch {
+    // the line's annotations with the side line's share of them handed back, and the
+    // side line itself added as a variation
+    fn merge(&self, history: &[Takeback], annotations: &[Annotation]) -> Vec<Annotation> {
+        let mut merged = self.annotations.clone();
+        let shared = self.ply.min(history.len());
+        if merged.len() < shared {
+            merged.resize_with(shared, Annotation::default);
+        }
+        for (idx, annotation) in annotations.iter().take(shared).enumerate() {
+            merged[idx] = annotation.clone();
+        }
+        let moves: Vec<String> = history
+            .get(self.ply..)
+            .unwrap_or_default()
+            .iter()
+            .map(|t| t.san.clone())
+            .collect();
+        if moves.is_empty() {
+            return merged;
+        }
+        if merged.len() <= self.ply {
+            merged.resize_with(self.ply + 1, Annotation::default);
+        }
+        merged[self.ply].variations.push(Variation {
+            moves,
+            annotations: annotations.get(self.ply..).unwrap_or_default().to_vec(),
+        });
+        merged
+    }
+}
+
+// a glyph and a comment for the move at `idx` in the history
+struct AnnotationDialog {
+    idx: usize,
+    nag: Option<u8>,
+    comment: String,
+}
+
+// a PGN file of more than one game, waiting for one to be picked
+struct PgnChooser {
+    games: PgnCollection,
+    filter: String,
+}
+
+struct GamesDialog {
+    games: Vec<correspondence::Entry>,
+    opponent: String,
+}
+
+struct Correspondence {
+    path: PathBuf,
+    color: Color,
+    // the plies in the file
+    saved: usize,
+}
+
+struct InputDialog {
+    input: String,
+    error: Option<String>,
+}
+
+// what it takes to roll the game back over one move
+#[derive(Clone)]
+struct Takeback {
+    mv: Move,
+    san: String,
+    captured: Option<Piece>,
+    token: StateToken,
+}
+
+impl App {
+    async fn new(fen: &str, engine: Option<String>) -> Result<Self, AppError> {
+        let gs = GameState::try_from_fen(fen)?;
+        let mut view = View::new().await?;
+        view.apply_settings(&Settings::load());
+        Ok(Self {
+            gs,
+            start_fen: fen.to_owned(),
+            view,
+            mouse: mouse_position(),
+            busy: true,
+            app_state: AppState::Free,
+            last_move: None,
+            auto_orient: false,
+            history: Vec::new(),
+            redo: Vec::new(),
+            fen_dialog: None,
+            move_dialog: None,
+            blindfold: Blindfold::Off,
+            overlay: Overlay::Off,
+            hint: None,
+            settings_open: false,
+            // a browser can't start the engine's process or its thread
+            analyzer: engine
+                .clone()
+                .filter(|_| !cfg!(target_arch = "wasm32"))
+                .map(Analyzer::spawn),
+            show_eval: true,
+            show_lines: true,
+            explorer: None,
+            cloud: None,
+            show_explorer: true,
+            eval_share: 0.5,
+            eval_label: String::new(),
+            trainer: None,
+            drill: None,
+            coach: None,
+            endgames: None,
+            spectator: None,
+            review: None,
+            scroll: 0.0,
+            tags: Vec::new(),
+            players: ["?".to_owned(), "?".to_owned()],
+            engine,
+            games_dir: games::DEFAULT_GAMES_DIR.into(),
+            clock: None,
+            flagged: None,
+            armageddon: false,
+            decided: None,
+            net: None,
+            chat_input: None,
+            lichess: None,
+            lichess_game: None,
+            opponent: None,
+            termination: None,
+            eco: EcoTable::builtin(),
+            opening: None,
+            opening_key: None,
+            report: None,
+            reported: false,
+            resume: None,
+            autosaved: None,
+            session: None,
+            recorded: false,
+            engine_dialog: None,
+            engine_config: None,
+            annotations: Vec::new(),
+            branches: Vec::new(),
+            annotation_dialog: None,
+            games_dialog: None,
+            pgn_chooser: None,
+            correspondence: None,
+        })
+    }
+
+    fn load_fen(&mut self, fen: &str) -> Result<(), AppError> {
+        self.gs = GameState::try_from_fen(fen)?;
+        self.start_fen = fen.to_owned();
+        self.app_state = AppState::Free;
+        self.last_move = None;
+        self.history.clear();
+        self.redo.clear();
+        self.review = None;
+        self.tags.clear();
+        self.view.set_header(false);
+        self.opening = None;
+        self.opening_key = None;
+        self.report = None;
+        self.reported = false;
+        self.recorded = false;
+        self.annotations.clear();
+        self.branches.clear();
+        self.correspondence = None;
+        self.flagged = None;
+        self.decided = None;
+        self.termination = None;
+        if let Some(clock) = &mut self.clock {
+            *clock = Clock::with_time_controls(clock.time_controls());
+        }
+        self.orient_board();
+        Ok(())
+    }
+
+    fn update_state(&mut self) {
+        self.view.update_screen();
+        let mouse = mouse_position();
+        let wheel = mouse_wheel();
+        self.busy = mouse != self.mouse
+            || wheel != (0.0, 0.0)
+            || is_mouse_button_down(MouseButton::Left)
+            || !get_keys_down().is_empty();
+        self.mouse = mouse;
+        self.update_analysis();
+        self.update_cloud();
+        self.update_dropped_files();
+        self.update_clock();
+        self.update_network();
+        self.update_lichess();
+        self.update_opponent();
+        self.update_coach();
+        self.update_spectator();
+        self.update_opening();
+        self.update_autosave();
+        self.update_correspondence();
+        self.update_session();
+        // the board doesn't take input while the dialog is up
+        if self.resume.is_some() {
+            self.update_resume_dialog();
+            return;
+        }
+        if self.engine_dialog.is_some() {
+            self.update_engine_dialog();
+            return;
+        }
+        if self.engine_config.is_some() {
+            self.update_engine_config();
+            return;
+        }
+        if self.fen_dialog.is_some() {
+            self.update_fen_dialog();
+            return;
+        }
+        if self.annotation_dialog.is_some() {
+            self.update_annotation_dialog();
+            return;
+        }
+        if self.games_dialog.is_some() {
+            self.update_games_dialog();
+            return;
+        }
+        if self.pgn_chooser.is_some() {
+            self.update_pgn_chooser();
+            return;
+        }
+        if self.move_dialog.is_some() {
+            self.update_move_dialog();
+            return;
+        }
+        if self
+            .coach
+            .as_ref()
+            .is_some_and(|coach| coach.asking.is_some())
+        {
+            self.update_coach_dialog();
+            return;
+        }
+        if self.settings_open {
+            self.settings_open = self.view.draw_settings();
+            if !self.settings_open {
+                self.view.settings().save();
+            }
+            if self.view.take_options_request() {
+                self.open_engine_config();
+            }
+            return;
+        }
+        if self.chat_input.is_some() {
+            self.update_chat_dialog();
+            return;
+        }
+        self.update_keys();
+        self.update_review_keys();
+        self.update_report();
+        self.update_analysis_lines();
+        self.update_explorer();
+        if self.trainer.is_some() {
+            self.update_trainer();
+        }
+        if self.drill.is_some() {
+            self.update_drill();
+        }
+        if self.endgames.is_some() {
+            self.update_endgames();
+        }
+        if self.net.is_some() {
+            self.update_net_controls();
+        }
+        if self.lichess.is_some() {
+            self.update_lichess_controls();
+        }
+        if self.opponent.is_some() {
+            self.update_opponent_controls();
+        }
+        if self.spectator.is_some() {
+            self.update_spectator_controls();
+        }
+        // the board is the opponent's until their reply is on it; an earlier position is
+        // only for looking at, unless side lines can be played from it
+        if self.review.is_some() && !self.annotating()
+            || self.flagged.is_some()
+            || self.net.as_ref().is_some_and(|net| {
+                !net.connected || net.color != self.gs.get_turn() || self.decided.is_some()
+            })
+            || self.lichess.as_ref().is_some_and(|lichess| {
+                lichess
+                    .game
+                    .as_ref()
+                    .is_none_or(|game| game.state.is_over())
+                    || lichess.color() != Some(self.gs.get_turn())
+            })
+            || self.trainer.as_ref().is_some_and(Trainer::waiting)
+            || self.drill.as_ref().is_some_and(Drill::waiting)
+            || self.coach.as_ref().is_some_and(Coach::waiting)
+            || (self.endgames.as_ref()).is_some_and(|endgames| endgames.waiting(&self.gs))
+            || self.spectator.is_some()
+            || self.opponent.as_ref().is_some_and(|opponent| {
+                opponent.color == self.gs.get_turn() || self.decided.is_some()
+            })
+        {
+            return;
+        }
+        if self.view.pressed(Action::TypeMove) && matches!(self.app_state, AppState::Free) {
+            self.move_dialog = Some(InputDialog {
+                input: String::new(),
+                error: None,
+            });
+            return;
+        }
+        match self.app_state {
+            AppState::Free => self.update_free(),
+            AppState::Clicked {
+                from,
+                piece,
+                targets,
+            } => self.update_clicked(from, piece, targets),
+            AppState::Selected { from, piece, .. } => self.update_selected(from, piece),
+            AppState::Promoting { from, to } => self.update_promoting(from, to),
+        }
+    }
+
+    fn update_keys(&mut self) {
+        if self.view.pressed(Action::Fullscreen) {
+            self.view.toggle_fullscreen();
+        }
+        if self.view.pressed(Action::Flip) {
+            self.auto_orient = false;
+            self.view.set_flipped(!self.view.is_flipped());
+        }
+        if self.view.pressed(Action::Mute) {
+            self.view.toggle_mute();
+        }
+        if self.view.pressed(Action::Eval) {
+            self.show_eval = !self.show_eval;
+        }
+        if self.view.pressed(Action::Lines) {
+            self.show_lines = !self.show_lines;
+        }
+        if self.view.pressed(Action::Explorer) {
+            self.show_explorer = !self.show_explorer;
+        }
+        if self.view.pressed(Action::Blindfold) {
+            self.blindfold = self.blindfold.next();
+        }
+        if self.view.pressed(Action::Overlay) {
+            self.overlay = self.overlay.next();
+        }
+        if self.view.pressed(Action::AutoOrient) {
+            self.auto_orient = !self.auto_orient;
+            self.orient_board();
+        }
+        if self.view.pressed(Action::Hint) {
+            self.request_hint();
+        }
+        // the game controls would pull the board out from under the puzzle or the opponent;
+        // against the engine, takebacks come with its own controls
+        if self.trainer.is_some()
+            || self.drill.is_some()
+            || self.endgames.is_some()
+            || self.net.is_some()
+            || self.lichess.is_some()
+            || self.opponent.is_some()
+            || self.spectator.is_some()
+        {
+            return;
+        }
+        if self.view.button(0, "Undo") || self.view.pressed(Action::Undo) {
+            self.undo();
+        }
+        if self.view.button(1, "Redo") || self.view.pressed(Action::Redo) {
+            self.redo();
+        }
+        if self.view.button(2, "New game") || self.view.pressed(Action::NewGame) {
+            let _ = self.load_fen(DEFAULT_FEN);
+        }
+        if !self.branches.is_empty() {
+            let leave = self.review.is_none() && self.view.pressed(Action::Back);
+            if self.view.button(3, "Back to game") || leave {
+                self.leave_branch();
+            }
+        } else if self.view.button(3, "Restart") {
+            let _ = self.load_fen(&self.start_fen.clone());
+        }
+        if self.view.pressed(Action::Annotate) {
+            self.open_annotation_dialog();
+        }
+        if self.view.button(4, "Load FEN") || self.view.pressed(Action::LoadFen) {
+            self.fen_dialog = Some(InputDialog {
+                input: String::new(),
+                error: None,
+            });
+        }
+        if self.view.button(5, "Settings") {
+            self.settings_open = true;
+        }
+        if self.view.button(6, "Save PGN") || self.view.pressed(Action::SavePgn) {
+            match games::save_game(&self.games_dir, &self.to_pgn()) {
+                Ok(path) => self.view.toast(format!("Saved {}", path.display())),
+                Err(err) => {
+                    log::warn!("couldn't save game to {}: {err}", self.games_dir.display());
+                    self.view.toast(format!("Couldn't save game: {err}"));
+                }
+            }
+        }
+        if self.view.pressed(Action::ExportImage) {
+            self.export_image();
+        }
+        if self.view.pressed(Action::MyGames) {
+            self.open_games_dialog();
+        }
+        if self.view.pressed(Action::CopyFen) {
+            clipboard_set(&self.gs.to_fen());
+            self.view.toast("Copied FEN");
+        }
+        if self.view.pressed(Action::CopyPgn) {
+            clipboard_set(&self.to_pgn().to_string());
+            self.view.toast("Copied PGN");
+        }
+        if self.view.pressed(Action::Paste)
+            && let Some(text) = clipboard_get()
+            && let Err(err) = self.paste(&text)
+        {
+            self.view.toast(format!("Couldn't paste: {err}"));
+        }
+    }
+
+    // nobody else has a say in the game on the board
+    fn casual(&self) -> bool {
+        self.trainer.is_none()
+            && self.drill.is_none()
+            && self.endgames.is_none()
+            && self.spectator.is_none()
+            && self.net.is_none()
+            && self.lichess.is_none()
+            && self.opponent.is_none()
+    }
+
+    // glyphs, comments and side lines, once no clock is running on the game
+    fn annotating(&self) -> bool {
+        self.casual() && (self.clock.is_none() || self.result().is_some())
+    }
+
+    fn open_annotation_dialog(&mut self) {
+        let ply = self.review.as_ref().map_or(self.history.len(), |r| r.ply);
+        let Some(idx) = ply.checked_sub(1).filter(|_| self.annotating()) else {
+            return;
+        };
+        let annotation = self.annotations.get(idx);
+        // only the move's own verdict is up for editing, other NAGs are kept
+        let nag = annotation.and_then(|a| a.nags.iter().copied().find(|&nag| nag <= 6));
+        let comment = annotation.and_then(|a| a.comment.clone());
+        self.annotation_dialog = Some(AnnotationDialog {
+            idx,
+            nag,
+            comment: comment.unwrap_or_default(),
+        });
+    }
+
+    fn update_annotation_dialog(&mut self) {
+        let Some(dialog) = &mut self.annotation_dialog else {
+            return;
+        };
+        let Some(takeback) = self.history.get(dialog.idx) else {
+            self.annotation_dialog = None;
+            return;
+        };
+        let title = format!("Annotate {}", takeback.san);
+        let response =
+            self.view
+                .draw_annotation_dialog(&title, &mut dialog.nag, &mut dialog.comment);
+        match response {
+            Some(DialogResponse::Submit) => {
+                let Some(dialog) = self.annotation_dialog.take() else {
+                    return;
+                };
+                if self.annotations.len() <= dialog.idx {
+                    self.annotations
+                        .resize_with(dialog.idx + 1, Annotation::default);
+                }
+                let annotation = &mut self.annotations[dialog.idx];
+                annotation.nags.retain(|&nag| nag > 6);
+                annotation.nags.splice(0..0, dialog.nag);
+                let comment = dialog.comment.trim();
+                annotation.comment = (!comment.is_empty()).then(|| comment.to_owned());
+            }
+            Some(DialogResponse::Cancel) => self.annotation_dialog = None,
+            None => {}
+        }
+    }
+
+    // sets the game aside and plays on from the reviewed position, the moves made from
+    // there making up a variation once the game is back
+    fn start_branch(&mut self) {
+        let Some(review) = self.review.take() else {
+            return;
+        };
+        let ply = review.ply;
+        let history = self.history[..ply].to_vec();
+        let annotations = self.annotations.iter().take(ply).cloned().collect();
+        self.branches.push(Branch {
+            ply,
+            gs: std::mem::replace(&mut self.gs, review.gs),
+            history: std::mem::replace(&mut self.history, history),
+            redo: std::mem::take(&mut self.redo),
+            annotations: std::mem::replace(&mut self.annotations, annotations),
+            last_move: self.last_move,
+        });
+        self.last_move = self.history.last().map(|t| (t.mv.from, t.mv.to));
+        self.app_state = AppState::Free;
+    }
+
+    // back to the line the side line started from, at the position it started at
+    fn leave_branch(&mut self) {
+        let Some(branch) = self.branches.pop() else {
+            return;
+        };
+        self.annotations = branch.merge(&self.history, &self.annotations);
+        self.gs = branch.gs;
+        self.history = branch.history;
+        self.redo = branch.redo;
+        self.last_move = branch.last_move;
+        self.app_state = AppState::Free;
+        self.review_ply(branch.ply);
+    }
+
+    // the game's own moves and everything said about them, side lines being played
+    // out included
+    fn main_line(&self) -> (Vec<String>, Vec<Annotation>) {
+        let mut history = &self.history;
+        let mut annotations = self.annotations.clone();
+        for branch in self.branches.iter().rev() {
+            annotations = branch.merge(history, &annotations);
+            history = &branch.history;
+        }
+        let moves = history.iter().map(|t| t.san.clone()).collect();
+        (moves, annotations)
+    }
+
+    // the report key asks for one in casual play, or puts it away; a finished game gets one anyway
+    fn update_report(&mut self) {
+        let casual = self.casual();
+        let over = self.result().is_some() && !self.history.is_empty();
+        if self.view.pressed(Action::Report) && self.report.take().is_none() && (casual || over) {
+            self.start_report();
+        } else if over && !self.reported {
+            self.reported = true;
+            self.start_report();
+        }
+        // moves since make it about another game
+        if self
+            .report
+            .as_ref()
+            .is_some_and(|report| report.plies != self.history.len())
+        {
+            self.report = None;
+        }
+        self.view.set_graph(self.report.is_some());
+        let Some(report) = &mut self.report else {
+            return;
+        };
+        report.update();
+        for warning in report.warnings() {
+            self.view.toast(warning);
+        }
+        let count = report.scores().len();
+        if is_mouse_button_pressed(MouseButton::Left)
+            && let Some(ply) = self.view.graph_ply_at(self.mouse, count)
+        {
+            self.review_ply(ply);
+        }
+    }
+
+    fn start_report(&mut self) {
+        let Some(engine) = self
+            .engine
+            .clone()
+            .filter(|_| !cfg!(target_arch = "wasm32"))
+        else {
+            self.view.toast("A report needs an engine");
+            return;
+        };
+        let mut gs = self.gs;
+        let mut fens = vec![gs.to_fen()];
+        for takeback in self.history.iter().rev() {
+            gs.unmake_move(takeback.mv, takeback.captured, takeback.token);
+            fens.push(gs.to_fen());
+        }
+        fens.reverse();
+        self.report = Some(Report::spawn(engine, fens));
+    }
+
+    // only in casual play: not for puzzles or games against people
+    fn request_hint(&mut self) {
+        if self.analyzer.is_none()
+            || self.review.is_some()
+            || self.trainer.is_some()
+            || self.drill.is_some()
+            || self.endgames.is_some()
+            || self.spectator.is_some()
+            || self.net.is_some()
+            || self.lichess.is_some()
+        {
+            return;
+        }
+        let fen = self.gs.to_fen();
+        match &mut self.hint {
+            Some(hint) if hint.fen == fen => hint.full = true,
+            _ => {
+                self.hint = Some(Hint {
+                    fen,
+                    mv: None,
+                    full: false,
+                })
+            }
+        }
+    }
+
+    // a PNG of the position on show, next to the saved games
+    fn export_image(&mut self) {
+        let path = match games::image_path(&self.games_dir) {
+            Ok(path) => path,
+            Err(err) => {
+                log::warn!("couldn't export image: {err}");
+                self.view.toast(format!("Couldn't export image: {err}"));
+                return;
+            }
+        };
+        let highlights = match self.last_move {
+            Some((from, to)) if self.review.is_none() => vec![from, to],
+            _ => Vec::new(),
+        };
+        let pieces: Vec<_> = self.shown().iter().collect();
+        let image = self.view.render_board(&pieces, &highlights);
+        let written = view::encode_png(&image)
+            .map_err(std::io::Error::other)
+            .and_then(|png| fs::write(&path, png));
+        match written {
+            Ok(()) => self.view.toast(format!("Saved {}", path.display())),
+            Err(err) => {
+                log::warn!("couldn't export image: {err}");
+                self.view.toast(format!("Couldn't export image: {err}"));
+            }
+        }
+    }
+
+    fn update_review_keys(&mut self) {
+        let ply = self.review.as_ref().map_or(self.history.len(), |r| r.ply);
+        if self.view.pressed(Action::Previous) {
+            self.review_ply(ply.saturating_sub(1));
+        }
+        if self.view.pressed(Action::Next) {
+            self.review_ply(ply + 1);
+        }
+        if self.view.pressed(Action::First) {
+            self.review_ply(0);
+        }
+        if self.view.pressed(Action::Last) || self.view.pressed(Action::Back) {
+            self.review = None;
+        }
+        self.update_scroll(ply);
+    }
+
+    // wheel up or a swipe left goes back a ply, over the board only
+    fn update_scroll(&mut self, ply: usize) {
+        if self.view.get_square_at_point(self.mouse).is_none() {
+            self.scroll = 0.0;
+            return;
+        }
+        let (x, y) = mouse_wheel();
+        // a notch is worth anything from 1 to 120 depending on the platform, while
+        // trackpads send fractions of one
+        self.scroll += (x - y).clamp(-1.0, 1.0);
+        let steps = self.scroll.trunc();
+        if steps == 0.0 {
+            return;
+        }
+        self.scroll -= steps;
+        let target = ply as isize + steps as isize;
+        self.review_ply(target.max(0) as usize);
+    }
+
+    // shows the position after `ply` moves, or the live game from the last move on
+    fn review_ply(&mut self, ply: usize) {
+        let before = *self.shown().board();
+        if ply >= self.history.len() {
+            self.review = None;
+            self.animate_jump(&before);
+            return;
+        }
+        let mut gs = self.gs;
+        for takeback in self.history[ply..].iter().rev() {
+            gs.unmake_move(takeback.mv, takeback.captured, takeback.token);
+        }
+        self.app_state = AppState::Free;
+        self.review = Some(Review { ply, gs });
+        self.animate_jump(&before);
+        if let Some(comment) = ply
+            .checked_sub(1)
+            .and_then(|idx| self.annotations.get(idx)?.comment.clone())
+        {
+            self.view.toast(comment);
+        }
+    }
+
+    // slides every piece that turns up on a square over from the nearest one the same kind of
+    // piece left, for jumps of more than a move as well as single steps
+    fn animate_jump(&mut self, before: &Board) {
+        let changes = before.diff(self.shown().board());
+        let mut left: Vec<(Square, Piece)> = changes
+            .iter()
+            .filter_map(|change| {
+                let piece = change.before.filter(|&p| change.after != Some(p))?;
+                Some((change.square, piece))
+            })
+            .collect();
+        for change in &changes {
+            let Some(piece) = change.after.filter(|&p| change.before != Some(p)) else {
+                continue;
+            };
+            let distance = |from: Square| {
+                let cols = (from.col() as i8 - change.square.col() as i8).abs();
+                let rows = (from.row() as i8 - change.square.row() as i8).abs();
+                cols.max(rows)
+            };
+            let nearest = (0..left.len())
+                .filter(|&idx| left[idx].1 == piece)
+                .min_by_key(|&idx| distance(left[idx].0));
+            if let Some(idx) = nearest {
+                let (from, _) = left.swap_remove(idx);
+                self.view.slide_piece(piece, from, change.square);
+            }
+        }
+    }
+
+    // the position on the board: the reviewed one, otherwise the game's
+    fn shown(&self) -> &GameState {
+        self.review.as_ref().map_or(&self.gs, |r| &r.gs)
+    }
+
+    fn update_net_controls(&mut self) {
+        let Some(net) = &self.net else {
+            return;
+        };
+        let draw_label = match (net.draw_offer_received, net.draw_offered) {
+            (true, _) => "Accept draw",
+            (false, true) => "Draw offered",
+            (false, false) => "Offer draw",
+        };
+        let rematch_label = match (net.rematch_offer_received, net.rematch_offered) {
+            (true, _) => "Accept rematch",
+            (false, true) => "Rematch offered",
+            (false, false) => "Rematch",
+        };
+        let chat_label = match net.unread {
+            0 => "Chat".to_owned(),
+            unread => format!("Chat ({unread})"),
+        };
+        let over = self.result().is_some();
+        if self.view.button(0, "Resign") && !over {
+            self.net_send(NetMessage::Resign);
+            let result = match self.net_color() {
+                Color::White => GameResult::BlackWins,
+                Color::Black => GameResult::WhiteWins,
+            };
+            self.end_game(result, Termination::Resignation);
+        }
+        if over {
+            if self.view.button(1, rematch_label) {
+                self.offer_rematch();
+            }
+        } else if self.view.button(1, draw_label) {
+            self.offer_draw();
+        }
+        if self.view.button(2, &chat_label) {
+            self.chat_input = Some(String::new());
+            if let Some(net) = &mut self.net {
+                net.unread = 0;
+            }
+        }
+        if self.view.button(3, "Settings") {
+            self.settings_open = true;
+        }
+    }
+
+    // our color in the network game
+    fn net_color(&self) -> Color {
+        self.net.as_ref().map_or(Color::White, |net| net.color)
+    }
+
+    fn offer_draw(&mut self) {
+        let Some(net) = &mut self.net else {
+            return;
+        };
+        if net.draw_offered {
+            return;
+        }
+        let accepted = net.draw_offer_received;
+        net.draw_offered = true;
+        net.peer.send(NetMessage::Draw);
+        if accepted {
+            self.end_game(GameResult::Draw, Termination::Agreement);
+        }
+    }
+
+    fn offer_rematch(&mut self) {
+        let Some(net) = &mut self.net else {
+            return;
+        };
+        if net.rematch_offered {
+            return;
+        }
+        let accepted = net.rematch_offer_received;
+        net.rematch_offered = true;
+        net.peer.send(NetMessage::Rematch);
+        if accepted {
+            self.rematch();
+        }
+    }
+
+    // the same start and time control again, with the colors swapped
+    fn rematch(&mut self) {
+        self.update_session();
+        self.players.swap(0, 1);
+        let start_fen = self.start_fen.clone();
+        if self.load_fen(&start_fen).is_err() {
+            return;
+        }
+        if let Some(opponent) = &mut self.opponent {
+            opponent.rematch();
+            self.view.set_flipped(opponent.color == Color::White);
+        }
+        if let Some(net) = &mut self.net {
+            net.rematch();
+            self.view.set_flipped(net.color == Color::Black);
+            if net.is_host() {
+                net.peer.send(NetMessage::Sync {
+                    seq: net.seq,
+                    fen: start_fen,
+                });
+            }
+        }
+    }
+
+    // every finished game against the engine or over the network goes into one file
+    // for the sitting
+    fn update_session(&mut self) {
+        let rated = self.opponent.is_some() || self.net.is_some() || self.spectator.is_some();
+        if self.recorded || !rated || self.result().is_none() {
+            return;
+        }
+        self.recorded = true;
+        let path = match &self.session {
+            Some(path) => Ok(path.clone()),
+            None => games::session_path(&self.games_dir),
+        };
+        match path.and_then(|path| games::append_game(&path, &self.to_pgn()).map(|_| path)) {
+            Ok(path) => self.session = Some(path),
+            Err(err) => {
+                log::warn!("couldn't add the game to the session: {err}");
+                self.view.toast(format!("Couldn't save game: {err}"));
+            }
+        }
+    }
+
+    fn end_game(&mut self, result: GameResult, termination: Termination) {
+        self.decided = Some(result);
+        self.termination = Some(termination);
+        self.app_state = AppState::Free;
+        if let Some(clock) = &mut self.clock {
+            clock.stop();
+        }
+        self.view.play_sound(SoundEffect::GameEnd);
+    }
+
+    fn net_send(&self, message: NetMessage) {
+        if let Some(net) = &self.net {
+            net.peer.send(message);
+        }
+    }
+
+    fn update_chat_dialog(&mut self) {
+        let (Some(net), Some(input)) = (&mut self.net, &mut self.chat_input) else {
+            self.chat_input = None;
+            return;
+        };
+        match self.view.draw_chat_dialog(&net.chat, input) {
+            Some(DialogResponse::Submit) => {
+                let text = input.trim().to_owned();
+                if !text.is_empty() {
+                    net.chat.push(format!("you: {text}"));
+                    net.peer.send(NetMessage::Chat(text));
+                }
+                input.clear();
+            }
+            Some(DialogResponse::Cancel) => self.chat_input = None,
+            None => {}
+        }
+    }
+
+    fn update_network(&mut self) {
+        let Some(net) = &self.net else {
+            return;
+        };
+        for event in net.peer.poll() {
+            self.handle_peer_event(event);
+        }
+    }
+
+    fn handle_peer_event(&mut self, event: PeerEvent) {
+        let fen = self.gs.to_fen();
+        let Some(net) = &mut self.net else {
+            return;
+        };
+        let message = match event {
+            PeerEvent::Connected => {
+                net.connected = true;
+                // the host's board is the one both sides go by
+                if net.is_host() {
+                    net.peer.send(NetMessage::Sync { seq: net.seq, fen });
+                }
+                return;
+            }
+            PeerEvent::Disconnected => {
+                net.connected = false;
+                return;
+            }
+            PeerEvent::Message(message) => message,
+        };
+        match message {
+            NetMessage::Move { seq, uci } => {
+                let Some(mv) = peer_move(&self.gs, net.color, seq, net.seq, &uci) else {
+                    net.peer.send(NetMessage::Resync);
+                    return;
+                };
+                net.seq += 1;
+                net.draw_offered = false;
+                net.draw_offer_received = false;
+                self.app_state = AppState::Free;
+                let promotion = mv.promotion().map(|figure| Piece {
+                    color: self.gs.get_turn(),
+                    figure,
+                });
+                if self.try_move(mv.from, mv.to, promotion) {
+                    self.slide_move(mv.from, mv.to, mv);
+                }
+            }
+            NetMessage::Sync { seq, fen: theirs } => {
+                net.seq = seq;
+                // a reconnect with nothing missed keeps the history
+                if theirs != fen {
+                    let before = *self.gs.board();
+                    match self.load_fen(&theirs) {
+                        Ok(()) => self.animate_jump(&before),
+                        Err(_) => log::warn!("peer sent a bad position: {theirs}"),
+                    }
+                }
+            }
+            NetMessage::Resync => net.peer.send(NetMessage::Sync { seq: net.seq, fen }),
+            NetMessage::Chat(text) => {
+                net.chat.push(format!("them: {text}"));
+                net.unread += 1;
+            }
+            NetMessage::Draw if net.draw_offered => {
+                self.end_game(GameResult::Draw, Termination::Agreement)
+            }
+            NetMessage::Draw => net.draw_offer_received = true,
+            NetMessage::Resign => {
+                let result = match net.color {
+                    Color::White => GameResult::WhiteWins,
+                    Color::Black => GameResult::BlackWins,
+                };
+                self.end_game(result, Termination::Resignation);
+            }
+            NetMessage::Rematch if net.rematch_offered => self.rematch(),
+            NetMessage::Rematch => net.rematch_offer_received = true,
+        }
+    }
+
+    fn update_opponent_controls(&mut self) {
+        let Some(opponent) = &self.opponent else {
+            return;
+        };
+        let (color, score, stopped) = (opponent.color, opponent.score, opponent.stopped);
+        let ply = self.history.len();
+        let declined = opponent.draw_declined == Some(ply);
+        let over = self.result().is_some();
+        if self.view.button(0, "Resign") && !over {
+            let result = match color {
+                Color::White => GameResult::WhiteWins,
+                Color::Black => GameResult::BlackWins,
+            };
+            self.end_game(result, Termination::Resignation);
+        }
+        let draw_label = if declined {
+            "Draw declined"
+        } else {
+            "Offer draw"
+        };
+        if over {
+            if self.view.button(1, "Rematch") {
+                self.rematch();
+            }
+        } else if stopped {
+            // without the engine the game goes on with both sides moved here
+            if self.view.button(1, "Play on") {
+                self.opponent = None;
+            }
+        } else if self.view.button(1, draw_label) && !declined {
+            if accepts_draw(score) {
+                self.end_game(GameResult::Draw, Termination::Agreement);
+            } else if let Some(opponent) = &mut self.opponent {
+                opponent.draw_declined = Some(ply);
+            }
+        }
+        if self.view.button(2, "Settings") {
+            self.settings_open = true;
+        }
+        if self.view.button(3, "New game") {
+            self.open_engine_dialog();
+        }
+        // takebacks only while the game is on and off the clock, which they'd leave behind
+        if over || stopped || self.clock.is_some() {
+            return;
+        }
+        if self.view.button(5, "Take back") || self.view.pressed(Action::Undo) {
+            self.take_back();
+        }
+        if self.view.button(6, "Redo") || self.view.pressed(Action::Redo) {
+            self.redo_take_back();
+        }
+    }
+
+    // back to the player's own turn: their last move, and the engine's reply to it if it's
+    // made one, with any search for a reply given up on
+    fn take_back(&mut self) {
+        let Some(opponent) = &mut self.opponent else {
+            return;
+        };
+        let plies = if self.gs.get_turn() == opponent.color {
+            1
+        } else {
+            2
+        };
+        if self.history.len() < plies {
+            return;
+        }
+        opponent.stop_thinking();
+        for _ in 0..plies {
+            self.undo();
+        }
+    }
+
+    // the player's move taken back, and the engine's reply too when there was one
+    fn redo_take_back(&mut self) {
+        self.redo();
+        let engine_to_move =
+            (self.opponent.as_ref()).is_some_and(|opponent| opponent.color == self.gs.get_turn());
+        if engine_to_move && !self.redo.is_empty() {
+            self.redo();
+        }
+    }
+
+    fn open_engine_dialog(&mut self) {
+        let Some(opponent) = &self.opponent else {
+            return;
+        };
+        let time_control = self
+            .clock
+            .as_ref()
+            .map(|clock| clock.time_control().to_string())
+            .unwrap_or_default();
+        self.engine_dialog = Some(EngineSetup::new(opponent.strength, time_control));
+    }
+
+    fn update_engine_dialog(&mut self) {
+        let Some(setup) = &mut self.engine_dialog else {
+            return;
+        };
+        match self.view.draw_engine_dialog(setup) {
+            Some(DialogResponse::Submit) => {
+                let time_control = match setup.time_control.trim() {
+                    "" => Ok(None),
+                    tc => tc.parse::<TimeControl>().map(Some),
+                };
+                match time_control {
+                    Ok(time_control) => {
+                        let strength = setup.strength();
+                        self.engine_dialog = None;
+                        self.start_engine_game(strength, time_control);
+                    }
+                    Err(err) => setup.error = Some(err.to_string()),
+                }
+            }
+            Some(DialogResponse::Cancel) => self.engine_dialog = None,
+            None => {}
+        }
+    }
+
+    fn open_engine_config(&mut self) {
+        match &self.engine {
+            Some(program) => self.engine_config = Some(EngineConfig::load(program.clone())),
+            None => self.view.toast("No engine to set up; start with --engine"),
+        }
+    }
+
+    // saved options go to engines started from now on, so the ones always running are
+    // started again
+    fn update_engine_config(&mut self) {
+        let Some(config) = &mut self.engine_config else {
+            return;
+        };
+        config.poll();
+        match self.view.draw_engine_options(config) {
+            Some(DialogResponse::Submit) => {
+                config.save();
+                self.engine_config = None;
+                if let Some(program) = self.engine.clone().filter(|_| self.analyzer.is_some()) {
+                    self.analyzer = Some(Analyzer::spawn(program));
+                }
+                self.coach = None;
+                self.view.toast("Engine options saved");
+            }
+            Some(DialogResponse::Cancel) => self.engine_config = None,
+            None => {}
+        }
+    }
+
+    // a fresh game from the starting position with the engine set up as chosen
+    fn start_engine_game(&mut self, strength: Strength, time_control: Option<TimeControl>) {
+        let Some(opponent) = &mut self.opponent else {
+            return;
+        };
+        opponent.configure(strength);
+        self.clock = time_control.map(|time_control| self.new_clock(time_control));
+        let start_fen = self.start_fen.clone();
+        let _ = self.load_fen(&start_fen);
+    }
+
+    // the engine's turn: ask for a move, then play it once it comes back
+    fn update_opponent(&mut self) {
+        // nothing to think about until the game is set up
+        if self.engine_dialog.is_some() {
+            return;
+        }
+        let ply = self.history.len();
+        let over = self.result().is_some();
+        let Some(opponent) = &mut self.opponent else {
+            return;
+        };
+        for warning in opponent.warnings() {
+            self.view.toast(warning);
+        }
+        match opponent.poll(ply) {
+            Some(Ok(uci)) => {
+                let Some(mv) = self.gs.parse_uci_move(&uci) else {
+                    log::warn!("engine played a move that doesn't fit the board: {uci}");
+                    return;
+                };
+                let promotion = mv.promotion().map(|figure| Piece {
+                    color: self.gs.get_turn(),
+                    figure,
+                });
+                if self.try_move(mv.from, mv.to, promotion) {
+                    self.slide_move(mv.from, mv.to, mv);
+                }
+                return;
+            }
+            Some(Err(err)) => {
+                log::warn!("opponent engine stopped: {err}");
+                self.view
+                    .toast(format!("Engine stopped: {err}. Play on to move for it"));
+            }
+            None => {}
+        }
+        if opponent.is_thinking()
+            || opponent.stopped
+            || opponent.color != self.gs.get_turn()
+            || over
+        {
+            return;
+        }
+        let limits = match &self.clock {
+            Some(clock) => {
+                let [white, black] = clock.time_controls();
+                GoLimits {
+                    wtime: Some(clock.remaining(Color::White)),
+                    btime: Some(clock.remaining(Color::Black)),
+                    winc: Some(white.increment),
+                    binc: Some(black.increment),
+                    moves_to_go: clock.moves_to_go(opponent.color),
+                    ..Default::default()
+                }
+            }
+            None => GoLimits {
+                move_time: Some(opponent::MOVE_TIME),
+                ..Default::default()
+            },
+        };
+        let moves = self.history.iter().map(|takeback| takeback.mv).collect();
+        opponent.think(ply, &self.start_fen, moves, limits);
+    }
+
+    fn update_spectator_controls(&mut self) {
+        let Some(spectator) = &mut self.spectator else {
+            return;
+        };
+        if spectator.finished {
+            if self.view.button(0, "Rematch") {
+                self.spectator = Some(spectator.rematch());
+            }
+            return;
+        }
+        let pause = if spectator.paused { "Resume" } else { "Pause" };
+        if self.view.button(0, pause) || self.view.pressed(Action::Pause) {
+            spectator.paused = !spectator.paused;
+        }
+        if self.view.button(1, "Step") {
+            spectator.step();
+        }
+        if self.view.button(2, "Slower") {
+            spectator.slower();
+        }
+        if self.view.button(3, "Faster") {
+            spectator.faster();
+        }
+    }
+
+    // the runner's moves onto the board as they come, and the runner on to the next
+    // search once each has been seen
+    fn update_spectator(&mut self) {
+        let now = get_time();
+        let Some(spectator) = &mut self.spectator else {
+            return;
+        };
+        let watched = spectator.poll(now);
+        let evaluation = spectator.evaluation();
+        if watched.is_none()
+            && spectator.release(now)
+            && let Some(clock) = &mut self.clock
+        {
+            clock.start(self.gs.get_turn());
+        }
+        if let Some(evaluation) = evaluation {
+            self.ease_eval_bar(&evaluation);
+        }
+        let Some(watched) = watched else {
+            return;
+        };
+        self.busy = true;
+        match watched {
+            Watched::Started(names) => {
+                let _ = self.load_fen(DEFAULT_FEN);
+                self.tags = vec![
+                    ("White".to_owned(), names[0].clone()),
+                    ("Black".to_owned(), names[1].clone()),
+                ];
+                self.view.set_header(true);
+                self.players = names;
+                if let Some(clock) = &mut self.clock {
+                    clock.start(Color::White);
+                }
+            }
+            Watched::Moved(mv, clock) => {
+                let mover = self.gs.get_turn();
+                let promotion = mv.promotion().map(|figure| Piece {
+                    color: mover,
+                    figure,
+                });
+                if self.try_move(mv.from, mv.to, promotion) {
+                    self.slide_move(mv.from, mv.to, mv);
+                    self.stamp_clock(clock.remaining(mover), None);
+                }
+                self.clock = Some(clock);
+            }
+            Watched::Finished(result, termination) => self.end_game(result, termination),
+            Watched::Failed(err) => {
+                log::warn!("engine game stopped: {err}");
+                self.view.toast(format!("Engine game stopped: {err}"));
+                if let Some(clock) = &mut self.clock {
+                    clock.stop();
+                }
+            }
+        }
+    }
+
+    fn update_lichess_controls(&mut self) {
+        let Some(lichess) = &self.lichess else {
+            return;
+        };
+        if lichess
+            .game
+            .as_ref()
+            .is_some_and(|game| !game.state.is_over())
+        {
+            if self.view.button(0, "Resign") {
+                lichess.resign();
+            }
+            if self.view.button(1, "Offer draw") {
+                lichess.offer_draw();
+            }
+            if self.view.button(2, "Settings") {
+                self.settings_open = true;
+            }
+            if self.view.button(3, "My games") {
+                self.open_games_dialog();
+            }
+            return;
+        }
+        if self.view.button(0, "Seek 10+0") {
+            lichess.seek(10, 0);
+        }
+        if self.view.button(1, "Settings") {
+            self.settings_open = true;
+        }
+        if self.view.button(2, "My games") {
+            self.open_games_dialog();
+            return;
+        }
+        let Some(lichess) = &self.lichess else {
+            return;
+        };
+        for (idx, challenge) in lichess.challenges.iter().take(4).enumerate() {
+            if self
+                .view
+                .button(3 + idx, &format!("Accept {}", challenge.summary))
+            {
+                lichess.accept(&challenge.id);
+            }
+        }
+    }
+
+    // brings the board in line with the server's game, replaying whatever is new
+    fn update_lichess(&mut self) {
+        let Some(lichess) = &mut self.lichess else {
+            return;
+        };
+        if !lichess.update(get_time()) {
+            return;
+        }
+        let (Some(game), Some(color)) = (lichess.game.clone(), lichess.color()) else {
+            return;
+        };
+        let played: Vec<String> = self.history.iter().map(|t| t.mv.to_string()).collect();
+        let new_game = self.lichess_game.as_ref() != Some(&game.id);
+        if new_game || !game.state.moves.starts_with(&played) {
+            if self.load_fen(&game.initial_fen).is_err() {
+                return;
+            }
+            self.lichess_game = Some(game.id.clone());
+            self.view.set_flipped(color == Color::Black);
+            self.tags = lichess_tags(&game);
+            self.view.set_header(true);
+        }
+        let played = self.history.len();
+        for uci in game.state.moves.iter().skip(played) {
+            let Some(mv) = self.gs.parse_uci_move(uci) else {
+                log::warn!("lichess sent a move that doesn't fit the board: {uci}");
+                break;
+            };
+            let promotion = mv.promotion().map(|figure| Piece {
+                color: self.gs.get_turn(),
+                figure,
+            });
+            self.app_state = AppState::Free;
+            if self.try_move(mv.from, mv.to, promotion) {
+                self.slide_move(mv.from, mv.to, mv);
+            }
+        }
+        if game.state.is_over() {
+            self.decided = match (game.state.winner, game.state.status.as_str()) {
+                (Some(Color::White), _) => Some(GameResult::WhiteWins),
+                (Some(Color::Black), _) => Some(GameResult::BlackWins),
+                (None, "draw" | "stalemate" | "outoftime") => Some(GameResult::Draw),
+                _ => None,
+            };
+        }
+    }
+
+    // tells the opponent about the move just made here
+    fn send_move(&mut self) {
+        if let (Some(lichess), Some(takeback)) = (&self.lichess, self.history.last()) {
+            lichess.play(&takeback.mv.to_string());
+        }
+        let (Some(net), Some(takeback)) = (&mut self.net, self.history.last()) else {
+            return;
+        };
+        net.peer.send(NetMessage::Move {
+            seq: net.seq,
+            uci: takeback.mv.to_string(),
+        });
+        net.seq += 1;
+        net.draw_offered = false;
+        net.draw_offer_received = false;
+    }
+
+    fn update_trainer(&mut self) {
+        if self.view.button(0, "Next puzzle") {
+            self.next_puzzle();
+        }
+        if self.view.button(1, "Settings") {
+            self.settings_open = true;
+        }
+        let Some(trainer) = &mut self.trainer else {
+            return;
+        };
+        let Some(mv) = trainer.due_reply(get_time()) else {
+            return;
+        };
+        let promotion = mv.promotion().map(|figure| Piece {
+            color: self.gs.get_turn(),
+            figure,
+        });
+        if self.try_move(mv.from, mv.to, promotion) {
+            self.slide_move(mv.from, mv.to, mv);
+        }
+    }
+
+    // sets up the next puzzle with the solver at the bottom; the opponent's first move
+    // follows in `update_trainer`
+    fn next_puzzle(&mut self) {
+        let Some(trainer) = &mut self.trainer else {
+            return;
+        };
+        trainer.next_puzzle(get_time());
+        let fen = trainer.puzzle().fen.clone();
+        let flipped = trainer.solver() == Color::Black;
+        self.auto_orient = false;
+        if self.load_fen(&fen).is_ok() {
+            self.view.set_flipped(flipped);
+        }
+    }
+
+    fn update_drill(&mut self) {
+        if self.view.button(0, "Next line") {
+            self.next_line();
+        }
+        if self.view.button(1, "Settings") {
+            self.settings_open = true;
+        }
+        let Some(drill) = &mut self.drill else {
+            return;
+        };
+        drill.check_line_end(&self.gs);
+        let Some(mv) = drill.due_reply(&self.gs, get_time()) else {
+            return;
+        };
+        let promotion = mv.promotion().map(|figure| Piece {
+            color: self.gs.get_turn(),
+            figure,
+        });
+        if self.try_move(mv.from, mv.to, promotion) {
+            self.slide_move(mv.from, mv.to, mv);
+        }
+    }
+
+    // back to the repertoire's start with the trainee at the bottom
+    fn next_line(&mut self) {
+        let Some(drill) = &mut self.drill else {
+            return;
+        };
+        drill.start_line(get_time());
+        let fen = drill.repertoire.start.to_fen();
+        let flipped = drill.repertoire.color == Color::Black;
+        self.auto_orient = false;
+        if self.load_fen(&fen).is_ok() {
+            self.view.set_flipped(flipped);
+        }
+    }
+
+    fn update_endgames(&mut self) {
+        let Some(endgames) = &self.endgames else {
+            return;
+        };
+        let (current, next) = (endgames.endgame, endgames.next_endgame());
+        if self.view.button(0, "Next endgame") {
+            self.start_endgame(next);
+        }
+        if self.view.button(1, "Again") {
+            self.start_endgame(current);
+        }
+        if self.view.button(2, "Settings") {
+            self.settings_open = true;
+        }
+        let Some(endgames) = &mut self.endgames else {
+            return;
+        };
+        endgames.judge(&self.gs, self.history.len());
+        let moves: Vec<Move> = self.history.iter().map(|takeback| takeback.mv).collect();
+        let Some(mv) = endgames.due_reply(&self.gs, &self.start_fen, &moves, get_time()) else {
+            return;
+        };
+        let promotion = mv.promotion().map(|figure| Piece {
+            color: self.gs.get_turn(),
+            figure,
+        });
+        if self.try_move(mv.from, mv.to, promotion) {
+            self.slide_move(mv.from, mv.to, mv);
+        }
+    }
+
+    // a fresh instance of `endgame` with the trainee at the bottom
+    fn start_endgame(&mut self, endgame: Endgame) {
+        let Some(endgames) = &mut self.endgames else {
+            return;
+        };
+        let fen = endgames.start(endgame).to_fen();
+        self.auto_orient = false;
+        if self.load_fen(&fen).is_ok() {
+            self.view.set_flipped(endgame.trainee() == Color::Black);
+        }
+    }
+
+    // headers of an opened game take precedence over the app's own, but the position's
+    // result and start are always its own
+    fn to_pgn(&self) -> PgnGame {
+        // a side line's position says nothing about how the game went
+        let result = self.branches.is_empty().then(|| self.result()).flatten();
+        let result = result.or_else(|| {
+            self.tags
+                .iter()
+                .find(|(name, _)| name == "Result")
+                .and_then(|(_, value)| value.parse().ok())
+        });
+        let (date, _) = games::now();
+        let result_tag = result.map_or("*".to_owned(), |r| r.to_string());
+        let mut tags: Vec<(String, String)> = [
+            ("Event", "Casual game"),
+            ("Site", "chessapp"),
+            ("Date", &date),
+            ("Round", "-"),
+            ("White", &self.players[0]),
+            ("Black", &self.players[1]),
+            ("Result", &result_tag),
+        ]
+        .into_iter()
+        .map(|(name, value)| (name.to_owned(), value.to_owned()))
+        .collect();
+        for (name, value) in &self.tags {
+            if matches!(name.as_str(), "Result" | "SetUp" | "FEN") {
+                continue;
+            }
+            match tags.iter_mut().find(|(tag, _)| tag == name) {
+                Some(tag) => tag.1 = value.clone(),
+                None => tags.push((name.clone(), value.clone())),
+            }
+        }
+        if let Some(clock) = &self.clock {
+            let [white, black] = clock.time_controls();
+            tags.push(("TimeControl".to_owned(), white.to_string()));
+            if black != white {
+                tags.push(("BlackTimeControl".to_owned(), black.to_string()));
+            }
+        }
+        if self.armageddon && !tags.iter().any(|(name, _)| name == "Armageddon") {
+            tags.push(("Armageddon".to_owned(), "1".to_owned()));
+        }
+        if let Some(termination) = self.termination {
+            tags.push(("Termination".to_owned(), termination.to_string()));
+        }
+        if let Some(engine) = &self.engine
+            && !tags.iter().any(|(name, _)| name == "Annotator")
+        {
+            tags.push(("Annotator".to_owned(), engine.clone()));
+        }
+        if self.start_fen != DEFAULT_FEN {
+            tags.push(("SetUp".to_owned(), "1".to_owned()));
+            tags.push(("FEN".to_owned(), self.start_fen.clone()));
+        }
+        let (moves, annotations) = self.main_line();
+        PgnGame {
+            tags,
+            moves,
+            annotations,
+            result,
+        }
+    }
+
+    // White's time control for both sides, or a short one for Black in Armageddon
+    fn new_clock(&self, time_control: TimeControl) -> Clock {
+        let black = match self.armageddon {
+            true => time_control.armageddon_black(),
+            false => time_control,
+        };
+        Clock::with_time_controls([time_control, black])
+    }
+
+    // the result the game's position decides on its own, if it's over; in Armageddon
+    // Black wins any draw
+    fn result(&self) -> Option<GameResult> {
+        let result = self.game_result();
+        match self.armageddon {
+            true => result.map(armageddon_result),
+            false => result,
+        }
+    }
+
+    fn game_result(&self) -> Option<GameResult> {
+        if self.decided.is_some() {
+            return self.decided;
+        }
+        match self.flagged {
+            Some(Color::White) => return Some(GameResult::BlackWins),
+            Some(Color::Black) => return Some(GameResult::WhiteWins),
+            None => {}
+        }
+        match self.gs.status() {
+            GameStatus::Ongoing => None,
+            GameStatus::Checkmate => Some(match self.gs.get_turn() {
+                Color::White => GameResult::BlackWins,
+                Color::Black => GameResult::WhiteWins,
+            }),
+            GameStatus::Stalemate
+            | GameStatus::InsufficientMaterial
+            | GameStatus::SeventyFiveMoves
+            | GameStatus::FivefoldRepetition => Some(GameResult::Draw),
+        }
+    }
+
+    // a FEN replaces the game, a PGN is replayed so its moves can be taken back
+    fn paste(&mut self, text: &str) -> Result<(), AppError> {
+        let text = text.trim();
+        if GameState::try_from_fen(text).is_ok() {
+            return self.load_fen(text);
+        }
+        self.load_pgn(text)
+    }
+
+    // a PGN file's game, shown from its starting position; a file of many asks which first
+    fn open_pgn(&mut self, text: &str) -> Result<(), AppError> {
+        let games: PgnCollection = text.parse()?;
+        if games.len() > 1 {
+            self.pgn_chooser = Some(PgnChooser {
+                games,
+                filter: String::new(),
+            });
+            return Ok(());
+        }
+        let game = games.into_iter().next().ok_or(AppError::EmptyPgn)?;
+        self.open_game(game)
+    }
+
+    fn open_game(&mut self, game: PgnGame) -> Result<(), AppError> {
+        self.load_game(game)?;
+        self.review_ply(0);
+        Ok(())
+    }
+
+    // the games going by players, event or date, only so many listed to keep a big file quick
+    fn update_pgn_chooser(&mut self) {
+        let Some(chooser) = &mut self.pgn_chooser else {
+            return;
+        };
+        let filter = chooser.filter.to_lowercase();
+        let (indices, lines): (Vec<usize>, Vec<String>) = chooser
+            .games
+            .iter()
+            .enumerate()
+            .map(|(idx, game)| (idx, format!("{}. {}", idx + 1, pgn_game_line(game))))
+            .filter(|(_, line)| line.to_lowercase().contains(&filter))
+            .take(CHOOSER_GAMES)
+            .unzip();
+        let total = chooser.games.len();
+        match self
+            .view
+            .draw_pgn_chooser(&lines, total, &mut chooser.filter)
+        {
+            Some(ChooserResponse::Open(pos)) => {
+                let Some(chooser) = self.pgn_chooser.take() else {
+                    return;
+                };
+                let Some(game) = chooser.games.into_iter().nth(indices[pos]) else {
+                    return;
+                };
+                if let Err(err) = self.open_game(game) {
+                    self.view.toast(format!("Couldn't open the game: {err}"));
+                }
+            }
+            Some(ChooserResponse::Close) => self.pgn_chooser = None,
+            None => {}
+        }
+    }
+
+    fn load_pgn(&mut self, text: &str) -> Result<(), AppError> {
+        let game = parse_pgn(text)?
+            .into_iter()
+            .next()
+            .ok_or(AppError::EmptyPgn)?;
+        self.load_game(game)
+    }
+
+    fn load_game(&mut self, game: PgnGame) -> Result<(), AppError> {
+        let played = game.replay()?;
+        self.load_fen(game.tag("FEN").unwrap_or(DEFAULT_FEN))?;
+        for (_, mv) in played {
+            let promotion = mv.promotion().map(|figure| Piece {
+                color: self.gs.get_turn(),
+                figure,
+            });
+            self.record_move(mv.from, mv.to, promotion)?;
+            self.last_move = Some((mv.from, mv.to));
+        }
+        self.tags = game.tags;
+        self.annotations = game.annotations;
+        self.view.set_header(!self.header_lines().is_empty());
+        self.orient_board();
+        Ok(())
+    }
+
+    // the match runner keeps the engines' clocks, this one only shows them
+    fn update_clock(&mut self) {
+        if self.spectator.is_some() {
+            return;
+        }
+        let Some(clock) = &mut self.clock else {
+            return;
+        };
+        let turn = self.gs.get_turn();
+        if self.flagged.is_none() && clock.remaining(turn).is_zero() {
+            clock.stop();
+            self.flagged = Some(turn);
+            self.view.play_sound(SoundEffect::GameEnd);
+        }
+    }
+
+    // hands the move over to the other clock, which starts with White's first move
+    fn press_clock(&mut self) {
+        let over = self.result().is_some();
+        let Some(clock) = &mut self.clock else {
+            return;
+        };
+        let elapsed = clock.elapsed();
+        if !clock.stop() {
+            self.flagged = Some(!self.gs.get_turn());
+            return;
+        }
+        let remaining = clock.remaining(!self.gs.get_turn());
+        if !over {
+            clock.start(self.gs.get_turn());
+        }
+        self.stamp_clock(remaining, elapsed);
+    }
+
+    // the mover's time kept with the last move, so it goes out with the PGN
+    fn stamp_clock(&mut self, remaining: Duration, elapsed: Option<Duration>) {
+        let Some(idx) = self.history.len().checked_sub(1) else {
+            return;
+        };
+        if self.annotations.len() <= idx {
+            self.annotations.resize_with(idx + 1, Annotation::default);
+        }
+        let annotation = &mut self.annotations[idx];
+        annotation.clock = Some(remaining);
+        annotation.elapsed = elapsed;
+    }
+
+    // the last time the game recorded for `color` in its first `ply` moves
+    fn recorded_clock(&self, color: Color, ply: usize) -> Option<Duration> {
+        let plies = self.history.len();
+        let black_first = (self.gs.get_turn() == Color::Black) != plies.is_multiple_of(2);
+        let annotations = &self.annotations[..ply.min(self.annotations.len())];
+        annotations
+            .iter()
+            .enumerate()
+            .rev()
+            .filter(|(idx, _)| (idx.is_multiple_of(2) == black_first) == (color == Color::Black))
+            .find_map(|(_, annotation)| annotation.clock)
+    }
+
+    fn update_dropped_files(&mut self) {
+        let Some(file) = get_dropped_files().into_iter().next() else {
+            return;
+        };
+        let Some(text) = file.bytes.and_then(|bytes| String::from_utf8(bytes).ok()) else {
+            self.view.toast("Couldn't read the dropped file");
+            return;
+        };
+        if let Err(err) = self.open_pgn(&text) {
+            self.view
+                .toast(format!("Couldn't open the dropped file: {err}"));
+        }
+    }
+
+    // players and then event details, leaving out what the PGN doesn't know
+    fn header_lines(&self) -> Vec<String> {
+        let tag = |name: &str| {
+            self.tags
+                .iter()
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| value.as_str())
+                .filter(|value| !value.trim_matches(['?', '.', '-', '*']).is_empty())
+        };
+        let player = |color: &str| {
+            let name = tag(color).unwrap_or("?");
+            match tag(&format!("{color}Elo")) {
+                Some(elo) => format!("{name} ({elo})"),
+                None => name.to_owned(),
+            }
+        };
+        let mut lines = Vec::new();
+        if tag("White").is_some() || tag("Black").is_some() {
+            lines.push(format!("{} - {}", player("White"), player("Black")));
+        }
+        let details: Vec<&str> = ["Event", "Site", "Date", "Result"]
+            .into_iter()
+            .filter_map(tag)
+            .collect();
+        if !details.is_empty() {
+            lines.push(details.join(", "));
+        }
+        if let Some(EcoOpening { eco, name }) = &self.opening {
+            lines.push(format!("{eco} {name}"));
+        }
+        lines
+    }
+
+    // the last named position the game went through, so the name stays once it leaves
+    // book
+    fn update_opening(&mut self) {
+        let key = self.gs.zobrist();
+        if self.opening_key == Some(key) {
+            return;
+        }
+        self.opening_key = Some(key);
+        let mut gs = self.gs;
+        let mut opening = self.eco.lookup(&gs);
+        for takeback in self.history.iter().rev() {
+            if opening.is_some() {
+                break;
+            }
+            gs.unmake_move(takeback.mv, takeback.captured, takeback.token);
+            opening = self.eco.lookup(&gs);
+        }
+        let opening = opening.cloned();
+        if opening != self.opening {
+            self.opening = opening;
+            self.view.set_header(!self.header_lines().is_empty());
+        }
+    }
+
+    // the engines at play have their own opinions for the bar
+    fn update_analysis(&mut self) {
+        if self.spectator.is_some() {
+            return;
+        }
+        let fen = self.shown().to_fen();
+        let Some(analyzer) = &mut self.analyzer else {
+            return;
+        };
+        analyzer.analyse(&fen);
+        for warning in analyzer.warnings() {
+            self.view.toast(warning);
+        }
+        if let Some(evaluation) = analyzer.evaluation().cloned() {
+            self.ease_eval_bar(&evaluation);
+            if let Some(hint) = &mut self.hint
+                && hint.fen == evaluation.fen
+                && let Some(uci) = &evaluation.best_move
+            {
+                hint.mv = self.gs.parse_uci_move(uci);
+                self.busy = true;
+            }
+        }
+    }
+
+    // the lines only show where a side line can be started from them
+    fn lines_docked(&self) -> bool {
+        let analysis = self.analyzer.is_some() || self.cloud.is_some();
+        self.show_lines && analysis && self.annotating()
+    }
+
+    // the engine's lines, with a row after them for lichess' when it's asked
+    fn lines_rows(&self) -> usize {
+        ANALYSIS_LINES + self.cloud.is_some() as usize
+    }
+
+    // each line with its panel slot and what it's labelled as coming from
+    fn docked_lines(&self) -> Vec<(usize, &'static str, &Line)> {
+        if !self.lines_docked() {
+            return Vec::new();
+        }
+        let slot = self.move_list_slot();
+        let evaluation = self.analyzer.as_ref().and_then(Analyzer::latest);
+        let engine = evaluation.map_or(&[][..], |evaluation| &evaluation.lines);
+        let mut lines: Vec<_> = (engine.iter().enumerate())
+            .map(|(idx, line)| (slot + idx, "", line))
+            .collect();
+        let fen = self.shown().to_fen();
+        let cloud = self
+            .cloud
+            .as_ref()
+            .and_then(|cloud| cloud.get(&fen)?.eval.as_ref());
+        lines.extend(cloud.map(|line| (slot + ANALYSIS_LINES, "Cloud ", line)));
+        lines
+    }
+
+    // a click on one of the lines plays its first move as a side line
+    fn update_analysis_lines(&mut self) {
+        let shown = *self.shown();
+        let mut chosen = None;
+        for (slot, source, line) in self.docked_lines() {
+            let label = format!("{source}{}", line_label(&shown, line));
+            if self.view.button(slot, &label) {
+                chosen = line.pv.first().and_then(|uci| shown.parse_uci_move(uci));
+            }
+        }
+        if let Some(mv) = chosen {
+            self.play_side_line(mv);
+        }
+    }
+
+    // lichess gets asked while there's a panel to show its answers in
+    fn update_cloud(&mut self) {
+        if !self.lines_docked() && !self.explorer_docked() {
+            return;
+        }
+        let fen = self.shown().to_fen();
+        if let Some(cloud) = &mut self.cloud {
+            cloud.update(&fen, get_time());
+        }
+    }
+
+    fn explorer_docked(&self) -> bool {
+        let explorer = self.explorer.is_some() || self.cloud.is_some();
+        self.show_explorer && explorer && self.annotating()
+    }
+
+    // the database's moves from the shown position, or lichess' without one, each playing
+    // itself as a side line
+    fn update_explorer(&mut self) {
+        if !self.explorer_docked() {
+            return;
+        }
+        let shown = *self.shown();
+        if let Some(explorer) = &mut self.explorer {
+            explorer.update(&shown);
+        }
+        let slot = self.explorer_slot();
+        let mut chosen = None;
+        let moves = self.explorer_moves().unwrap_or_default();
+        for (idx, continuation) in moves.iter().take(EXPLORER_MOVES).enumerate() {
+            if self
+                .view
+                .button(slot + idx, &continuation_label(&shown, continuation))
+            {
+                chosen = Some(continuation.mv);
+            }
+        }
+        if let Some(mv) = chosen {
+            self.play_side_line(mv);
+        }
+    }
+
+    fn explorer_moves(&self) -> Option<&[Continuation]> {
+        match (&self.explorer, &self.cloud) {
+            (Some(explorer), _) => Some(&explorer.moves),
+            (None, Some(cloud)) => Some(&cloud.get(&self.shown().to_fen())?.moves),
+            (None, None) => None,
+        }
+    }
+
+    fn play_side_line(&mut self, mv: Move) {
+        let shown = *self.shown();
+        // from the end of the game too, rather than carrying it on
+        if self.review.is_none() {
+            self.review = Some(Review {
+                ply: self.history.len(),
+                gs: self.gs,
+            });
+        }
+        let promotion = mv.promotion().map(|figure| Piece {
+            color: shown.get_turn(),
+            figure,
+        });
+        self.play_move(mv.from, mv.to, promotion);
+    }
+
+    fn ease_eval_bar(&mut self, evaluation: &Evaluation) {
+        let step = (get_frame_time() * 6.0).min(1.0);
+        let gap = evaluation.white_share() - self.eval_share;
+        self.eval_share += gap * step;
+        self.busy |= gap.abs() > 0.002;
+        self.eval_label = evaluation.label();
+    }
+
+    fn update_resume_dialog(&mut self) {
+        let response = self.view.draw_confirm_dialog(
+            "Unfinished game",
+            "Pick up the game left unfinished last time?",
+            "Resume",
+            "Discard",
+        );
+        let Some(response) = response else {
+            return;
+        };
+        let Some(text) = self.resume.take() else {
+            return;
+        };
+        match response {
+            DialogResponse::Submit => {
+                // a resumed game is already under way
+                self.engine_dialog = None;
+                if let Err(err) = self.resume_game(&text) {
+                    log::warn!("couldn't resume the autosaved game: {err}");
+                    self.view.toast(format!("Couldn't resume: {err}"));
+                }
+            }
+            DialogResponse::Cancel => games::clear_autosave(&self.games_dir),
+        }
+    }
+
+    // the game goes to disk after every move, and every AUTOSAVE_SECS for the clocks,
+    // until it ends; only games kept here, not puzzles or games held by the other side
+    fn update_autosave(&mut self) {
+        if self.resume.is_some()
+            || self.trainer.is_some()
+            || self.drill.is_some()
+            || self.endgames.is_some()
+            || self.spectator.is_some()
+            || self.net.is_some()
+            || self.lichess.is_some()
+            || self.correspondence.is_some()
+        {
+            return;
+        }
+        if self.history.is_empty() || self.result().is_some() {
+            if self.autosaved.take().is_some() {
+                games::clear_autosave(&self.games_dir);
+            }
+            return;
+        }
+        let now = get_time();
+        let plies = self.history.len();
+        let due = self.autosaved.is_none_or(|(saved_plies, saved_at)| {
+            saved_plies != plies || self.clock.is_some() && now - saved_at >= games::AUTOSAVE_SECS
+        });
+        if !due {
+            return;
+        }
+        let mut game = self.to_pgn();
+        if let Some(clock) = &self.clock {
+            for (tag, color) in [("WhiteClock", Color::White), ("BlackClock", Color::Black)] {
+                let secs = format!("{:.3}", clock.remaining(color).as_secs_f64());
+                game.tags.push((tag.to_owned(), secs));
+            }
+        }
+        if let Err(err) = games::autosave(&self.games_dir, &game) {
+            log::warn!("couldn't autosave to {}: {err}", self.games_dir.display());
+        }
+        // a failed write waits for the next turn rather than retrying every frame
+        self.autosaved = Some((plies, now));
+    }
+
+    // the game's file keeps up with the board, side lines aside
+    fn update_correspondence(&mut self) {
+        let Some(correspondence) = &self.correspondence else {
+            return;
+        };
+        if correspondence.saved == self.history.len() || !self.branches.is_empty() {
+            return;
+        }
+        let path = correspondence.path.clone();
+        if let Err(err) = correspondence::save(&path, &self.to_pgn()) {
+            log::warn!("couldn't save {}: {err}", path.display());
+            self.view.toast(format!("Couldn't save the game: {err}"));
+        }
+        // a failed write waits for the next move rather than retrying every frame
+        if let Some(correspondence) = &mut self.correspondence {
+            correspondence.saved = self.history.len();
+        }
+    }
+
+    fn open_games_dialog(&mut self) {
+        if let Some(lichess) = &self.lichess {
+            lichess.refresh_playing();
+        }
+        self.games_dialog = Some(GamesDialog {
+            games: correspondence::list(&self.games_dir),
+            opponent: String::new(),
+        });
+    }
+
+    // the games on file come first, then lichess's as they arrive
+    fn update_games_dialog(&mut self) {
+        let Some(dialog) = &mut self.games_dialog else {
+            return;
+        };
+        let online = self.lichess.as_ref().map_or(&[][..], |l| &l.playing);
+        let lines: Vec<String> = dialog
+            .games
+            .iter()
+            .map(correspondence::Entry::summary)
+            .chain(online.iter().map(|game| game.summary()))
+            .collect();
+        let response = self.view.draw_games_dialog(&lines, &mut dialog.opponent);
+        match response {
+            Some(GamesResponse::Open(idx)) => {
+                let Some(dialog) = self.games_dialog.take() else {
+                    return;
+                };
+                match dialog.games.get(idx) {
+                    Some(entry) => self.open_correspondence(entry.path.clone(), entry.color),
+                    None => {
+                        if let Some(lichess) = &self.lichess
+                            && let Some(game) = lichess.playing.get(idx - dialog.games.len())
+                        {
+                            lichess.open(&game.id);
+                        }
+                    }
+                }
+            }
+            Some(GamesResponse::New(color)) => {
+                let opponent = dialog.opponent.trim().to_owned();
+                if opponent.is_empty() {
+                    self.view.toast("Name the opponent first");
+                    return;
+                }
+                self.games_dialog = None;
+                let name = &self.players[color as usize];
+                match correspondence::create(&self.games_dir, name, &opponent, color) {
+                    Ok(path) => self.open_correspondence(path, color),
+                    Err(err) => {
+                        log::warn!("couldn't start a correspondence game: {err}");
+                        self.view.toast(format!("Couldn't start the game: {err}"));
+                    }
+                }
+            }
+            Some(GamesResponse::Close) => self.games_dialog = None,
+            None => {}
+        }
+    }
+
+    // the game at its latest position, our side at the bottom
+    fn open_correspondence(&mut self, path: PathBuf, color: Color) {
+        let loaded = match fs::read_to_string(&path) {
+            Ok(text) => self.load_pgn(&text).map_err(|err| err.to_string()),
+            Err(err) => Err(err.to_string()),
+        };
+        if let Err(err) = loaded {
+            log::warn!("couldn't open {}: {err}", path.display());
+            self.view.toast(format!("Couldn't open the game: {err}"));
+            return;
+        }
+        self.auto_orient = false;
+        self.view.set_flipped(color == Color::Black);
+        self.correspondence = Some(Correspondence {
+            path,
+            color,
+            saved: self.history.len(),
+        });
+    }
+
+    // an autosave, with the clocks as they were and running again
+    fn resume_game(&mut self, text: &str) -> Result<(), AppError> {
+        self.load_pgn(text)?;
+        let tag = |name: &str| {
+            self.tags
+                .iter()
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| value.clone())
+        };
+        let seconds = |name: &str| {
+            tag(name)?
+                .parse::<f64>()
+                .ok()
+                .and_then(|secs| Duration::try_from_secs_f64(secs).ok())
+        };
+        let time_control = tag("TimeControl").and_then(|tc| tc.parse::<TimeControl>().ok());
+        // Black's own, for time odds or Armageddon, if it differed from White's
+        let black_time_control = tag("BlackTimeControl")
+            .and_then(|tc| tc.parse::<TimeControl>().ok())
+            .or(time_control);
+        self.armageddon = tag("Armageddon").is_some();
+        if let (Some(time_control), Some(black_time_control), Some(white), Some(black)) = (
+            time_control,
+            black_time_control,
+            seconds("WhiteClock"),
+            seconds("BlackClock"),
+        ) {
+            // the first mover's clock only starts counting after their first move
+            let plies = self.history.len() as u32;
+            let (first, second) = (plies.div_ceil(2), plies / 2);
+            let black_first = (self.gs.get_turn() == Color::Black) == plies.is_multiple_of(2);
+            let moves_made = if black_first {
+                [second, first.saturating_sub(1)]
+            } else {
+                [first.saturating_sub(1), second]
+            };
+            let mut clock = Clock::restore(
+                [time_control, black_time_control],
+                [white, black],
+                moves_made,
+            );
+            clock.start(self.gs.get_turn());
+            self.clock = Some(clock);
+        }
+        // the clock writes these itself
+        self.tags.retain(|(name, _)| {
+            !matches!(
+                name.as_str(),
+                "WhiteClock" | "BlackClock" | "TimeControl" | "BlackTimeControl" | "Armageddon"
+            )
+        });
+        Ok(())
+    }
+
+    fn update_fen_dialog(&mut self) {
+        let Some(dialog) = &mut self.fen_dialog else {
+            return;
+        };
+        let response = self.view.draw_input_dialog(
+            "Load FEN",
+            "Load",
+            &mut dialog.input,
+            dialog.error.as_deref(),
+        );
+        match response {
+            Some(DialogResponse::Submit) => {
+                let fen = dialog.input.trim().to_owned();
+                match self.load_fen(&fen) {
+                    Ok(()) => self.fen_dialog = None,
+                    Err(err) => {
+                        if let Some(dialog) = &mut self.fen_dialog {
+                            dialog.error = Some(err.to_string());
+                        }
+                    }
+                }
+            }
+            Some(DialogResponse::Cancel) => self.fen_dialog = None,
+            None => {}
+        }
+    }
+
+    // SAN, long algebraic or UCI
+    fn update_move_dialog(&mut self) {
+        let Some(dialog) = &mut self.move_dialog else {
+            return;
+        };
+        let response =
+            self.view
+                .draw_input_dialog("Move", "Play", &mut dialog.input, dialog.error.as_deref());
+        match response {
+            Some(DialogResponse::Submit) => {
+                let text = dialog.input.trim();
+                let mv = self
+                    .gs
+                    .parse_san(text)
+                    .or_else(|err| self.gs.parse_lan(text).map_err(|_| err))
+                    .or_else(|err| self.gs.parse_uci_move(text).ok_or(err));
+                let mv = match mv {
+                    Ok(mv) => mv,
+                    Err(err) => {
+                        dialog.error = Some(err.to_string());
+                        return;
+                    }
+                };
+                self.move_dialog = None;
+                let promotion = mv.promotion().map(|figure| Piece {
+                    color: self.gs.get_turn(),
+                    figure,
+                });
+                if self.play_move(mv.from, mv.to, promotion) {
+                    self.slide_move(mv.from, mv.to, mv);
+                }
+            }
+            Some(DialogResponse::Cancel) => self.move_dialog = None,
+            None => {}
+        }
+    }
+
+    fn undo(&mut self) {
+        let Some(Takeback {
+            mv,
+            captured,
+            token,
+            ..
+        }) = self.history.pop()
+        else {
+            return;
+        };
+        self.gs.unmake_move(mv, captured, token);
+        self.review = None;
+        self.slide_move(mv.to, mv.from, mv);
+        self.redo.push(mv);
+        self.app_state = AppState::Free;
+        self.last_move = self.history.last().map(|t| (t.mv.from, t.mv.to));
+        self.orient_board();
+    }
+
+    fn redo(&mut self) {
+        let Some(mv) = self.redo.pop() else {
+            return;
+        };
+        self.app_state = AppState::Free;
+        let promotion = mv.promotion().map(|figure| Piece {
+            color: self.gs.get_turn(),
+            figure,
+        });
+        if self.try_move(mv.from, mv.to, promotion) {
+            self.slide_move(mv.from, mv.to, mv);
+        }
+    }
+
+    // slides whatever now stands on `to` over from `from`, with the rook too when castling
+    fn slide_move(&mut self, from: Square, to: Square, mv: Move) {
+        if let Some(piece) = self.gs.get_sq(to) {
+            self.view.slide_piece(piece, from, to);
+        }
+        let (rook_from, rook_to) = match mv.kind {
+            MoveKind::KingCastle => (Column::H, Column::F),
+            MoveKind::QueenCastle => (Column::A, Column::D),
+            _ => return,
+        };
+        let row = mv.from.row();
+        let (rook_from, rook_to) = (
+            Square::from_coords(rook_from, row),
+            Square::from_coords(rook_to, row),
+        );
+        // the rook goes the same way as the king
+        let (rook_from, rook_to) = if to == mv.to {
+            (rook_from, rook_to)
+        } else {
+            (rook_to, rook_from)
+        };
+        if let Some(rook) = self.gs.get_sq(rook_to) {
+            self.view.slide_piece(rook, rook_from, rook_to);
+        }
+    }
+
+    // a fresh move from the player invalidates whatever could be redone
+    fn play_move(&mut self, from: Square, to: Square, promotion: Option<Piece>) -> bool {
+        // a new move from an earlier position only sets the game aside once it's been let
+        // through
+        let branching = self.review.is_some();
+        if let Some(review) = &self.review {
+            let ply = review.ply;
+            let mv = review.gs.legal_moves().into_iter().find(|mv| {
+                mv.from == from && mv.to == to && mv.promotion() == promotion.map(|p| p.figure)
+            });
+            let Some(mv) = mv else {
+                self.view.play_sound(SoundEffect::Illegal);
+                return false;
+            };
+            // the move the game went on with just steps through it
+            if Some(mv) == self.history.get(ply).map(|t| t.mv) {
+                self.review_ply(ply + 1);
+                return true;
+            }
+        }
+        if !self.coach_accepts(from, to, promotion) {
+            return false;
+        }
+        if !self.puzzle_accepts(from, to, promotion) {
+            self.view.play_sound(SoundEffect::Illegal);
+            return false;
+        }
+        if branching {
+            self.start_branch();
+        }
+        let made = self.try_move(from, to, promotion);
+        if made {
+            self.redo.clear();
+            self.send_move();
+        }
+        made
+    }
+
+    // the coach holds the move back while the engine has a look at it
+    fn coach_accepts(&mut self, from: Square, to: Square, promotion: Option<Piece>) -> bool {
+        let gs = *self.shown();
+        let Some(coach) = &mut self.coach else {
+            return true;
+        };
+        let mv = gs.legal_moves().into_iter().find(|mv| {
+            mv.from == from && mv.to == to && mv.promotion() == promotion.map(|p| p.figure)
+        });
+        mv.is_none_or(|mv| coach.accepts(&gs, mv))
+    }
+
+    // the coach comes and goes with the setting, and only looks at moves played here on the
+    // board or against the engine
+    fn update_coach(&mut self) {
+        let wanted = self.view.coaches() && (self.casual() || self.opponent.is_some());
+        match (&self.coach, &self.engine) {
+            (None, Some(program)) if wanted => self.coach = Some(Coach::spawn(program.clone())),
+            (Some(_), _) if !wanted => self.coach = None,
+            _ => {}
+        }
+        let gs = *self.shown();
+        if let Some(probe) = self.coach.as_mut().and_then(|coach| coach.poll(&gs)) {
+            self.play_probe(probe);
+        }
+    }
 
-#[macroquad::main("Chess")]
-async fn main() -> Result<(), anyhow::Error> {
-    let mut app = App::new(CASTLECHECK).await?;
-    println!("{}", app.gs);
-    loop {
-        app.update_state();
-        app.draw_state().await;
+    fn update_coach_dialog(&mut self) {
+        let Some(blunder) = self.coach.as_ref().and_then(|coach| coach.asking.as_ref()) else {
+            return;
+        };
+        let text = blunder.describe();
+        let Some(response) =
+            self.view
+                .draw_confirm_dialog("Are you sure?", &text, "Play it", "Think again")
+        else {
+            return;
+        };
+        let Some(coach) = &mut self.coach else {
+            return;
+        };
+        let Some(blunder) = coach.asking.take() else {
+            return;
+        };
+        // either way it's been asked about, so trying it again plays it
+        coach.clear(&blunder.probe);
+        if matches!(response, DialogResponse::Submit) {
+            self.play_probe(blunder.probe);
+        }
     }
-}
 
-struct App {
-    gs: GameState,
-    view: View,
-    mouse: (f32, f32),
-    app_state: AppState,
-    last_move: Option<(Square, Square)>,
-}
+    fn play_probe(&mut self, probe: Probe) {
+        // the board may have moved on while the engine looked, or the question was open
+        if !probe.is_for(self.shown()) {
+            return;
+        }
+        let mv = probe.mv;
+        let promotion = mv.promotion().map(|figure| Piece {
+            color: probe.gs.get_turn(),
+            figure,
+        });
+        if self.play_move(mv.from, mv.to, promotion) {
+            self.slide_move(mv.from, mv.to, mv);
+        }
+    }
 
-impl App {
-    async fn new(fen: &str) -> Result<Self, AppError> {
-        let gs = GameState::try_from_fen(fen)?;
-        let view = View::new().await;
-        Ok(Self {
-            gs,
-            view,
-            mouse: mouse_position(),
-            app_state: AppState::Free,
-            last_move: None,
-        })
+    // a move that's legal but off the puzzle's solution, or off the repertoire, is turned
+    // away before it's made
+    fn puzzle_accepts(&mut self, from: Square, to: Square, promotion: Option<Piece>) -> bool {
+        if self.trainer.is_none() && self.drill.is_none() {
+            return true;
+        }
+        let gs = *self.shown();
+        let mv = gs.legal_moves().into_iter().find(|mv| {
+            mv.from == from && mv.to == to && mv.promotion() == promotion.map(|p| p.figure)
+        });
+        let Some(mv) = mv else {
+            return true;
+        };
+        let now = get_time();
+        match (&mut self.trainer, &mut self.drill) {
+            (Some(trainer), _) => trainer.submit(&gs, mv, now),
+            (_, Some(drill)) => drill.submit(&gs, mv, now),
+            _ => true,
+        }
     }
 
-    fn update_state(&mut self) {
-        self.view.update_screen();
-        self.mouse = mouse_position();
-        match self.app_state {
-            AppState::Free => self.update_free(),
-            AppState::Clicked { from, piece } => self.update_clicked(from, piece),
-            AppState::Promoting { from, to } => self.update_promoting(from, to),
+    fn try_move(&mut self, from: Square, to: Square, promotion: Option<Piece>) -> bool {
+        let before = self.gs;
+        let res = self.record_move(from, to, promotion);
+        let made = res.is_ok();
+        if made && let Some(takeback) = self.history.last() {
+            self.view.announce(before.describe_move(takeback.mv));
+        }
+        self.process_move_result(from, to, res);
+        made
+    }
+
+    // makes the move and keeps what undo and the PGN need, without any feedback
+    fn record_move(
+        &mut self,
+        from: Square,
+        to: Square,
+        promotion: Option<Piece>,
+    ) -> Result<MoveOutcome, MoveError> {
+        let token = self.gs.snapshot();
+        let mv = self.gs.legal_moves().into_iter().find(|mv| {
+            mv.from == from && mv.to == to && mv.promotion() == promotion.map(|p| p.figure)
+        });
+        let san = mv.map(|mv| self.gs.to_san(mv));
+        let res = match promotion {
+            Some(piece) => self.gs.make_promotion(from, to, piece),
+            None => self.gs.make_move(from, to),
+        };
+        if let Ok(outcome) = res {
+            self.review = None;
+            // whatever was said about an undone move doesn't carry over to this one
+            self.annotations.truncate(self.history.len());
+            match mv.zip(san) {
+                Some((mv, san)) => self.history.push(Takeback {
+                    mv,
+                    san,
+                    captured: outcome.captured,
+                    token,
+                }),
+                // can't roll back across a move we failed to identify
+                None => self.history.clear(),
+            }
+        }
+        res
+    }
+
+    fn orient_board(&mut self) {
+        if self.auto_orient {
+            self.view.set_flipped(self.gs.get_turn() == Color::Black);
         }
     }
 
@@ -52,144 +2916,843 @@ impl App {
         if !is_mouse_button_pressed(MouseButton::Left) {
             return;
         }
-        let Some(square) = self.view.get_square_at_point(self.mouse) else {
-            return;
-        };
-        let Some(piece) = self.gs.get_sq(square) else {
+        if let Some(square) = self.view.get_square_at_point(self.mouse) {
+            self.pick_up(square);
+        }
+    }
+
+    fn pick_up(&mut self, square: Square) {
+        let Some(piece) = self.shown().get_sq(square) else {
             return;
         };
+        let targets = self
+            .shown()
+            .legal_moves()
+            .into_iter()
+            .filter(|mv| mv.from == square)
+            .fold(BitBoard::default(), |acc, mv| acc | mv.to.into());
         self.app_state = AppState::Clicked {
             from: square,
             piece,
+            targets,
         };
     }
 
-    fn update_clicked(&mut self, from: Square, _piece: Piece) {
+    fn update_clicked(&mut self, from: Square, piece: Piece, targets: BitBoard) {
         if is_mouse_button_down(MouseButton::Left) {
             return;
         }
         self.app_state = AppState::Free;
         let Some(to) = self.view.get_square_at_point(self.mouse) else {
+            self.view.return_piece(piece, self.mouse, from);
             return;
         };
-        let res = self.gs.make_move(from, to);
-        self.process_move_result(from, to, res);
+        // released where it was picked up: keep it selected for click-to-move
+        if to == from {
+            self.app_state = AppState::Selected {
+                from,
+                piece,
+                targets,
+            };
+            return;
+        }
+        if !self.play_move(from, to, None) && matches!(self.app_state, AppState::Free) {
+            self.view.return_piece(piece, self.mouse, from);
+        }
     }
 
-    fn update_promoting(&mut self, from: Square, to: Square) {
+    fn update_selected(&mut self, from: Square, piece: Piece) {
         if !is_mouse_button_pressed(MouseButton::Left) {
             return;
         }
         self.app_state = AppState::Free;
-        let Some(clicked) = self.view.get_square_at_point(self.mouse) else {
+        let Some(to) = self.view.get_square_at_point(self.mouse) else {
             return;
         };
-        if clicked.col() != to.col() {
+        if to == from {
+            return;
+        }
+        if self
+            .shown()
+            .get_sq(to)
+            .is_some_and(|p| p.color == piece.color)
+        {
+            self.pick_up(to);
+            return;
+        }
+        if self.play_move(from, to, None)
+            && let Some(&Takeback { mv, .. }) = self.history.last()
+        {
+            self.slide_move(from, to, mv);
+        }
+    }
+
+    fn update_promoting(&mut self, from: Square, to: Square) {
+        if is_key_pressed(KeyCode::Escape) {
+            self.app_state = AppState::Free;
             return;
         }
-        let Some(piece) = self.get_promotion_piece(clicked.row()) else {
+        if !is_mouse_button_pressed(MouseButton::Left) {
+            return;
+        }
+        // a click anywhere but on a choice cancels the promotion
+        self.app_state = AppState::Free;
+        let color = self.shown().get_turn();
+        let Some(figure) = self.view.promotion_choice(to.col(), color, self.mouse) else {
             return;
         };
-        let res = self.gs.make_promotion(from, to, piece);
-        self.process_move_result(from, to, res);
+        self.play_move(from, to, Some(Piece { color, figure }));
     }
 
     fn process_move_result(
         &mut self,
         from: Square,
         to: Square,
-        res: Result<Option<Piece>, MoveError>,
+        res: Result<MoveOutcome, MoveError>,
     ) {
-        println!("{:?}", res);
-        println!("{}", self.gs);
+        match &res {
+            Ok(_) => {
+                if let Some(takeback) = self.history.last() {
+                    log::info!("played {}, now {}", takeback.san, self.gs.to_fen());
+                }
+            }
+            Err(err) => log::debug!("{from}{to} not played: {err}"),
+        }
         match res {
-            Ok(Some(_)) => {
+            Ok(outcome) => {
+                self.press_clock();
                 self.last_move = Some((from, to));
-                self.view.play_capture_sound();
+                self.view.play_sound(self.move_sound(outcome));
+                self.orient_board();
             }
-            Ok(None) => {
-                self.last_move = Some((from, to));
-                self.view.play_move_sound();
+            Err(MoveError::Promoting) => {
+                self.app_state = AppState::Promoting { from, to };
+            }
+            Err(
+                MoveError::KingInCheck
+                | MoveError::IllegalMove
+                | MoveError::FailedEp
+                | MoveError::WrongTurn,
+            ) if self.view.explains() => {
+                self.view.play_sound(SoundEffect::Illegal);
+                match self.gs.explain_illegal(from, to) {
+                    Some(reason) => self.view.toast(format!("Illegal move: {reason}")),
+                    None => self.view.toast("Illegal move"),
+                }
             }
             Err(MoveError::KingInCheck) => {
-                self.view.play_in_check_sound();
+                self.view.play_sound(SoundEffect::Illegal);
+                self.view.toast("Illegal move: king would be in check");
             }
-            Err(MoveError::Promoting) => {
-                self.app_state = AppState::Promoting { from, to };
+            Err(MoveError::IllegalMove | MoveError::FailedEp) => {
+                self.view.play_sound(SoundEffect::Illegal);
+                self.view.toast("Illegal move");
             }
-            _ => {}
+            Err(MoveError::EmptySquare | MoveError::WrongTurn | MoveError::GameOver) => {}
         }
     }
 
-    fn get_promotion_piece(&self, rank: Row) -> Option<Piece> {
-        let color = self.gs.get_turn();
-        let figure = match color {
-            Color::Black => match rank {
-                Row::One => Some(Figure::Queen),
-                Row::Two => Some(Figure::Rook),
-                Row::Three => Some(Figure::Knight),
-                Row::Four => Some(Figure::Bishop),
-                _ => None,
+    // what the move just played sounds like, the outcome taking precedence
+    fn move_sound(&self, outcome: MoveOutcome) -> SoundEffect {
+        if outcome.status.is_over() {
+            return SoundEffect::GameEnd;
+        }
+        if outcome.check {
+            return SoundEffect::Check;
+        }
+        match self.history.last().map(|t| t.mv) {
+            Some(mv) if mv.is_castle() => SoundEffect::Castle,
+            Some(mv) if mv.promotion().is_some() => SoundEffect::Promotion,
+            _ if outcome.captured.is_some() => SoundEffect::Capture,
+            _ => SoundEffect::Move,
+        }
+    }
+
+    // everything but the held piece and pieces still sliding into place
+    fn draw_pieces(&self, held: Option<Square>) {
+        for (square, piece) in self.shown().iter() {
+            if Some(square) != held
+                && !self.view.is_animating(square)
+                && self.blindfold.shows(piece)
+            {
+                self.view.draw_piece_at_square(piece, square);
+            }
+        }
+    }
+
+    // squares under fire, pieces left en prise and mates in the air, for whoever is to move in
+    // the shown position
+    fn draw_overlay(&self) {
+        let board = self.shown().board();
+        let mover = self.shown().get_turn();
+        let (threats, cover, hanging) = match self.overlay {
+            Overlay::Off => return,
+            Overlay::Influence => {
+                self.view.draw_influence(&influence_map(board), mover);
+                return;
+            }
+            Overlay::Threats => (
+                board.attack_map(!mover),
+                BitBoard::default(),
+                board.hanging_pieces(mover),
+            ),
+            Overlay::Both => (
+                board.attack_map(!mover),
+                board.attack_map(mover),
+                board.hanging_pieces(mover) | board.hanging_pieces(!mover),
+            ),
+        };
+        self.view.draw_attacks(threats, cover);
+        // mates the opponent is set up for: where the rook or knight would land
+        for motif in find_motifs(self.shown(), !mover) {
+            match motif {
+                Motif::BackRank { entries, .. } => entries
+                    .into_iter()
+                    .for_each(|square| self.view.draw_warning(square)),
+                Motif::Smothered { square, .. } => self.view.draw_warning(square),
+                _ => {}
+            }
+        }
+        for square in hanging.iter() {
+            if board
+                .get_sq(square)
+                .is_some_and(|piece| self.blindfold.shows(piece))
+            {
+                self.view.draw_hanging(square);
+            }
+        }
+    }
+
+    fn draw_hint(&self) {
+        let Some(Hint {
+            fen,
+            mv: Some(mv),
+            full,
+        }) = &self.hint
+        else {
+            return;
+        };
+        if self.review.is_some() || *fen != self.gs.to_fen() {
+            return;
+        }
+        if *full {
+            self.view.draw_arrow(mv.from, mv.to);
+        } else {
+            self.view.draw_piece_hint(mv.from);
+        }
+    }
+
+    fn draw_hints(&self, targets: BitBoard) {
+        for square in targets.iter() {
+            // a capture ring would give a hidden piece away
+            match self.gs.get_sq(square) {
+                Some(piece) if self.blindfold.shows(piece) => self.view.draw_capture_hint(square),
+                _ => self.view.draw_move_hint(square),
+            }
+        }
+    }
+
+    fn draw_trainer(&self, trainer: &Trainer) {
+        let puzzle = trainer.puzzle();
+        let status = match trainer.status {
+            PuzzleStatus::Solving => format!("{:?} to play", trainer.solver()),
+            PuzzleStatus::Missed => "Not the move, try again".to_owned(),
+            PuzzleStatus::Solved => "Solved!".to_owned(),
+            PuzzleStatus::Failed => "Solved, after a miss".to_owned(),
+        };
+        let lines = [
+            format!("Puzzle {} ({})", puzzle.id, puzzle.rating),
+            status,
+            format!("Rating {:.0}", trainer.rating),
+            format!("Solved {} of {}", trainer.solved, trainer.attempted),
+        ];
+        for (idx, line) in lines.iter().enumerate() {
+            self.view.panel_text(3 + idx, line);
+        }
+    }
+
+    fn draw_drill(&self, drill: &Drill) {
+        let status = match drill.status {
+            DrillStatus::Playing => format!("{:?} to play", drill.repertoire.color),
+            DrillStatus::Missed => "Not your move here, try again".to_owned(),
+            DrillStatus::Done => "End of the line".to_owned(),
+        };
+        let lines = [
+            status,
+            format!("Right {}, wrong {}", drill.right, drill.wrong),
+            format!("Due {} of {}", drill.due_count(), drill.position_count()),
+        ];
+        for (idx, line) in lines.iter().enumerate() {
+            self.view.panel_text(3 + idx, line);
+        }
+    }
+
+    fn draw_endgames(&self, endgames: &EndgameTrainer) {
+        let endgame = endgames.endgame;
+        let status = match endgames.status {
+            EndgameStatus::Playing => match endgame.goal() {
+                Goal::Win => format!(
+                    "Mate in {} moves",
+                    (100 - self.gs.get_half_move().min(100)).div_ceil(2)
+                ),
+                Goal::Draw => "Hold the draw".to_owned(),
             },
-            Color::White => match rank {
-                Row::Eight => Some(Figure::Queen),
-                Row::Seven => Some(Figure::Rook),
-                Row::Six => Some(Figure::Knight),
-                Row::Five => Some(Figure::Bishop),
-                _ => None,
+            EndgameStatus::Done => "Well played".to_owned(),
+            EndgameStatus::Failed(reason) => reason.to_owned(),
+        };
+        let lines = [
+            endgame.to_string(),
+            status,
+            format!("Done {} of {}", endgames.done, endgames.attempted),
+        ];
+        for (idx, line) in lines.iter().enumerate() {
+            self.view.panel_text(3 + idx, line);
+        }
+    }
+
+    fn draw_spectator(&self, spectator: &Spectator) {
+        let status = match self.result() {
+            Some(result) => format!("Game over: {result}"),
+            None if spectator.paused => "Paused".to_owned(),
+            None => format!("{}s between moves", spectator.move_delay()),
+        };
+        self.view.panel_text(4, &status);
+        for color in [Color::White, Color::Black] {
+            let Some((gs, search)) = &spectator.searches[color as usize] else {
+                continue;
+            };
+            let score = match search.score {
+                Some(Score::Mate(n)) => format!("#{n}"),
+                Some(Score::Centipawns(cp)) => format!("{:+.2}", cp as f32 / 100.0),
+                None => "?".to_owned(),
+            };
+            let depth = search.depth.unwrap_or_default();
+            let pv = gs.pv_to_san(&search.pv);
+            let pv = pv.iter().take(PV_PLIES).map(String::as_str);
+            let line = format!(
+                "{color:?} {score} d{depth} {}",
+                pv.collect::<Vec<_>>().join(" ")
+            );
+            self.view.panel_text(5 + color as usize, &line);
+        }
+        // drawn after both lines so the board covers neither's text
+        for color in [Color::White, Color::Black] {
+            if let Some((gs, search)) = &spectator.searches[color as usize] {
+                self.draw_pv_preview(gs, &search.pv, PV_PLIES, 5 + color as usize);
+            }
+        }
+    }
+
+    // the buttons are the panel's own; only the previews are left to draw
+    fn draw_analysis_lines(&self) {
+        for (slot, _, line) in self.docked_lines() {
+            self.draw_pv_preview(self.shown(), &line.pv, LINE_PLIES, slot);
+        }
+    }
+
+    // where the first `plies` of a PV line in panel `slot` end, while the mouse is over it
+    fn draw_pv_preview(&self, gs: &GameState, pv: &[String], plies: usize, slot: usize) {
+        if pv.is_empty() || self.view.panel_slot_at(self.mouse) != Some(slot) {
+            return;
+        }
+        let end = gs.pv_end(&pv[..pv.len().min(plies)]);
+        let pieces: Vec<(Square, Piece)> = end
+            .board()
+            .iter()
+            .filter(|&(_, piece)| self.blindfold.shows(piece))
+            .collect();
+        self.view.draw_mini_board(&pieces, slot);
+    }
+
+    fn draw_move_list(&self) {
+        let review = self
+            .report
+            .as_ref()
+            .and_then(|report| report.review.as_ref());
+        let sans: Vec<String> = self
+            .history
+            .iter()
+            .enumerate()
+            .map(|(ply, t)| {
+                let judgement = review.and_then(|review| review.moves.get(ply)?.judgement);
+                // the report's verdict over the one given by hand
+                let glyph = judgement.map(|j| j.glyph().to_owned()).or_else(|| {
+                    let nags = &self.annotations.get(ply)?.nags;
+                    nags.iter()
+                        .find(|&&nag| nag <= 6)
+                        .map(|&nag| nag_glyph(nag))
+                });
+                format!("{}{}", t.san, glyph.unwrap_or_default())
+            })
+            .collect();
+        // the move number only goes up after Black's moves
+        let plies = sans.len();
+        let black_first = (self.gs.get_turn() == Color::Black) != plies.is_multiple_of(2);
+        let black_moves = (plies + black_first as usize) / 2;
+        let first_move = self.gs.get_full_move() - black_moves as u16;
+        let current = self.review.as_ref().map_or(plies, |r| r.ply);
+        let slot = match self.explorer_docked() {
+            true => self.explorer_slot() + EXPLORER_MOVES,
+            false => self.explorer_slot(),
+        };
+        self.view
+            .draw_move_list(slot, &sans, first_move, black_first, current);
+    }
+
+    fn explorer_slot(&self) -> usize {
+        match self.lines_docked() {
+            true => self.move_list_slot() + self.lines_rows(),
+            false => self.move_list_slot(),
+        }
+    }
+
+    fn draw_explorer(&self) {
+        if !self.explorer_docked() || self.explorer_moves().is_some_and(|m| !m.is_empty()) {
+            return;
+        }
+        let text = match &self.explorer {
+            Some(explorer) => format!("Not in the {} games", explorer.game_count()),
+            None if self.explorer_moves().is_none() => "Asking lichess...".to_owned(),
+            None => "No games on lichess".to_owned(),
+        };
+        self.view.panel_text(self.explorer_slot(), &text);
+    }
+
+    // below the clocks when there are any; the analysis lines go here too, pushing it down
+    fn move_list_slot(&self) -> usize {
+        let recorded = self.annotations.iter().any(|a| a.clock.is_some());
+        let clocks = self.clock.is_some() || self.lichess_clocks().is_some() || recorded;
+        if clocks { 9 } else { 7 }
+    }
+
+    fn draw_report(&self, report: &Report) {
+        let shares: Vec<Option<f32>> = report
+            .scores()
+            .iter()
+            .map(|score| score.map(|score| win_percent(score) / 100.0))
+            .collect();
+        let current = self.review.as_ref().map_or(self.history.len(), |r| r.ply);
+        let (lines, marks) = match &report.review {
+            None => {
+                let progress = format!("Reviewing {} of {}", report.progress(), shares.len());
+                (vec![progress], Vec::new())
+            }
+            Some(review) => {
+                let summary = |color: Color| {
+                    let mut line =
+                        format!("{color:?} {:.0}%", review.accuracy(color).unwrap_or(100.0));
+                    for judgement in Judgement::ALL.iter().rev() {
+                        let count = review.count(color, *judgement);
+                        if count > 0 {
+                            line += &format!(" {}{count}", judgement.glyph());
+                        }
+                    }
+                    line
+                };
+                let marks = review
+                    .moves
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(ply, mv)| Some((ply, mv.judgement?)))
+                    .collect();
+                (vec![summary(Color::White), summary(Color::Black)], marks)
+            }
+        };
+        self.view.draw_eval_graph(&lines, &shares, current, &marks);
+    }
+
+    fn draw_opponent_status(&self, opponent: &Opponent) {
+        let status = match (self.result(), self.termination) {
+            (Some(result), Some(termination)) => format!("{result} by {termination}"),
+            (Some(result), None) => format!("Game over: {result}"),
+            _ if opponent.stopped => "Engine stopped".to_owned(),
+            _ if opponent.is_thinking() => "Engine thinking".to_owned(),
+            _ => format!("Playing {:?}", !opponent.color),
+        };
+        self.view.panel_text(4, &status);
+    }
+
+    fn draw_lichess_status(&self, lichess: &Lichess) {
+        let playing = lichess.game.as_ref().is_some_and(|g| !g.state.is_over());
+        let status = match (&lichess.error, &lichess.user) {
+            (Some(err), _) => err.clone(),
+            (None, None) => "Signing in to lichess".to_owned(),
+            (None, Some(_)) if playing => String::new(),
+            (None, Some(user)) => match lichess.game.as_ref() {
+                Some(game) => format!("{}: {}", user, game.state.status),
+                None => format!("Signed in as {user}"),
             },
         };
-        figure.map(|f| Piece { color, figure: f })
+        self.view.panel_text(if playing { 3 } else { 2 }, &status);
+        let Some((white, black)) = self.lichess_clocks() else {
+            return;
+        };
+        let top = if self.view.is_flipped() {
+            Color::White
+        } else {
+            Color::Black
+        };
+        let turn = self.gs.get_turn();
+        for (slot, color) in [(7, top), (8, !top)] {
+            let remaining = match color {
+                Color::White => white,
+                Color::Black => black,
+            };
+            let flagged = remaining.is_zero();
+            self.view.draw_clock(
+                slot,
+                &format_clock(remaining),
+                playing && color == turn,
+                flagged,
+            );
+        }
+    }
+
+    // the server's clocks, run down locally for the side to move since they were sent
+    fn lichess_clocks(&self) -> Option<(Duration, Duration)> {
+        let game = self.lichess.as_ref()?.game.as_ref()?;
+        let state = &game.state;
+        let mut times = [state.wtime, state.btime].map(Duration::from_millis);
+        // lichess starts the clocks once both sides have moved
+        if !state.is_over() && state.moves.len() >= 2 {
+            let elapsed = Duration::from_secs_f64((get_time() - game.synced_at).max(0.0));
+            let side = &mut times[self.gs.get_turn() as usize];
+            *side = side.saturating_sub(elapsed);
+        }
+        Some((times[0], times[1]))
+    }
+
+    fn draw_net_status(&self, net: &NetGame) {
+        let status = match self.result() {
+            Some(result) => format!("Game over: {result}"),
+            None if !net.connected && net.is_host() => "Waiting for opponent".to_owned(),
+            None if !net.connected => "Connecting".to_owned(),
+            None => format!("Playing {:?}", net.color),
+        };
+        self.view.panel_text(5, &status);
+    }
+
+    // the clock of whoever sits at the top of the board goes first
+    fn draw_clocks(&self, clock: &Clock) {
+        let top = if self.view.is_flipped() {
+            Color::White
+        } else {
+            Color::Black
+        };
+        let turn = self.gs.get_turn();
+        let running = self.result().is_none() && !self.history.is_empty();
+        for (slot, color) in [(7, top), (8, !top)] {
+            let time = format_clock(clock.remaining(color));
+            let flagged = self.flagged == Some(color);
+            self.view
+                .draw_clock(slot, &time, running && color == turn, flagged);
+        }
+    }
+
+    // what was said about the move that led to the position on the board
+    fn shown_annotation(&self) -> Option<&Annotation> {
+        let ply = self.review.as_ref().map_or(self.history.len(), |r| r.ply);
+        self.annotations.get(ply.checked_sub(1)?)
+    }
+
+    // the times a game came with, as they stood at the move on the board
+    fn draw_recorded_clocks(&self) {
+        if self.lichess_clocks().is_some() {
+            return;
+        }
+        let top = if self.view.is_flipped() {
+            Color::White
+        } else {
+            Color::Black
+        };
+        let ply = self.review.as_ref().map_or(self.history.len(), |r| r.ply);
+        for (slot, color) in [(7, top), (8, !top)] {
+            if let Some(time) = self.recorded_clock(color, ply) {
+                self.view
+                    .draw_clock(slot, &format_clock(time), false, false);
+            }
+        }
     }
 
-    async fn draw_state(&self) {
+    fn draw_state(&self) {
         self.view.draw_board();
-        if let Some(last_move) = self.last_move {
+        self.view.draw_header(&self.header_lines());
+        if (self.analyzer.is_some() || self.spectator.is_some()) && self.show_eval {
+            self.view.draw_eval_bar(self.eval_share, &self.eval_label);
+        }
+        let last_move = match &self.review {
+            Some(review) => review
+                .ply
+                .checked_sub(1)
+                .map(|ply| (self.history[ply].mv.from, self.history[ply].mv.to)),
+            None => self.last_move,
+        };
+        if let Some(last_move) = last_move {
             self.view.draw_highlight(last_move.0);
             self.view.draw_highlight(last_move.1);
         }
+        let marked = self.shown_annotation();
+        for &mark in marked.map_or(&[][..], |a| &a.marks) {
+            self.view.draw_marked_square(mark);
+        }
+        let shown = self.shown();
+        let turn = shown.get_turn();
+        if shown.board().is_in_check(turn)
+            && let Some(king) = shown.board().pieces(Figure::King, turn).iter().next()
+        {
+            self.view.draw_check(king);
+        }
+        self.draw_overlay();
         match self.app_state {
             AppState::Free => {
-                for (square, piece) in self.gs.iter() {
-                    self.view.draw_piece_at_square(piece, square);
-                }
+                self.draw_pieces(None);
+                self.view
+                    .draw_animations(|piece| self.blindfold.shows(piece));
             }
-            AppState::Clicked { from, piece } => {
-                for (s, p) in self.gs.iter() {
-                    if s != from {
-                        self.view.draw_piece_at_square(p, s);
-                    }
+            AppState::Clicked {
+                from,
+                piece,
+                targets,
+            } => {
+                self.draw_pieces(Some(from));
+                if self.blindfold.shows(piece) {
+                    self.view.draw_ghost_piece(piece, from);
                 }
-                self.view.draw_piece_at_point(piece, self.mouse);
+                self.view
+                    .draw_animations(|piece| self.blindfold.shows(piece));
+                self.draw_hints(targets);
+                if let Some(square) = self.view.get_square_at_point(self.mouse)
+                    && square != from
+                {
+                    self.view.draw_drop_target(square);
+                }
+                // centred on the cursor wherever it was picked up
+                if self.blindfold.shows(piece) {
+                    self.view.draw_piece_at_point(piece, self.mouse);
+                }
+            }
+            AppState::Selected { from, targets, .. } => {
+                self.view.draw_selection(from);
+                self.draw_pieces(None);
+                self.view
+                    .draw_animations(|piece| self.blindfold.shows(piece));
+                self.draw_hints(targets);
             }
             AppState::Promoting { from, to } => {
-                for (s, p) in self.gs.iter() {
-                    if s != from {
-                        self.view.draw_piece_at_square(p, s);
-                    }
-                }
-                self.view.draw_highlight(from);
+                self.draw_pieces(Some(from));
+                self.view.draw_selection(from);
                 self.view
-                    .draw_promotion_widget(to.col(), self.gs.get_turn());
+                    .draw_promotion_widget(to.col(), self.gs.get_turn(), self.mouse);
             }
         }
-        self.view.next_frame().await;
+        self.draw_hint();
+        for &arrow in marked.map_or(&[][..], |a| &a.arrows) {
+            self.view.draw_marked_arrow(arrow);
+        }
+        if let Some(trainer) = &self.trainer {
+            self.draw_trainer(trainer);
+        }
+        if let Some(drill) = &self.drill {
+            self.draw_drill(drill);
+        }
+        if let Some(endgames) = &self.endgames {
+            self.draw_endgames(endgames);
+        }
+        if let Some(spectator) = &self.spectator {
+            self.draw_spectator(spectator);
+        }
+        let side_line = (!self.branches.is_empty()).then_some("Side line");
+        let to_move = self.correspondence.as_ref().and_then(|correspondence| {
+            let yours = correspondence.color == self.gs.get_turn();
+            self.result()
+                .is_none()
+                .then_some(if yours { "Your move" } else { "Their move" })
+        });
+        let badges = [
+            self.blindfold.label(),
+            self.overlay.label(),
+            side_line,
+            to_move,
+        ];
+        for (row, label) in badges.into_iter().flatten().enumerate() {
+            self.view.draw_badge(row, label);
+        }
+        match &self.clock {
+            Some(clock) if self.review.is_none() => self.draw_clocks(clock),
+            _ => self.draw_recorded_clocks(),
+        }
+        if let Some(net) = &self.net {
+            self.draw_net_status(net);
+        }
+        if let Some(lichess) = &self.lichess {
+            self.draw_lichess_status(lichess);
+        }
+        if let Some(opponent) = &self.opponent {
+            self.draw_opponent_status(opponent);
+        }
+        if let Some(report) = &self.report {
+            self.draw_report(report);
+        }
+        self.draw_move_list();
+        self.draw_analysis_lines();
+        self.draw_explorer();
+        self.view.draw_toasts();
+    }
+}
+
+// score, depth and the start of the line in SAN, as far as it goes from `gs`
+fn line_label(gs: &GameState, line: &Line) -> String {
+    let sans = gs.pv_to_san(&line.pv);
+    let sans: Vec<&str> = sans.iter().take(LINE_PLIES).map(String::as_str).collect();
+    format!("{} d{} {}", line.label(), line.depth, sans.join(" "))
+}
+
+// the move, how often it was played and how those games went, in percent
+fn continuation_label(gs: &GameState, continuation: &Continuation) -> String {
+    let games = continuation.games.max(1) as f32;
+    let percent = |n: u32| (n as f32 * 100.0 / games).round();
+    format!(
+        "{} {} {}/{}/{}%",
+        gs.to_san(continuation.mv),
+        continuation.games,
+        percent(continuation.white_wins),
+        percent(continuation.draws),
+        percent(continuation.black_wins)
+    )
+}
+
+fn lichess_tags(game: &LichessGame) -> Vec<(String, String)> {
+    [
+        ("Event", "lichess game".to_owned()),
+        ("Site", format!("https://lichess.org/{}", game.id)),
+        ("White", game.white.1.clone()),
+        ("Black", game.black.1.clone()),
+    ]
+    .into_iter()
+    .map(|(name, value)| (name.to_owned(), value))
+    .collect()
+}
+
+// "White - Black, Event Date, result" with "?" for what the file leaves out
+fn pgn_game_line(game: &PgnGame) -> String {
+    let tag = |name| game.tag(name).unwrap_or("?");
+    let result = game.result.map_or("*".to_owned(), |r| r.to_string());
+    format!(
+        "{} - {}, {} {}, {result}",
+        tag("White"),
+        tag("Black"),
+        tag("Event"),
+        tag("Date")
+    )
+}
+
+// m:ss, with tenths in the last ten seconds
+fn format_clock(remaining: Duration) -> String {
+    let secs = remaining.as_secs();
+    if secs < 10 {
+        return format!("0:{:04.1}", remaining.as_secs_f64());
+    }
+    format!("{}:{:02}", secs / 60, secs % 60)
+}
+
+// visualisation training: the position is played as usual with some or all of it unseen
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Blindfold {
+    Off,
+    PawnsOnly,
+    Full,
+}
+
+impl Blindfold {
+    fn next(self) -> Self {
+        match self {
+            Blindfold::Off => Blindfold::PawnsOnly,
+            Blindfold::PawnsOnly => Blindfold::Full,
+            Blindfold::Full => Blindfold::Off,
+        }
+    }
+
+    fn shows(self, piece: Piece) -> bool {
+        match self {
+            Blindfold::Off => true,
+            Blindfold::PawnsOnly => piece.figure == Figure::Pawn,
+            Blindfold::Full => false,
+        }
+    }
+
+    fn label(self) -> Option<&'static str> {
+        match self {
+            Blindfold::Off => None,
+            Blindfold::PawnsOnly => Some("Pawns only (B)"),
+            Blindfold::Full => Some("Blindfold (B)"),
+        }
+    }
+}
+
+// shading for attacked squares, toggled with T
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Overlay {
+    Off,
+    Threats,
+    Both,
+    // who has more pieces on each square
+    Influence,
+}
+
+impl Overlay {
+    fn next(self) -> Self {
+        match self {
+            Overlay::Off => Overlay::Threats,
+            Overlay::Threats => Overlay::Both,
+            Overlay::Both => Overlay::Influence,
+            Overlay::Influence => Overlay::Off,
+        }
+    }
+
+    fn label(self) -> Option<&'static str> {
+        match self {
+            Overlay::Off => None,
+            Overlay::Threats => Some("Threats (T)"),
+            Overlay::Both => Some("Attacks (T)"),
+            Overlay::Influence => Some("Influence (T)"),
+        }
     }
 }
 
 enum AppState {
     Free,
-    Clicked { from: Square, piece: Piece },
-    Promoting { from: Square, to: Square },
+    Clicked {
+        from: Square,
+        piece: Piece,
+        targets: BitBoard,
+    },
+    // picked up with a click and released in place, waiting for the destination click
+    Selected {
+        from: Square,
+        piece: Piece,
+        targets: BitBoard,
+    },
+    Promoting {
+        from: Square,
+        to: Square,
+    },
 }
 
 pub mod errors {
-    use chesslib::errors::ParseFenError;
+    use chesslib::errors::{MoveError, ParseFenError, ParsePgnError};
     use thiserror::Error;
 
     #[derive(Error, Debug)]
     pub enum AppError {
         #[error(transparent)]
         ParseFenError(#[from] ParseFenError),
+        #[error(transparent)]
+        ParsePgnError(#[from] ParsePgnError),
+        #[error(transparent)]
+        MoveError(#[from] MoveError),
+        #[error("no game found in PGN")]
+        EmptyPgn,
+        #[error("couldn't load {0}; is the assets directory next to the program?")]
+        MissingAsset(&'static str),
     }
 }
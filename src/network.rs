@@ -0,0 +1,170 @@
+use chesslib::{netplay::NetMessage, prelude::*};
+use std::{
+    io::{self, BufRead, BufReader, Write},
+    net::{Shutdown, TcpListener, TcpStream},
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver, RecvTimeoutError, Sender},
+    },
+    thread,
+    time::Duration,
+};
+
+const RETRY: Duration = Duration::from_secs(2);
+const POLL: Duration = Duration::from_millis(100);
+
+pub enum PeerEvent {
+    Connected,
+    Disconnected,
+    Message(NetMessage),
+}
+
+// the other player's connection on its own thread, made again whenever it drops
+pub struct Peer {
+    outgoing: Sender<NetMessage>,
+    events: Receiver<PeerEvent>,
+}
+
+impl Peer {
+    pub fn host(addr: &str) -> io::Result<Self> {
+        let listener = TcpListener::bind(addr)?;
+        Ok(Self::spawn(move || {
+            listener.accept().map(|(stream, _)| stream)
+        }))
+    }
+
+    pub fn connect(addr: String) -> Self {
+        Self::spawn(move || TcpStream::connect(&addr))
+    }
+
+    fn spawn(mut connect: impl FnMut() -> io::Result<TcpStream> + Send + 'static) -> Self {
+        let (outgoing, outgoing_rx) = mpsc::channel();
+        let (events_tx, events) = mpsc::channel();
+        thread::spawn(move || {
+            loop {
+                let stream = match connect() {
+                    Ok(stream) => stream,
+                    Err(err) => {
+                        log::trace!("no peer yet: {err}");
+                        thread::sleep(RETRY);
+                        continue;
+                    }
+                };
+                log::info!("peer connected from {:?}", stream.peer_addr());
+                if events_tx.send(PeerEvent::Connected).is_err()
+                    || !serve(stream, &outgoing_rx, &events_tx)
+                    || events_tx.send(PeerEvent::Disconnected).is_err()
+                {
+                    return;
+                }
+            }
+        });
+        Self { outgoing, events }
+    }
+
+    pub fn send(&self, message: NetMessage) {
+        let _ = self.outgoing.send(message);
+    }
+
+    pub fn poll(&self) -> Vec<PeerEvent> {
+        self.events.try_iter().collect()
+    }
+}
+
+// pumps one connection until it drops; false once the app has gone away
+fn serve(stream: TcpStream, outgoing: &Receiver<NetMessage>, events: &Sender<PeerEvent>) -> bool {
+    // whatever was said while nobody was listening is stale now
+    outgoing.try_iter().for_each(drop);
+    let Ok(reader) = stream.try_clone() else {
+        return true;
+    };
+    let alive = Arc::new(AtomicBool::new(true));
+    let (reader_alive, reader_events) = (alive.clone(), events.clone());
+    thread::spawn(move || {
+        for line in BufReader::new(reader).lines() {
+            let Ok(line) = line else {
+                break;
+            };
+            log::debug!("peer > {line}");
+            match line.parse() {
+                Ok(message) => {
+                    if reader_events.send(PeerEvent::Message(message)).is_err() {
+                        break;
+                    }
+                }
+                Err(err) => log::warn!("ignoring peer: {err}"),
+            }
+        }
+        reader_alive.store(false, Ordering::Relaxed);
+    });
+    let mut writer = stream;
+    let mut app_alive = true;
+    while alive.load(Ordering::Relaxed) {
+        match outgoing.recv_timeout(POLL) {
+            Ok(message) => {
+                log::debug!("peer < {message}");
+                if writeln!(writer, "{message}").is_err() {
+                    break;
+                }
+            }
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => {
+                app_alive = false;
+                break;
+            }
+        }
+    }
+    let _ = writer.shutdown(Shutdown::Both);
+    app_alive
+}
+
+// a game against someone on another instance; the host plays White first, and the
+// colors swap with every rematch
+pub struct NetGame {
+    pub peer: Peer,
+    pub color: Color,
+    // the host's board is the one both sides go by
+    host: bool,
+    pub connected: bool,
+    // moves played since the game began, carried by every move to catch gaps
+    pub seq: usize,
+    pub draw_offered: bool,
+    pub draw_offer_received: bool,
+    pub rematch_offered: bool,
+    pub rematch_offer_received: bool,
+    pub chat: Vec<String>,
+    pub unread: usize,
+}
+
+impl NetGame {
+    pub fn new(peer: Peer, color: Color) -> Self {
+        Self {
+            peer,
+            color,
+            host: color == Color::White,
+            connected: false,
+            seq: 0,
+            draw_offered: false,
+            draw_offer_received: false,
+            rematch_offered: false,
+            rematch_offer_received: false,
+            chat: Vec::new(),
+            unread: 0,
+        }
+    }
+
+    pub fn is_host(&self) -> bool {
+        self.host
+    }
+
+    // the next game, with the other color
+    pub fn rematch(&mut self) {
+        self.color = !self.color;
+        self.seq = 0;
+        self.draw_offered = false;
+        self.draw_offer_received = false;
+        self.rematch_offered = false;
+        self.rematch_offer_received = false;
+    }
+}
@@ -0,0 +1,240 @@
+use crate::{engine_options, supervisor::Supervisor};
+use chesslib::{
+    errors::UciError,
+    moves::Move,
+    pieces::Color,
+    uci::{GoLimits, Score, SearchResult, UciEngine},
+};
+use std::{
+    sync::mpsc::{self, Receiver, Sender},
+    thread,
+    time::Duration,
+};
+
+// how long the engine gets for a move when there's no clock, and how far past its
+// allotment it may run before it's given up on
+pub const MOVE_TIME: Duration = Duration::from_secs(1);
+const TIMEOUT_MARGIN: Duration = Duration::from_secs(5);
+// the span of UCI_Elo most engines that offer it accept
+pub const MIN_ELO: u32 = 1350;
+pub const MAX_ELO: u32 = 2850;
+
+// how strong and how adventurous the engine plays, for engines that take the options
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct Strength {
+    // full strength when there's none
+    pub elo: Option<u32>,
+    // centipawns the engine gives up to steer clear of a draw, higher plays sharper
+    pub contempt: i32,
+}
+
+// what the pre-game dialog is set to
+pub struct EngineSetup {
+    pub limit_strength: bool,
+    pub elo: f32,
+    pub contempt: f32,
+    pub time_control: String,
+    pub error: Option<String>,
+}
+
+impl EngineSetup {
+    pub fn new(strength: Strength, time_control: String) -> Self {
+        Self {
+            limit_strength: strength.elo.is_some(),
+            elo: strength.elo.unwrap_or(1500) as f32,
+            contempt: strength.contempt as f32,
+            time_control,
+            error: None,
+        }
+    }
+
+    pub fn strength(&self) -> Strength {
+        Strength {
+            elo: self.limit_strength.then_some(self.elo.round() as u32),
+            contempt: self.contempt.round() as i32,
+        }
+    }
+}
+
+enum Request {
+    Configure(Strength),
+    Search(Search),
+}
+
+struct Search {
+    id: usize,
+    fen: String,
+    moves: Vec<Move>,
+    limits: GoLimits,
+}
+
+// a UCI engine playing one side of the game on its own thread
+pub struct Opponent {
+    pub color: Color,
+    pub strength: Strength,
+    requests: Sender<Request>,
+    replies: Receiver<(usize, Result<SearchResult, UciError>)>,
+    warnings: Receiver<String>,
+    searches: usize,
+    // the search whose reply is wanted, by number, and the ply it's for
+    awaiting: Option<(usize, usize)>,
+    // its opinion after its last move, from its own side
+    pub score: Option<Score>,
+    // the ply a draw offer was last turned down at
+    pub draw_declined: Option<usize>,
+    pub stopped: bool,
+}
+
+impl Opponent {
+    pub fn spawn(program: String, color: Color) -> Self {
+        let (requests, request_rx) = mpsc::channel();
+        let (reply_tx, replies) = mpsc::channel();
+        let (warning_tx, warnings) = mpsc::channel();
+        thread::spawn(move || {
+            let mut supervisor = Supervisor::new(warning_tx);
+            let result = engine_options::spawn(&program)
+                .and_then(|mut engine| run(&mut engine, &request_rx, &reply_tx, &mut supervisor));
+            if let Err(err) = result {
+                let _ = reply_tx.send((0, Err(err)));
+            }
+        });
+        Self {
+            color,
+            strength: Strength::default(),
+            requests,
+            replies,
+            warnings,
+            searches: 0,
+            awaiting: None,
+            score: None,
+            draw_declined: None,
+            stopped: false,
+        }
+    }
+
+    // the next game, with the other color
+    pub fn rematch(&mut self) {
+        self.color = !self.color;
+        self.score = None;
+        self.draw_declined = None;
+    }
+
+    // takes effect from its next search
+    pub fn configure(&mut self, strength: Strength) {
+        self.strength = strength;
+        let _ = self.requests.send(Request::Configure(strength));
+    }
+
+    // engine restarts since last asked; the search it was on goes on after one
+    pub fn warnings(&self) -> impl Iterator<Item = String> + '_ {
+        self.warnings.try_iter()
+    }
+
+    pub fn is_thinking(&self) -> bool {
+        self.awaiting.is_some()
+    }
+
+    // the game so far, from its starting position, with the engine to move
+    pub fn think(&mut self, ply: usize, fen: &str, moves: Vec<Move>, limits: GoLimits) {
+        self.searches += 1;
+        let search = Search {
+            id: self.searches,
+            fen: fen.to_owned(),
+            moves,
+            limits,
+        };
+        if self.requests.send(Request::Search(search)).is_ok() {
+            self.awaiting = Some((self.searches, ply));
+        }
+    }
+
+    // gives up on the search under way, e.g. for a takeback; its move is dropped when it
+    // comes
+    pub fn stop_thinking(&mut self) {
+        self.awaiting = None;
+    }
+
+    // the engine's move once it has one; a reply to a search given up on, or about a ply
+    // the game has since left behind, is dropped
+    pub fn poll(&mut self, ply: usize) -> Option<Result<String, UciError>> {
+        let (searched, reply) = self.replies.try_iter().last()?;
+        let result = match reply {
+            // an error ends the search too, so the engine's turn isn't left waiting on it
+            Err(err) => {
+                self.awaiting = None;
+                self.stopped = true;
+                return Some(Err(err));
+            }
+            Ok(result) => result,
+        };
+        let (_, awaited) = self.awaiting.filter(|&(id, _)| id == searched)?;
+        self.awaiting = None;
+        if awaited != ply {
+            return None;
+        }
+        self.score = result.score;
+        Some(Ok(result.best_move))
+    }
+}
+
+fn run(
+    engine: &mut UciEngine,
+    requests: &Receiver<Request>,
+    replies: &Sender<(usize, Result<SearchResult, UciError>)>,
+    supervisor: &mut Supervisor,
+) -> Result<(), UciError> {
+    engine.new_game()?;
+    while let Ok(request) = requests.recv() {
+        let search = match request {
+            Request::Configure(strength) => {
+                configure(engine, strength)?;
+                continue;
+            }
+            Request::Search(search) => search,
+        };
+        let allotted = search.limits.move_time.unwrap_or_default()
+            + search
+                .limits
+                .wtime
+                .max(search.limits.btime)
+                .unwrap_or_default();
+        // a crash or a hang gets the engine started again and the same search sent over
+        let result = loop {
+            let result = engine
+                .set_position(Some(&search.fen), &search.moves)
+                .and_then(|_| engine.go(&search.limits, allotted + TIMEOUT_MARGIN));
+            match result {
+                Ok(result) => {
+                    supervisor.succeeded();
+                    break Ok(result);
+                }
+                Err(err) => {
+                    if let Err(err) = supervisor.recover(engine, err) {
+                        break Err(err);
+                    }
+                }
+            }
+        };
+        if replies.send((search.id, result)).is_err() {
+            break;
+        }
+    }
+    Ok(())
+}
+
+// options an engine doesn't offer are left alone
+fn configure(engine: &mut UciEngine, strength: Strength) -> Result<(), UciError> {
+    if engine.has_option("UCI_LimitStrength") {
+        let limit = strength.elo.is_some().to_string();
+        engine.set_option("UCI_LimitStrength", &limit)?;
+    }
+    if let Some(elo) = strength.elo
+        && engine.has_option("UCI_Elo")
+    {
+        engine.set_option("UCI_Elo", &elo.to_string())?;
+    }
+    if engine.has_option("Contempt") {
+        engine.set_option("Contempt", &strength.contempt.to_string())?;
+    }
+    engine.is_ready()
+}
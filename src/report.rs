@@ -0,0 +1,128 @@
+use crate::{engine_options, supervisor::Supervisor};
+use chesslib::{
+    GameState,
+    analysis::review::GameReview,
+    errors::UciError,
+    pieces::Color,
+    uci::{GoLimits, Score, UciEngine},
+};
+use std::{
+    sync::mpsc::{self, Receiver, Sender},
+    thread,
+    time::Duration,
+};
+
+const REPORT_DEPTH: u32 = 14;
+const SEARCH_TIMEOUT: Duration = Duration::from_secs(60);
+
+// an engine going over every position of a game on its own thread, for the graph and
+// the move verdicts once it's through
+pub struct Report {
+    // the game's length when it was asked for
+    pub plies: usize,
+    scores: Vec<Option<Score>>,
+    results: Receiver<(usize, Score)>,
+    warnings: Receiver<String>,
+    first_mover: Color,
+    pub review: Option<GameReview>,
+}
+
+impl Report {
+    // `fens` has the starting position first and the one after the last move last
+    pub fn spawn(program: String, fens: Vec<String>) -> Self {
+        let first_mover = match fens.first().map(|fen| fen.split(' ').nth(1)) {
+            Some(Some("b")) => Color::Black,
+            _ => Color::White,
+        };
+        let plies = fens.len().saturating_sub(1);
+        let scores = vec![None; fens.len()];
+        let (result_tx, results) = mpsc::channel();
+        let (warning_tx, warnings) = mpsc::channel();
+        thread::spawn(move || {
+            let mut supervisor = Supervisor::new(warning_tx.clone());
+            let result = engine_options::spawn(&program)
+                .and_then(|mut engine| run(&mut engine, &fens, &result_tx, &mut supervisor));
+            if let Err(err) = result {
+                log::warn!("report engine {program} stopped: {err}");
+                let _ = warning_tx.send(format!("Game report stopped: {err}"));
+            }
+        });
+        Self {
+            plies,
+            scores,
+            results,
+            warnings,
+            first_mover,
+            review: None,
+        }
+    }
+
+    pub fn update(&mut self) {
+        for (idx, score) in self.results.try_iter() {
+            self.scores[idx] = Some(score);
+        }
+        if self.review.is_none() && self.scores.iter().all(Option::is_some) {
+            let scores = self.scores.iter().flatten().copied().collect();
+            self.review = Some(GameReview::new(scores, self.first_mover));
+        }
+    }
+
+    pub fn warnings(&self) -> impl Iterator<Item = String> + '_ {
+        self.warnings.try_iter()
+    }
+
+    // White's scores so far, one per position
+    pub fn scores(&self) -> &[Option<Score>] {
+        &self.scores
+    }
+
+    pub fn progress(&self) -> usize {
+        self.scores.iter().flatten().count()
+    }
+}
+
+fn run(
+    engine: &mut UciEngine,
+    fens: &[String],
+    results: &Sender<(usize, Score)>,
+    supervisor: &mut Supervisor,
+) -> Result<(), UciError> {
+    let limits = GoLimits {
+        depth: Some(REPORT_DEPTH),
+        ..Default::default()
+    };
+    engine.new_game()?;
+    for (idx, fen) in fens.iter().enumerate() {
+        let gs = GameState::try_from_fen(fen).unwrap_or_default();
+        let white = gs.get_turn() == Color::White;
+        // nothing to search once the game is over; a mate counts as one for the winner
+        let score = if gs.legal_move_count() == 0 {
+            match (gs.board().is_in_check(gs.get_turn()), white) {
+                (false, _) => Score::Centipawns(0),
+                (true, true) => Score::Mate(-1),
+                (true, false) => Score::Mate(1),
+            }
+        } else {
+            let result = loop {
+                let searched = engine
+                    .set_position(Some(fen), &[])
+                    .and_then(|_| engine.go(&limits, SEARCH_TIMEOUT));
+                match searched {
+                    Ok(result) => break result,
+                    Err(err) => supervisor.recover(engine, err)?,
+                }
+            };
+            supervisor.succeeded();
+            match result.score {
+                Some(score) if white => score,
+                Some(Score::Centipawns(cp)) => Score::Centipawns(-cp),
+                Some(Score::Mate(n)) => Score::Mate(-n),
+                None => Score::Centipawns(0),
+            }
+        };
+        if results.send((idx, score)).is_err() {
+            break;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,94 @@
+use crate::keys::KeyBindings;
+use std::{fmt::Display, fs};
+
+const SETTINGS_FILE: &str = "settings.txt";
+
+// the look and sound chosen in the settings window, kept between runs as `key = value`
+// lines; themes go by name so adding assets doesn't shift them. Anything missing or
+// unreadable keeps the default, key bindings included.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Settings {
+    pub pieces: Option<String>,
+    pub board: Option<String>,
+    pub highlights: Option<String>,
+    pub volume: Option<f32>,
+    pub muted: Option<bool>,
+    pub animate: Option<bool>,
+    pub keys: KeyBindings,
+    pub announce: Option<bool>,
+    // a program that reads text aloud, given it as its last argument, e.g. `espeak -s 150`
+    pub speech: Option<String>,
+    pub explain: Option<bool>,
+    pub coach: Option<bool>,
+}
+
+impl Settings {
+    pub fn load() -> Self {
+        fs::read_to_string(SETTINGS_FILE)
+            .map(|text| Self::parse(&text))
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) {
+        if let Err(err) = fs::write(SETTINGS_FILE, self.to_string()) {
+            log::warn!("couldn't save {SETTINGS_FILE}: {err}");
+        }
+    }
+
+    fn parse(text: &str) -> Self {
+        let mut settings = Self::default();
+        for (key, value) in text.lines().filter_map(|line| line.split_once('=')) {
+            let value = value.trim();
+            match key.trim() {
+                "pieces" => settings.pieces = Some(value.to_owned()),
+                "board" => settings.board = Some(value.to_owned()),
+                "highlights" => settings.highlights = Some(value.to_owned()),
+                "volume" => settings.volume = value.parse().ok(),
+                "muted" => settings.muted = value.parse().ok(),
+                "animate" => settings.animate = value.parse().ok(),
+                "announce" => settings.announce = value.parse().ok(),
+                "explain" => settings.explain = value.parse().ok(),
+                "coach" => settings.coach = value.parse().ok(),
+                "speech" => settings.speech = Some(value.to_owned()).filter(|s| !s.is_empty()),
+                key => {
+                    if let Some(action) = key.strip_prefix("key.") {
+                        settings.keys.parse_line(action, value);
+                    }
+                }
+            }
+        }
+        settings
+    }
+}
+
+impl Display for Settings {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let names = [
+            ("pieces", &self.pieces),
+            ("board", &self.board),
+            ("highlights", &self.highlights),
+            ("speech", &self.speech),
+        ];
+        for (key, value) in names {
+            if let Some(value) = value {
+                writeln!(f, "{key} = {value}")?;
+            }
+        }
+        if let Some(volume) = self.volume {
+            writeln!(f, "volume = {volume}")?;
+        }
+        let flags = [
+            ("muted", self.muted),
+            ("animate", self.animate),
+            ("announce", self.announce),
+            ("explain", self.explain),
+            ("coach", self.coach),
+        ];
+        for (key, value) in flags {
+            if let Some(value) = value {
+                writeln!(f, "{key} = {value}")?;
+            }
+        }
+        write!(f, "{}", self.keys)
+    }
+}
@@ -0,0 +1,206 @@
+use crate::{analysis::Evaluation, engine_options};
+use chesslib::{
+    GameState,
+    clock::{Clock, TimeControl},
+    engine_match::{GameEvent, MatchConfig, Opening, Termination, play_game_watched},
+    errors::MatchError,
+    moves::Move,
+    pgn::GameResult,
+    pieces::Color,
+    uci::{Score, SearchResult},
+};
+use std::{
+    sync::mpsc::{self, Receiver, Sender},
+    thread,
+};
+
+// the least time each move stays on the board before the next search starts, from
+// slowest to fastest
+const MOVE_DELAYS: [f64; 5] = [4.0, 2.0, 1.0, 0.5, 0.0];
+const DEFAULT_SPEED: usize = 2;
+
+enum Update {
+    Started([String; 2]),
+    Searching(GameState, SearchResult),
+    Moved(Move, Clock),
+    Finished(Result<(GameResult, Termination), MatchError>),
+}
+
+// what the board has to catch up with
+pub enum Watched {
+    Started([String; 2]),
+    Moved(Move, Clock),
+    Finished(GameResult, Termination),
+    Failed(MatchError),
+}
+
+// two UCI engines playing each other through the match runner on their own thread; after
+// every move the runner holds on, with the clocks stopped, until the board lets it go on
+pub struct Spectator {
+    // White's program first
+    pub programs: [String; 2],
+    pub time_control: TimeControl,
+    updates: Receiver<Update>,
+    go_ahead: Sender<()>,
+    // each side's latest search and the position it was of, from its own point of view
+    pub searches: [Option<(GameState, SearchResult)>; 2],
+    // whoever searched last
+    last: Option<Color>,
+    // when the last move went on the board, while the runner waits
+    moved_at: Option<f64>,
+    step: bool,
+    pub paused: bool,
+    pub speed: usize,
+    pub finished: bool,
+}
+
+impl Spectator {
+    pub fn spawn(programs: [String; 2], time_control: TimeControl) -> Self {
+        let (update_tx, updates) = mpsc::channel();
+        let (go_ahead, go_ahead_rx) = mpsc::channel();
+        let worker_programs = programs.clone();
+        thread::spawn(move || {
+            let result = run(&worker_programs, time_control, &update_tx, &go_ahead_rx);
+            let _ = update_tx.send(Update::Finished(result));
+        });
+        Self {
+            programs,
+            time_control,
+            updates,
+            go_ahead,
+            searches: [None, None],
+            last: None,
+            moved_at: None,
+            step: false,
+            paused: false,
+            speed: DEFAULT_SPEED,
+            finished: false,
+        }
+    }
+
+    // the same pairing with the colors the other way round
+    pub fn rematch(&self) -> Self {
+        let [white, black] = self.programs.clone();
+        let mut spectator = Self::spawn([black, white], self.time_control);
+        spectator.paused = self.paused;
+        spectator.speed = self.speed;
+        spectator
+    }
+
+    pub fn move_delay(&self) -> f64 {
+        MOVE_DELAYS[self.speed]
+    }
+
+    pub fn faster(&mut self) {
+        self.speed = (self.speed + 1).min(MOVE_DELAYS.len() - 1);
+    }
+
+    pub fn slower(&mut self) {
+        self.speed = self.speed.saturating_sub(1);
+    }
+
+    // lets the move on the board be answered while paused
+    pub fn step(&mut self) {
+        self.step = self.moved_at.is_some();
+    }
+
+    // the next thing for the board, if the runner has sent one
+    pub fn poll(&mut self, now: f64) -> Option<Watched> {
+        loop {
+            match self.updates.try_recv().ok()? {
+                Update::Started(names) => return Some(Watched::Started(names)),
+                Update::Searching(gs, search) => {
+                    let color = gs.get_turn();
+                    self.searches[color as usize] = Some((gs, search));
+                    self.last = Some(color);
+                }
+                Update::Moved(mv, clock) => {
+                    self.moved_at = Some(now);
+                    return Some(Watched::Moved(mv, clock));
+                }
+                Update::Finished(result) => {
+                    self.finished = true;
+                    self.moved_at = None;
+                    return Some(match result {
+                        Ok((result, termination)) => Watched::Finished(result, termination),
+                        Err(err) => Watched::Failed(err),
+                    });
+                }
+            }
+        }
+    }
+
+    // the latest word from either engine, turned round to White's point of view
+    pub fn evaluation(&self) -> Option<Evaluation> {
+        let (gs, search) = self.searches[self.last? as usize].as_ref()?;
+        let score = match search.score? {
+            score if gs.get_turn() == Color::White => score,
+            Score::Centipawns(cp) => Score::Centipawns(-cp),
+            Score::Mate(n) => Score::Mate(-n),
+        };
+        Some(Evaluation {
+            fen: gs.to_fen(),
+            score,
+            depth: search.depth.unwrap_or_default(),
+            best_move: search.pv.first().cloned(),
+            lines: Vec::new(),
+        })
+    }
+
+    // sends the runner on to the next search once the move has been shown long enough,
+    // or a step was asked for; true when it goes
+    pub fn release(&mut self, now: f64) -> bool {
+        let Some(moved_at) = self.moved_at else {
+            return false;
+        };
+        let shown = now - moved_at >= self.move_delay();
+        if !(self.step || !self.paused && shown) {
+            return false;
+        }
+        self.step = false;
+        self.moved_at = None;
+        self.go_ahead.send(()).is_ok()
+    }
+}
+
+fn run(
+    programs: &[String; 2],
+    time_control: TimeControl,
+    updates: &Sender<Update>,
+    go_ahead: &Receiver<()>,
+) -> Result<(GameResult, Termination), MatchError> {
+    let mut white = engine_options::spawn(&programs[0])?;
+    let mut black = engine_options::spawn(&programs[1])?;
+    let names = [white.name().to_owned(), black.name().to_owned()];
+    updates
+        .send(Update::Started(names))
+        .map_err(|_| MatchError::Abandoned)?;
+    let config = MatchConfig {
+        event: "Engine game".to_owned(),
+        time_control,
+        draw: None,
+        resign: None,
+        max_moves: None,
+        black_time_control: None,
+        armageddon: false,
+    };
+    let record = play_game_watched(
+        [&mut white, &mut black],
+        &Opening::default(),
+        1,
+        &config,
+        |event| match event {
+            GameEvent::Searching(gs, search) => {
+                updates.send(Update::Searching(*gs, search.clone())).is_ok()
+            }
+            // the board keeps the runner waiting here for as long as it likes
+            GameEvent::Moved(mv, _, clock) => {
+                updates.send(Update::Moved(mv, clock.clone())).is_ok() && go_ahead.recv().is_ok()
+            }
+        },
+    );
+    white.quit();
+    black.quit();
+    let record = record?;
+    Ok((record.result, record.termination))
+}
@@ -0,0 +1,17 @@
+use std::{process::Command, thread};
+
+// hands the text to a text-to-speech program, e.g. `espeak -s 150`, without waiting on
+// it; the program gets the text as its last argument
+pub fn speak(command: &str, text: &str) {
+    let mut words = command.split_whitespace();
+    let Some(program) = words.next() else {
+        return;
+    };
+    let mut command = Command::new(program);
+    command.args(words).arg(text);
+    thread::spawn(move || {
+        if let Err(err) = command.status() {
+            log::warn!("couldn't run the speech command: {err}");
+        }
+    });
+}
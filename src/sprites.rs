@@ -0,0 +1,111 @@
+use crate::assets::{BUILTIN_PIECES, add_builtin, list_assets, load_texture_asset, read_asset};
+use chesslib::prelude::*;
+use macroquad::{math::Rect, texture::Texture2D};
+
+const PIECES_DIR: &str = "assets/pieces";
+
+// where each piece sits on a sprite sheet: white on the first row, black on the second,
+// columns in `order`
+#[derive(Debug, Copy, Clone)]
+pub struct SheetLayout {
+    order: [Figure; 6],
+    cell: f32,
+    gap: f32,
+}
+
+impl Default for SheetLayout {
+    fn default() -> Self {
+        use Figure::*;
+        Self {
+            order: [King, Queen, Bishop, Knight, Rook, Pawn],
+            cell: 170.0,
+            gap: 1.0,
+        }
+    }
+}
+
+impl SheetLayout {
+    pub fn rect(&self, piece: Piece) -> Rect {
+        let col = self
+            .order
+            .iter()
+            .position(|&figure| figure == piece.figure)
+            .unwrap_or_default();
+        let row = match piece.color {
+            Color::White => 0,
+            Color::Black => 1,
+        };
+        let step = self.cell + self.gap;
+        Rect::new(col as f32 * step, row as f32 * step, self.cell, self.cell)
+    }
+
+    // sidecar lines like `order = KQBNRP`, `cell = 170` and `gap = 1`;
+    // missing keys keep the default
+    fn parse(text: &str) -> Option<Self> {
+        let mut layout = Self::default();
+        for line in text.lines().filter(|line| !line.trim().is_empty()) {
+            let (key, value) = line.split_once('=')?;
+            let value = value.trim();
+            match key.trim() {
+                "order" => {
+                    let figures = value
+                        .chars()
+                        .map(|c| Piece::try_from(c).ok().map(|p| p.figure))
+                        .collect::<Option<Vec<_>>>()?;
+                    layout.order = figures.try_into().ok()?;
+                    if Figure::iter().any(|figure| !layout.order.contains(figure)) {
+                        return None;
+                    }
+                }
+                "cell" => layout.cell = value.parse().ok()?,
+                "gap" => layout.gap = value.parse().ok()?,
+                _ => {}
+            }
+        }
+        Some(layout)
+    }
+}
+
+pub struct PieceSet {
+    pub name: String,
+    pub texture: Texture2D,
+    pub layout: SheetLayout,
+}
+
+// every png under assets/pieces, laid out by its optional .txt sidecar, and the
+// built-in set
+pub async fn load_piece_sets() -> Vec<PieceSet> {
+    let mut paths = list_assets(PIECES_DIR, "png").await;
+    add_builtin(&mut paths, BUILTIN_PIECES);
+    let mut sets = Vec::with_capacity(paths.len());
+    for path in paths {
+        let Some(layout) = load_layout(&path).await else {
+            log::warn!("skipping {path}: bad layout file");
+            continue;
+        };
+        let Some(texture) = load_texture_asset(&path).await else {
+            log::warn!("skipping {path}: couldn't load texture");
+            continue;
+        };
+        let name = file_stem(&path).to_owned();
+        sets.push(PieceSet {
+            name,
+            texture,
+            layout,
+        });
+    }
+    sets
+}
+
+async fn load_layout(sheet: &str) -> Option<SheetLayout> {
+    let sidecar = format!("{}.txt", sheet.trim_end_matches(".png"));
+    match read_asset(&sidecar).await {
+        Some(text) => SheetLayout::parse(&text),
+        None => Some(SheetLayout::default()),
+    }
+}
+
+pub fn file_stem(path: &str) -> &str {
+    let name = path.rsplit('/').next().unwrap_or(path);
+    name.rsplit_once('.').map_or(name, |(stem, _)| stem)
+}
@@ -0,0 +1,38 @@
+use chesslib::{errors::UciError, uci::UciEngine};
+use std::sync::mpsc::Sender;
+
+// times in a row an engine is started again before it's given up on
+const MAX_RESTARTS: u32 = 3;
+
+// looks after an engine on its thread: one that crashes or stops answering is started
+// again, with a word to the GUI, until it has failed too often in a row
+pub struct Supervisor {
+    restarts: u32,
+    warnings: Sender<String>,
+}
+
+impl Supervisor {
+    pub fn new(warnings: Sender<String>) -> Self {
+        Self {
+            restarts: 0,
+            warnings,
+        }
+    }
+
+    // Ok once `engine` is running again, for whatever it failed at to be sent over again
+    pub fn recover(&mut self, engine: &mut UciEngine, err: UciError) -> Result<(), UciError> {
+        if self.restarts >= MAX_RESTARTS {
+            return Err(err);
+        }
+        self.restarts += 1;
+        engine.restart()?;
+        let _ = self
+            .warnings
+            .send(format!("{} restarted after: {err}", engine.name()));
+        Ok(())
+    }
+
+    pub fn succeeded(&mut self) {
+        self.restarts = 0;
+    }
+}
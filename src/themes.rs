@@ -0,0 +1,122 @@
+use crate::{
+    assets::{BUILTIN_BOARD, add_builtin, list_assets, load_texture_asset, read_asset},
+    sprites::file_stem,
+};
+use macroquad::{color::Color as RgbaColor, texture::Texture2D};
+
+const BOARDS_DIR: &str = "assets/boards";
+const FLAT_THEMES_FILE: &str = "assets/boards/flat.txt";
+
+pub enum BoardTheme {
+    Texture {
+        name: String,
+        texture: Texture2D,
+    },
+    // drawn square by square, so it needs no assets at all
+    Flat {
+        name: String,
+        light: RgbaColor,
+        dark: RgbaColor,
+    },
+}
+
+impl BoardTheme {
+    pub fn name(&self) -> &str {
+        match self {
+            BoardTheme::Texture { name, .. } | BoardTheme::Flat { name, .. } => name,
+        }
+    }
+
+    fn flat(name: &str, light: u32, dark: u32) -> Self {
+        BoardTheme::Flat {
+            name: name.to_owned(),
+            light: RgbaColor::from_hex(light),
+            dark: RgbaColor::from_hex(dark),
+        }
+    }
+}
+
+// the board and highlights picked together by the high contrast switch
+pub const HIGH_CONTRAST: &str = "high-contrast";
+
+// square tints laid over the board; one preset keeps clear of red-green pairs. A non-zero
+// outline, as a share of the square, also rings the tinted squares in solid color.
+pub struct HighlightTheme {
+    pub name: &'static str,
+    pub last_move: RgbaColor,
+    pub selection: RgbaColor,
+    pub check: RgbaColor,
+    pub hint: RgbaColor,
+    pub outline: f32,
+}
+
+pub const HIGHLIGHT_THEMES: [HighlightTheme; 4] = [
+    HighlightTheme {
+        name: "classic",
+        last_move: RgbaColor::new(0.2, 0.2, 0.78, 0.31),
+        selection: RgbaColor::new(0.2, 0.2, 0.78, 0.45),
+        check: RgbaColor::new(0.85, 0.1, 0.1, 0.55),
+        hint: RgbaColor::new(0.1, 0.6, 0.25, 0.75),
+        outline: 0.0,
+    },
+    HighlightTheme {
+        name: "green",
+        last_move: RgbaColor::new(0.61, 0.78, 0.0, 0.41),
+        selection: RgbaColor::new(0.08, 0.47, 0.11, 0.5),
+        check: RgbaColor::new(0.85, 0.1, 0.1, 0.55),
+        hint: RgbaColor::new(0.0, 0.45, 0.7, 0.75),
+        outline: 0.0,
+    },
+    // Okabe-Ito sky blue, blue, vermillion and orange
+    HighlightTheme {
+        name: "color-blind",
+        last_move: RgbaColor::new(0.34, 0.71, 0.91, 0.45),
+        selection: RgbaColor::new(0.0, 0.45, 0.7, 0.5),
+        check: RgbaColor::new(0.84, 0.37, 0.0, 0.65),
+        hint: RgbaColor::new(0.9, 0.62, 0.0, 0.8),
+        outline: 0.0,
+    },
+    // strong colors with thick rings, for telling squares apart at a glance
+    HighlightTheme {
+        name: HIGH_CONTRAST,
+        last_move: RgbaColor::new(1.0, 0.85, 0.0, 0.5),
+        selection: RgbaColor::new(0.0, 0.4, 1.0, 0.55),
+        check: RgbaColor::new(1.0, 0.0, 0.0, 0.85),
+        hint: RgbaColor::new(0.85, 0.0, 0.85, 0.9),
+        outline: 0.1,
+    },
+];
+
+// board textures first, then the built-in flat boards and any listed in flat.txt
+pub async fn load_board_themes() -> Vec<BoardTheme> {
+    let mut themes = Vec::new();
+    let mut paths = list_assets(BOARDS_DIR, "png").await;
+    add_builtin(&mut paths, BUILTIN_BOARD);
+    for path in paths {
+        let name = file_stem(&path).to_owned();
+        let Some(texture) = load_texture_asset(&path).await else {
+            log::warn!("skipping {path}: couldn't load texture");
+            continue;
+        };
+        themes.push(BoardTheme::Texture { name, texture });
+    }
+    themes.extend([
+        BoardTheme::flat("brown", 0xf0d9b5, 0xb58863),
+        BoardTheme::flat("green", 0xeeeed2, 0x769656),
+        BoardTheme::flat("blue", 0xdee3e6, 0x8ca2ad),
+        BoardTheme::flat(HIGH_CONTRAST, 0xffffff, 0x707070),
+    ]);
+    if let Some(text) = read_asset(FLAT_THEMES_FILE).await {
+        themes.extend(text.lines().filter_map(parse_flat_theme));
+    }
+    themes
+}
+
+// `name light dark` with colors as hex, e.g. `walnut #e8c99b #9c6a3c`
+fn parse_flat_theme(line: &str) -> Option<BoardTheme> {
+    let mut fields = line.split_whitespace();
+    let name = fields.next()?;
+    let mut color = || u32::from_str_radix(fields.next()?.trim_start_matches('#'), 16).ok();
+    let (light, dark) = (color()?, color()?);
+    Some(BoardTheme::flat(name, light, dark))
+}
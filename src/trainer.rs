@@ -0,0 +1,123 @@
+use chesslib::{
+    prelude::*,
+    puzzles::Puzzle,
+    ratings::{DEFAULT_K, elo_update},
+};
+
+const START_RATING: f64 = 1500.0;
+// pause before the opponent's move so the solver sees it happen
+const REPLY_DELAY: f64 = 0.5;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PuzzleStatus {
+    Solving,
+    // played a wrong move, still looking for the right one
+    Missed,
+    Solved,
+    Failed,
+}
+
+// puzzles served closest to the solver's rating, one move of the solution at a time
+pub struct Trainer {
+    puzzles: Vec<Puzzle>,
+    played: Vec<bool>,
+    current: usize,
+    // next ply of the solution; the solver plays the odd ones
+    ply: usize,
+    reply_at: Option<f64>,
+    pub status: PuzzleStatus,
+    pub rating: f64,
+    pub solved: u32,
+    pub attempted: u32,
+}
+
+impl Trainer {
+    pub fn new(puzzles: Vec<Puzzle>) -> Self {
+        Self {
+            played: vec![false; puzzles.len()],
+            puzzles,
+            current: 0,
+            ply: 0,
+            reply_at: None,
+            status: PuzzleStatus::Solving,
+            rating: START_RATING,
+            solved: 0,
+            attempted: 0,
+        }
+    }
+
+    pub fn puzzle(&self) -> &Puzzle {
+        &self.puzzles[self.current]
+    }
+
+    // the side the puzzle is solved for, who moves second
+    pub fn solver(&self) -> Color {
+        // a puzzle that doesn't load never gets as far as being solved
+        self.puzzle()
+            .start_position()
+            .map_or(Color::White, |gs| !gs.get_turn())
+    }
+
+    // picks the unplayed puzzle nearest the solver's rating, starting over once all are done
+    pub fn next_puzzle(&mut self, now: f64) {
+        if self.played.iter().all(|&played| played) {
+            self.played.fill(false);
+        }
+        let rating = self.rating;
+        self.current = (0..self.puzzles.len())
+            .filter(|&idx| !self.played[idx])
+            .min_by_key(|&idx| (self.puzzles[idx].rating as f64 - rating).abs() as u32)
+            .unwrap_or_default();
+        self.played[self.current] = true;
+        self.ply = 0;
+        self.status = PuzzleStatus::Solving;
+        self.reply_at = Some(now + REPLY_DELAY);
+    }
+
+    pub fn waiting(&self) -> bool {
+        self.reply_at.is_some()
+    }
+
+    // the opponent's next move once its delay is up
+    pub fn due_reply(&mut self, now: f64) -> Option<Move> {
+        if self.reply_at.is_none_or(|at| now < at) {
+            return None;
+        }
+        self.reply_at = None;
+        let mv = self.puzzle().moves.get(self.ply).copied();
+        self.ply += 1;
+        mv
+    }
+
+    // judges the solver's move in `gs`, moving the solution along when it's right
+    pub fn submit(&mut self, gs: &GameState, mv: Move, now: f64) -> bool {
+        if !matches!(self.status, PuzzleStatus::Solving | PuzzleStatus::Missed) {
+            return true;
+        }
+        if !self.puzzle().accepts(gs, self.ply, mv) {
+            if self.status == PuzzleStatus::Solving {
+                self.status = PuzzleStatus::Missed;
+                self.score(0.0);
+            }
+            return false;
+        }
+        self.ply += 1;
+        if self.ply < self.puzzle().moves.len() {
+            self.reply_at = Some(now + REPLY_DELAY);
+        } else if self.status == PuzzleStatus::Missed {
+            self.status = PuzzleStatus::Failed;
+        } else {
+            self.status = PuzzleStatus::Solved;
+            self.solved += 1;
+            self.score(1.0);
+        }
+        true
+    }
+
+    // a puzzle counts once, on the first miss or on a clean solve
+    fn score(&mut self, score: f64) {
+        let opponent = self.puzzle().rating as f64;
+        self.rating = elo_update(self.rating, opponent, score, DEFAULT_K);
+        self.attempted += 1;
+    }
+}
@@ -1,111 +1,1362 @@
-use chesslib::prelude::*;
+use crate::{
+    audio::{Audio, SoundEffect},
+    engine_options::{EngineConfig, FieldValue},
+    errors::AppError,
+    keys::{Action, KeyBinding, KeyBindings},
+    opponent::{EngineSetup, MAX_ELO, MIN_ELO},
+    settings::Settings,
+    speech,
+    sprites::{PieceSet, load_piece_sets},
+    themes::{BoardTheme, HIGH_CONTRAST, HIGHLIGHT_THEMES, HighlightTheme, load_board_themes},
+};
+use chesslib::{
+    analysis::review::Judgement,
+    board::bitboard::BitBoard,
+    pgn::{Arrow, MarkColor, SquareMark, nag_glyph},
+    prelude::*,
+    uci::OptionKind,
+};
 use macroquad::{
-    audio::{Sound, load_sound, play_sound_once},
-    color::WHITE,
+    camera::{Camera2D, set_camera, set_default_camera},
+    color::{Color as RgbaColor, WHITE},
+    hash,
+    input::{KeyCode, get_last_key_pressed, is_key_pressed},
     math::{Rect, Vec2},
-    shapes::draw_rectangle,
-    texture::{DrawTextureParams, Texture2D, draw_texture_ex, load_texture},
-    window::{next_frame, screen_height, screen_width},
+    shapes::{
+        draw_circle, draw_circle_lines, draw_line, draw_rectangle, draw_rectangle_lines,
+        draw_triangle,
+    },
+    text::{draw_text, measure_text},
+    texture::{DrawTextureParams, Image, draw_texture_ex, render_target},
+    time::get_time,
+    ui::{
+        root_ui,
+        widgets::{Button, Window},
+    },
+    window::{clear_background, next_frame, screen_height, screen_width, set_fullscreen},
 };
+use std::collections::VecDeque;
 
 type MousePos = (f32, f32);
 
+pub enum DialogResponse {
+    Submit,
+    Cancel,
+}
+
+// what came of the list of correspondence games
+pub enum GamesResponse {
+    Open(usize),
+    New(Color),
+    Close,
+}
+
+// what came of the chooser for a file of many games
+pub enum ChooserResponse {
+    Open(usize),
+    Close,
+}
+
+const HINT_COLOR: RgbaColor = RgbaColor::new(0.0, 0.0, 0.0, 0.25);
+const EVAL_WHITE: RgbaColor = RgbaColor::new(0.95, 0.95, 0.95, 1.0);
+const EVAL_BLACK: RgbaColor = RgbaColor::new(0.2, 0.2, 0.2, 1.0);
+const BACKDROP_COLOR: RgbaColor = RgbaColor::new(0.0, 0.0, 0.0, 0.5);
+const HOVER_COLOR: RgbaColor = RgbaColor::new(0.8, 0.8, 0.8, 1.0);
+const CURRENT_PLY_COLOR: RgbaColor = RgbaColor::new(0.35, 0.55, 0.85, 1.0);
+const PANEL_MARGIN: f32 = 10.0;
+const PANEL_WIDTH: f32 = 220.0;
+const ANIMATION_SECS: f64 = 0.15;
+const EVAL_BAR_WIDTH: f32 = 28.0;
+const BUTTON_SPACING: f32 = 30.0;
+const MOVE_LIST_ROW: f32 = 20.0;
+const HEADER_HEIGHT: f32 = 70.0;
+// frame rate with no input and nothing moving on screen
+const IDLE_FPS: u32 = 10;
+const TOAST_SECS: f64 = 2.5;
+const TOAST_FADE_SECS: f64 = 0.5;
+const MAX_TOASTS: usize = 3;
+const EXPORT_SQUARE: f32 = 100.0;
+const LOADING_STEPS: usize = 3;
+const GHOST_ALPHA: f32 = 0.35;
+const FLAG_COLOR: RgbaColor = RgbaColor::new(0.75, 0.2, 0.2, 1.0);
+const THREAT_COLOR: RgbaColor = RgbaColor::new(0.9, 0.15, 0.15, 0.3);
+const COVER_COLOR: RgbaColor = RgbaColor::new(0.15, 0.4, 0.9, 0.25);
+// shading per piece of majority on a square, up to the cap
+const INFLUENCE_ALPHA: f32 = 0.12;
+const MAX_INFLUENCE_ALPHA: f32 = 0.5;
+const HANGING_COLOR: RgbaColor = RgbaColor::new(1.0, 0.6, 0.0, 0.9);
+const WARNING_COLOR: RgbaColor = RgbaColor::new(0.7, 0.1, 0.8, 0.9);
+const GRAPH_HEIGHT: f32 = 90.0;
+const MINI_SQUARE: f32 = 24.0;
+// room for the summary lines over the graph
+const GRAPH_TEXT: f32 = 44.0;
+const INACCURACY_COLOR: RgbaColor = RgbaColor::new(0.95, 0.8, 0.2, 1.0);
+const MISTAKE_COLOR: RgbaColor = RgbaColor::new(0.95, 0.5, 0.1, 1.0);
+const BLUNDER_COLOR: RgbaColor = RgbaColor::new(0.85, 0.15, 0.15, 1.0);
+// lichess's study colors
+const MARK_GREEN: RgbaColor = RgbaColor::new(0.08, 0.47, 0.11, 0.8);
+const MARK_RED: RgbaColor = RgbaColor::new(0.53, 0.13, 0.13, 0.8);
+const MARK_YELLOW: RgbaColor = RgbaColor::new(0.9, 0.56, 0.0, 0.8);
+const MARK_BLUE: RgbaColor = RgbaColor::new(0.0, 0.19, 0.53, 0.8);
+const MARK_SQUARE_ALPHA: f32 = 0.5;
+
 pub struct View {
     width: f32,
     height: f32,
+    // top left of the board, which is centred in the space left of the panel
+    board_origin: Vec2,
     board_size: f32,
     square_size: f32,
-    board_texture: Texture2D,
-    piece_texture: Texture2D,
-    highlight_texture: Texture2D,
-    move_sound: Sound,
-    capture_sound: Sound,
-    in_check_sound: Sound,
+    // right-hand column for buttons, move list, clock and eval
+    panel: Rect,
+    eval_bar: Rect,
+    // evaluation graph along the bottom of the panel, while `show_graph`
+    graph: Rect,
+    show_graph: bool,
+    // room kept above the board for the loaded game's headers
+    header: bool,
+    flipped: bool,
+    board_themes: Vec<BoardTheme>,
+    board_theme: usize,
+    piece_sets: Vec<PieceSet>,
+    piece_set: usize,
+    highlights: usize,
+    audio: Audio,
+    animations: Vec<Animation>,
+    animate: bool,
+    fullscreen: bool,
+    pub fps_cap: Option<u32>,
+    // drop to IDLE_FPS while nothing happens, instead of redrawing the same frame
+    idle_saving: bool,
+    frame_start: f64,
+    // newest last, each dropped TOAST_SECS after it went up
+    toasts: VecDeque<Toast>,
+    keys: KeyBindings,
+    // moves read out as they're played, shown and handed to the speech command if set
+    announce: bool,
+    speech: Option<String>,
+    // beginners get told why a move isn't allowed
+    explain: bool,
+    // the engine looks over the player's moves and asks about blunders
+    coach: bool,
+    // the key bindings page of the settings, and the action waiting for a key
+    keys_open: bool,
+    // the engine options were asked for from the settings, which the app opens
+    options_requested: bool,
+    capturing: Option<Action>,
+    key_error: Option<String>,
+}
+
+struct Toast {
+    text: String,
+    start: f64,
+}
+
+// a piece sliding from a point on screen onto its square
+struct Animation {
+    piece: Piece,
+    from: Vec2,
+    to: Square,
+    start: f64,
 }
 
 impl View {
-    pub async fn new() -> Self {
-        let width = screen_width();
-        let height = screen_height();
-        let board_size = width.min(height);
-        let square_size = board_size / 8.0;
-        Self {
-            width,
-            height,
-            board_size,
-            square_size,
-            board_texture: load_texture("assets/boards/default.png").await.unwrap(),
-            piece_texture: load_texture("assets/pieces/wiki_chess.png").await.unwrap(),
-            highlight_texture: load_texture("assets/boards/highlight.png").await.unwrap(),
-            move_sound: load_sound("assets/sounds/Move.ogg").await.unwrap(),
-            capture_sound: load_sound("assets/sounds/Capture.ogg").await.unwrap(),
-            in_check_sound: load_sound("assets/sounds/Error.ogg").await.unwrap(),
-        }
-    }
-
-    pub async fn next_frame(&self) {
+    pub async fn new() -> Result<Self, AppError> {
+        draw_loading(0, "board themes").await;
+        let board_themes = load_board_themes().await;
+        draw_loading(1, "pieces").await;
+        let piece_sets = load_piece_sets().await;
+        if piece_sets.is_empty() {
+            return Err(AppError::MissingAsset("a piece set"));
+        }
+        draw_loading(2, "sounds").await;
+        let audio = Audio::load().await;
+        let mut view = Self {
+            width: 0.0,
+            height: 0.0,
+            board_origin: Vec2::ZERO,
+            board_size: 0.0,
+            square_size: 0.0,
+            panel: Rect::default(),
+            eval_bar: Rect::default(),
+            graph: Rect::default(),
+            show_graph: false,
+            header: false,
+            flipped: false,
+            board_themes,
+            board_theme: 0,
+            piece_sets,
+            piece_set: 0,
+            highlights: 0,
+            audio,
+            animations: Vec::new(),
+            animate: true,
+            fullscreen: false,
+            fps_cap: None,
+            idle_saving: true,
+            frame_start: 0.0,
+            toasts: VecDeque::new(),
+            keys: KeyBindings::default(),
+            announce: false,
+            speech: None,
+            explain: false,
+            coach: false,
+            keys_open: false,
+            options_requested: false,
+            capturing: None,
+            key_error: None,
+        };
+        view.update_screen();
+        Ok(view)
+    }
+
+    // waits out the rest of the frame under the cap, or the idle rate when not `busy`
+    pub async fn next_frame(&mut self, busy: bool) {
+        let idle =
+            !busy && self.animations.is_empty() && self.toasts.is_empty() && self.idle_saving;
+        let fps = if idle { Some(IDLE_FPS) } else { self.fps_cap };
+        if let Some(fps) = fps.filter(|&fps| fps > 0) {
+            let spare = 1.0 / fps as f64 - (get_time() - self.frame_start);
+            if spare > 0.0 {
+                pause(spare);
+            }
+        }
         next_frame().await;
+        self.frame_start = get_time();
+    }
+
+    pub fn toggle_fullscreen(&mut self) {
+        self.fullscreen = !self.fullscreen;
+        set_fullscreen(self.fullscreen);
+    }
+
+    pub fn slide_piece(&mut self, piece: Piece, from: Square, to: Square) {
+        let (x, y) = self.square_origin(from);
+        self.slide_piece_from(piece, Vec2::new(x, y), to);
+    }
+
+    // snap-back for drops that didn't make a move
+    pub fn return_piece(&mut self, piece: Piece, (x, y): MousePos, to: Square) {
+        let half = self.square_size / 2.0;
+        self.slide_piece_from(piece, Vec2::new(x - half, y - half), to);
+    }
+
+    fn slide_piece_from(&mut self, piece: Piece, from: Vec2, to: Square) {
+        if !self.animate {
+            return;
+        }
+        self.animations.retain(|a| a.to != to);
+        self.animations.push(Animation {
+            piece,
+            from,
+            to,
+            start: get_time(),
+        });
+    }
+
+    // the piece on this square is still travelling and is drawn by `draw_animations`
+    pub fn is_animating(&self, square: Square) -> bool {
+        self.animations.iter().any(|a| a.to == square)
+    }
+
+    // only the pieces `shown` lets through
+    pub fn draw_animations(&self, shown: impl Fn(Piece) -> bool) {
+        let now = get_time();
+        for animation in self.animations.iter().filter(|a| shown(a.piece)) {
+            let t = ((now - animation.start) / ANIMATION_SECS).clamp(0.0, 1.0) as f32;
+            let t = t * t * (3.0 - 2.0 * t);
+            let (x, y) = self.square_origin(animation.to);
+            let at = animation.from.lerp(Vec2::new(x, y), t);
+            self.draw_piece_at(animation.piece, at.x, at.y);
+        }
     }
 
     pub fn update_screen(&mut self) {
+        let now = get_time();
+        self.animations.retain(|a| now - a.start < ANIMATION_SECS);
+        self.toasts.retain(|t| now - t.start < TOAST_SECS);
         self.width = screen_width();
         self.height = screen_height();
-        self.board_size = self.width.min(self.height);
+        let board_width = (self.width - PANEL_WIDTH - EVAL_BAR_WIDTH).max(0.0);
+        let top = if self.header { HEADER_HEIGHT } else { 0.0 };
+        self.board_size = board_width.min(self.height - top).max(0.0);
         self.square_size = self.board_size / 8.0;
+        self.board_origin = Vec2::new(
+            (board_width - self.board_size) / 2.0,
+            top + (self.height - top - self.board_size) / 2.0,
+        );
+        self.eval_bar = Rect::new(
+            board_width,
+            self.board_origin.y,
+            EVAL_BAR_WIDTH,
+            self.board_size,
+        );
+        let panel_x = board_width + EVAL_BAR_WIDTH;
+        self.panel = Rect::new(panel_x, 0.0, self.width - panel_x, self.height);
+        self.graph = Rect::new(
+            panel_x + PANEL_MARGIN,
+            self.height - GRAPH_HEIGHT - PANEL_MARGIN,
+            (self.panel.w - 2.0 * PANEL_MARGIN).max(0.0),
+            GRAPH_HEIGHT,
+        );
+    }
+
+    pub fn set_header(&mut self, header: bool) {
+        self.header = header;
+        self.update_screen();
+    }
+
+    // up to two lines centred over the board
+    pub fn draw_header(&self, lines: &[String]) {
+        let centre = self.board_origin.x + self.board_size / 2.0;
+        let mut y = self.board_origin.y - HEADER_HEIGHT + 20.0;
+        for (line, size) in lines.iter().zip([22, 16, 16]) {
+            let width = measure_text(line, None, size, 1.0).width;
+            draw_text(line, centre - width / 2.0, y, size as f32, EVAL_WHITE);
+            y += 22.0;
+        }
+    }
+
+    pub fn set_graph(&mut self, show: bool) {
+        self.show_graph = show;
+    }
+
+    // White's share of each position so far, left to right, White's side at the
+    // bottom; `marks` are moves by index, drawn on the position they led to
+    pub fn draw_eval_graph(
+        &self,
+        lines: &[String],
+        shares: &[Option<f32>],
+        current: usize,
+        marks: &[(usize, Judgement)],
+    ) {
+        let Rect { x, y, w, h } = self.graph;
+        for (idx, line) in lines.iter().take(2).enumerate() {
+            let text_y = y - GRAPH_TEXT + 16.0 + idx as f32 * 20.0;
+            draw_text(line, x, text_y, 18.0, EVAL_WHITE);
+        }
+        draw_rectangle(x, y, w, h, EVAL_BLACK);
+        let step = w / shares.len().saturating_sub(1).max(1) as f32;
+        let point =
+            |idx: usize, share: f32| Vec2::new(x + idx as f32 * step, y + h * (1.0 - share));
+        // filled in under the line, a column at a time
+        for (idx, pair) in shares.windows(2).enumerate() {
+            let (Some(a), Some(b)) = (pair[0], pair[1]) else {
+                continue;
+            };
+            let (a, b) = (point(idx, a), point(idx + 1, b));
+            let (bottom_a, bottom_b) = (Vec2::new(a.x, y + h), Vec2::new(b.x, y + h));
+            draw_triangle(a, b, bottom_a, EVAL_WHITE);
+            draw_triangle(b, bottom_b, bottom_a, EVAL_WHITE);
+        }
+        draw_line(x, y + h / 2.0, x + w, y + h / 2.0, 1.0, HOVER_COLOR);
+        let current_x = x + current as f32 * step;
+        draw_line(current_x, y, current_x, y + h, 2.0, CURRENT_PLY_COLOR);
+        for &(mv, judgement) in marks {
+            if let Some(Some(share)) = shares.get(mv + 1) {
+                let at = point(mv + 1, *share);
+                draw_circle(at.x, at.y, 3.5, judgement_color(judgement));
+            }
+        }
+    }
+
+    // the position under a click on the graph, out of `count`
+    pub fn graph_ply_at(&self, (x, y): MousePos, count: usize) -> Option<usize> {
+        if !self.show_graph || count == 0 || !self.graph.contains(Vec2::new(x, y)) {
+            return None;
+        }
+        let step = self.graph.w / count.saturating_sub(1).max(1) as f32;
+        let ply = ((x - self.graph.x) / step).round() as usize;
+        Some(ply.min(count - 1))
+    }
+
+    pub fn is_flipped(&self) -> bool {
+        self.flipped
+    }
+
+    pub fn set_flipped(&mut self, flipped: bool) {
+        self.flipped = flipped;
     }
 
     pub fn draw_board(&self) {
-        draw_texture_ex(&self.board_texture, 0.0, 0.0, WHITE, DrawTextureParams {
-            dest_size: Some(Vec2::splat(self.board_size)),
-            ..Default::default()
-        });
+        match &self.board_themes[self.board_theme] {
+            BoardTheme::Texture { texture, .. } => {
+                let Vec2 { x, y } = self.board_origin;
+                draw_texture_ex(texture, x, y, WHITE, DrawTextureParams {
+                    dest_size: Some(Vec2::splat(self.board_size)),
+                    ..Default::default()
+                });
+            }
+            &BoardTheme::Flat { light, dark, .. } => {
+                for square in (!BitBoard::default()).iter() {
+                    let (x, y) = self.square_origin(square);
+                    let color = if square.is_dark() { dark } else { light };
+                    draw_rectangle(x, y, self.square_size, self.square_size, color);
+                }
+            }
+        }
+    }
+
+    // dims the board and stacks the choices from the promotion square towards the centre
+    pub fn draw_promotion_widget(&self, col: Column, turn: Color, mouse: MousePos) {
+        let Vec2 { x, y } = self.board_origin;
+        draw_rectangle(x, y, self.board_size, self.board_size, BACKDROP_COLOR);
+        let hovered = self.promotion_choice(col, turn, mouse);
+        for (square, figure) in promotion_squares(col, turn) {
+            let (x, y) = self.square_origin(square);
+            let color = if hovered == Some(figure) {
+                HOVER_COLOR
+            } else {
+                WHITE
+            };
+            draw_rectangle(x, y, self.square_size, self.square_size, color);
+            self.draw_piece_at_square(
+                Piece {
+                    color: turn,
+                    figure,
+                },
+                square,
+            );
+        }
     }
 
-    pub fn draw_promotion_widget(&self, col: Column, turn: Color) {
-        let (y_coord, rows) = match turn {
-            Color::White => (0.0, [Row::Eight, Row::Seven, Row::Six, Row::Five]),
-            Color::Black => (4.0 * self.square_size, [
-                Row::One,
-                Row::Two,
-                Row::Three,
-                Row::Four,
-            ]),
+    // the promotion piece under the mouse, if any
+    pub fn promotion_choice(&self, col: Column, turn: Color, mouse: MousePos) -> Option<Figure> {
+        let square = self.get_square_at_point(mouse)?;
+        promotion_squares(col, turn)
+            .into_iter()
+            .find_map(|(s, figure)| (s == square).then_some(figure))
+    }
+
+    // White's share fills from White's side of the board
+    pub fn draw_eval_bar(&self, white_share: f32, label: &str) {
+        let Rect { x, y, w, h } = self.eval_bar;
+        let white_h = h * white_share.clamp(0.0, 1.0);
+        let (white_y, black_y) = if self.flipped {
+            (y, y + white_h)
+        } else {
+            (y + h - white_h, y)
         };
-        draw_rectangle(
-            col as u8 as f32 * self.square_size,
-            y_coord,
-            self.square_size,
-            4.0 * self.square_size,
-            WHITE,
+        draw_rectangle(x, black_y, w, h - white_h, EVAL_BLACK);
+        draw_rectangle(x, white_y, w, white_h, EVAL_WHITE);
+        // label sits on the leading side's end of the bar
+        let white_leads = white_share >= 0.5;
+        let (text_color, text_y) = match (white_leads, self.flipped) {
+            (true, false) => (EVAL_BLACK, y + h - 6.0),
+            (true, true) => (EVAL_BLACK, y + 14.0),
+            (false, false) => (EVAL_WHITE, y + 14.0),
+            (false, true) => (EVAL_WHITE, y + h - 6.0),
+        };
+        let size = measure_text(label, None, 14, 1.0);
+        draw_text(label, x + (w - size.width) / 2.0, text_y, 14.0, text_color);
+    }
+
+    fn highlight_theme(&self) -> &HighlightTheme {
+        &HIGHLIGHT_THEMES[self.highlights]
+    }
+
+    fn tint_square(&self, square: Square, color: RgbaColor) {
+        let (x, y) = self.square_origin(square);
+        draw_rectangle(x, y, self.square_size, self.square_size, color);
+        let thickness = self.square_size * self.highlight_theme().outline;
+        if thickness > 0.0 {
+            draw_rectangle_lines(
+                x + thickness / 2.0,
+                y + thickness / 2.0,
+                self.square_size - thickness,
+                self.square_size - thickness,
+                thickness,
+                RgbaColor { a: 1.0, ..color },
+            );
+        }
+    }
+
+    // the squares the last move left and landed on
+    pub fn draw_highlight(&self, square: Square) {
+        self.tint_square(square, self.highlight_theme().last_move);
+    }
+
+    pub fn draw_selection(&self, square: Square) {
+        self.tint_square(square, self.highlight_theme().selection);
+    }
+
+    // the king in check, shaded from the middle outwards
+    pub fn draw_check(&self, square: Square) {
+        let (x, y) = self.square_center(square);
+        let color = self.highlight_theme().check;
+        for step in 1..=4 {
+            let radius = self.square_size / 2.0 * (1.0 - 0.2 * (step - 1) as f32);
+            draw_circle(x, y, radius, RgbaColor {
+                a: color.a / 3.0,
+                ..color
+            });
+        }
+        let thickness = self.square_size * self.highlight_theme().outline;
+        if thickness > 0.0 {
+            let radius = self.square_size / 2.0 - thickness / 2.0;
+            draw_circle_lines(x, y, radius, thickness, RgbaColor { a: 1.0, ..color });
+        }
+    }
+
+    // dot on an empty square the held piece can move to
+    pub fn draw_move_hint(&self, square: Square) {
+        let (x, y) = self.square_center(square);
+        draw_circle(x, y, self.square_size / 6.0, HINT_COLOR);
+    }
+
+    // ring around a piece the held piece can capture
+    pub fn draw_capture_hint(&self, square: Square) {
+        let (x, y) = self.square_center(square);
+        let thickness = self.square_size / 12.0;
+        draw_circle_lines(
+            x,
+            y,
+            self.square_size / 2.0 - thickness / 2.0,
+            thickness,
+            HINT_COLOR,
         );
-        let figures = [Figure::Queen, Figure::Rook, Figure::Knight, Figure::Bishop];
-        for (r, f) in rows.into_iter().zip(figures.into_iter()) {
-            let square = Square::from_coords(col, r);
-            let piece = Piece {
-                color: turn,
-                figure: f,
+    }
+
+    // red over squares the opponent attacks, blue over the ones the mover covers;
+    // contested squares get both
+    pub fn draw_attacks(&self, threats: BitBoard, cover: BitBoard) {
+        for (squares, color) in [(cover, COVER_COLOR), (threats, THREAT_COLOR)] {
+            for square in squares.iter() {
+                let (x, y) = self.square_origin(square);
+                draw_rectangle(x, y, self.square_size, self.square_size, color);
+            }
+        }
+    }
+
+    // each square in the color of the side with more pieces on it, the deeper the bigger
+    // the majority; `mover` takes the cover color as with the attacks
+    pub fn draw_influence(&self, influence: &[[u8; 64]; 2], mover: Color) {
+        for square in (0..64).filter_map(Square::new) {
+            let ours = i32::from(influence[mover as usize][square]);
+            let theirs = i32::from(influence[!mover as usize][square]);
+            let color = match ours - theirs {
+                0 => continue,
+                diff if diff > 0 => COVER_COLOR,
+                _ => THREAT_COLOR,
             };
-            self.draw_piece_at_square(piece, square);
+            let alpha = (INFLUENCE_ALPHA * (ours - theirs).abs() as f32).min(MAX_INFLUENCE_ALPHA);
+            let (x, y) = self.square_origin(square);
+            draw_rectangle(x, y, self.square_size, self.square_size, RgbaColor {
+                a: alpha,
+                ..color
+            });
         }
     }
 
-    pub fn draw_highlight(&self, square: Square) {
-        let x = square.col() as u8 as f32 * self.square_size;
-        let y = (7 - square.row() as u8) as f32 * self.square_size;
-        draw_texture_ex(&self.highlight_texture, x, y, WHITE, DrawTextureParams {
-            dest_size: Some(Vec2::splat(self.square_size)),
-            ..Default::default()
+    // cross over a square a mating pattern runs through
+    pub fn draw_warning(&self, square: Square) {
+        let (x, y) = self.square_origin(square);
+        let inset = self.square_size / 4.0;
+        let (near, far) = (inset, self.square_size - inset);
+        let thickness = self.square_size / 16.0;
+        draw_line(
+            x + near,
+            y + near,
+            x + far,
+            y + far,
+            thickness,
+            WARNING_COLOR,
+        );
+        draw_line(
+            x + far,
+            y + near,
+            x + near,
+            y + far,
+            thickness,
+            WARNING_COLOR,
+        );
+    }
+
+    // outline around a piece that can be taken for free
+    pub fn draw_hanging(&self, square: Square) {
+        let (x, y) = self.square_origin(square);
+        let thickness = self.square_size / 16.0;
+        draw_rectangle_lines(
+            x + thickness / 2.0,
+            y + thickness / 2.0,
+            self.square_size - thickness,
+            self.square_size - thickness,
+            thickness,
+            HANGING_COLOR,
+        );
+    }
+
+    pub fn draw_arrow(&self, from: Square, to: Square) {
+        self.arrow(from, to, self.highlight_theme().hint);
+    }
+
+    // the graphics a PGN comment draws on the board
+    pub fn draw_marked_arrow(&self, arrow: Arrow) {
+        self.arrow(arrow.from, arrow.to, mark_color(arrow.color));
+    }
+
+    pub fn draw_marked_square(&self, mark: SquareMark) {
+        let color = RgbaColor {
+            a: MARK_SQUARE_ALPHA,
+            ..mark_color(mark.color)
+        };
+        self.tint_square(mark.square, color);
+    }
+
+    // an arrow from the middle of one square to the next, head ending short of the centre
+    fn arrow(&self, from: Square, to: Square, color: RgbaColor) {
+        let from = Vec2::from(self.square_center(from));
+        let to = Vec2::from(self.square_center(to));
+        let Some(dir) = (to - from).try_normalize() else {
+            return;
+        };
+        let width = self.square_size / 6.0;
+        let head = self.square_size / 2.5;
+        let tip = to - dir * self.square_size / 6.0;
+        let base = tip - dir * head;
+        draw_line(from.x, from.y, base.x, base.y, width, color);
+        let side = dir.perp() * head / 2.0;
+        draw_triangle(tip, base + side, base - side, color);
+    }
+
+    // ring marking which piece the engine would move, without saying where
+    pub fn draw_piece_hint(&self, square: Square) {
+        let (x, y) = self.square_center(square);
+        let thickness = self.square_size / 12.0;
+        draw_circle_lines(
+            x,
+            y,
+            self.square_size / 2.0 - thickness / 2.0,
+            thickness,
+            self.highlight_theme().hint,
+        );
+    }
+
+    // buttons stack down the panel to the right of the board
+    pub fn button(&self, slot: usize, label: &str) -> bool {
+        let position = Vec2::new(
+            self.panel.x + PANEL_MARGIN,
+            self.panel.y + PANEL_MARGIN + slot as f32 * BUTTON_SPACING,
+        );
+        root_ui().button(position, label)
+    }
+
+    pub fn explains(&self) -> bool {
+        self.explain
+    }
+
+    pub fn coaches(&self) -> bool {
+        self.coach
+    }
+
+    // a move or other news in plain words, for when the board is hard to follow
+    pub fn announce(&mut self, text: String) {
+        if !self.announce {
+            return;
+        }
+        if let Some(command) = &self.speech {
+            speech::speak(command, &text);
+        }
+        self.toast(text);
+    }
+
+    // a short message over the bottom of the board that fades out on its own
+    pub fn toast(&mut self, text: impl Into<String>) {
+        if self.toasts.len() == MAX_TOASTS {
+            self.toasts.pop_front();
+        }
+        self.toasts.push_back(Toast {
+            text: text.into(),
+            start: get_time(),
         });
     }
 
+    // newest at the bottom, older ones stacked above it
+    pub fn draw_toasts(&self) {
+        let now = get_time();
+        let bottom = self.board_origin.y + self.board_size - 12.0;
+        for (row, toast) in self.toasts.iter().rev().enumerate() {
+            let alpha = ((TOAST_SECS - (now - toast.start)) / TOAST_FADE_SECS).min(1.0) as f32;
+            let size = measure_text(&toast.text, None, 20, 1.0);
+            let (w, h) = (size.width + 24.0, 32.0);
+            let x = self.board_origin.x + (self.board_size - w) / 2.0;
+            let y = bottom - h - row as f32 * (h + 6.0);
+            let backdrop = RgbaColor {
+                a: BACKDROP_COLOR.a * 1.5 * alpha,
+                ..BACKDROP_COLOR
+            };
+            draw_rectangle(x, y, w, h, backdrop);
+            let text = RgbaColor {
+                a: alpha,
+                ..EVAL_WHITE
+            };
+            draw_text(&toast.text, x + 12.0, y + 22.0, 20.0, text);
+        }
+    }
+
+    // a tag in the board's top left corner for a mode that changes what's shown,
+    // further ones stacking below
+    pub fn draw_badge(&self, row: usize, text: &str) {
+        let Vec2 { x, y } = self.board_origin;
+        let y = y + row as f32 * 30.0;
+        let width = measure_text(text, None, 20, 1.0).width;
+        draw_rectangle(x, y, width + 12.0, 26.0, BACKDROP_COLOR);
+        draw_text(text, x + 6.0, y + 19.0, 20.0, EVAL_WHITE);
+    }
+
+    // a line of text in the panel, in the same slots as the buttons
+    pub fn panel_text(&self, slot: usize, text: &str) {
+        let y = self.panel.y + PANEL_MARGIN + (slot as f32 + 0.6) * BUTTON_SPACING;
+        draw_text(text, self.panel.x + PANEL_MARGIN, y, 20.0, EVAL_WHITE);
+    }
+
+    // the panel slot under the mouse, for hovering over its lines of text
+    pub fn panel_slot_at(&self, (x, y): MousePos) -> Option<usize> {
+        let top = self.panel.y + PANEL_MARGIN;
+        if !self.panel.contains(Vec2::new(x, y)) || y < top {
+            return None;
+        }
+        Some(((y - top) / BUTTON_SPACING) as usize)
+    }
+
+    // a small board of `pieces` left of the panel, level with `slot`, e.g. where a line ends
+    pub fn draw_mini_board(&self, pieces: &[(Square, Piece)], slot: usize) {
+        let size = MINI_SQUARE * 8.0;
+        let x = self.panel.x - size - PANEL_MARGIN;
+        let y = (self.panel.y + PANEL_MARGIN + slot as f32 * BUTTON_SPACING)
+            .min(self.height - size - PANEL_MARGIN)
+            .max(0.0);
+        draw_rectangle(x - 4.0, y - 4.0, size + 8.0, size + 8.0, BACKDROP_COLOR);
+        match &self.board_themes[self.board_theme] {
+            BoardTheme::Texture { texture, .. } => {
+                draw_texture_ex(texture, x, y, WHITE, DrawTextureParams {
+                    dest_size: Some(Vec2::splat(size)),
+                    ..Default::default()
+                });
+            }
+            &BoardTheme::Flat { light, dark, .. } => {
+                for square in (!BitBoard::default()).iter() {
+                    let (col, row) = self.grid_position(square);
+                    let color = if square.is_dark() { dark } else { light };
+                    let (x, y) = (x + col * MINI_SQUARE, y + row * MINI_SQUARE);
+                    draw_rectangle(x, y, MINI_SQUARE, MINI_SQUARE, color);
+                }
+            }
+        }
+        for &(square, piece) in pieces {
+            let (col, row) = self.grid_position(square);
+            let (x, y) = (x + col * MINI_SQUARE, y + row * MINI_SQUARE);
+            self.draw_piece_sized(piece, x, y, MINI_SQUARE, WHITE);
+        }
+    }
+
+    // a player's remaining time, lit up while it's running and red once it's out
+    pub fn draw_clock(&self, slot: usize, time: &str, running: bool, flagged: bool) {
+        let x = self.panel.x + PANEL_MARGIN;
+        let y = self.panel.y + PANEL_MARGIN + slot as f32 * BUTTON_SPACING;
+        let (background, text) = match (flagged, running) {
+            (true, _) => (FLAG_COLOR, EVAL_WHITE),
+            (false, true) => (EVAL_WHITE, EVAL_BLACK),
+            (false, false) => (EVAL_BLACK, EVAL_WHITE),
+        };
+        draw_rectangle(x, y, 120.0, BUTTON_SPACING - 4.0, background);
+        draw_text(time, x + 8.0, y + 20.0, 24.0, text);
+    }
+
+    // numbered movetext from the panel's `slot` down, scrolled to keep the ply after
+    // `current` moves in view and highlighted
+    pub fn draw_move_list(
+        &self,
+        slot: usize,
+        sans: &[String],
+        first_move: u16,
+        black_first: bool,
+        current: usize,
+    ) {
+        let x = self.panel.x + PANEL_MARGIN;
+        let top = self.panel.y + PANEL_MARGIN + slot as f32 * BUTTON_SPACING;
+        let bottom = if self.show_graph {
+            self.graph.y - GRAPH_TEXT
+        } else {
+            self.panel.bottom()
+        };
+        let visible = ((bottom - top) / MOVE_LIST_ROW).max(1.0) as usize;
+        let offset = black_first as usize;
+        let current_row = (current + offset).saturating_sub(1) / 2;
+        let first_row = (current_row + 1).saturating_sub(visible);
+        let columns = [x + 40.0, x + 110.0];
+        for (idx, san) in sans.iter().enumerate() {
+            let cell = idx + offset;
+            let row = cell / 2;
+            if row < first_row || row >= first_row + visible {
+                continue;
+            }
+            let y = top + (row - first_row) as f32 * MOVE_LIST_ROW;
+            if cell.is_multiple_of(2) || idx == 0 {
+                let number = format!("{}.", first_move as usize + row);
+                draw_text(&number, x, y + 15.0, 18.0, HOVER_COLOR);
+            }
+            let column = columns[cell % 2];
+            if idx + 1 == current {
+                draw_rectangle(column - 4.0, y, 66.0, MOVE_LIST_ROW, CURRENT_PLY_COLOR);
+            }
+            draw_text(san, column, y + 15.0, 18.0, EVAL_WHITE);
+        }
+    }
+
+    // modal text box, e.g. for pasting a FEN, with the last parse error under it
+    pub fn draw_input_dialog(
+        &self,
+        title: &str,
+        action: &str,
+        input: &mut String,
+        error: Option<&str>,
+    ) -> Option<DialogResponse> {
+        let size = Vec2::new(self.width.min(480.0), 140.0);
+        let position = Vec2::new((self.width - size.x) / 2.0, (self.height - size.y) / 2.0);
+        let mut response = None;
+        Window::new(hash!(), position, size)
+            .label(title)
+            .movable(false)
+            .ui(&mut root_ui(), |ui| {
+                ui.input_text(hash!(), "", input);
+                ui.label(None, error.unwrap_or(""));
+                if ui.button(None, action) {
+                    response = Some(DialogResponse::Submit);
+                }
+                ui.same_line(0.0);
+                if ui.button(None, "Cancel") {
+                    response = Some(DialogResponse::Cancel);
+                }
+            });
+        if is_key_pressed(KeyCode::Enter) {
+            response = Some(DialogResponse::Submit);
+        }
+        if is_key_pressed(KeyCode::Escape) {
+            response = Some(DialogResponse::Cancel);
+        }
+        response
+    }
+
+    // a question with a yes and a no, Enter and Escape answering for them
+    pub fn draw_confirm_dialog(
+        &self,
+        title: &str,
+        text: &str,
+        action: &str,
+        cancel: &str,
+    ) -> Option<DialogResponse> {
+        let size = Vec2::new(self.width.min(480.0), 110.0);
+        let position = Vec2::new((self.width - size.x) / 2.0, (self.height - size.y) / 2.0);
+        let mut response = None;
+        Window::new(hash!(), position, size)
+            .label(title)
+            .movable(false)
+            .ui(&mut root_ui(), |ui| {
+                ui.label(None, text);
+                if ui.button(None, action) {
+                    response = Some(DialogResponse::Submit);
+                }
+                ui.same_line(0.0);
+                if ui.button(None, cancel) {
+                    response = Some(DialogResponse::Cancel);
+                }
+            });
+        if is_key_pressed(KeyCode::Enter) {
+            response = Some(DialogResponse::Submit);
+        }
+        if is_key_pressed(KeyCode::Escape) {
+            response = Some(DialogResponse::Cancel);
+        }
+        response
+    }
+
+    // strength, style and time control for a game against the engine
+    pub fn draw_engine_dialog(&self, setup: &mut EngineSetup) -> Option<DialogResponse> {
+        let size = Vec2::new(self.width.min(480.0), 230.0);
+        let position = Vec2::new((self.width - size.x) / 2.0, (self.height - size.y) / 2.0);
+        let mut response = None;
+        Window::new(hash!(), position, size)
+            .label("Play the engine")
+            .movable(false)
+            .ui(&mut root_ui(), |ui| {
+                ui.checkbox(hash!(), "Limit strength", &mut setup.limit_strength);
+                if setup.limit_strength {
+                    let elos = MIN_ELO as f32..MAX_ELO as f32;
+                    ui.slider(hash!(), "Elo", elos, &mut setup.elo);
+                }
+                ui.slider(hash!(), "Contempt", -100.0..100.0, &mut setup.contempt);
+                ui.label(None, "Time control, e.g. 300+2 (blank for none)");
+                ui.input_text(hash!(), "", &mut setup.time_control);
+                ui.label(None, setup.error.as_deref().unwrap_or(""));
+                if ui.button(None, "Play") {
+                    response = Some(DialogResponse::Submit);
+                }
+                ui.same_line(0.0);
+                if ui.button(None, "Cancel") {
+                    response = Some(DialogResponse::Cancel);
+                }
+            });
+        if is_key_pressed(KeyCode::Enter) {
+            response = Some(DialogResponse::Submit);
+        }
+        if is_key_pressed(KeyCode::Escape) {
+            response = Some(DialogResponse::Cancel);
+        }
+        response
+    }
+
+    pub fn take_options_request(&mut self) -> bool {
+        std::mem::take(&mut self.options_requested)
+    }
+
+    // a control per option the engine offers, of the kind it declared
+    pub fn draw_engine_options(&self, config: &mut EngineConfig) -> Option<DialogResponse> {
+        let rows = config.fields.len() + 3;
+        let size = Vec2::new(
+            self.width.min(420.0),
+            (60.0 + 24.0 * rows as f32).min(self.height - 20.0),
+        );
+        let position = Vec2::new((self.width - size.x) / 2.0, (self.height - size.y) / 2.0);
+        let title = config.engine.as_deref().unwrap_or("Engine options");
+        let mut response = None;
+        Window::new(hash!(), position, size)
+            .label(title)
+            .movable(false)
+            .ui(&mut root_ui(), |ui| {
+                if let Some(error) = &config.error {
+                    ui.label(None, error);
+                } else if config.engine.is_none() {
+                    ui.label(None, "Asking the engine...");
+                }
+                for (idx, field) in config.fields.iter_mut().enumerate() {
+                    let id = hash!("engine option", idx);
+                    let name = field.option.name.as_str();
+                    match (&mut field.value, &field.option.kind) {
+                        (FieldValue::Check(on), _) => ui.checkbox(id, name, on),
+                        (FieldValue::Spin(value), &OptionKind::Spin { min, max, .. }) => {
+                            ui.drag(id, name, (min as f32, max as f32), value)
+                        }
+                        (FieldValue::Combo(idx), OptionKind::Combo { vars, .. }) => {
+                            let vars: Vec<&str> = vars.iter().map(String::as_str).collect();
+                            ui.combo_box(id, name, &vars, idx);
+                        }
+                        (FieldValue::Text(text), _) => ui.input_text(id, name, text),
+                        _ => {}
+                    }
+                }
+                if ui.button(None, "Save") {
+                    response = Some(DialogResponse::Submit);
+                }
+                ui.same_line(0.0);
+                if ui.button(None, "Cancel") {
+                    response = Some(DialogResponse::Cancel);
+                }
+            });
+        if is_key_pressed(KeyCode::Escape) {
+            response = Some(DialogResponse::Cancel);
+        }
+        response
+    }
+
+    // one line per game, each opening it; a new game needs the opponent's name
+    pub fn draw_games_dialog(
+        &self,
+        games: &[String],
+        opponent: &mut String,
+    ) -> Option<GamesResponse> {
+        let size = Vec2::new(self.width.min(480.0), 300.0);
+        let position = Vec2::new((self.width - size.x) / 2.0, (self.height - size.y) / 2.0);
+        let mut response = None;
+        Window::new(hash!(), position, size)
+            .label("My games")
+            .movable(false)
+            .ui(&mut root_ui(), |ui| {
+                if games.is_empty() {
+                    ui.label(None, "No games going");
+                }
+                // numbered to match the order games are picked up in
+                for (idx, game) in games.iter().enumerate() {
+                    if ui.button(None, format!("{}. {game}", idx + 1).as_str()) {
+                        response = Some(GamesResponse::Open(idx));
+                    }
+                }
+                ui.label(None, "Opponent");
+                ui.input_text(hash!(), "", opponent);
+                if ui.button(None, "New as White") {
+                    response = Some(GamesResponse::New(Color::White));
+                }
+                ui.same_line(0.0);
+                if ui.button(None, "New as Black") {
+                    response = Some(GamesResponse::New(Color::Black));
+                }
+                ui.same_line(0.0);
+                if ui.button(None, "Close") {
+                    response = Some(GamesResponse::Close);
+                }
+            });
+        if is_key_pressed(KeyCode::Escape) {
+            response = Some(GamesResponse::Close);
+        }
+        response
+    }
+
+    // the games of a file, each opening it; `total` says how many the filter left out
+    pub fn draw_pgn_chooser(
+        &self,
+        games: &[String],
+        total: usize,
+        filter: &mut String,
+    ) -> Option<ChooserResponse> {
+        let size = Vec2::new(self.width.min(560.0), self.height.min(400.0));
+        let position = Vec2::new((self.width - size.x) / 2.0, (self.height - size.y) / 2.0);
+        let mut response = None;
+        Window::new(hash!(), position, size)
+            .label("Choose a game")
+            .movable(false)
+            .ui(&mut root_ui(), |ui| {
+                ui.label(None, "Filter");
+                ui.input_text(hash!(), "", filter);
+                if ui.button(None, "Close") {
+                    response = Some(ChooserResponse::Close);
+                }
+                if games.len() < total {
+                    ui.label(None, &format!("{} of {total} games", games.len()));
+                }
+                for (idx, game) in games.iter().enumerate() {
+                    if ui.button(None, game.as_str()) {
+                        response = Some(ChooserResponse::Open(idx));
+                    }
+                }
+            });
+        if is_key_pressed(KeyCode::Escape) {
+            response = Some(ChooserResponse::Close);
+        }
+        response
+    }
+
+    // a verdict on the move from the usual glyphs, and a comment on it
+    pub fn draw_annotation_dialog(
+        &self,
+        title: &str,
+        nag: &mut Option<u8>,
+        comment: &mut String,
+    ) -> Option<DialogResponse> {
+        let size = Vec2::new(self.width.min(480.0), 170.0);
+        let position = Vec2::new((self.width - size.x) / 2.0, (self.height - size.y) / 2.0);
+        let mut response = None;
+        Window::new(hash!(), position, size)
+            .label(title)
+            .movable(false)
+            .ui(&mut root_ui(), |ui| {
+                for glyph in 1..=6 {
+                    // the chosen one is bracketed, a second click clears it
+                    let label = if *nag == Some(glyph) {
+                        format!("[{}]", nag_glyph(glyph))
+                    } else {
+                        nag_glyph(glyph)
+                    };
+                    if ui.button(None, label.as_str()) {
+                        *nag = (*nag != Some(glyph)).then_some(glyph);
+                    }
+                    ui.same_line(0.0);
+                }
+                ui.label(None, "");
+                ui.label(None, "Comment");
+                ui.input_text(hash!(), "", comment);
+                if ui.button(None, "Save") {
+                    response = Some(DialogResponse::Submit);
+                }
+                ui.same_line(0.0);
+                if ui.button(None, "Cancel") {
+                    response = Some(DialogResponse::Cancel);
+                }
+            });
+        if is_key_pressed(KeyCode::Enter) {
+            response = Some(DialogResponse::Submit);
+        }
+        if is_key_pressed(KeyCode::Escape) {
+            response = Some(DialogResponse::Cancel);
+        }
+        response
+    }
+
+    // the conversation so far with a line to type the next message into
+    pub fn draw_chat_dialog(&self, lines: &[String], input: &mut String) -> Option<DialogResponse> {
+        let size = Vec2::new(self.width.min(480.0), 260.0);
+        let position = Vec2::new((self.width - size.x) / 2.0, (self.height - size.y) / 2.0);
+        let mut response = None;
+        Window::new(hash!(), position, size)
+            .label("Chat")
+            .movable(false)
+            .ui(&mut root_ui(), |ui| {
+                let shown = lines.len().saturating_sub(8);
+                for line in &lines[shown..] {
+                    ui.label(None, line);
+                }
+                ui.input_text(hash!(), "", input);
+                if ui.button(None, "Send") {
+                    response = Some(DialogResponse::Submit);
+                }
+                ui.same_line(0.0);
+                if ui.button(None, "Close") {
+                    response = Some(DialogResponse::Cancel);
+                }
+            });
+        if is_key_pressed(KeyCode::Enter) {
+            response = Some(DialogResponse::Submit);
+        }
+        if is_key_pressed(KeyCode::Escape) {
+            response = Some(DialogResponse::Cancel);
+        }
+        response
+    }
+
+    pub fn pressed(&self, action: Action) -> bool {
+        self.keys.pressed(action)
+    }
+
+    // returns false once the window is closed
+    pub fn draw_settings(&mut self) -> bool {
+        if self.keys_open {
+            self.keys_open = self.draw_key_bindings();
+            return true;
+        }
+        let entries = self.piece_sets.len() + self.board_themes.len() + HIGHLIGHT_THEMES.len() + 15;
+        let size = Vec2::new(240.0, 80.0 + 25.0 * entries as f32);
+        let position = Vec2::new((self.width - size.x) / 2.0, (self.height - size.y) / 2.0);
+        let mut open = true;
+        let mut piece_set = self.piece_set;
+        let mut board_theme = self.board_theme;
+        let mut highlights = self.highlights;
+        let (mut muted, mut volume) = (self.audio.muted, self.audio.volume);
+        let mut animate = self.animate;
+        let (mut fullscreen, mut idle_saving) = (self.fullscreen, self.idle_saving);
+        let mut fps_cap = self.fps_cap;
+        let mut keys_open = false;
+        let mut announce = self.announce;
+        let mut explain = self.explain;
+        let mut coach = self.coach;
+        let high_contrast = self.board_themes[board_theme].name() == HIGH_CONTRAST
+            && HIGHLIGHT_THEMES[highlights].name == HIGH_CONTRAST;
+        let mut contrast = high_contrast;
+        Window::new(hash!(), position, size)
+            .label("Settings")
+            .movable(false)
+            .ui(&mut root_ui(), |ui| {
+                ui.label(None, "Pieces");
+                for (idx, set) in self.piece_sets.iter().enumerate() {
+                    if Button::new(set.name.as_str())
+                        .selected(idx == piece_set)
+                        .ui(ui)
+                    {
+                        piece_set = idx;
+                    }
+                }
+                ui.label(None, "Board");
+                for (idx, theme) in self.board_themes.iter().enumerate() {
+                    if Button::new(theme.name())
+                        .selected(idx == board_theme)
+                        .ui(ui)
+                    {
+                        board_theme = idx;
+                    }
+                }
+                ui.label(None, "Highlights");
+                for (idx, theme) in HIGHLIGHT_THEMES.iter().enumerate() {
+                    if Button::new(theme.name).selected(idx == highlights).ui(ui) {
+                        highlights = idx;
+                    }
+                }
+                ui.label(None, "Sound");
+                ui.checkbox(hash!(), "Mute", &mut muted);
+                ui.slider(hash!(), "Volume", 0.0..1.0, &mut volume);
+                ui.label(None, "Animation");
+                ui.checkbox(hash!(), "Animate moves", &mut animate);
+                ui.label(None, "Display");
+                ui.checkbox(hash!(), "Fullscreen", &mut fullscreen);
+                ui.checkbox(hash!(), "Save power when idle", &mut idle_saving);
+                for (cap, label) in [(None, "No cap"), (Some(30), "30 fps"), (Some(60), "60 fps")] {
+                    if Button::new(label).selected(fps_cap == cap).ui(ui) {
+                        fps_cap = cap;
+                    }
+                }
+                ui.label(None, "Accessibility");
+                ui.checkbox(hash!(), "Announce moves", &mut announce);
+                ui.checkbox(hash!(), "High contrast", &mut contrast);
+                ui.label(None, "Learning");
+                ui.checkbox(hash!(), "Explain illegal moves", &mut explain);
+                ui.checkbox(hash!(), "Ask before blunders", &mut coach);
+                if ui.button(None, "Key bindings") {
+                    keys_open = true;
+                }
+                if ui.button(None, "Engine options") {
+                    self.options_requested = true;
+                    open = false;
+                }
+                if ui.button(None, "Close") {
+                    open = false;
+                }
+            });
+        self.keys_open = keys_open;
+        self.announce = announce;
+        self.explain = explain;
+        self.coach = coach;
+        // switching it off goes back to the stock board and highlights
+        if contrast != high_contrast {
+            let boards = self.board_themes.iter().map(BoardTheme::name);
+            let highlight_names = HIGHLIGHT_THEMES.iter().map(|theme| theme.name);
+            let name = contrast.then(|| HIGH_CONTRAST.to_owned());
+            board_theme = find_name(boards, &name).unwrap_or_default();
+            highlights = find_name(highlight_names, &name).unwrap_or_default();
+        }
+        self.piece_set = piece_set;
+        self.board_theme = board_theme;
+        self.highlights = highlights;
+        (self.audio.muted, self.audio.volume) = (muted, volume);
+        self.animate = animate;
+        if fullscreen != self.fullscreen {
+            self.toggle_fullscreen();
+        }
+        (self.idle_saving, self.fps_cap) = (idle_saving, fps_cap);
+        keys_open || open && !is_key_pressed(KeyCode::Escape)
+    }
+
+    // a button per action showing its keys; clicking one waits for the next key, which
+    // replaces them unless another action has it already. Escape gives up waiting.
+    fn draw_key_bindings(&mut self) -> bool {
+        let waiting = self.capturing.is_some();
+        if let Some(action) = self.capturing
+            && let Some(key) = get_last_key_pressed()
+        {
+            let binding = KeyBinding::held(key);
+            if key == KeyCode::Escape && binding == KeyBinding::held(KeyCode::Escape) {
+                self.capturing = None;
+                self.key_error = None;
+            } else if KeyBinding::bindable(key) {
+                self.capturing = None;
+                self.key_error = match self.keys.conflict(action, binding) {
+                    Some(other) => Some(format!("{binding} is taken by {}", other.label())),
+                    None => {
+                        self.keys.set(action, vec![binding]);
+                        None
+                    }
+                };
+            }
+        }
+        let rows = Action::ALL.len() + 3;
+        let size = Vec2::new(320.0, (60.0 + 22.0 * rows as f32).min(self.height - 20.0));
+        let position = Vec2::new((self.width - size.x) / 2.0, (self.height - size.y) / 2.0);
+        let mut open = true;
+        let mut capturing = self.capturing;
+        let mut reset = false;
+        let keys = &self.keys;
+        Window::new(hash!(), position, size)
+            .label("Key bindings")
+            .movable(false)
+            .ui(&mut root_ui(), |ui| {
+                let message = match (capturing, &self.key_error) {
+                    (Some(action), _) => format!("Press a key for {}", action.label()),
+                    (None, Some(error)) => error.clone(),
+                    (None, None) => String::new(),
+                };
+                ui.label(None, &message);
+                for action in Action::ALL {
+                    let bound: Vec<String> =
+                        keys.get(action).iter().map(|k| k.to_string()).collect();
+                    let bound = if capturing == Some(action) {
+                        "...".to_owned()
+                    } else if bound.is_empty() {
+                        "-".to_owned()
+                    } else {
+                        bound.join(", ")
+                    };
+                    ui.label(None, action.label());
+                    ui.same_line(140.0);
+                    if ui.button(None, bound.as_str()) {
+                        capturing = Some(action);
+                    }
+                }
+                if ui.button(None, "Defaults") {
+                    reset = true;
+                }
+                ui.same_line(0.0);
+                if ui.button(None, "Back") {
+                    open = false;
+                }
+            });
+        if capturing != self.capturing {
+            self.key_error = None;
+        }
+        self.capturing = capturing;
+        if reset {
+            self.keys = KeyBindings::default();
+            self.capturing = None;
+        }
+        // an Escape that gave up waiting doesn't close the page as well
+        open && (waiting || !is_key_pressed(KeyCode::Escape))
+    }
+
+    pub fn settings(&self) -> Settings {
+        Settings {
+            pieces: Some(self.piece_sets[self.piece_set].name.clone()),
+            board: Some(self.board_themes[self.board_theme].name().to_owned()),
+            highlights: Some(self.highlight_theme().name.to_owned()),
+            volume: Some(self.audio.volume),
+            muted: Some(self.audio.muted),
+            animate: Some(self.animate),
+            keys: self.keys.clone(),
+            announce: Some(self.announce),
+            speech: self.speech.clone(),
+            explain: Some(self.explain),
+            coach: Some(self.coach),
+        }
+    }
+
+    pub fn apply_settings(&mut self, settings: &Settings) {
+        let pieces = self.piece_sets.iter().map(|set| set.name.as_str());
+        if let Some(idx) = find_name(pieces, &settings.pieces) {
+            self.piece_set = idx;
+        }
+        let boards = self.board_themes.iter().map(BoardTheme::name);
+        if let Some(idx) = find_name(boards, &settings.board) {
+            self.board_theme = idx;
+        }
+        let highlights = HIGHLIGHT_THEMES.iter().map(|theme| theme.name);
+        if let Some(idx) = find_name(highlights, &settings.highlights) {
+            self.highlights = idx;
+        }
+        self.audio.volume = settings.volume.unwrap_or(self.audio.volume).clamp(0.0, 1.0);
+        self.audio.muted = settings.muted.unwrap_or(self.audio.muted);
+        self.animate = settings.animate.unwrap_or(self.animate);
+        self.announce = settings.announce.unwrap_or(self.announce);
+        self.speech = settings.speech.clone();
+        self.explain = settings.explain.unwrap_or(self.explain);
+        self.coach = settings.coach.unwrap_or(self.coach);
+        // a key on two actions would do both, so the file's wishes give way
+        let conflicts = settings.keys.conflicts();
+        for (action, other, binding) in &conflicts {
+            log::warn!("{binding} is bound to both {action:?} and {other:?}");
+        }
+        self.keys = if conflicts.is_empty() {
+            settings.keys.clone()
+        } else {
+            KeyBindings::default()
+        };
+    }
+
     pub fn get_square_at_point(&self, (x, y): MousePos) -> Option<Square> {
+        let (x, y) = (x - self.board_origin.x, y - self.board_origin.y);
         if x <= 0.0 || y <= 0.0 || self.board_size <= x || self.board_size <= y {
             return None;
         }
-        let (col, row) = (
+        let (mut col, mut row) = (
             (x / self.square_size).floor() as u8,
             ((self.board_size - y) / self.square_size).floor() as u8,
         );
+        if self.flipped {
+            (col, row) = (7 - col, 7 - row);
+        }
         let col = col.try_into().ok()?;
         let row = row.try_into().ok()?;
         Some(Square::from_coords(col, row))
@@ -118,115 +1369,185 @@ impl View {
     }
 
     pub fn draw_piece_at_square(&self, piece: Piece, square: Square) {
-        let top_left_x = square.col() as u8 as f32 * self.square_size;
-        let top_left_y = (7 - square.row() as u8) as f32 * self.square_size;
+        let (top_left_x, top_left_y) = self.square_origin(square);
         self.draw_piece_at(piece, top_left_x, top_left_y);
     }
 
-    pub fn play_move_sound(&self) {
-        play_sound_once(&self.move_sound);
+    // a faded copy of a dragged piece left where it came from
+    pub fn draw_ghost_piece(&self, piece: Piece, square: Square) {
+        let (x, y) = self.square_origin(square);
+        self.draw_piece_tinted(piece, x, y, RgbaColor {
+            a: GHOST_ALPHA,
+            ..WHITE
+        });
+    }
+
+    // outline of the square a dragged piece would be dropped on
+    pub fn draw_drop_target(&self, square: Square) {
+        let (x, y) = self.square_origin(square);
+        let thickness = self.square_size / 16.0;
+        let color = RgbaColor {
+            a: 0.9,
+            ..self.highlight_theme().selection
+        };
+        draw_rectangle_lines(
+            x + thickness / 2.0,
+            y + thickness / 2.0,
+            self.square_size - thickness,
+            self.square_size - thickness,
+            thickness,
+            color,
+        );
+    }
+
+    // the board alone, themed and turned as on screen, drawn off screen at a fixed size
+    pub fn render_board(&mut self, pieces: &[(Square, Piece)], highlights: &[Square]) -> Image {
+        let size = EXPORT_SQUARE * 8.0;
+        let target = render_target(size as u32, size as u32);
+        let mut camera = Camera2D::from_display_rect(Rect::new(0.0, 0.0, size, size));
+        camera.render_target = Some(target.clone());
+        set_camera(&camera);
+        let screen = (self.board_origin, self.board_size, self.square_size);
+        (self.board_origin, self.board_size, self.square_size) = (Vec2::ZERO, size, EXPORT_SQUARE);
+        clear_background(WHITE);
+        self.draw_board();
+        for &square in highlights {
+            self.draw_highlight(square);
+        }
+        for &(square, piece) in pieces {
+            self.draw_piece_at_square(piece, square);
+        }
+        (self.board_origin, self.board_size, self.square_size) = screen;
+        set_default_camera();
+        target.texture.get_texture_data()
     }
 
-    pub fn play_capture_sound(&self) {
-        play_sound_once(&self.capture_sound);
+    pub fn play_sound(&self, effect: SoundEffect) {
+        self.audio.play(effect);
     }
 
-    pub fn play_in_check_sound(&self) {
-        play_sound_once(&self.in_check_sound);
+    pub fn toggle_mute(&mut self) {
+        self.audio.muted = !self.audio.muted;
     }
 
-    fn draw_piece_at(&self, piece: Piece, x: f32, y: f32) {
-        let rectangle = match piece {
-            WHITE_KING => WK_RECTANGLE,
-            WHITE_QUEEN => WQ_RECTANGLE,
-            WHITE_BISHOP => WB_RECTANGLE,
-            WHITE_KNIGHT => WN_RECTANGLE,
-            WHITE_ROOK => WR_RECTANGLE,
-            WHITE_PAWN => WP_RECTANGLE,
-            BLACK_KING => BK_RECTANGLE,
-            BLACK_QUEEN => BQ_RECTANGLE,
-            BLACK_BISHOP => BB_RECTANGLE,
-            BLACK_KNIGHT => BN_RECTANGLE,
-            BLACK_ROOK => BR_RECTANGLE,
-            BLACK_PAWN => BP_RECTANGLE,
+    // column and row of `square` counted from the top left, as the board is turned
+    fn grid_position(&self, square: Square) -> (f32, f32) {
+        let (col, row) = (square.col() as u8, square.row() as u8);
+        let (col, row) = if self.flipped {
+            (7 - col, row)
+        } else {
+            (col, 7 - row)
         };
-        draw_texture_ex(&self.piece_texture, x, y, WHITE, DrawTextureParams {
-            dest_size: Some(Vec2::splat(self.square_size)),
-            source: Some(rectangle),
+        (col as f32, row as f32)
+    }
+
+    // top left corner of a square on screen, honouring the board orientation
+    fn square_origin(&self, square: Square) -> (f32, f32) {
+        let (col, row) = self.grid_position(square);
+        (
+            self.board_origin.x + col * self.square_size,
+            self.board_origin.y + row * self.square_size,
+        )
+    }
+
+    fn square_center(&self, square: Square) -> (f32, f32) {
+        let (x, y) = self.square_origin(square);
+        (x + self.square_size / 2.0, y + self.square_size / 2.0)
+    }
+
+    fn draw_piece_at(&self, piece: Piece, x: f32, y: f32) {
+        self.draw_piece_tinted(piece, x, y, WHITE);
+    }
+
+    fn draw_piece_tinted(&self, piece: Piece, x: f32, y: f32, tint: RgbaColor) {
+        self.draw_piece_sized(piece, x, y, self.square_size, tint);
+    }
+
+    fn draw_piece_sized(&self, piece: Piece, x: f32, y: f32, size: f32, tint: RgbaColor) {
+        let set = &self.piece_sets[self.piece_set];
+        draw_texture_ex(&set.texture, x, y, tint, DrawTextureParams {
+            dest_size: Some(Vec2::splat(size)),
+            source: Some(set.layout.rect(piece)),
             ..Default::default()
         });
     }
 }
 
-const WK_RECTANGLE: Rect = Rect {
-    x: 0.0,
-    y: 0.0,
-    w: 170.0,
-    h: 170.0,
-};
-const WQ_RECTANGLE: Rect = Rect {
-    x: 171.0,
-    y: 0.0,
-    w: 170.0,
-    h: 170.0,
-};
-const WB_RECTANGLE: Rect = Rect {
-    x: 342.0,
-    y: 0.0,
-    w: 170.0,
-    h: 170.0,
-};
-const WN_RECTANGLE: Rect = Rect {
-    x: 513.0,
-    y: 0.0,
-    w: 170.0,
-    h: 170.0,
-};
-const WR_RECTANGLE: Rect = Rect {
-    x: 684.0,
-    y: 0.0,
-    w: 170.0,
-    h: 170.0,
-};
-const WP_RECTANGLE: Rect = Rect {
-    x: 855.0,
-    y: 0.0,
-    w: 170.0,
-    h: 170.0,
-};
-const BK_RECTANGLE: Rect = Rect {
-    x: 0.0,
-    y: 171.0,
-    w: 170.0,
-    h: 170.0,
-};
-const BQ_RECTANGLE: Rect = Rect {
-    x: 171.0,
-    y: 171.0,
-    w: 170.0,
-    h: 170.0,
-};
-const BB_RECTANGLE: Rect = Rect {
-    x: 342.0,
-    y: 171.0,
-    w: 170.0,
-    h: 170.0,
-};
-const BN_RECTANGLE: Rect = Rect {
-    x: 513.0,
-    y: 171.0,
-    w: 170.0,
-    h: 170.0,
-};
-const BR_RECTANGLE: Rect = Rect {
-    x: 684.0,
-    y: 171.0,
-    w: 170.0,
-    h: 170.0,
-};
-const BP_RECTANGLE: Rect = Rect {
-    x: 855.0,
-    y: 171.0,
-    w: 170.0,
-    h: 170.0,
-};
+fn find_name<'a>(
+    mut names: impl Iterator<Item = &'a str>,
+    wanted: &Option<String>,
+) -> Option<usize> {
+    let wanted = wanted.as_deref()?;
+    names.position(|name| name == wanted)
+}
+
+// a frame with a bar for how far along start-up is, `step` of LOADING_STEPS done
+async fn draw_loading(step: usize, what: &str) {
+    clear_background(RgbaColor::from_hex(0x302e2b));
+    let (w, h) = (screen_width(), screen_height());
+    let (bar_w, bar_h) = ((w * 0.5).min(400.0), 12.0);
+    let (x, y) = ((w - bar_w) / 2.0, h / 2.0);
+    let text = format!("Loading {what}");
+    let text_w = measure_text(&text, None, 20, 1.0).width;
+    draw_text(&text, (w - text_w) / 2.0, y - 16.0, 20.0, EVAL_WHITE);
+    draw_rectangle(x, y, bar_w, bar_h, BACKDROP_COLOR);
+    let done = step as f32 / LOADING_STEPS as f32;
+    draw_rectangle(x, y, bar_w * done, bar_h, CURRENT_PLY_COLOR);
+    next_frame().await;
+}
+
+// browsers pace frames themselves and can't block the page
+#[cfg(not(target_arch = "wasm32"))]
+fn pause(secs: f64) {
+    std::thread::sleep(std::time::Duration::from_secs_f64(secs));
+}
+
+#[cfg(target_arch = "wasm32")]
+fn pause(_secs: f64) {}
+
+// `image` as a PNG file's bytes, flipped the right way up: render targets come out
+// bottom row first
+pub fn encode_png(image: &Image) -> Result<Vec<u8>, png::EncodingError> {
+    let row = image.width as usize * 4;
+    let flipped: Vec<u8> = image
+        .bytes
+        .chunks_exact(row)
+        .rev()
+        .flatten()
+        .copied()
+        .collect();
+    let mut bytes = Vec::new();
+    let mut encoder = png::Encoder::new(&mut bytes, image.width as u32, image.height as u32);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(&flipped)?;
+    writer.finish()?;
+    Ok(bytes)
+}
+
+fn promotion_squares(col: Column, turn: Color) -> [(Square, Figure); 4] {
+    let rows = match turn {
+        Color::White => [Row::Eight, Row::Seven, Row::Six, Row::Five],
+        Color::Black => [Row::One, Row::Two, Row::Three, Row::Four],
+    };
+    let figures = [Figure::Queen, Figure::Rook, Figure::Knight, Figure::Bishop];
+    [0, 1, 2, 3].map(|idx| (Square::from_coords(col, rows[idx]), figures[idx]))
+}
+
+fn mark_color(color: MarkColor) -> RgbaColor {
+    match color {
+        MarkColor::Green => MARK_GREEN,
+        MarkColor::Red => MARK_RED,
+        MarkColor::Yellow => MARK_YELLOW,
+        MarkColor::Blue => MARK_BLUE,
+    }
+}
+
+fn judgement_color(judgement: Judgement) -> RgbaColor {
+    match judgement {
+        Judgement::Inaccuracy => INACCURACY_COLOR,
+        Judgement::Mistake => MISTAKE_COLOR,
+        Judgement::Blunder => BLUNDER_COLOR,
+    }
+}